@@ -7,6 +7,10 @@ mod service;
 mod cli;
 mod command;
 
-fn main() -> sc_cli::Result<()> {
-	command::run()
+fn main() {
+	// Report errors with their message rather than their debug representation.
+	if let Err(e) = command::run() {
+		eprintln!("Error: {}", e);
+		std::process::exit(1);
+	}
 }
@@ -51,6 +51,12 @@ pub trait BenchmarkDescription {
 
 pub trait Benchmark {
 	fn run(&mut self, mode: Mode) -> std::time::Duration;
+
+	/// Time spent in execution alone by an operation measured by `run`, for the benchmarks that
+	/// can measure it separately.
+	fn execution(&mut self) -> Option<std::time::Duration> {
+		None
+	}
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -58,6 +64,10 @@ pub struct BenchmarkOutput {
 	name: String,
 	raw_average: u64,
 	average: u64,
+	/// The slowest run.
+	worst: u64,
+	/// Average time spent in execution, the rest of `raw_average` being spent outside of it.
+	execution_average: Option<u64>,
 }
 
 pub struct NsFormatter(pub u64);
@@ -107,36 +117,68 @@ impl fmt::Display for BenchmarkOutput {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
 			f,
-			"{}: avg {}, w_avg {}",
+			"{}: avg {}, w_avg {}, worst {}",
 			self.name,
 			NsFormatter(self.raw_average),
 			NsFormatter(self.average),
-		)
+			NsFormatter(self.worst),
+		)?;
+
+		if let Some(execution) = self.execution_average {
+			let share = (execution as f64 / self.raw_average.max(1) as f64).min(1.0);
+			write!(
+				f,
+				", execution {} ({:.0}%), storage commit {:.0}%",
+				NsFormatter(execution),
+				share * 100.0,
+				(1.0 - share) * 100.0,
+			)?;
+		}
+
+		Ok(())
     }
 }
 
+/// Run `benchmark` `repeat` times, `repeat` being at least 1.
 pub fn run_benchmark(
 	benchmark: Box<dyn BenchmarkDescription>,
 	mode: Mode,
+	repeat: usize,
 ) -> BenchmarkOutput {
 	let name = benchmark.name().to_owned();
 	let mut benchmark = benchmark.setup();
 
 	let mut durations: Vec<u128> = vec![];
-	for _ in 0..50 {
+	let mut executions: Vec<u128> = vec![];
+	for _ in 0..repeat {
 		let duration = benchmark.run(mode);
 		durations.push(duration.as_nanos());
+		if let Some(execution) = benchmark.execution() {
+			executions.push(execution.as_nanos());
+		}
 	}
 
 	durations.sort();
 
+	// The weighted average leaves out the fastest and slowest fifth of the runs.
+	let skip = durations.len() / 5;
+	let take = durations.len() - 2 * skip;
+
 	let raw_average = (durations.iter().sum::<u128>() / (durations.len() as u128)) as u64;
-	let average = (durations.iter().skip(10).take(30).sum::<u128>() / 30) as u64;
+	let average = (durations.iter().skip(skip).take(take).sum::<u128>() / take as u128) as u64;
+	let worst = durations[durations.len() - 1] as u64;
+	let execution_average = if executions.is_empty() {
+		None
+	} else {
+		Some((executions.iter().sum::<u128>() / executions.len() as u128) as u64)
+	};
 
 	BenchmarkOutput {
 		name: name.into(),
 		raw_average,
 		average,
+		worst,
+		execution_average,
 	}
 }
 
@@ -63,11 +63,24 @@ struct Opt {
 	/// so that actual interval can be selected in the profiler of choice.
 	#[structopt(short, long, default_value = "regular")]
 	mode: BenchmarkMode,
+
+	/// Number of runs of each benchmark.
+	#[structopt(long, default_value = "50")]
+	repeat: usize,
+
+	/// Write the results as json to the given file.
+	#[structopt(long, value_name = "PATH")]
+	record: Option<std::path::PathBuf>,
 }
 
 fn main() {
 	let opt = Opt::from_args();
 
+	if opt.repeat == 0 {
+		eprintln!("`--repeat` must be at least 1");
+		std::process::exit(1);
+	}
+
 	if !opt.json {
 		sc_cli::init_logger("");
 	}
@@ -145,7 +158,7 @@ fn main() {
 	for benchmark in benchmarks {
 		if opt.filter.as_ref().map(|f| benchmark.path().has(f)).unwrap_or(true) {
 			log::info!("Starting {}", benchmark.name());
-			let result = run_benchmark(benchmark, opt.mode);
+			let result = run_benchmark(benchmark, opt.mode, opt.repeat);
 			log::info!("{}", result);
 
 			results.push(result);
@@ -157,8 +170,15 @@ fn main() {
 		std::process::exit(1);
 	}
 
-	if opt.json {
+	if opt.json || opt.record.is_some() {
 		let json_result: String = serde_json::to_string(&results).expect("Failed to construct json");
-		println!("{}", json_result);
+
+		if let Some(path) = opt.record.as_ref() {
+			std::fs::write(path, &json_result).expect("Failed to write the results");
+		}
+
+		if opt.json {
+			println!("{}", json_result);
+		}
 	}
 }
@@ -20,6 +20,10 @@
 
 #![warn(missing_docs)]
 
-fn main() -> sc_cli::Result<()> {
-	node_cli::run()
+fn main() {
+	// Report errors with their message rather than their debug representation.
+	if let Err(e) = node_cli::run() {
+		eprintln!("Error: {}", e);
+		std::process::exit(1);
+	}
 }
@@ -19,7 +19,7 @@
 //! Substrate chain configurations.
 
 use sc_chain_spec::ChainSpecExtension;
-use sp_core::{Pair, Public, crypto::UncheckedInto, sr25519};
+use sp_core::{Pair, Public, crypto::UncheckedInto, ed25519, sr25519};
 use serde::{Serialize, Deserialize};
 use node_runtime::{
 	AuthorityDiscoveryConfig, BabeConfig, BalancesConfig, ContractsConfig, CouncilConfig,
@@ -45,6 +45,9 @@ type AccountPublic = <Signature as Verify>::Signer;
 
 const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
 
+const ENDOWMENT: Balance = 10_000_000 * DOLLARS;
+const STASH: Balance = 100 * DOLLARS;
+
 /// Node `ChainSpec` extensions.
 ///
 /// Additional parameters for some Substrate core modules,
@@ -236,9 +239,6 @@ pub fn testnet_genesis(
 	});
 	let num_endowed_accounts = endowed_accounts.len();
 
-	const ENDOWMENT: Balance = 10_000_000 * DOLLARS;
-	const STASH: Balance = 100 * DOLLARS;
-
 	GenesisConfig {
 		frame_system: Some(SystemConfig {
 			code: WASM_BINARY.to_vec(),
@@ -324,6 +324,50 @@ pub fn testnet_genesis(
 	}
 }
 
+/// Replace the initial authorities of `genesis` with the given sr25519 and ed25519 keys, e.g. for
+/// `build-spec --authority`.
+///
+/// The sr25519 key of an authority is its stash, its controller and all its session keys but the
+/// GRANDPA one. The stash is bonded with the stake of the first previous staker, and endowed with
+/// it if needed.
+pub fn set_authorities(
+	genesis: &mut GenesisConfig,
+	authorities: &[(sr25519::Public, ed25519::Public)],
+) {
+	let stake = genesis.pallet_staking.as_ref()
+		.and_then(|staking| staking.stakers.first())
+		.map_or(STASH, |staker| staker.2);
+	let authorities = authorities.iter()
+		.map(|&(sr25519, ed25519)| {
+			let account = AccountPublic::from(sr25519).into_account();
+			let keys = session_keys(ed25519.into(), sr25519.into(), sr25519.into(), sr25519.into());
+			(account, keys)
+		})
+		.collect::<Vec<_>>();
+
+	if let Some(balances) = &mut genesis.pallet_balances {
+		for (account, _) in &authorities {
+			match balances.balances.iter_mut().find(|(endowed, _)| endowed == account) {
+				Some((_, balance)) => *balance = (*balance).max(stake),
+				None => balances.balances.push((account.clone(), stake)),
+			}
+		}
+	}
+	if let Some(session) = &mut genesis.pallet_session {
+		session.keys = authorities.iter()
+			.map(|(account, keys)| (account.clone(), account.clone(), keys.clone()))
+			.collect();
+	}
+	if let Some(staking) = &mut genesis.pallet_staking {
+		staking.validator_count = authorities.len() as u32 * 2;
+		staking.minimum_validator_count = authorities.len() as u32;
+		staking.stakers = authorities.iter()
+			.map(|(account, _)| (account.clone(), account.clone(), stake, StakerStatus::Validator))
+			.collect();
+		staking.invulnerables = authorities.iter().map(|(account, _)| account.clone()).collect();
+	}
+}
+
 fn development_config_genesis() -> GenesisConfig {
 	testnet_genesis(
 		vec![
@@ -449,4 +493,18 @@ pub(crate) mod tests {
 	fn test_staging_test_net_chain_spec() {
 		staging_testnet_config().build_storage().unwrap();
 	}
+
+	#[test]
+	fn test_set_authorities() {
+		let mut genesis = development_config_genesis();
+		let bob = get_from_seed::<sr25519::Public>("Bob");
+		set_authorities(&mut genesis, &[(bob, get_from_seed::<ed25519::Public>("Bob"))]);
+
+		let bob = AccountPublic::from(bob).into_account();
+		let staking = genesis.pallet_staking.as_ref().unwrap();
+		assert_eq!(staking.stakers.len(), 1);
+		assert_eq!(staking.stakers[0].0, bob);
+		assert_eq!(genesis.pallet_session.as_ref().unwrap().keys[0].0, bob);
+		genesis.build_storage().unwrap();
+	}
 }
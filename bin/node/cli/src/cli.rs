@@ -47,4 +47,12 @@ pub enum Subcommand {
 	/// The custom benchmark subcommmand benchmarking runtime pallets.
 	#[structopt(name = "benchmark", about = "Benchmark runtime pallets.")]
 	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
+
+	/// Measure the read and write costs of the database, to derive the weights of storage access.
+	#[structopt(name = "benchmark-storage", about = "Benchmark database reads and writes.")]
+	BenchmarkStorage(frame_benchmarking_cli::StorageCmd),
+
+	/// Compare the hardware against the reference hardware of validators.
+	#[structopt(name = "benchmark-machine", about = "Benchmark the hardware.")]
+	BenchmarkMachine(frame_benchmarking_cli::MachineCmd),
 }
@@ -30,6 +30,19 @@ impl SubstrateCli for Cli {
 		env!("SUBSTRATE_CLI_IMPL_VERSION")
 	}
 
+	fn build_info() -> Vec<(&'static str, String)> {
+		vec![
+			("commit", env!("SUBSTRATE_CLI_COMMIT").into()),
+			("profile", env!("SUBSTRATE_CLI_PROFILE").into()),
+			("target", env!("SUBSTRATE_CLI_TARGET").into()),
+			("runtime", format!(
+				"{}-{}",
+				node_runtime::VERSION.spec_name,
+				node_runtime::VERSION.spec_version,
+			)),
+		]
+	}
+
 	fn description() -> &'static str {
 		env!("CARGO_PKG_DESCRIPTION")
 	}
@@ -51,15 +64,19 @@ impl SubstrateCli for Cli {
 	}
 
 	fn load_spec(&self, id: &str) -> std::result::Result<Box<dyn sc_service::ChainSpec>, String> {
-		Ok(match id {
-			"dev" => Box::new(chain_spec::development_config()),
-			"local" => Box::new(chain_spec::local_testnet_config()),
-			"" | "fir" | "flaming-fir" => Box::new(chain_spec::flaming_fir_config()?),
-			"staging" => Box::new(chain_spec::staging_testnet_config()),
-			path => Box::new(chain_spec::ChainSpec::from_json_file(
-				std::path::PathBuf::from(path),
-			)?),
-		})
+		let spec = match id {
+			"dev" => chain_spec::development_config(),
+			"local" => chain_spec::local_testnet_config(),
+			"" | "fir" | "flaming-fir" => chain_spec::flaming_fir_config()?,
+			"staging" => chain_spec::staging_testnet_config(),
+			id => {
+				let spec: chain_spec::ChainSpec = sc_cli::load_chain_spec(id)?;
+				return Ok(Box::new(spec.with_authorities_hook(chain_spec::set_authorities)));
+			},
+		};
+		log::debug!("Using the built-in chain specification `{}`", spec.id());
+
+		Ok(Box::new(spec.with_authorities_hook(chain_spec::set_authorities)))
 	}
 }
 
@@ -92,6 +109,16 @@ pub fn run() -> Result<()> {
 				Ok(())
 			}
 		}
+		Some(Subcommand::BenchmarkStorage(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+
+			runner.sync_run(|config| cmd.run(config))
+		}
+		Some(Subcommand::BenchmarkMachine(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+
+			runner.sync_run(|config| cmd.run(config))
+		}
 		Some(Subcommand::Base(subcommand)) => {
 			let runner = cli.create_runner(subcommand)?;
 
@@ -37,3 +37,88 @@ fn build_spec_works() {
 
 	let _value: serde_json::Value = serde_json::from_slice(output.stdout.as_slice()).unwrap();
 }
+
+#[test]
+fn build_spec_to_file_works() {
+	let base_path = tempdir().expect("could not create a temp dir");
+	let spec_path = base_path.path().join("spec.json");
+
+	let output = Command::new(cargo_bin("substrate"))
+		.args(&["build-spec", "--dev", "--raw", "-d"])
+		.arg(base_path.path())
+		.arg("--output")
+		.arg(&spec_path)
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+
+	// The spec only goes to the file, the summary is written to stderr.
+	assert!(output.stdout.is_empty());
+	assert!(String::from_utf8_lossy(&output.stderr).contains("Chain spec written to"));
+
+	let spec = std::fs::read(&spec_path).unwrap();
+	let _value: serde_json::Value = serde_json::from_slice(&spec).unwrap();
+}
+
+#[test]
+fn build_spec_adds_local_bootnode() {
+	let base_path = tempdir().expect("could not create a temp dir");
+
+	let output = Command::new(cargo_bin("substrate"))
+		.args(&["build-spec", "--dev", "--bootnode-port", "40333", "-d"])
+		.arg(base_path.path())
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+
+	let spec: serde_json::Value = serde_json::from_slice(output.stdout.as_slice()).unwrap();
+	let boot_nodes = spec["bootNodes"].as_array().unwrap();
+	assert_eq!(boot_nodes.len(), 1);
+	assert!(boot_nodes[0].as_str().unwrap().starts_with("/ip4/127.0.0.1/tcp/40333/p2p/"));
+
+	// The generated key is persisted so the node later runs with the same peer id.
+	assert!(base_path.path().join("chains/dev/network/secret_ed25519").exists());
+
+	let output = Command::new(cargo_bin("substrate"))
+		.args(&["build-spec", "--dev", "--disable-default-bootnode", "-d"])
+		.arg(base_path.path())
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+
+	let spec: serde_json::Value = serde_json::from_slice(output.stdout.as_slice()).unwrap();
+	assert!(spec["bootNodes"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn build_spec_to_gzip_file_works() {
+	use std::io::Read;
+
+	let base_path = tempdir().expect("could not create a temp dir");
+	let spec_path = base_path.path().join("spec.json.gz");
+
+	let output = Command::new(cargo_bin("substrate"))
+		.args(&["build-spec", "--dev", "-d"])
+		.arg(base_path.path())
+		.arg("--output")
+		.arg(&spec_path)
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+
+	let mut spec = Vec::new();
+	flate2::read::GzDecoder::new(std::fs::File::open(&spec_path).unwrap())
+		.read_to_end(&mut spec)
+		.unwrap();
+	let _value: serde_json::Value = serde_json::from_slice(&spec).unwrap();
+
+	// The compressed spec can be used as `--chain`.
+	let output = Command::new(cargo_bin("substrate"))
+		.args(&["build-spec", "--raw", "-d"])
+		.arg(base_path.path())
+		.arg("--chain")
+		.arg(&spec_path)
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+}
@@ -38,3 +38,38 @@ fn check_block_works() {
 		.unwrap();
 	assert!(status.success());
 }
+
+#[test]
+fn check_block_range_works() {
+	let base_path = tempdir().expect("could not create a temp dir");
+
+	common::run_dev_node_for_a_while(base_path.path());
+
+	let output = Command::new(cargo_bin("substrate"))
+		.args(&["check-block", "--dev", "--pruning", "archive", "--to", "2", "-d"])
+		.arg(base_path.path())
+		.arg("1")
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+
+	let stdout = String::from_utf8_lossy(&output.stdout);
+	assert!(stdout.contains("#1: ok"), "{}", stdout);
+	assert!(stdout.contains("#2: ok"), "{}", stdout);
+	assert!(stdout.contains("Checked 2 blocks"), "{}", stdout);
+}
+
+#[test]
+fn check_block_rejects_an_inverted_range() {
+	let base_path = tempdir().expect("could not create a temp dir");
+
+	common::run_dev_node_for_a_while(base_path.path());
+
+	let status = Command::new(cargo_bin("substrate"))
+		.args(&["check-block", "--dev", "--pruning", "archive", "--to", "1", "-d"])
+		.arg(base_path.path())
+		.arg("2")
+		.status()
+		.unwrap();
+	assert!(!status.success());
+}
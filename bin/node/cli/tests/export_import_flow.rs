@@ -19,7 +19,7 @@
 #![cfg(unix)]
 
 use assert_cmd::cargo::cargo_bin;
-use std::{process::Command, fs, path::PathBuf};
+use std::{process::{Command, Stdio}, fs, path::PathBuf};
 use tempfile::{tempdir, TempDir};
 use regex::Regex;
 
@@ -41,6 +41,7 @@ struct ExportImportRevertExecutor<'a> {
 /// Format options for export / import commands.
 enum FormatOpt {
 	Json,
+	JsonLines,
 	Binary,
 }
 
@@ -84,6 +85,12 @@ impl<'a> ExportImportRevertExecutor<'a> {
 		let arguments: Vec<&str> = match format_opt {
 			FormatOpt::Binary => vec![&sub_command_str, "--dev", "--pruning", "archive", "--binary", "-d"],
 			FormatOpt::Json => vec![&sub_command_str, "--dev", "--pruning", "archive", "-d"],
+			// The import command detects the JSON lines format by itself.
+			FormatOpt::JsonLines => match sub_command {
+				SubCommand::ExportBlocks =>
+					vec![&sub_command_str, "--dev", "--pruning", "archive", "--format", "jsonl", "-d"],
+				SubCommand::ImportBlocks => vec![&sub_command_str, "--dev", "--pruning", "archive", "-d"],
+			},
 		};
 
 		let tmp: TempDir;
@@ -166,7 +173,7 @@ impl<'a> ExportImportRevertExecutor<'a> {
 	/// Runs the `revert` command.
 	fn run_revert(&self) {
 		let output = Command::new(cargo_bin("substrate"))
-			.args(&["revert", "--dev", "--pruning", "archive", "-d"])
+			.args(&["revert", "-y", "--dev", "--pruning", "archive", "-d"])
 			.arg(&self.base_path.path())
 			.output()
 			.unwrap();
@@ -209,4 +216,77 @@ fn export_import_revert() {
 	executor.run(FormatOpt::Json, FormatOpt::Json, false);
 	// JSON and binary should fail.
 	executor.run(FormatOpt::Json, FormatOpt::Binary, true);
+	// JSON lines and JSON lines should work.
+	executor.run(FormatOpt::JsonLines, FormatOpt::JsonLines, false);
+	// JSON lines and binary should fail.
+	executor.run(FormatOpt::JsonLines, FormatOpt::Binary, true);
+}
+
+#[test]
+fn import_blocks_from_stdin() {
+	let base_path = tempdir().expect("could not create a temp dir");
+	let exported_blocks_file = base_path.path().join("exported_blocks");
+	let import_path = tempdir().expect("could not create a temp dir");
+
+	common::run_dev_node_for_a_while(base_path.path());
+
+	let status = Command::new(cargo_bin("substrate"))
+		.args(&["export-blocks", "--dev", "--pruning", "archive", "--binary", "-d"])
+		.arg(base_path.path())
+		.arg(&exported_blocks_file)
+		.status()
+		.unwrap();
+	assert!(status.success());
+
+	let output = Command::new(cargo_bin("substrate"))
+		.args(&["import-blocks", "--dev", "--pruning", "archive", "--binary", "-d"])
+		.arg(import_path.path())
+		.stdin(Stdio::from(fs::File::open(&exported_blocks_file).unwrap()))
+		.output()
+		.unwrap();
+
+	let logged_output = String::from_utf8_lossy(&output.stderr).to_string();
+	assert!(!contains_error(&logged_output), "expected not to error but error'd!");
+	assert!(output.status.success());
+	assert!(logged_output.contains("Reading blocks from stdin"));
+	assert!(Regex::new(r"Imported \d* blocks").unwrap().is_match(&logged_output));
+}
+
+#[test]
+fn import_blocks_resumes_from_checkpoint() {
+	let base_path = tempdir().expect("could not create a temp dir");
+	let exported_blocks_file = base_path.path().join("exported_blocks");
+	let import_path = tempdir().expect("could not create a temp dir");
+	let checkpoint_file = import_path.path().join("checkpoint.json");
+
+	common::run_dev_node_for_a_while(base_path.path());
+
+	let status = Command::new(cargo_bin("substrate"))
+		.args(&["export-blocks", "--dev", "--pruning", "archive", "--binary", "-d"])
+		.arg(base_path.path())
+		.arg(&exported_blocks_file)
+		.status()
+		.unwrap();
+	assert!(status.success());
+
+	let import = || Command::new(cargo_bin("substrate"))
+		.args(&["import-blocks", "--dev", "--pruning", "archive", "--binary", "-d"])
+		.arg(import_path.path())
+		.arg("--checkpoint-file")
+		.arg(&checkpoint_file)
+		.arg(&exported_blocks_file)
+		.output()
+		.unwrap();
+
+	let output = import();
+	assert!(output.status.success());
+	let checkpoint: serde_json::Value =
+		serde_json::from_slice(&fs::read(&checkpoint_file).unwrap()).unwrap();
+	assert!(checkpoint["number"].as_u64().unwrap() > 0);
+
+	// The second import skips all the blocks recorded in the checkpoint.
+	let output = import();
+	let logged_output = String::from_utf8_lossy(&output.stderr).to_string();
+	assert!(output.status.success());
+	assert!(logged_output.contains("Resuming import after block"));
 }
@@ -0,0 +1,70 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#![cfg(unix)]
+
+use assert_cmd::cargo::cargo_bin;
+use std::{fs, process::Command};
+use tempfile::tempdir;
+
+pub mod common;
+
+#[test]
+fn import_state_works() {
+	let base_path = tempdir().expect("could not create a temp dir");
+
+	common::run_dev_node_for_a_while(base_path.path());
+
+	let output = Command::new(cargo_bin("substrate"))
+		.args(&["export-state", "--dev", "--pruning", "archive", "-d"])
+		.arg(base_path.path())
+		.args(&["--at", "1"])
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+
+	let mut spec: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+	assert!(spec["genesis"]["raw"]["stateRoot"].is_string());
+	let spec_path = base_path.path().join("state.json");
+	fs::write(&spec_path, serde_json::to_vec(&spec).unwrap()).unwrap();
+
+	let import_path = base_path.path().join("imported");
+	let status = Command::new(cargo_bin("substrate"))
+		.args(&["import-state", "--chain"])
+		.arg(&spec_path)
+		.arg("-d")
+		.arg(&import_path)
+		.status()
+		.unwrap();
+	assert!(status.success());
+
+	// A state that doesn't match the recorded root is rejected and leaves no database behind.
+	spec["genesis"]["raw"]["stateRoot"] = format!("0x{}", "00".repeat(32)).into();
+	fs::write(&spec_path, serde_json::to_vec(&spec).unwrap()).unwrap();
+
+	let mismatch_path = base_path.path().join("mismatch");
+	let status = Command::new(cargo_bin("substrate"))
+		.args(&["import-state", "--chain"])
+		.arg(&spec_path)
+		.arg("-d")
+		.arg(&mismatch_path)
+		.status()
+		.unwrap();
+	assert!(!status.success());
+	assert!(!mismatch_path.join("chains").join("dev_1").join("db").exists());
+}
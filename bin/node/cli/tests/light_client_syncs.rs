@@ -0,0 +1,101 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#![cfg(unix)]
+
+use assert_cmd::cargo::cargo_bin;
+use nix::sys::signal::{kill, Signal::SIGINT};
+use nix::unistd::Pid;
+use regex::Regex;
+use std::convert::TryInto;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::tempdir;
+
+pub mod common;
+
+/// Send the lines of `output` through the returned channel, from another thread.
+fn lines(output: impl Read + Send + 'static) -> Receiver<String> {
+	let (sender, receiver) = channel();
+	thread::spawn(move || {
+		for line in BufReader::new(output).lines() {
+			if sender.send(line.unwrap()).is_err() {
+				break;
+			}
+		}
+	});
+	receiver
+}
+
+/// The first capture of `regex` in the lines received within `secs` seconds.
+fn find(lines: &Receiver<String>, regex: &Regex, secs: u64) -> Option<String> {
+	let deadline = Instant::now() + Duration::from_secs(secs);
+	while let Some(timeout) = deadline.checked_duration_since(Instant::now()) {
+		let line = lines.recv_timeout(timeout).ok()?;
+		if let Some(captures) = regex.captures(&line) {
+			return Some(captures[1].to_string());
+		}
+	}
+	None
+}
+
+fn stop(mut child: Child) {
+	kill(Pid::from_raw(child.id().try_into().unwrap()), SIGINT).unwrap();
+	assert!(common::wait_for(&mut child, 40).map(|x| x.success()).unwrap_or_default());
+}
+
+#[test]
+fn light_client_syncs_the_headers_of_a_full_node() {
+	let full_path = tempdir().expect("could not create a temp dir");
+	let light_path = tempdir().expect("could not create a temp dir");
+
+	let mut full = Command::new(cargo_bin("substrate"))
+		.args(&["--dev", "--port", "30391", "--rpc-port", "9941", "--ws-port", "9951", "--no-prometheus", "-d"])
+		.arg(full_path.path())
+		.stderr(Stdio::piped())
+		.spawn()
+		.unwrap();
+	let full_lines = lines(full.stderr.take().unwrap());
+
+	let identity = Regex::new(r"Local node identity is: (\w+)").unwrap();
+	let peer_id = find(&full_lines, &identity, 60).expect("the full node must log its identity");
+
+	let mut light = Command::new(cargo_bin("substrate"))
+		.args(&["--chain", "dev", "--light", "--port", "30392", "--rpc-port", "9942", "--ws-port", "9952"])
+		.args(&["--no-prometheus", "--bootnodes"])
+		.arg(format!("/ip4/127.0.0.1/tcp/30391/p2p/{}", peer_id))
+		.arg("-d")
+		.arg(light_path.path())
+		.stderr(Stdio::piped())
+		.spawn()
+		.unwrap();
+	let light_lines = lines(light.stderr.take().unwrap());
+
+	// The informant of the light client reports the headers it imported from the full node.
+	let best = Regex::new(r"best: #([1-9]\d*)").unwrap();
+	let synced = find(&light_lines, &best, 120);
+
+	stop(light);
+	stop(full);
+
+	assert!(synced.is_some(), "the light client must sync the headers produced by the full node");
+	assert!(light_path.path().join("chains/dev/light").exists(), "the light client uses its own database");
+}
@@ -0,0 +1,47 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! # Running
+//! Running this fuzzer can be done with `cargo hfuzz run decode_hex`. `honggfuzz` CLI options can
+//! be used by setting `HFUZZ_RUN_ARGS`, such as `-n 4` to use 4 threads.
+//!
+//! # Debugging a panic
+//! Once a panic is found, it can be debugged with
+//! `cargo hfuzz run-debug decode_hex hfuzz_workspace/decode_hex/*.fuzz`.
+//!
+//! # More information
+//! More information about `honggfuzz` can be found
+//! [here](https://docs.rs/honggfuzz/).
+
+use honggfuzz::fuzz;
+
+#[path = "../../src/hex_input.rs"]
+mod hex_input;
+
+fn main() {
+	loop {
+		fuzz!(|data: &[u8]| {
+			// Must never panic, whatever the input.
+			if let Ok(decoded) = hex_input::decode_hex(data) {
+				// Anything accepted is the hex encoding of what it decodes to.
+				let digits = String::from_utf8_lossy(data).trim().to_ascii_lowercase();
+				assert_eq!(hex::encode(&decoded), digits.trim_start_matches("0x"));
+			}
+		});
+	}
+}
@@ -0,0 +1,197 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The `batch-verify` subcommand, verifying many signatures at once.
+
+use crate::{decode_hex, decode_signature, Crypto, Error, PublicOf, PublicT, SignatureOf, SignatureT};
+use serde_json::Value;
+use sp_core::crypto::Ss58Codec;
+
+/// A signature of a message to verify with a public key.
+pub(crate) struct Entry<C: Crypto> {
+	pub public: PublicOf<C>,
+	pub message: Vec<u8>,
+	pub signature: SignatureOf<C>,
+}
+
+/// Parse the entries of a file, either a JSON array of objects with the `public`, `message` and
+/// `signature` fields, or CSV lines of the same values in this order.
+///
+/// Messages and signatures are hex-encoded, public keys hex-encoded or SS58. Empty CSV lines and
+/// a `public,message,signature` header are skipped.
+pub(crate) fn parse_entries<C: Crypto>(content: &str) -> Result<Vec<Entry<C>>, Error>
+where
+	SignatureOf<C>: SignatureT,
+	PublicOf<C>: PublicT,
+{
+	if content.trim_start().starts_with('[') {
+		parse_json(content)
+	} else {
+		parse_csv(content)
+	}
+}
+
+fn parse_json<C: Crypto>(content: &str) -> Result<Vec<Entry<C>>, Error>
+where
+	SignatureOf<C>: SignatureT,
+	PublicOf<C>: PublicT,
+{
+	let values: Vec<Value> = serde_json::from_str(content)
+		.map_err(|e| Error::Formatted(format!("Invalid JSON: {}", e)))?;
+
+	values.iter().enumerate().map(|(index, value)| {
+		let field = |name: &str| value.get(name).and_then(Value::as_str).ok_or_else(|| Error::Formatted(
+			format!("Entry {}: missing the `{}` string", index, name),
+		));
+		parse_entry(index, field("public")?, field("message")?, field("signature")?)
+	}).collect()
+}
+
+fn parse_csv<C: Crypto>(content: &str) -> Result<Vec<Entry<C>>, Error>
+where
+	SignatureOf<C>: SignatureT,
+	PublicOf<C>: PublicT,
+{
+	content.lines()
+		.map(str::trim)
+		.filter(|line| !line.is_empty() && *line != "public,message,signature")
+		.enumerate()
+		.map(|(index, line)| match line.split(',').map(str::trim).collect::<Vec<_>>()[..] {
+			[public, message, signature] => parse_entry(index, public, message, signature),
+			_ => Err(Error::Formatted(format!(
+				"Entry {}: expected `public,message,signature`, got `{}`",
+				index,
+				line,
+			))),
+		})
+		.collect()
+}
+
+fn parse_entry<C: Crypto>(index: usize, public: &str, message: &str, signature: &str) -> Result<Entry<C>, Error>
+where
+	SignatureOf<C>: SignatureT,
+	PublicOf<C>: PublicT,
+{
+	let in_entry = |e: Error| Error::Formatted(format!("Entry {}: {}", index, e));
+
+	Ok(Entry {
+		public: parse_public::<C>(public).map_err(in_entry)?,
+		message: decode_hex(message).map_err(in_entry)?,
+		signature: decode_signature::<C>(signature).map_err(in_entry)?,
+	})
+}
+
+/// Parse a hex-encoded or SS58 public key.
+fn parse_public<C: Crypto>(public: &str) -> Result<PublicOf<C>, Error> {
+	let bytes = match decode_hex(public) {
+		Ok(bytes) => bytes,
+		Err(_) => return PublicOf::<C>::from_ss58check(public)
+			.map_err(|_| Error::Static("invalid public key, expecting hex or SS58")),
+	};
+
+	let mut key = PublicOf::<C>::default();
+	if key.as_ref().len() != bytes.len() {
+		return Err(Error::Formatted(format!(
+			"public key has an invalid length. read {} bytes, expected {} bytes",
+			bytes.len(),
+			key.as_ref().len(),
+		)));
+	}
+	key.as_mut().copy_from_slice(&bytes);
+	Ok(key)
+}
+
+/// The indices of the entries whose signature is invalid.
+///
+/// The entries are batch-verified if the scheme supports it, and only verified one by one to find
+/// the invalid ones if the batch fails.
+pub(crate) fn invalid_entries<C: Crypto>(entries: &[Entry<C>]) -> Vec<usize> {
+	if C::verify_all(entries) {
+		return Vec::new();
+	}
+
+	entries.iter()
+		.enumerate()
+		.filter(|(_, entry)| !C::verify_entry(entry))
+		.map(|(index, _)| index)
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Ed25519, Sr25519};
+	use sp_core::{ed25519, sr25519, Pair};
+
+	fn line<P: Pair>(pair: &P, message: &[u8]) -> String
+	where
+		P::Public: AsRef<[u8]>,
+		P::Signature: AsRef<[u8]>,
+	{
+		format!("0x{},{},{}", hex::encode(pair.public()), hex::encode(message), hex::encode(pair.sign(message)))
+	}
+
+	#[test]
+	fn csv_entries_are_verified() {
+		let alice = ed25519::Pair::from_string("//Alice", None).unwrap();
+		let bob = ed25519::Pair::from_string("//Bob", None).unwrap();
+		let mut invalid = line(&bob, b"other");
+		invalid.replace_range(..66, &format!("0x{}", hex::encode(alice.public())));
+
+		let content = format!(
+			"public,message,signature\n{}\n\n{}\n{}\n",
+			line(&alice, b"first"),
+			invalid,
+			line(&bob, b"third"),
+		);
+		let mut entries = parse_entries::<Ed25519>(&content).unwrap();
+
+		assert_eq!(entries.len(), 3);
+		assert_eq!(invalid_entries(&entries), vec![1]);
+		assert!(invalid_entries(&[entries.remove(0)]).is_empty());
+	}
+
+	#[test]
+	fn json_entries_are_verified() {
+		let alice = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let content = serde_json::json!([
+			{
+				"public": alice.public().to_ss58check(),
+				"message": hex::encode(b"message"),
+				"signature": hex::encode(alice.sign(b"message")),
+			},
+			{
+				"public": alice.public().to_ss58check(),
+				"message": hex::encode(b"other"),
+				"signature": hex::encode(alice.sign(b"message")),
+			},
+		]).to_string();
+		let entries = parse_entries::<Sr25519>(&content).unwrap();
+
+		assert_eq!(invalid_entries(&entries), vec![1]);
+	}
+
+	#[test]
+	fn malformed_entries_are_rejected() {
+		assert!(parse_entries::<Ed25519>("0x00,00").is_err());
+		assert!(parse_entries::<Ed25519>("0x00,00,00").is_err());
+		assert!(parse_entries::<Ed25519>(r#"[{"public": "0x00"}]"#).is_err());
+		assert!(parse_entries::<Ed25519>("[not json").is_err());
+		assert!(parse_entries::<Ed25519>("").unwrap().is_empty());
+	}
+}
@@ -0,0 +1,85 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Decoding of hex encoded user input.
+//!
+//! Kept free of the rest of subkey so the fuzzer can build it on its own.
+
+/// Decode hex encoded `input`.
+///
+/// Surrounding whitespace and a `0x` prefix are ignored, digits can be upper or lower case.
+pub fn decode_hex<T: AsRef<[u8]>>(input: T) -> Result<Vec<u8>, String> {
+	let mut input = input.as_ref();
+	while let Some((first, rest)) = input.split_first() {
+		if !first.is_ascii_whitespace() { break }
+		input = rest;
+	}
+	while let Some((last, rest)) = input.split_last() {
+		if !last.is_ascii_whitespace() { break }
+		input = rest;
+	}
+	if input.starts_with(b"0x") || input.starts_with(b"0X") {
+		input = &input[2..];
+	}
+
+	hex::decode(input).map_err(|e| format!("Invalid hex ({})", e))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn decodes_unprefixed_and_prefixed_input() {
+		assert_eq!(decode_hex("0102ff"), Ok(vec![1, 2, 255]));
+		assert_eq!(decode_hex("0x0102ff"), Ok(vec![1, 2, 255]));
+		assert_eq!(decode_hex("0X0102ff"), Ok(vec![1, 2, 255]));
+		assert_eq!(decode_hex(""), Ok(vec![]));
+		assert_eq!(decode_hex("0x"), Ok(vec![]));
+	}
+
+	#[test]
+	fn decodes_mixed_case_input() {
+		assert_eq!(decode_hex("aBcDeF"), Ok(vec![0xab, 0xcd, 0xef]));
+		assert_eq!(decode_hex("0xABcdEF"), Ok(vec![0xab, 0xcd, 0xef]));
+	}
+
+	#[test]
+	fn ignores_surrounding_whitespace() {
+		assert_eq!(decode_hex("  0x0102\n"), Ok(vec![1, 2]));
+		assert_eq!(decode_hex(b"\t0102\r\n"), Ok(vec![1, 2]));
+		assert_eq!(decode_hex(" \n "), Ok(vec![]));
+	}
+
+	#[test]
+	fn rejects_invalid_input() {
+		// Odd number of digits.
+		assert!(decode_hex("012").is_err());
+		assert!(decode_hex("0x0").is_err());
+		// Invalid digits.
+		assert!(decode_hex("0g").is_err());
+		assert!(decode_hex("xx").is_err());
+		// Whitespace and prefixes are only accepted around the digits.
+		assert!(decode_hex("01 02").is_err());
+		assert!(decode_hex("0x0x01").is_err());
+		assert!(decode_hex(" 0x 01").is_err());
+		// Multi-byte characters.
+		assert!(decode_hex("0é").is_err());
+		assert!(decode_hex(&[0xff, 0xfe][..]).is_err());
+	}
+}
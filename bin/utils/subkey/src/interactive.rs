@@ -0,0 +1,270 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The `interactive` subcommand, running commands read line by line.
+
+use crate::{
+	decode_hex, decode_signature, has_key, insert_into_keystore, parse_key_type, parse_public_key,
+	Crypto, Error, PrintOptions, PublicOf, PublicT, SignatureOf, SignatureT,
+};
+use node_primitives::AccountId;
+use sc_keystore::KeyStorePtr;
+use sp_core::{crypto::{Ss58AddressFormat, Ss58Codec}, hexdisplay::HexDisplay, Pair};
+use std::convert::TryInto;
+use std::io::{stdout, BufRead, Write};
+
+const HELP: &str = "\
+Commands:
+  inspect [uri]                     Print the keys of a URI, prompting for it if not given.
+  verify <sig> <message> [uri]      Verify the signature of a hex-encoded message with the key of a
+                                    URI, prompting for it if not given.
+  ss58-convert <address> <network>  Print an SS58 address in the format of another network.
+  insert <key-type> [uri]           Insert the key of a URI into the in-memory keystore of the
+                                    session and print its public key, prompting for the URI if not
+                                    given.
+  has-key <public> <key-type>       Check that the in-memory keystore of the session holds a key.
+  help                              Print this help.
+  exit                              Leave, as does the end of the input.";
+
+/// What to do after a command.
+#[derive(Debug, PartialEq)]
+enum Step {
+	Continue,
+	Exit,
+}
+
+/// Run the commands read from `input` until `exit` or the end of the input.
+///
+/// The global options given with `interactive` apply to every command. A failing command prints
+/// its error and doesn't end the session. `insert` and `has-key` share an in-memory keystore that
+/// lives as long as the session.
+pub(crate) fn run<C: Crypto, R: BufRead>(options: &PrintOptions, mut input: R) -> Result<(), Error>
+where
+	SignatureOf<C>: SignatureT,
+	PublicOf<C>: PublicT,
+{
+	println!("Type `help` for the available commands.");
+
+	let keystore = sc_keystore::Store::new_in_memory();
+	let mut line = String::new();
+	loop {
+		print!("subkey> ");
+		stdout().flush()?;
+
+		line.clear();
+		if input.read_line(&mut line)? == 0 {
+			println!();
+			return Ok(());
+		}
+
+		match execute_line::<C>(options, &keystore, &line) {
+			Ok(Step::Continue) => {},
+			Ok(Step::Exit) => return Ok(()),
+			Err(e) => eprintln!("Error: {}", e),
+		}
+	}
+}
+
+fn execute_line<C: Crypto>(
+	options: &PrintOptions,
+	keystore: &KeyStorePtr,
+	line: &str,
+) -> Result<Step, Error>
+where
+	SignatureOf<C>: SignatureT,
+	PublicOf<C>: PublicT,
+{
+	let words = line.split_whitespace().collect::<Vec<_>>();
+
+	match &words[..] {
+		[] => {},
+		["exit"] | ["quit"] => return Ok(Step::Exit),
+		["help"] => println!("{}", HELP),
+		["inspect", uri @ ..] if uri.len() <= 1 => {
+			let uri = uri_or_prompt(uri.first())?;
+			C::print_from_uri(&uri, options)?;
+		},
+		["verify", signature, message, uri @ ..] if uri.len() <= 1 => {
+			let uri = uri_or_prompt(uri.first())?;
+			if verify::<C>(signature, message, &uri)? {
+				println!("Signature verifies correctly.");
+			} else {
+				return Err(Error::Static("Signature invalid."));
+			}
+		},
+		["ss58-convert", address, network] => println!("{}", convert_ss58(address, network)?),
+		["insert", key_type, uri @ ..] if uri.len() <= 1 => {
+			let uri = uri_or_prompt(uri.first())?;
+			let public = insert::<C>(keystore, key_type, &uri, options.password)?;
+			println!("0x{}", HexDisplay::from(&public.as_ref()));
+		},
+		["has-key", public, key_type] => {
+			let public = parse_public_key::<C>(public)?;
+			if has_key(&*keystore.read(), parse_key_type(key_type)?, public.as_ref()) {
+				println!("The keystore holds the key.");
+			} else {
+				return Err(Error::Static("The keystore doesn't hold the key."));
+			}
+		},
+		_ => return Err(Error::Formatted(format!(
+			"Unknown command or wrong arguments: `{}`. Type `help` for the available commands.",
+			line.trim(),
+		))),
+	}
+
+	Ok(Step::Continue)
+}
+
+/// The given URI, or one prompted for without echoing it, as it may be a secret.
+fn uri_or_prompt(uri: Option<&&str>) -> Result<String, Error> {
+	match uri {
+		Some(uri) => Ok(uri.to_string()),
+		None => Ok(rpassword::read_password_from_tty(Some("URI: "))?),
+	}
+}
+
+/// Verify the hex-encoded `signature` of the hex-encoded `message` with the public key of `uri`.
+fn verify<C: Crypto>(signature: &str, message: &str, uri: &str) -> Result<bool, Error>
+where
+	SignatureOf<C>: SignatureT,
+	PublicOf<C>: PublicT,
+{
+	let signature = decode_signature::<C>(signature)?;
+	let message = decode_hex(message)?;
+	let public = parse_public_key::<C>(uri)?;
+
+	Ok(<C::Pair as Pair>::verify(&signature, &message, &public))
+}
+
+/// Insert the key of `uri` into `keystore` under `key_type`, returning its public key.
+fn insert<C: Crypto>(
+	keystore: &KeyStorePtr,
+	key_type: &str,
+	uri: &str,
+	password: Option<&str>,
+) -> Result<PublicOf<C>, Error>
+where
+	PublicOf<C>: PublicT,
+{
+	let key_type = parse_key_type(key_type)?;
+	let pair = <C::Pair as Pair>::from_string(uri, password)
+		.map_err(|_| Error::Static("Invalid secret URI."))?;
+
+	insert_into_keystore(&mut *keystore.write(), key_type, uri, pair.public().as_ref())?;
+	Ok(pair.public())
+}
+
+/// Re-encode an SS58 `address` in the format of `network`.
+fn convert_ss58(address: &str, network: &str) -> Result<String, Error> {
+	let network: Ss58AddressFormat = network
+		.try_into()
+		.map_err(|_| Error::Static("Invalid network name. See `subkey list-networks`."))?;
+	let (account, _) = AccountId::from_ss58check_with_version(address)
+		.map_err(|e| Error::Formatted(format!("Invalid SS58 address: {:?}", e)))?;
+
+	Ok(account.to_ss58check_with_version(network))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{OutputType, Sr25519};
+	use sp_core::sr25519;
+
+	fn options() -> PrintOptions<'static> {
+		PrintOptions {
+			password: None,
+			network: None,
+			output: OutputType::Text,
+			show_entropy: false,
+			allow_secrets: true,
+		}
+	}
+
+	fn keystore() -> KeyStorePtr {
+		sc_keystore::Store::new_in_memory()
+	}
+
+	#[test]
+	fn exit_and_end_of_input_end_the_session() {
+		assert_eq!(execute_line::<Sr25519>(&options(), &keystore(), "exit\n").unwrap(), Step::Exit);
+		assert_eq!(
+			execute_line::<Sr25519>(&options(), &keystore(), "  \n").unwrap(),
+			Step::Continue,
+		);
+		assert!(run::<Sr25519, _>(&options(), &b"help\n\nunknown\n"[..]).is_ok());
+	}
+
+	#[test]
+	fn wrong_commands_fail() {
+		assert!(execute_line::<Sr25519>(&options(), &keystore(), "unknown").is_err());
+		assert!(execute_line::<Sr25519>(&options(), &keystore(), "ss58-convert").is_err());
+		assert!(execute_line::<Sr25519>(&options(), &keystore(), "inspect a b").is_err());
+	}
+
+	#[test]
+	fn verify_works() {
+		let pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let signature = hex::encode(pair.sign(b"message"));
+		let public = format!("0x{}", hex::encode(pair.public()));
+		let line = format!("verify {} {} {}", signature, hex::encode("message"), public);
+
+		assert_eq!(
+			execute_line::<Sr25519>(&options(), &keystore(), &line).unwrap(),
+			Step::Continue,
+		);
+		assert!(!verify::<Sr25519>(&signature, &hex::encode("other"), &public).unwrap());
+	}
+
+	#[test]
+	fn verify_rejects_a_short_public_key() {
+		let pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let signature = hex::encode(pair.sign(b"message"));
+		let public = format!("0x{}", hex::encode(&pair.public().0[..16]));
+
+		assert!(verify::<Sr25519>(&signature, &hex::encode("message"), &public).is_err());
+	}
+
+	#[test]
+	fn insert_and_has_key_share_the_session_keystore() {
+		let keystore = keystore();
+		let execute = |line: &str| execute_line::<Sr25519>(&options(), &keystore, line);
+		let pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let public = format!("0x{}", hex::encode(pair.public()));
+
+		assert!(execute(&format!("has-key {} gran", public)).is_err());
+		assert_eq!(execute("insert gran //Alice").unwrap(), Step::Continue);
+		assert_eq!(execute(&format!("has-key {} gran", public)).unwrap(), Step::Continue);
+		assert!(execute(&format!("has-key {} babe", public)).is_err());
+		assert!(insert::<Sr25519>(&keystore, "gran", "not a valid uri", None).is_err());
+	}
+
+	#[test]
+	fn convert_ss58_works() {
+		let pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let account = AccountId::from(pair.public());
+		let polkadot = account.to_ss58check_with_version(Ss58AddressFormat::PolkadotAccount);
+
+		assert_eq!(
+			convert_ss58(&polkadot, "kusama").unwrap(),
+			account.to_ss58check_with_version(Ss58AddressFormat::KusamaAccount),
+		);
+		assert!(convert_ss58(&polkadot, "unknown-network").is_err());
+		assert!(convert_ss58("not an address", "kusama").is_err());
+	}
+}
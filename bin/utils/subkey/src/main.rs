@@ -21,26 +21,36 @@
 extern crate test;
 
 use bip39::{Language, Mnemonic, MnemonicType};
-use clap::{App, ArgMatches, SubCommand};
+use clap::{App, Arg, ArgMatches, SubCommand};
 use codec::{Decode, Encode};
 use hex_literal::hex;
 use itertools::Itertools;
 use libp2p::identity::{ed25519 as libp2p_ed25519, PublicKey};
 use node_primitives::{Balance, Hash, Index, AccountId, Signature};
-use node_runtime::{BalancesCall, Call, Runtime, SignedPayload, UncheckedExtrinsic, VERSION};
+use node_runtime::{
+	BalancesCall, Call, Runtime, SignedExtra, SignedPayload, UncheckedExtrinsic, VERSION,
+};
+use rand::{rngs::{OsRng, StdRng}, RngCore, SeedableRng};
 use serde_json::json;
 use sp_core::{
-	crypto::{set_default_ss58_version, Ss58AddressFormat, Ss58Codec},
-	ed25519, sr25519, ecdsa, Pair, Public, H256, hexdisplay::HexDisplay,
+	crypto::{set_default_ss58_version, KeyTypeId, Ss58AddressFormat, Ss58Codec},
+	ed25519, sr25519, ecdsa, Pair, Public, H256, hexdisplay::HexDisplay, traits::BareCryptoStore,
+};
+use sp_runtime::{
+	traits::{AccountIdConversion, IdentifyAccount, SignedExtension, Verify}, generic::Era, ModuleId,
 };
-use sp_runtime::{traits::{AccountIdConversion, IdentifyAccount, Verify}, generic::Era, ModuleId};
 use std::{
-	convert::{TryInto, TryFrom}, io::{stdin, Read}, str::FromStr, path::PathBuf, fs, fmt,
+	convert::{TryInto, TryFrom}, io::{stdin, Read}, str::FromStr, path::{Path, PathBuf}, fs, fmt,
 };
 
+mod batch;
+mod hex_input;
+mod interactive;
+mod metadata;
 mod rpc;
 mod vanity;
 
+#[derive(Clone, Copy)]
 enum OutputType {
 	Json,
 	Text,
@@ -59,12 +69,110 @@ impl<'a> TryFrom<&'a str> for OutputType {
 
 }
 
+/// How `print_from_uri` prints keys, set by the global options.
+#[derive(Clone, Copy)]
+struct PrintOptions<'a> {
+	/// Password of the secret URIs.
+	password: Option<&'a str>,
+	/// Network to print the addresses for, instead of the one of the URI.
+	network: Option<Ss58AddressFormat>,
+	output: OutputType,
+	show_entropy: bool,
+	/// Whether secrets may be printed, public URIs are always printed.
+	allow_secrets: bool,
+}
+
+/// The cryptography of the keys, selected with `--scheme`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CryptoScheme {
+	Sr25519,
+	Ed25519,
+	Ecdsa,
+}
+
+impl<'a> TryFrom<&'a str> for CryptoScheme {
+	type Error = Error;
+
+	/// Parse a scheme by its name, or a common alias of it, in any case.
+	fn try_from(s: &'a str) -> Result<CryptoScheme, Error> {
+		match s.to_ascii_lowercase().as_str() {
+			"sr25519" | "schnorrkel" => Ok(CryptoScheme::Sr25519),
+			"ed25519" | "edwards" => Ok(CryptoScheme::Ed25519),
+			"ecdsa" | "secp256k1" | "k1" => Ok(CryptoScheme::Ecdsa),
+			_ => Err(Error::Formatted(format!(
+				"Invalid scheme `{}`. One of sr25519 (or schnorrkel), ed25519 (or edwards) and \
+				ecdsa (or secp256k1, k1), in any case.",
+				s,
+			))),
+		}
+	}
+}
+
+/// The scheme given with `--scheme` or one of the scheme flags, sr25519 by default.
+fn crypto_scheme(matches: &ArgMatches) -> Result<CryptoScheme, Error> {
+	let flags = [
+		("sr25519", CryptoScheme::Sr25519),
+		("ed25519", CryptoScheme::Ed25519),
+		("secp256k1", CryptoScheme::Ecdsa),
+	];
+	let flag = flags.iter().find(|(name, _)| matches.is_present(name)).map(|(_, scheme)| *scheme);
+
+	match (matches.value_of("scheme"), flag) {
+		(Some(_), Some(_)) => Err(Error::Static(
+			"`--scheme` can't be combined with --sr25519, --ed25519 or --secp256k1",
+		)),
+		(Some(scheme), None) => scheme.try_into(),
+		(None, flag) => Ok(flag.unwrap_or(CryptoScheme::Sr25519)),
+	}
+}
+
+/// The error of the VRF commands for the schemes without a VRF.
+const VRF_UNSUPPORTED: &str = "VRFs are only supported by the sr25519 scheme, pass --sr25519.";
+
+/// The signing context of VRFs, unless given with `--context`.
+const DEFAULT_VRF_CONTEXT: &str = "substrate";
+
 trait Crypto: Sized {
 	type Pair: Pair<Public = Self::Public>;
 	type Public: Public + Ss58Codec + AsRef<[u8]> + std::hash::Hash;
 	fn pair_from_suri(suri: &str, password: Option<&str>) -> Self::Pair {
 		Self::Pair::from_string(suri, password).expect("Invalid phrase")
 	}
+	/// The pair of `uri` if it is a raw seed, see `raw_seed`, with the seed.
+	fn pair_from_raw_seed(uri: &str) -> Option<(Self::Pair, SeedOf<Self>)> {
+		let bytes = raw_seed(uri)?;
+		let mut seed = SeedOf::<Self>::default();
+		if seed.as_ref().len() != bytes.len() {
+			return None;
+		}
+		seed.as_mut().copy_from_slice(&bytes);
+		// Unlike `from_seed`, fails instead of panicking on seeds that aren't valid secret keys.
+		let pair = Self::Pair::from_seed_slice(&bytes).ok()?;
+		Some((pair, seed))
+	}
+	/// The pair of the secret `suri`, built straight from the seed if it is a raw seed.
+	fn read_secret(suri: &str, password: Option<&str>) -> Self::Pair {
+		match Self::pair_from_raw_seed(suri) {
+			Some((pair, _)) => pair,
+			None => Self::pair_from_suri(suri, password),
+		}
+	}
+	/// Whether the signature of `entry` is valid.
+	fn verify_entry(entry: &batch::Entry<Self>) -> bool {
+		<Self::Pair as Pair>::verify(&entry.signature, &entry.message, &entry.public)
+	}
+	/// Whether the signatures of all `entries` are valid, batch-verified if the scheme supports it.
+	fn verify_all(entries: &[batch::Entry<Self>]) -> bool {
+		entries.iter().all(Self::verify_entry)
+	}
+	/// The sr25519 pair of the secret `suri`, the only scheme with a VRF.
+	fn vrf_pair(_suri: &str, _password: Option<&str>) -> Result<sr25519::Pair, Error> {
+		Err(Error::Static(VRF_UNSUPPORTED))
+	}
+	/// The sr25519 public key of `uri`, the only scheme with a VRF.
+	fn vrf_public(_uri: &str) -> Result<sr25519::Public, Error> {
+		Err(Error::Static(VRF_UNSUPPORTED))
+	}
 	fn ss58_from_pair(pair: &Self::Pair) -> String where
 		<Self::Pair as Pair>::Public: PublicT,
 	{
@@ -73,28 +181,82 @@ trait Crypto: Sized {
 	fn public_from_pair(pair: &Self::Pair) -> Self::Public {
 		pair.public()
 	}
-	fn print_from_uri(
-		uri: &str,
+	/// Derive the public key of `uri`, as printed by `print_from_uri`.
+	fn public_from_uri(uri: &str, password: Option<&str>) -> Option<Self::Public> {
+		if let Some((pair, _)) = Self::pair_from_raw_seed(uri) {
+			Some(Self::public_from_pair(&pair))
+		} else if let Ok((pair, _)) = Self::Pair::from_phrase(uri, password) {
+			Some(Self::public_from_pair(&pair))
+		} else if let Ok((pair, _)) = Self::Pair::from_string_with_seed(uri, password) {
+			Some(Self::public_from_pair(&pair))
+		} else {
+			<Self::Pair as Pair>::Public::from_string_with_version(uri).ok().map(|(public, _)| public)
+		}
+	}
+
+	/// The `0x`-prefixed hex account ID of a secret or public URI.
+	fn account_id_from_uri(uri: &str, password: Option<&str>) -> Result<String, Error> where
+		<Self::Pair as Pair>::Public: PublicT,
+	{
+		Self::public_from_uri(uri, password)
+			.map(format_account_id::<Self>)
+			.ok_or(Error::Static("Invalid URI; expecting either a secret URI or a public URI."))
+	}
+
+	/// The keys of a secret phrase as a JSON object.
+	fn phrase_json(
+		phrase: &str,
 		password: Option<&str>,
-		network_override: Option<Ss58AddressFormat>,
-		output: OutputType,
-	) where
+		network: Ss58AddressFormat,
+		show_entropy: bool,
+	) -> Result<serde_json::Value, Error> where
 		<Self::Pair as Pair>::Public: PublicT,
 	{
+		let (pair, seed) = Self::Pair::from_phrase(phrase, password)
+			.map_err(|_| Error::Static("Invalid phrase"))?;
+		let public_key = Self::public_from_pair(&pair);
+
+		let mut json = json!({
+			"secretPhrase": phrase,
+			"networkId": String::from(network),
+			"secretSeed": format_seed::<Self>(seed),
+			"publicKey": format_public_key::<Self>(public_key.clone()),
+			"accountId": format_account_id::<Self>(public_key),
+			"ss58Address": Self::ss58_from_pair(&pair),
+		});
+		if let Some(entropy) = phrase_entropy(phrase, show_entropy)? {
+			json["entropy"] = entropy.into();
+		}
+
+		Ok(json)
+	}
+
+	/// Print the keys of `uri`, or fail if it is neither a phrase, a secret URI nor a public URI.
+	///
+	/// Secrets are only printed if `options.allow_secrets` is set, public URIs are always printed.
+	fn print_from_uri(uri: &str, options: &PrintOptions) -> Result<(), Error> where
+		<Self::Pair as Pair>::Public: PublicT,
+	{
+		let PrintOptions {
+			password,
+			network: network_override,
+			output,
+			show_entropy,
+			allow_secrets,
+		} = *options;
 		let v = network_override.unwrap_or_default();
+		let secret = match Self::pair_from_raw_seed(uri) {
+			Some((pair, seed)) => Ok((pair, Some(seed))),
+			None => Self::Pair::from_string_with_seed(uri, password),
+		};
 		if let Ok((pair, seed)) = Self::Pair::from_phrase(uri, password) {
+			check_secrets_allowed(allow_secrets)?;
 			let public_key = Self::public_from_pair(&pair);
+			let entropy = phrase_entropy(uri, show_entropy)?;
 
 			match output {
 				OutputType::Json => {
-					let json = json!({
-						"secretPhrase": uri,
-						"networkId": String::from(v),
-						"secretSeed": format_seed::<Self>(seed),
-						"publicKey": format_public_key::<Self>(public_key.clone()),
-						"accountId": format_account_id::<Self>(public_key),
-						"ss58Address": Self::ss58_from_pair(&pair),
-					});
+					let json = Self::phrase_json(uri, password, v, show_entropy)?;
 					println!("{}", serde_json::to_string_pretty(&json).expect("Json pretty print failed"));
 				},
 				OutputType::Text => {
@@ -111,14 +273,18 @@ trait Crypto: Sized {
 						format_account_id::<Self>(public_key),
 						Self::ss58_from_pair(&pair),
 					);
+					if let Some(entropy) = entropy {
+						println!("  Entropy:            {}", entropy);
+					}
 				},
 			}
-		} else if let Ok((pair, seed)) = Self::Pair::from_string_with_seed(uri, password) {
+		} else if let Ok((pair, seed)) = secret {
+			check_secrets_allowed(allow_secrets)?;
 			let public_key = Self::public_from_pair(&pair);
 
 			match output {
 				OutputType::Json => {
-					let json = json!({
+					let mut json = json!({
 						"secretKeyUri": uri,
 						"networkId": String::from(v),
 						"secretSeed": if let Some(seed) = seed { format_seed::<Self>(seed) } else { "n/a".into() },
@@ -126,6 +292,9 @@ trait Crypto: Sized {
 						"accountId": format_account_id::<Self>(public_key),
 						"ss58Address": Self::ss58_from_pair(&pair),
 					});
+					if show_entropy {
+						json["entropy"] = "n/a".into();
+					}
 					println!("{}", serde_json::to_string_pretty(&json).expect("Json pretty print failed"));
 				},
 				OutputType::Text => {
@@ -142,22 +311,32 @@ trait Crypto: Sized {
 						format_account_id::<Self>(public_key),
 						Self::ss58_from_pair(&pair),
 					);
+					if show_entropy {
+						println!("  Entropy:            n/a");
+					}
 				},
 			}
 		} else if let Ok((public_key, v)) =
 			<Self::Pair as Pair>::Public::from_string_with_version(uri)
 		{
+			let detected = detect_network::<Self>(uri);
 			let v = network_override.unwrap_or(v);
 
 			match output {
 				OutputType::Json => {
-					let json = json!({
+					let mut json = json!({
 						"publicKeyUri": uri,
 						"networkId": String::from(v),
 						"publicKey": format_public_key::<Self>(public_key.clone()),
 						"accountId": format_account_id::<Self>(public_key.clone()),
 						"ss58Address": public_key.to_ss58check_with_version(v),
 					});
+					if let Some(detected) = detected {
+						json["detectedNetwork"] = json!({
+							"name": String::from(detected),
+							"prefix": u8::from(detected),
+						});
+					}
 					println!("{}", serde_json::to_string_pretty(&json).expect("Json pretty print failed"));
 				},
 				OutputType::Text => {
@@ -172,11 +351,16 @@ trait Crypto: Sized {
 						format_account_id::<Self>(public_key.clone()),
 						public_key.to_ss58check_with_version(v),
 					);
+					if let Some(detected) = detected {
+						println!("  Detected network:   {} ({})", String::from(detected), u8::from(detected));
+					}
 				},
 			}
 		} else {
-			eprintln!("Invalid phrase/URI given");
+			return Err(Error::Static("Invalid phrase/URI given"));
 		}
+
+		Ok(())
 	}
 }
 
@@ -189,6 +373,14 @@ impl Crypto for Ed25519 {
 	fn pair_from_suri(suri: &str, password_override: Option<&str>) -> Self::Pair {
 		ed25519::Pair::from_legacy_string(suri, password_override)
 	}
+
+	fn verify_all(entries: &[batch::Entry<Self>]) -> bool {
+		ed25519::verify_batch(
+			entries.iter().map(|entry| &entry.message[..]).collect(),
+			entries.iter().map(|entry| &entry.signature).collect(),
+			entries.iter().map(|entry| &entry.public).collect(),
+		)
+	}
 }
 
 struct Sr25519;
@@ -196,6 +388,14 @@ struct Sr25519;
 impl Crypto for Sr25519 {
 	type Pair = sr25519::Pair;
 	type Public = sr25519::Public;
+
+	fn vrf_pair(suri: &str, password: Option<&str>) -> Result<sr25519::Pair, Error> {
+		Ok(Self::read_secret(suri, password))
+	}
+
+	fn vrf_public(uri: &str) -> Result<sr25519::Public, Error> {
+		Self::public_from_uri(uri, None).ok_or(Error::Static("Invalid phrase/URI given"))
+	}
 }
 
 struct Ecdsa;
@@ -237,10 +437,21 @@ fn get_usage() -> String {
 		-e, --ed25519 'Use Ed25519/BIP39 cryptography'
 		-k, --secp256k1 'Use SECP256k1/ECDSA/BIP39 cryptography'
 		-s, --sr25519 'Use Schnorr/Ristretto x25519/BIP39 cryptography'
+		[scheme] --scheme <scheme> 'The cryptography to use. One of sr25519, ed25519, ecdsa. \
+			Default is sr25519.'
 		[network] -n, --network <network> 'Specify a network. One of {}. Default is {}'
 		[password] -p, --password <password> 'The password for the key'
 		--password-interactive 'You will be prompted for the password for the key.'
-		[output] -o, --output <output> 'Specify an output format. One of text, json. Default is text.'
+		[output] -o, --output <output> 'Specify the output format of every command. One of text, json. \
+			Default is text.'
+		--show-entropy 'Also print the BIP39 entropy of secret phrases, as hex.'
+		--allow-secret-redirect 'Print secrets even if the output is not a terminal.'
+		--account-id-only 'Only print the hex-encoded account ID of the key, for scripts. Applies to \
+			inspect and moduleid.'
+		[junction] --junction <junction> 'A derivation path, like //stash or /hot, appended to the \
+			URIs read by inspect, sign, verify, sign-transaction and insert.'
+		[metadata] --metadata <metadata> 'A file with the SCALE or hex-encoded runtime metadata, \
+			used to check and name the calls of transactions.'
 	", networks, default_network)
 }
 
@@ -256,7 +467,12 @@ fn get_app<'a, 'b>(usage: &'a str) -> App<'a, 'b> {
 				.args_from_usage("[words] -w, --words <words> \
 						'The number of words in the phrase to generate. One of 12 \
 						(default), 15, 18, 21 and 24.'
-				"),
+					[count] --count <count> 'The number of independent phrases to generate, \
+						printed as a JSON array or a numbered list.'
+				")
+				.arg(seed_rng_arg("TEST ONLY, INSECURE: generate the key from a random \
+					number generator seeded with these 32 hex-encoded bytes, so the same seed \
+					always gives the same key. Never use it for real keys.")),
 			SubCommand::with_name("generate-node-key")
 				.about("Generate a random node libp2p key, save it to file and print its peer ID")
 				.args_from_usage("[file] 'Name of file to save secret key to'"),
@@ -266,6 +482,8 @@ fn get_app<'a, 'b>(usage: &'a str) -> App<'a, 'b> {
 						secret URI (with derivation paths and password), SS58 or public URI. \
 						If the value is a file, the file content is used as URI. \
 						If not given, you will be prompted for the URI.'
+					[expected-pubkey] --expected-pubkey <expected-pubkey> 'Fail unless the public key of the URI \
+						is the given hex-encoded public key.'
 				"),
 			SubCommand::with_name("inspect-node-key")
 				.about("Print the peer ID corresponding to the node key in the given file")
@@ -286,7 +504,16 @@ fn get_app<'a, 'b>(usage: &'a str) -> App<'a, 'b> {
 					-n, --nonce <nonce> 'The nonce.'
 					-p, --password <password> 'The password for the key.'
 					-h, --prior-block-hash <prior-block-hash> 'The prior block hash, hex-encoded.'
-					-s, --suri <suri> 'The secret key URI.'
+					[suri] -s, --suri <suri> 'The secret key URI. \
+						If the value is a file, the file content is used as URI. \
+						If not given, you will be prompted for the URI.'
+					[count] --count <count> 'The number of transactions to sign, with consecutive \
+						nonces starting at the given nonce. Default is 1.'
+				"),
+			SubCommand::with_name("decode-extrinsic")
+				.about("Decode a hex-encoded UncheckedExtrinsic, as returned by sign-transaction")
+				.args_from_usage("
+					<extrinsic> 'The extrinsic, hex-encoded.'
 				"),
 			SubCommand::with_name("transfer")
 				.about("Author and sign a Node pallet_balances::Transfer transaction with a given (secret) key")
@@ -303,7 +530,10 @@ fn get_app<'a, 'b>(usage: &'a str) -> App<'a, 'b> {
 				.args_from_usage("
 					-n, --number <number> 'Number of keys to generate'
 					<pattern> 'Desired pattern'
-				"),
+				")
+				.arg(seed_rng_arg("TEST ONLY, INSECURE: search from a random number generator \
+					seeded with these 32 hex-encoded bytes, so the same seed always gives the same \
+					key. Never use it for real keys.")),
 			SubCommand::with_name("verify")
 				.about("Verify a signature for a message, provided on STDIN, with a given \
 						(public or secret) key")
@@ -314,6 +544,34 @@ fn get_app<'a, 'b>(usage: &'a str) -> App<'a, 'b> {
 						If the value is a file, the file content is used as URI. \
 						If not given, you will be prompted for the URI.'
 				"),
+			SubCommand::with_name("batch-verify")
+				.about("Verify many signatures at once, batch-verified for ed25519, and print the \
+						indices of the invalid ones")
+				.args_from_usage("
+					<file> 'A JSON array of objects with the `public`, `message` and `signature` fields, \
+						or CSV lines of the same values. Messages and signatures are hex-encoded, \
+						public keys hex-encoded or SS58.'
+				"),
+			SubCommand::with_name("vrf-sign")
+				.about("Evaluate the VRF of an sr25519 (secret) key on a message, provided on STDIN, \
+						and print its output and proof")
+				.args_from_usage("
+					-h, --hex 'The message on STDIN is hex-encoded data'
+					[context] --context <context> 'The signing context of the VRF. Default is substrate.'
+					<suri> 'The secret key URI. \
+						If the value is a file, the file content is used as URI.'
+				"),
+			SubCommand::with_name("vrf-verify")
+				.about("Verify the VRF output and proof for a message, provided on STDIN, with a given \
+						sr25519 (public or secret) key")
+				.args_from_usage("
+					-h, --hex 'The message on STDIN is hex-encoded data'
+					[context] --context <context> 'The signing context of the VRF. Default is substrate.'
+					<vrf-output> 'VRF output, hex-encoded.'
+					<proof> 'VRF proof, hex-encoded.'
+					<uri> 'The public or secret key URI. \
+						If the value is a file, the file content is used as URI.'
+				"),
 			SubCommand::with_name("insert")
 				.about("Insert a key to the keystore of a node")
 				.args_from_usage("
@@ -322,12 +580,31 @@ fn get_app<'a, 'b>(usage: &'a str) -> App<'a, 'b> {
 						If not given, you will be prompted for the URI.'
 					<key-type> 'Key type, examples: \"gran\", or \"imon\" '
 					[node-url] 'Node JSON-RPC endpoint, default \"http:://localhost:9933\"'
+					[in-memory] --in-memory 'Insert the key into an in-memory keystore instead of \
+						the keystore of a node, check that it can be read back and print its \
+						public key. Nothing is written to disk, for tests. `subkey interactive` \
+						keeps the keystore between its `insert` and `has-key` commands.'
+					[keystore-path] --keystore-path <keystore-path> 'Insert the key straight into the \
+						keystore directory at this path instead of through the node RPC, for nodes \
+						that are not started yet.'
+				"),
+			SubCommand::with_name("has-key")
+				.about("Check that the keystore directory at a path holds a key")
+				.args_from_usage("
+					<public> 'The public key, hex-encoded, or a public URI.'
+					<key-type> 'Key type, examples: \"gran\", or \"imon\" '
+					--keystore-path <keystore-path> 'The keystore directory to look into.'
 				"),
 			SubCommand::with_name("moduleid")
 				.about("Inspect a module ID address")
 				.args_from_usage("
 					<id> 'The module ID used to derive the account'
-				")
+				"),
+			SubCommand::with_name("list-networks")
+				.about("List the network names accepted by --network, with their SS58 prefixes"),
+			SubCommand::with_name("interactive")
+				.about("Run inspect, verify, ss58-convert, insert and has-key commands read line \
+						by line, until `exit` or the end of the input"),
 		])
 }
 
@@ -335,13 +612,11 @@ fn main() -> Result<(), Error> {
 	let usage = get_usage();
 	let matches = get_app(&usage).get_matches();
 
-	if matches.is_present("ed25519") {
-		return execute::<Ed25519>(matches);
+	match crypto_scheme(&matches)? {
+		CryptoScheme::Sr25519 => execute::<Sr25519>(matches),
+		CryptoScheme::Ed25519 => execute::<Ed25519>(matches),
+		CryptoScheme::Ecdsa => execute::<Ecdsa>(matches),
 	}
-	if matches.is_present("secp256k1") {
-		return execute::<Ecdsa>(matches)
-	}
-	return execute::<Sr25519>(matches)
 }
 
 /// Get `URI` from CLI or prompt the user.
@@ -367,6 +642,44 @@ fn get_uri(match_name: &str, matches: &ArgMatches) -> Result<String, Error> {
 	Ok(uri)
 }
 
+/// Check that `junction` is a derivation path: one or more `/soft` or `//hard` junctions.
+fn check_junction(junction: &str) -> Result<(), Error> {
+	let invalid = || Error::Formatted(format!(
+		"Invalid junction `{}`: expected a derivation path like `//stash` or `/hot`",
+		junction,
+	));
+
+	let mut rest = junction;
+	if rest.is_empty() {
+		return Err(invalid());
+	}
+	while !rest.is_empty() {
+		rest = if rest.starts_with("//") {
+			&rest[2..]
+		} else if rest.starts_with('/') {
+			&rest[1..]
+		} else {
+			return Err(invalid());
+		};
+		let end = rest.find('/').unwrap_or(rest.len());
+		// Also rejects `///`, which would start a password.
+		if end == 0 {
+			return Err(invalid());
+		}
+		rest = &rest[end..];
+	}
+
+	Ok(())
+}
+
+/// Append `junction` to the derivation path of `uri`, keeping a `///password` last.
+fn append_junction(uri: &str, junction: &str) -> String {
+	match uri.find("///") {
+		Some(password) => format!("{}{}{}", &uri[..password], junction, &uri[password..]),
+		None => format!("{}{}", uri, junction),
+	}
+}
+
 #[derive(derive_more::Display, derive_more::From)]
 enum Error {
 	Static(&'static str),
@@ -380,6 +693,36 @@ impl fmt::Debug for Error {
 	}
 }
 
+/// The network embedded in an SS58 public URI, or `None` for a hex-encoded public key.
+fn detect_network<C: Crypto>(uri: &str) -> Option<Ss58AddressFormat> {
+	if uri.starts_with("0x") {
+		return None;
+	}
+
+	PublicOf::<C>::from_string_with_version(uri).ok().map(|(_, network)| network)
+}
+
+/// The hex-encoded BIP39 entropy of `phrase`, if it should be shown.
+fn phrase_entropy(phrase: &str, show_entropy: bool) -> Result<Option<String>, Error> {
+	if !show_entropy {
+		return Ok(None);
+	}
+
+	let mnemonic = Mnemonic::from_phrase(phrase, Language::English)
+		.map_err(|e| Error::Formatted(format!("Invalid phrase: {}", e)))?;
+	Ok(Some(format!("0x{}", HexDisplay::from(&mnemonic.entropy()))))
+}
+
+/// Fail unless printing secrets is allowed.
+fn check_secrets_allowed(allowed: bool) -> Result<(), Error> {
+	if allowed {
+		Ok(())
+	} else {
+		Err(Error::Static("Refusing to print secrets as the output is not a terminal, \
+			pass `--allow-secret-redirect` to print them anyway."))
+	}
+}
+
 fn static_err(msg: &'static str) -> Result<(), Error> {
 	Err(Error::Static(msg))
 }
@@ -423,10 +766,73 @@ where
 		None => OutputType::Text,
 	 };
 
+	let show_entropy = matches.is_present("show-entropy");
+	let account_id_only = matches.is_present("account-id-only");
+	// Secrets redirected to a file easily end up in logs.
+	let allow_secret_redirect = matches.is_present("allow-secret-redirect");
+	let allow_secrets = allow_secret_redirect || atty::is(atty::Stream::Stdout);
+	let allow_secrets_on_stderr = allow_secret_redirect || atty::is(atty::Stream::Stderr);
+
+	let print_options = PrintOptions {
+		password,
+		network: maybe_network,
+		output,
+		show_entropy,
+		allow_secrets,
+	};
+
+	let junction = matches.value_of("junction");
+	if let Some(junction) = junction {
+		check_junction(junction)?;
+	}
+	// The URI of an argument with the junction applied.
+	let read_uri = |name: &str, matches: &ArgMatches| -> Result<String, Error> {
+		let uri = get_uri(name, matches)?;
+		Ok(match junction {
+			Some(junction) => {
+				let uri = append_junction(&uri, junction);
+				// The URI may be a secret.
+				if allow_secrets_on_stderr {
+					eprintln!("Using the URI `{}`", uri);
+				}
+				uri
+			},
+			None => uri,
+		})
+	};
+
+	let metadata = match matches.value_of("metadata") {
+		Some(path) => Some(metadata::Metadata::from_file(path.as_ref())?),
+		None => None,
+	};
+
 	match matches.subcommand() {
+		("generate", Some(_)) | ("vanity", Some(_)) if account_id_only => {
+			return static_err("`--account-id-only` would drop the secret of the generated key, \
+				use it with `inspect` instead.");
+		}
 		("generate", Some(matches)) => {
-			let mnemonic = generate_mnemonic(matches)?;
-			C::print_from_uri(mnemonic.phrase(), password, maybe_network, output);
+			let mnemonics = generate_mnemonics(matches)?;
+
+			match (output, matches.is_present("count")) {
+				(_, false) => C::print_from_uri(mnemonics[0].phrase(), &print_options)?,
+				(OutputType::Json, true) => {
+					check_secrets_allowed(allow_secrets)?;
+					let json = mnemonics.iter()
+						.map(|mnemonic| C::phrase_json(
+							mnemonic.phrase(),
+							password,
+							maybe_network.unwrap_or_default(),
+							show_entropy,
+						))
+						.collect::<Result<Vec<_>, _>>()?;
+					println!("{}", serde_json::to_string_pretty(&json).expect("Json pretty print failed"));
+				},
+				(OutputType::Text, true) => for (index, mnemonic) in mnemonics.iter().enumerate() {
+					print!("{}. ", index + 1);
+					C::print_from_uri(mnemonic.phrase(), &print_options)?;
+				},
+			}
 		}
 		("generate-node-key", Some(matches)) => {
 			let file = matches.value_of("file").ok_or(Error::Static("Output file name is required"))?;
@@ -437,10 +843,19 @@ where
 
 			fs::write(file, secret.as_ref())?;
 
-			println!("{}", peer_id);
+			println!("{}", format_value(output, "peerId", &peer_id.to_string()));
 		}
 		("inspect", Some(matches)) => {
-			C::print_from_uri(&get_uri("uri", &matches)?, password, maybe_network, output);
+			let uri = read_uri("uri", matches)?;
+			if account_id_only {
+				println!("{}", C::account_id_from_uri(&uri, password)?);
+			} else {
+				C::print_from_uri(&uri, &print_options)?;
+			}
+
+			if let Some(expected) = matches.value_of("expected-pubkey") {
+				check_public_key::<C>(&uri, password, expected)?;
+			}
 		}
 		("inspect-node-key", Some(matches)) => {
 			let file = matches.value_of("file").ok_or(Error::Static("Input file name is required"))?;
@@ -451,36 +866,86 @@ where
 			let keypair = libp2p_ed25519::Keypair::from(secret);
 			let peer_id = PublicKey::Ed25519(keypair.public()).into_peer_id();
 
-			println!("{}", peer_id);
+			println!("{}", format_value(output, "peerId", &peer_id.to_string()));
 		}
 		("sign", Some(matches)) => {
-			let suri = get_uri("suri", &matches)?;
+			let suri = read_uri("suri", matches)?;
 			let should_decode = matches.is_present("hex");
 
 			let message = read_message_from_stdin(should_decode)?;
 			let signature = do_sign::<C>(&suri, message, password)?;
-			println!("{}", signature);
+			println!("{}", format_value(output, "signature", &signature));
 		}
 		("verify", Some(matches)) => {
-			let uri = get_uri("uri", &matches)?;
+			let uri = read_uri("uri", matches)?;
 			let should_decode = matches.is_present("hex");
 
 			let message = read_message_from_stdin(should_decode)?;
 			let is_valid_signature = do_verify::<C>(matches, &uri, message)?;
 			if is_valid_signature {
-				println!("Signature verifies correctly.");
+				match output {
+					OutputType::Json => println!("{}", json!({ "valid": true })),
+					OutputType::Text => println!("Signature verifies correctly."),
+				}
 			} else {
 				return static_err("Signature invalid.");
 			}
 		}
+		("batch-verify", Some(matches)) => {
+			let file = matches.value_of("file").expect("file is required; qed");
+			let entries = batch::parse_entries::<C>(&fs::read_to_string(file)?)?;
+			let invalid = batch::invalid_entries::<C>(&entries);
+
+			match output {
+				OutputType::Json => {
+					let json = json!({ "total": entries.len(), "invalid": invalid });
+					println!("{}", serde_json::to_string_pretty(&json).expect("Json pretty print failed"));
+				},
+				OutputType::Text => {
+					for index in &invalid {
+						println!("Entry {}: signature invalid.", index);
+					}
+					println!("{} of {} signatures verify correctly.", entries.len() - invalid.len(), entries.len());
+				},
+			}
+			if !invalid.is_empty() {
+				return static_err("Some signatures are invalid.");
+			}
+		}
+		("vrf-sign", Some(matches)) => {
+			let pair = C::vrf_pair(&read_uri("suri", matches)?, password)?;
+			let context = matches.value_of("context").unwrap_or(DEFAULT_VRF_CONTEXT);
+
+			let message = read_message_from_stdin(matches.is_present("hex"))?;
+			let (vrf_output, proof) = pair.vrf_sign(context.as_bytes(), &message);
+			print_vrf(output, &vrf_output, &proof);
+		}
+		("vrf-verify", Some(matches)) => {
+			let public = C::vrf_public(&read_uri("uri", matches)?)?;
+			let context = matches.value_of("context").unwrap_or(DEFAULT_VRF_CONTEXT);
+			let mut vrf_output = [0; sr25519::VRF_OUTPUT_LENGTH];
+			decode_fixed(matches, "vrf-output", &mut vrf_output)?;
+			let mut proof = [0; sr25519::VRF_PROOF_LENGTH];
+			decode_fixed(matches, "proof", &mut proof)?;
+
+			let message = read_message_from_stdin(matches.is_present("hex"))?;
+			if sr25519::Pair::vrf_verify(&public, context.as_bytes(), &message, &vrf_output, &proof) {
+				match output {
+					OutputType::Json => println!("{}", json!({ "valid": true })),
+					OutputType::Text => println!("VRF output and proof verify correctly."),
+				}
+			} else {
+				return static_err("VRF output or proof invalid.");
+			}
+		}
 		("vanity", Some(matches)) => {
 			let desired: String = matches
 				.value_of("pattern")
 				.map(str::to_string)
 				.unwrap_or_default();
-			let result = vanity::generate_key::<C>(&desired)?;
+			let result = vanity::generate_key::<C>(&desired, &mut *key_rng(matches)?)?;
 			let formated_seed = format_seed::<C>(result.seed);
-			C::print_from_uri(&formated_seed, None, maybe_network, output);
+			C::print_from_uri(&formated_seed, &PrintOptions { password: None, ..print_options })?;
 		}
 		("transfer", Some(matches)) => {
 			let signer = read_pair::<C>(matches.value_of("from"), password)?;
@@ -491,34 +956,111 @@ where
 			let amount = read_required_parameter::<Balance>(matches, "amount")?;
 			let function = Call::Balances(BalancesCall::transfer(to.into(), amount));
 
-			let extrinsic = create_extrinsic::<C>(function, index, signer, genesis_hash);
+			let extrinsic = ExtrinsicBuilder::<C>::new()
+				.call(function)
+				.nonce(index)
+				.signer(signer)
+				.genesis_hash(genesis_hash)
+				.build()?;
 
-			print_extrinsic(extrinsic);
+			let extrinsic = format!("0x{}", HexDisplay::from(&extrinsic.encode()));
+			println!("{}", format_value(output, "extrinsic", &extrinsic));
 		}
 		("sign-transaction", Some(matches)) => {
-			let signer = read_pair::<C>(matches.value_of("suri"), password)?;
+			let suri = read_uri("suri", matches)?;
+			let signer = read_pair::<C>(Some(&suri), password)?;
 			let index = read_required_parameter::<Index>(matches, "nonce")?;
 			let genesis_hash = read_genesis_hash(matches)?;
 
+			let count = match matches.value_of("count") {
+				Some(count) => u32::from_str(count)
+					.ok()
+					.filter(|count| *count > 0)
+					.ok_or(Error::Static("Invalid `count' parameter; expecting a positive integer."))?,
+				None => 1,
+			};
+
 			let call = matches.value_of("call").expect("call is required; qed");
 			let function: Call = hex::decode(&call)
 				.ok()
 				.and_then(|x| Decode::decode(&mut &x[..]).ok())
 				.unwrap();
 
-			let extrinsic = create_extrinsic::<C>(function, index, signer, genesis_hash);
+			if let Some(metadata) = &metadata {
+				let encoded = function.encode();
+				if metadata.call_name(&encoded).is_none() {
+					return Err(Error::Formatted(format!(
+						"The call (module index {}, call index {}) is not in the given metadata",
+						encoded[0],
+						encoded[1],
+					)));
+				}
+			}
+
+			let extrinsics = (0..count)
+				.map(|i| {
+					let index = index.checked_add(i)
+						.ok_or(Error::Static("The nonce overflows for the given count."))?;
+					ExtrinsicBuilder::<C>::new()
+						.call(function.clone())
+						.nonce(index)
+						.signer(signer.clone())
+						.genesis_hash(genesis_hash)
+						.build()
+				})
+				.collect::<Result<Vec<_>, Error>>()?;
 
-			print_extrinsic(extrinsic);
+			match output {
+				OutputType::Json => {
+					let json = extrinsics.iter()
+						.map(|extrinsic| format!("0x{}", HexDisplay::from(&extrinsic.encode())))
+						.collect::<Vec<_>>();
+					println!("{}", serde_json::to_string_pretty(&json).expect("Json pretty print failed"));
+				},
+				OutputType::Text => extrinsics.into_iter().for_each(print_extrinsic),
+			}
+		}
+		("decode-extrinsic", Some(matches)) => {
+			let extrinsic = matches.value_of("extrinsic").expect("extrinsic is required; qed");
+			print_decoded_extrinsic(&decode_extrinsic(extrinsic)?, metadata.as_ref(), output)?;
 		}
 		("insert", Some(matches)) => {
-			let suri = get_uri("suri", &matches)?;
+			let suri = read_uri("suri", matches)?;
 			let pair = read_pair::<C>(Some(&suri), password)?;
 			let node_url = matches.value_of("node-url").unwrap_or("http://localhost:9933");
 			let key_type = matches.value_of("key-type").ok_or(Error::Static("Key type id is required"))?;
 
-			// Just checking
-			let _key_type_id = sp_core::crypto::KeyTypeId::try_from(key_type)
-				.map_err(|_| Error::Static("Cannot convert argument to keytype: argument should be 4-character string"))?;
+			let key_type_id = parse_key_type(key_type)?;
+
+			let targets = ["node-url", "in-memory", "keystore-path"].iter()
+				.filter(|target| matches.is_present(target))
+				.count();
+			if targets > 1 {
+				return Err(Error::Static(
+					"Give only one of the node URL, --in-memory and --keystore-path",
+				));
+			}
+
+			if let Some(path) = matches.value_of("keystore-path") {
+				let public = pair.public();
+				let file = insert_into_local_keystore(
+					path,
+					password,
+					key_type_id,
+					&suri,
+					public.as_ref(),
+				)?;
+				println!("Inserted the {} key into {}", key_type, file.display());
+				return Ok(());
+			}
+
+			if matches.is_present("in-memory") {
+				let store = sc_keystore::Store::new_in_memory();
+				let public = pair.public();
+				insert_into_keystore(&mut *store.write(), key_type_id, &suri, public.as_ref())?;
+				println!("0x{}", HexDisplay::from(&public.as_ref()));
+				return Ok(());
+			}
 
 			let rpc = rpc::RpcClient::new(node_url.to_string());
 
@@ -528,6 +1070,23 @@ where
 				sp_core::Bytes(pair.public().as_ref().to_vec()),
 			);
 		}
+		("has-key", Some(matches)) => {
+			let public = matches.value_of("public").expect("public is required; qed");
+			let key_type = matches.value_of("key-type").expect("key-type is required; qed");
+			let path = matches.value_of("keystore-path").expect("keystore-path is required; qed");
+			let public = parse_public_key::<C>(public)?;
+			let key_type = parse_key_type(key_type)?;
+
+			if !Path::new(path).is_dir() {
+				return Err(Error::Formatted(format!("There is no keystore at {}", path)));
+			}
+			let store = open_local_keystore(path, password)?;
+			if has_key(&*store.read(), key_type, public.as_ref()) {
+				println!("The keystore holds the key.");
+			} else {
+				return Err(Error::Static("The keystore doesn't hold the key."));
+			}
+		}
 		("moduleid", Some(matches)) => {
 			let id = get_uri("id", &matches)?;
 			if id.len() != 8 {
@@ -540,7 +1099,15 @@ where
 			let account_id: AccountId = ModuleId(id_fixed_array).into_account();
 			let v = maybe_network.unwrap_or(Ss58AddressFormat::SubstrateAccount);
 
-			C::print_from_uri(&account_id.to_ss58check_with_version(v), password, maybe_network, output);
+			if account_id_only {
+				println!("0x{}", HexDisplay::from(&account_id.as_ref()));
+			} else {
+				C::print_from_uri(&account_id.to_ss58check_with_version(v), &print_options)?;
+			}
+		}
+		("list-networks", Some(_)) => print_networks(output),
+		("interactive", Some(_)) => {
+			interactive::run::<C, _>(&print_options, stdin().lock())?;
 		}
 		_ => print_usage(&matches),
 	}
@@ -548,8 +1115,33 @@ where
 	Ok(())
 }
 
-/// Creates a new randomly generated mnemonic phrase.
-fn generate_mnemonic(matches: &ArgMatches) -> Result<Mnemonic, Error> {
+/// The `--seed-rng` argument, hidden from the help as it is only meant for tests.
+fn seed_rng_arg(help: &'static str) -> Arg<'static, 'static> {
+	Arg::with_name("seed-rng")
+		.long("seed-rng")
+		.value_name("hex-seed")
+		.takes_value(true)
+		.hidden(true)
+		.help(help)
+}
+
+/// The random number generator to generate keys with.
+///
+/// This is the OS generator, unless `--seed-rng` asks for a deterministic one for tests.
+fn key_rng(matches: &ArgMatches) -> Result<Box<dyn RngCore>, Error> {
+	if matches.value_of("seed-rng").is_none() {
+		return Ok(Box::new(OsRng));
+	}
+
+	let mut seed = <StdRng as SeedableRng>::Seed::default();
+	decode_fixed(matches, "seed-rng", &mut seed)?;
+	eprintln!("WARNING: `--seed-rng` is given, the generated keys are NOT random. Use them for tests only.");
+
+	Ok(Box::new(StdRng::from_seed(seed)))
+}
+
+/// Creates `--count` new randomly generated mnemonic phrases, each from its own entropy.
+fn generate_mnemonics(matches: &ArgMatches) -> Result<Vec<Mnemonic>, Error> {
 	let words = match matches.value_of("words") {
 		Some(words) => {
 			let num = usize::from_str(words).map_err(|_| Error::Static("Invalid number given for --words"))?;
@@ -558,7 +1150,21 @@ fn generate_mnemonic(matches: &ArgMatches) -> Result<Mnemonic, Error> {
 		},
 		None => MnemonicType::Words12,
 	};
-	Ok(Mnemonic::new(words, Language::English))
+	let count = match matches.value_of("count") {
+		Some(count) => usize::from_str(count)
+			.ok()
+			.filter(|count| *count > 0)
+			.ok_or(Error::Static("Invalid number given for --count, expecting a positive number"))?,
+		None => 1,
+	};
+
+	let mut rng = key_rng(matches)?;
+	(0..count).map(|_| {
+		let mut entropy = vec![0; words.entropy_bits() / 8];
+		rng.fill_bytes(&mut entropy);
+		Mnemonic::from_entropy(&entropy, Language::English)
+			.map_err(|e| Error::Formatted(format!("Invalid entropy: {}", e)))
+	}).collect()
 }
 
 fn do_sign<C: Crypto>(suri: &str, message: Vec<u8>, password: Option<&str>) -> Result<String, Error>
@@ -582,8 +1188,25 @@ where
 	Ok(<<C as Crypto>::Pair as Pair>::verify(&signature, &message, &pubkey))
 }
 
+/// Check that the public key of `uri` is the hex-encoded `expected` public key.
+fn check_public_key<C: Crypto>(uri: &str, password: Option<&str>, expected: &str) -> Result<(), Error> {
+	let expected = decode_hex(expected.trim_start_matches("0x"))?;
+	let public_key = C::public_from_uri(uri, password)
+		.ok_or(Error::Static("Invalid phrase/URI given"))?;
+
+	if public_key.as_ref() != &expected[..] {
+		return Err(Error::Formatted(format!(
+			"Public key mismatch: expected 0x{}, derived {}",
+			HexDisplay::from(&expected),
+			format_public_key::<C>(public_key),
+		)));
+	}
+
+	Ok(())
+}
+
 fn decode_hex<T: AsRef<[u8]>>(message: T) -> Result<Vec<u8>, Error> {
-	hex::decode(message).map_err(|e| Error::Formatted(format!("Invalid hex ({})", e)))
+	hex_input::decode_hex(message).map_err(Error::Formatted)
 }
 
 fn read_message_from_stdin(should_decode: bool) -> Result<Vec<u8>, Error> {
@@ -630,6 +1253,15 @@ where
 	let sig_data = matches
 		.value_of("sig")
 		.expect("signature parameter is required; thus it can't be None; qed");
+	decode_signature::<C>(sig_data)
+}
+
+/// Decode a hex-encoded signature.
+fn decode_signature<C: Crypto>(sig_data: &str) -> Result<SignatureOf<C>, Error>
+where
+	SignatureOf<C>: SignatureT,
+	PublicOf<C>: PublicT,
+{
 	let mut signature = <<C as Crypto>::Pair as Pair>::Signature::default();
 	let sig_data = decode_hex(sig_data)?;
 	if sig_data.len() != signature.as_ref().len() {
@@ -648,17 +1280,33 @@ where
 	PublicOf<C>: PublicT,
 {
 	let uri = matched_uri.expect("parameter is required; thus it can't be None; qed");
+	parse_public_key::<C>(uri).expect("Invalid URI; expecting either a secret URI or a public URI.")
+}
+
+/// Parse a hex-encoded public key or a public URI.
+fn parse_public_key<C: Crypto>(uri: &str) -> Result<PublicOf<C>, Error>
+where
+	PublicOf<C>: PublicT,
+{
 	let uri = if uri.starts_with("0x") {
 		&uri[2..]
 	} else {
 		uri
 	};
 	if let Ok(pubkey_vec) = hex::decode(uri) {
-		<C as Crypto>::Public::from_slice(pubkey_vec.as_slice())
+		let mut public = PublicOf::<C>::default();
+		if pubkey_vec.len() != public.as_ref().len() {
+			return Err(Error::Formatted(format!(
+				"Invalid public key length: expected {} bytes, got {}",
+				public.as_ref().len(),
+				pubkey_vec.len(),
+			)));
+		}
+		public.as_mut().copy_from_slice(&pubkey_vec);
+		Ok(public)
 	} else {
 		<C as Crypto>::Public::from_string(uri)
-			.ok()
-			.expect("Invalid URI; expecting either a secret URI or a public URI.")
+			.map_err(|_| Error::Static("Invalid URI; expecting either a secret URI or a public URI."))
 	}
 }
 
@@ -686,7 +1334,71 @@ fn read_pair<C: Crypto>(
 	PublicOf<C>: PublicT,
 {
 	let suri = matched_suri.ok_or(Error::Static("parameter is required; thus it can't be None; qed"))?;
-	Ok(C::pair_from_suri(suri, password))
+	Ok(C::read_secret(suri, password))
+}
+
+/// Parse a 4-character key type, such as `gran`.
+fn parse_key_type(key_type: &str) -> Result<KeyTypeId, Error> {
+	KeyTypeId::try_from(key_type).map_err(|_| Error::Static(
+		"Cannot convert argument to keytype: argument should be 4-character string",
+	))
+}
+
+/// Whether `store` holds the key `public` under `key_type`.
+fn has_key(store: &dyn BareCryptoStore, key_type: KeyTypeId, public: &[u8]) -> bool {
+	store.has_keys(&[(public.to_vec(), key_type)])
+}
+
+/// Insert the key of `suri` into `store` under `key_type` and check that it can be read back.
+fn insert_into_keystore(
+	store: &mut dyn BareCryptoStore,
+	key_type: KeyTypeId,
+	suri: &str,
+	public: &[u8],
+) -> Result<(), Error> {
+	store.insert_unknown(key_type, suri, public)
+		.map_err(|_| Error::Static("Cannot insert the key into the keystore"))?;
+
+	if !has_key(store, key_type, public) {
+		return Err(Error::Static("The inserted key cannot be read back from the keystore"));
+	}
+	Ok(())
+}
+
+/// Open the keystore directory at `path` with the keystore `password`, creating it if it doesn't
+/// exist.
+fn open_local_keystore(
+	path: &str,
+	password: Option<&str>,
+) -> Result<sc_keystore::KeyStorePtr, Error> {
+	sc_keystore::Store::open(path, password.map(|password| password.to_string().into()))
+		.map_err(|e| Error::Formatted(format!("Cannot open the keystore at {}: {}", path, e)))
+}
+
+/// Insert the key of `suri` into the keystore directory at `path`, opened with the keystore
+/// `password`, returning the file written.
+fn insert_into_local_keystore(
+	path: &str,
+	password: Option<&str>,
+	key_type: KeyTypeId,
+	suri: &str,
+	public: &[u8],
+) -> Result<PathBuf, Error> {
+	let store = open_local_keystore(path, password)?;
+	insert_into_keystore(&mut *store.write(), key_type, suri, public)?;
+
+	let file = store.read().key_file_path(public, key_type);
+	Ok(file.expect("a keystore opened at a path has a file for every key; qed"))
+}
+
+/// The seed of `uri` if it is a raw seed: `0x` followed by the 64 hex digits of a 32 byte seed,
+/// without derivation paths or password.
+fn raw_seed(uri: &str) -> Option<Vec<u8>> {
+	if uri.len() == 66 && uri.starts_with("0x") {
+		hex::decode(&uri[2..]).ok()
+	} else {
+		None
+	}
 }
 
 fn format_signature<C: Crypto>(signature: &SignatureOf<C>) -> String {
@@ -707,41 +1419,138 @@ fn format_account_id<C: Crypto>(public_key: PublicOf<C>) -> String where
 	format!("0x{}", HexDisplay::from(&public_key.into_runtime().into_account().as_ref()))
 }
 
-fn create_extrinsic<C: Crypto>(
-	function: Call,
+/// Builder of signed extrinsics, wrapping `create_extrinsic`.
+///
+/// The call, signer and genesis hash are required. By default the extrinsic is immortal, with
+/// nonce 0 and no tip.
+struct ExtrinsicBuilder<C: Crypto> {
+	function: Option<Call>,
 	index: Index,
-	signer: C::Pair,
-	genesis_hash: H256,
-) -> UncheckedExtrinsic where
+	tip: Balance,
+	era: Era,
+	checkpoint: Option<H256>,
+	signer: Option<C::Pair>,
+	genesis_hash: Option<H256>,
+}
+
+impl<C: Crypto> ExtrinsicBuilder<C> where
 	PublicOf<C>: PublicT,
 	SignatureOf<C>: SignatureT,
 {
-	let extra = |i: Index, f: Balance| {
+	fn new() -> Self {
+		ExtrinsicBuilder {
+			function: None,
+			index: 0,
+			tip: 0,
+			era: Era::Immortal,
+			checkpoint: None,
+			signer: None,
+			genesis_hash: None,
+		}
+	}
+
+	fn call(mut self, function: Call) -> Self {
+		self.function = Some(function);
+		self
+	}
+
+	fn nonce(mut self, index: Index) -> Self {
+		self.index = index;
+		self
+	}
+
+	fn tip(mut self, tip: Balance) -> Self {
+		self.tip = tip;
+		self
+	}
+
+	/// Set the era of a mortal extrinsic, with the hash of the block the era starts at.
+	fn era(mut self, era: Era, checkpoint: H256) -> Self {
+		self.era = era;
+		self.checkpoint = Some(checkpoint);
+		self
+	}
+
+	fn signer(mut self, signer: C::Pair) -> Self {
+		self.signer = Some(signer);
+		self
+	}
+
+	fn genesis_hash(mut self, genesis_hash: H256) -> Self {
+		self.genesis_hash = Some(genesis_hash);
+		self
+	}
+
+	fn build(self) -> Result<UncheckedExtrinsic, Error> {
+		let function = self.function.ok_or(Error::Static("The extrinsic call is missing."))?;
+		let signer = self.signer.ok_or(Error::Static("The extrinsic signer is missing."))?;
+		let genesis_hash = self.genesis_hash
+			.ok_or(Error::Static("The genesis hash of the extrinsic is missing."))?;
+		// An immortal extrinsic is checked against the genesis block.
+		let checkpoint = self.checkpoint.unwrap_or(genesis_hash);
+
+		Ok(create_extrinsic::<C>(
+			function,
+			self.index,
+			signer,
+			genesis_hash,
+			self.era,
+			checkpoint,
+			self.tip,
+		))
+	}
+}
+
+/// The data signed along with the `SignedExtra` of an extrinsic, which isn't part of it.
+pub type AdditionalSigned = <SignedExtra as SignedExtension>::AdditionalSigned;
+
+/// Build the signed extensions of an extrinsic of the node runtime, with their additional signed
+/// data.
+///
+/// `checkpoint` is the hash of the block the era starts at, the genesis hash for an immortal
+/// extrinsic.
+pub fn signed_extra(
+	index: Index,
+	era: Era,
+	genesis_hash: Hash,
+	checkpoint: Hash,
+	tip: Balance,
+) -> (SignedExtra, AdditionalSigned) {
+	(
 		(
 			frame_system::CheckSpecVersion::<Runtime>::new(),
 			frame_system::CheckTxVersion::<Runtime>::new(),
 			frame_system::CheckGenesis::<Runtime>::new(),
-			frame_system::CheckEra::<Runtime>::from(Era::Immortal),
-			frame_system::CheckNonce::<Runtime>::from(i),
+			frame_system::CheckEra::<Runtime>::from(era),
+			frame_system::CheckNonce::<Runtime>::from(index),
 			frame_system::CheckWeight::<Runtime>::new(),
-			pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(f),
+			pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(tip),
 			pallet_grandpa::ValidateEquivocationReport::<Runtime>::new(),
-		)
-	};
-	let raw_payload = SignedPayload::from_raw(
-		function,
-		extra(index, 0),
+		),
 		(
 			VERSION.spec_version,
 			VERSION.transaction_version,
 			genesis_hash,
-			genesis_hash,
-			(),
-			(),
-			(),
-			(),
+			checkpoint,
+			(), (), (), (),
 		),
-	);
+	)
+}
+
+fn create_extrinsic<C: Crypto>(
+	function: Call,
+	index: Index,
+	signer: C::Pair,
+	genesis_hash: H256,
+	era: Era,
+	checkpoint: H256,
+	tip: Balance,
+) -> UncheckedExtrinsic where
+	PublicOf<C>: PublicT,
+	SignatureOf<C>: SignatureT,
+{
+	let (extra, additional) = signed_extra(index, era, genesis_hash, checkpoint, tip);
+	let raw_payload = SignedPayload::from_raw(function, extra, additional);
 	let signature = raw_payload.using_encoded(|payload| signer.sign(payload)).into_runtime();
 	let signer = signer.public().into_runtime();
 	let (function, extra, _) = raw_payload.deconstruct();
@@ -754,10 +1563,195 @@ fn create_extrinsic<C: Crypto>(
 	)
 }
 
+/// `value` alone as text, or as the `field` of a JSON object.
+fn format_value(output: OutputType, field: &str, value: &str) -> String {
+	match output {
+		OutputType::Json => serde_json::to_string_pretty(&json!({ field: value }))
+			.expect("Json pretty print failed"),
+		OutputType::Text => value.to_string(),
+	}
+}
+
 fn print_extrinsic(extrinsic: UncheckedExtrinsic) {
 	println!("0x{}", HexDisplay::from(&extrinsic.encode()));
 }
 
+/// `codec::Input` over a slice that keeps track of the number of bytes read.
+struct OffsetInput<'a> {
+	data: &'a [u8],
+	offset: usize,
+}
+
+impl<'a> codec::Input for OffsetInput<'a> {
+	fn remaining_len(&mut self) -> Result<Option<usize>, codec::Error> {
+		Ok(Some(self.data.len() - self.offset))
+	}
+
+	fn read(&mut self, into: &mut [u8]) -> Result<(), codec::Error> {
+		let end = self.offset + into.len();
+		if end > self.data.len() {
+			return Err("Not enough data to fill buffer".into());
+		}
+		into.copy_from_slice(&self.data[self.offset..end]);
+		self.offset = end;
+		Ok(())
+	}
+}
+
+/// Decode a hex-encoded extrinsic, reporting the byte offset at which decoding failed.
+fn decode_extrinsic(extrinsic: &str) -> Result<UncheckedExtrinsic, Error> {
+	let data = decode_hex(extrinsic.trim_start_matches("0x"))?;
+	let mut input = OffsetInput { data: &data, offset: 0 };
+
+	let extrinsic = UncheckedExtrinsic::decode(&mut input).map_err(|e| Error::Formatted(format!(
+		"Invalid extrinsic at byte offset {}: {}",
+		input.offset,
+		e,
+	)))?;
+	if input.offset != data.len() {
+		return Err(Error::Formatted(format!(
+			"Invalid extrinsic: unexpected trailing data at byte offset {}",
+			input.offset,
+		)));
+	}
+
+	Ok(extrinsic)
+}
+
+/// Read the era, the nonce and the tip from the signed extensions of an extrinsic.
+///
+/// The extensions don't expose their values, so they are decoded from the encoding of each one.
+fn era_nonce_and_tip(extra: &SignedExtra) -> Result<(Era, Index, Balance), Error> {
+	let invalid = |name, e: codec::Error| Error::Formatted(format!("Invalid {}: {}", name, e));
+	let era = Era::decode(&mut &extra.3.encode()[..]).map_err(|e| invalid("era", e))?;
+	let nonce = <codec::Compact<Index>>::decode(&mut &extra.4.encode()[..])
+		.map_err(|e| invalid("nonce", e))?;
+	let tip = <codec::Compact<Balance>>::decode(&mut &extra.6.encode()[..])
+		.map_err(|e| invalid("tip", e))?;
+	Ok((era, nonce.0, tip.0))
+}
+
+fn print_decoded_extrinsic(
+	extrinsic: &UncheckedExtrinsic,
+	metadata: Option<&metadata::Metadata>,
+	output: OutputType,
+) -> Result<(), Error> {
+	let encoded_call = extrinsic.function.encode();
+	let call = format!("0x{}", HexDisplay::from(&encoded_call));
+	let call_name = metadata
+		.and_then(|metadata| metadata.call_name(&encoded_call))
+		.map(|(module, call)| format!("{}.{}", module, call));
+
+	let signed = match &extrinsic.signature {
+		Some((address, _, extra)) => {
+			let signer = match address {
+				node_runtime::Address::Id(id) => id.to_ss58check(),
+				node_runtime::Address::Index(index) => format!("index {}", index),
+			};
+			let (era, nonce, tip) = era_nonce_and_tip(extra)?;
+			Some((signer, era, nonce, tip))
+		},
+		None => None,
+	};
+
+	match output {
+		OutputType::Json => {
+			let mut json = match signed {
+				Some((signer, era, nonce, tip)) => json!({
+					"signed": true,
+					"signer": signer,
+					"nonce": nonce,
+					"era": format!("{:?}", era),
+					"tip": tip.to_string(),
+					"call": call,
+				}),
+				None => json!({
+					"signed": false,
+					"call": call,
+				}),
+			};
+			if let Some(call_name) = call_name {
+				json["callName"] = call_name.into();
+			}
+			println!("{}", serde_json::to_string_pretty(&json).expect("Json pretty print failed"));
+		},
+		OutputType::Text => {
+			let call = match call_name {
+				Some(call_name) => format!("{} ({})", call, call_name),
+				None => call,
+			};
+			match signed {
+				Some((signer, era, nonce, tip)) => println!("Signed extrinsic:\n  \
+					Signer: {}\n  \
+					Nonce:  {}\n  \
+					Era:    {:?}\n  \
+					Tip:    {}\n  \
+					Call:   {}",
+					signer,
+					nonce,
+					era,
+					tip,
+					call,
+				),
+				None => println!("Unsigned extrinsic:\n  Call:   {}", call),
+			}
+		},
+	}
+
+	Ok(())
+}
+
+/// Print the output and the proof of a VRF, hex-encoded.
+fn print_vrf(output: OutputType, vrf_output: &[u8], proof: &[u8]) {
+	let vrf_output = format!("0x{}", HexDisplay::from(&vrf_output));
+	let proof = format!("0x{}", HexDisplay::from(&proof));
+
+	match output {
+		OutputType::Json => {
+			let json = json!({ "output": vrf_output, "proof": proof });
+			println!("{}", serde_json::to_string_pretty(&json).expect("Json pretty print failed"));
+		},
+		OutputType::Text => println!("VRF output: {}\nVRF proof:  {}", vrf_output, proof),
+	}
+}
+
+/// Decode the hex-encoded argument `name` into `fixed`, which it must fill exactly.
+fn decode_fixed(matches: &ArgMatches, name: &str, fixed: &mut [u8]) -> Result<(), Error> {
+	let value = matches.value_of(name).expect("argument is required; qed");
+	let bytes = decode_hex(value)?;
+
+	if fixed.len() != bytes.len() {
+		return Err(Error::Formatted(format!(
+			"`{}` must have {} bytes, got {}",
+			name,
+			fixed.len(),
+			bytes.len(),
+		)));
+	}
+	fixed.copy_from_slice(&bytes);
+	Ok(())
+}
+
+/// Print the known SS58 networks with their prefixes.
+fn print_networks(output: OutputType) {
+	let networks = Ss58AddressFormat::all().iter().map(|format| (String::from(*format), u8::from(*format)));
+
+	match output {
+		OutputType::Json => {
+			let json = networks
+				.map(|(name, prefix)| json!({ "name": name, "prefix": prefix }))
+				.collect::<Vec<_>>();
+			println!("{}", serde_json::to_string_pretty(&json).expect("Json pretty print failed"));
+		},
+		OutputType::Text => {
+			println!("Prefix  Network");
+			for (name, prefix) in networks {
+				println!("{:>6}  {}", prefix, name);
+			}
+		},
+	}
+}
+
 fn print_usage(matches: &ArgMatches) {
 	println!("{}", matches.usage());
 }
@@ -765,6 +1759,18 @@ fn print_usage(matches: &ArgMatches) {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use quickcheck::TestResult;
+
+	/// Text output of secrets, without entropy.
+	fn options() -> PrintOptions<'static> {
+		PrintOptions {
+			password: None,
+			network: None,
+			output: OutputType::Text,
+			show_entropy: false,
+			allow_secrets: true,
+		}
+	}
 
 	fn test_generate_sign_verify<CryptoType: Crypto>()
 	where
@@ -780,7 +1786,7 @@ mod tests {
 
 		let matches = app.clone().get_matches_from(arg_vec);
 		let matches = matches.subcommand().1.unwrap();
-		let mnemonic = generate_mnemonic(matches).expect("generate failed");
+		let mnemonic = generate_mnemonics(matches).expect("generate failed").remove(0);
 
 		let (pair, seed) =
 			<<CryptoType as Crypto>::Pair as Pair>::from_phrase(mnemonic.phrase(), password)
@@ -811,6 +1817,397 @@ mod tests {
 		test_generate_sign_verify::<Sr25519>();
 	}
 
+	#[test]
+	fn vrf_is_only_supported_by_sr25519() {
+		let pair = Sr25519::vrf_pair("//Alice", None).unwrap();
+		let public = Sr25519::vrf_public(&format!("0x{}", hex::encode(pair.public()))).unwrap();
+		let (output, proof) = pair.vrf_sign(DEFAULT_VRF_CONTEXT.as_bytes(), b"message");
+		assert!(sr25519::Pair::vrf_verify(&public, DEFAULT_VRF_CONTEXT.as_bytes(), b"message", &output, &proof));
+
+		assert!(Ed25519::vrf_pair("//Alice", None).is_err());
+		assert!(Ecdsa::vrf_public("//Alice").is_err());
+	}
+
+	#[test]
+	fn decode_fixed_checks_the_length() {
+		let usage = get_usage();
+		let matches = get_app(&usage).get_matches_from(&["subkey", "vrf-verify", "0x0102", "0x03", "//Alice"]);
+		let matches = matches.subcommand_matches("vrf-verify").unwrap();
+
+		let mut output = [0; 2];
+		decode_fixed(matches, "vrf-output", &mut output).unwrap();
+		assert_eq!(output, [1, 2]);
+		assert!(decode_fixed(matches, "proof", &mut [0; 2]).is_err());
+	}
+
+	#[test]
+	fn seed_rng_generates_the_same_phrase() {
+		let usage = get_usage();
+		let app = get_app(&usage);
+		let seed = format!("0x{}", "2a".repeat(32));
+		let generate = |args: &[&str]| {
+			let matches = app.clone().get_matches_from(args);
+			generate_mnemonics(matches.subcommand_matches("generate").unwrap()).unwrap().remove(0).into_phrase()
+		};
+
+		let phrase = generate(&["subkey", "generate", "--seed-rng", &seed]);
+		assert_eq!(phrase, generate(&["subkey", "generate", "--seed-rng", &seed]));
+		assert_ne!(phrase, generate(&["subkey", "generate", "--seed-rng", &format!("0x{}", "2b".repeat(32))]));
+		assert_ne!(phrase, generate(&["subkey", "generate"]));
+		assert_eq!(generate(&["subkey", "generate", "-w", "24", "--seed-rng", &seed]).split(' ').count(), 24);
+
+		let matches = app.clone().get_matches_from(&["subkey", "generate", "--seed-rng", "0x2a"]);
+		assert!(generate_mnemonics(matches.subcommand_matches("generate").unwrap()).is_err());
+	}
+
+	#[test]
+	fn count_generates_independent_phrases() {
+		let usage = get_usage();
+		let app = get_app(&usage);
+		let generate = |args: &[&str]| {
+			let matches = app.clone().get_matches_from(args);
+			generate_mnemonics(matches.subcommand_matches("generate").unwrap())
+		};
+
+		let phrases = generate(&["subkey", "generate", "--count", "3"]).unwrap()
+			.into_iter()
+			.map(Mnemonic::into_phrase)
+			.collect::<Vec<_>>();
+		assert_eq!(phrases.len(), 3);
+		assert!(phrases[0] != phrases[1] && phrases[1] != phrases[2] && phrases[0] != phrases[2]);
+		assert_eq!(generate(&["subkey", "generate"]).unwrap().len(), 1);
+		assert!(generate(&["subkey", "generate", "--count", "0"]).is_err());
+
+		let json = Sr25519::phrase_json(&phrases[0], None, Default::default(), true).unwrap();
+		assert_eq!(json["secretPhrase"], phrases[0].as_str());
+		assert!(json["entropy"].is_string());
+	}
+
+	/// Format the seed and public key of the pair derived from `bytes` and check that they decode
+	/// to the original values.
+	fn seed_and_public_key_round_trip<C: Crypto>(bytes: Vec<u8>) -> TestResult {
+		let mut seed = SeedOf::<C>::default();
+		for (byte, random) in seed.as_mut().iter_mut().zip(bytes.into_iter().cycle()) {
+			*byte = random;
+		}
+		// Not every sequence of bytes is a valid secret key, e.g. zero for ecdsa.
+		let public_key = match C::Pair::from_seed_slice(seed.as_ref()) {
+			Ok(pair) => pair.public(),
+			Err(_) => return TestResult::discard(),
+		};
+
+		TestResult::from_bool(
+			decode_hex(format_seed::<C>(seed.clone())).ok().as_deref() == Some(seed.as_ref())
+				&& decode_hex(format_public_key::<C>(public_key.clone())).ok().as_deref()
+					== Some(public_key.as_ref())
+		)
+	}
+
+	#[test]
+	fn format_seed_and_public_key_round_trip_for_ed25519() {
+		quickcheck::quickcheck(
+			seed_and_public_key_round_trip::<Ed25519> as fn(Vec<u8>) -> TestResult,
+		);
+	}
+
+	#[test]
+	fn format_seed_and_public_key_round_trip_for_sr25519() {
+		quickcheck::quickcheck(
+			seed_and_public_key_round_trip::<Sr25519> as fn(Vec<u8>) -> TestResult,
+		);
+	}
+
+	#[test]
+	fn format_seed_and_public_key_round_trip_for_ecdsa() {
+		quickcheck::quickcheck(
+			seed_and_public_key_round_trip::<Ecdsa> as fn(Vec<u8>) -> TestResult,
+		);
+	}
+
+	#[test]
+	fn account_id_from_uri_works() {
+		let stash = sr25519::Pair::from_string("//Alice//stash", None).unwrap();
+		let expected = format_account_id::<Sr25519>(stash.public());
+
+		assert_eq!(Sr25519::account_id_from_uri("//Alice//stash", None).unwrap(), expected);
+		assert_eq!(Sr25519::account_id_from_uri(&stash.public().to_ss58check(), None).unwrap(), expected);
+		assert_eq!(expected.len(), 66);
+		assert!(Sr25519::account_id_from_uri("not a valid uri", None).is_err());
+	}
+
+	#[test]
+	fn detect_network_works() {
+		let public = sr25519::Pair::from_string("//Alice", None).unwrap().public();
+		let kusama = public.to_ss58check_with_version(Ss58AddressFormat::KusamaAccount);
+
+		assert!(detect_network::<Sr25519>(&kusama) == Some(Ss58AddressFormat::KusamaAccount));
+		assert!(detect_network::<Sr25519>(&format!("{}/stash", kusama)) == Some(Ss58AddressFormat::KusamaAccount));
+		assert!(detect_network::<Sr25519>(&format!("0x{}", hex::encode(public))).is_none());
+		let network = Some(Default::default());
+		let options = PrintOptions { network, output: OutputType::Json, ..options() };
+		assert!(Sr25519::print_from_uri(&kusama, &options).is_ok());
+	}
+
+	#[test]
+	fn print_from_uri_should_fail_on_invalid_uri() {
+		let json = PrintOptions { output: OutputType::Json, show_entropy: true, ..options() };
+
+		assert!(Sr25519::print_from_uri("//Alice", &options()).is_ok());
+		assert!(Sr25519::print_from_uri("not a valid uri", &options()).is_err());
+		assert!(Ed25519::print_from_uri("0xzz", &json).is_err());
+	}
+
+	#[test]
+	fn print_from_uri_should_only_print_secrets_if_allowed() {
+		let address = Sr25519::ss58_from_pair(&Sr25519::pair_from_suri("//Alice", None));
+
+		let options = PrintOptions { allow_secrets: false, ..options() };
+
+		assert!(Sr25519::print_from_uri("//Alice", &options).is_err());
+		assert!(Sr25519::print_from_uri(&address, &options).is_ok());
+	}
+
+	#[test]
+	fn print_from_uri_should_show_entropy() {
+		let phrase = Mnemonic::new(MnemonicType::Words12, Language::English);
+
+		let text = PrintOptions { show_entropy: true, ..options() };
+		let json = PrintOptions { output: OutputType::Json, ..text };
+
+		assert!(Sr25519::print_from_uri(phrase.phrase(), &json).is_ok());
+		assert!(Sr25519::print_from_uri(phrase.phrase(), &text).is_ok());
+		assert!(Sr25519::print_from_uri("//Alice", &text).is_ok());
+	}
+
+	fn raw_seeds_should_work<C: Crypto>()
+	where
+		SignatureOf<C>: SignatureT,
+		PublicOf<C>: PublicT,
+	{
+		let seed = [7u8; 32];
+		let uri = format!("0x{}", hex::encode(seed));
+		let expected = C::Pair::from_seed_slice(&seed).unwrap().public();
+
+		assert_eq!(C::read_secret(&uri, None).public(), expected);
+		assert!(C::public_from_uri(&uri, None) == Some(expected));
+		assert!(C::print_from_uri(&uri, &options()).is_ok());
+	}
+
+	#[test]
+	fn raw_seeds_should_work_for_all_schemes() {
+		raw_seeds_should_work::<Ed25519>();
+		raw_seeds_should_work::<Sr25519>();
+		raw_seeds_should_work::<Ecdsa>();
+
+		assert_eq!(raw_seed(&format!("0x{}", "01".repeat(32))), Some(vec![1; 32]));
+		// Not raw seeds: no prefix, wrong length, derivation paths.
+		assert_eq!(raw_seed(&"01".repeat(32)), None);
+		assert_eq!(raw_seed(&format!("0x{}", "01".repeat(31))), None);
+		assert_eq!(raw_seed(&format!("0x{}//Alice", "01".repeat(32))), None);
+		// Not a valid ECDSA secret key.
+		assert!(Ecdsa::pair_from_raw_seed(&format!("0x{}", "00".repeat(32))).is_none());
+	}
+
+	#[test]
+	fn junctions_should_be_checked_and_appended() {
+		for junction in &["//stash", "/hot", "//stash/0//1", "//1"] {
+			assert!(check_junction(junction).is_ok(), "{}", junction);
+		}
+		for junction in &["", "stash", "//", "/", "///password", "//stash/", "//a///b"] {
+			assert!(check_junction(junction).is_err(), "{}", junction);
+		}
+
+		assert_eq!(append_junction("//Alice", "//stash"), "//Alice//stash");
+		assert_eq!(append_junction("bottom drive//Alice///pass", "/hot"), "bottom drive//Alice/hot///pass");
+
+		let expected = Sr25519::pair_from_suri("//Alice//stash", None).public();
+		assert_eq!(Sr25519::read_secret(&append_junction("//Alice", "//stash"), None).public(), expected);
+	}
+
+	#[test]
+	fn check_public_key_should_work() {
+		let uri = "//Alice";
+		let public_key = format_public_key::<Sr25519>(Sr25519::pair_from_suri(uri, None).public());
+
+		assert!(check_public_key::<Sr25519>(uri, None, &public_key).is_ok());
+		assert!(check_public_key::<Sr25519>(uri, None, &public_key[2..]).is_ok());
+
+		let other = format_public_key::<Sr25519>(Sr25519::pair_from_suri("//Bob", None).public());
+		match check_public_key::<Sr25519>(uri, None, &other) {
+			Err(Error::Formatted(msg)) => assert!(msg.starts_with("Public key mismatch")),
+			_ => panic!("a mismatching public key must be an error"),
+		}
+
+		assert!(check_public_key::<Sr25519>(uri, None, "0xnothex").is_err());
+	}
+
+	#[test]
+	fn decode_extrinsic_should_work() {
+		let signer = Sr25519::pair_from_suri("//Alice", None);
+		let function = Call::Balances(BalancesCall::transfer(
+			Sr25519::pair_from_suri("//Bob", None).public().into_runtime().into_account().into(),
+			100,
+		));
+		let extrinsic = ExtrinsicBuilder::<Sr25519>::new()
+			.call(function)
+			.nonce(7)
+			.signer(signer)
+			.genesis_hash(Default::default())
+			.build()
+			.expect("all the required fields are set");
+		let encoded = format!("0x{}", HexDisplay::from(&extrinsic.encode()));
+
+		let decoded = decode_extrinsic(&encoded).expect("decoding failed");
+		assert_eq!(decoded.encode(), extrinsic.encode());
+		let (_, _, extra) = decoded.signature.as_ref().expect("the extrinsic is signed");
+		let (_, nonce, tip) = era_nonce_and_tip(extra).expect("the extensions decode");
+		assert_eq!((nonce, tip), (7, 0));
+
+		match decode_extrinsic(&encoded[..encoded.len() - 2]) {
+			Err(Error::Formatted(msg)) => assert!(msg.starts_with("Invalid extrinsic at byte offset")),
+			_ => panic!("a truncated extrinsic must not decode"),
+		}
+		match decode_extrinsic(&format!("{}00", encoded)) {
+			Err(Error::Formatted(msg)) => assert!(msg.contains("trailing data")),
+			_ => panic!("trailing data must be reported"),
+		}
+	}
+
+	#[test]
+	fn extrinsic_builder_should_work() {
+		let signer = Sr25519::pair_from_suri("//Alice", None);
+		let function = Call::System(frame_system::Call::remark(vec![]));
+
+		let missing_signer = ExtrinsicBuilder::<Sr25519>::new()
+			.call(function.clone())
+			.genesis_hash(Default::default())
+			.build();
+		match missing_signer {
+			Err(Error::Static(msg)) => assert!(msg.contains("signer")),
+			_ => panic!("the signer is required"),
+		}
+
+		let era = Era::mortal(64, 10);
+		let extrinsic = ExtrinsicBuilder::<Sr25519>::new()
+			.call(function)
+			.nonce(3)
+			.tip(5)
+			.era(era, H256::repeat_byte(1))
+			.signer(signer)
+			.genesis_hash(Default::default())
+			.build()
+			.expect("all the required fields are set");
+
+		let (_, _, extra) = extrinsic.signature.expect("the extrinsic is signed");
+		assert_eq!(extra.3.encode(), frame_system::CheckEra::<Runtime>::from(era).encode());
+		assert_eq!(extra.4.encode(), frame_system::CheckNonce::<Runtime>::from(3).encode());
+		assert_eq!(
+			extra.6.encode(),
+			pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(5).encode(),
+		);
+	}
+
+	#[test]
+	fn crypto_scheme_accepts_aliases_in_any_case() {
+		for (name, scheme) in &[
+			("sr25519", CryptoScheme::Sr25519),
+			("SR25519", CryptoScheme::Sr25519),
+			("Schnorrkel", CryptoScheme::Sr25519),
+			("ed25519", CryptoScheme::Ed25519),
+			("edwards", CryptoScheme::Ed25519),
+			("ecdsa", CryptoScheme::Ecdsa),
+			("secp256k1", CryptoScheme::Ecdsa),
+			("K1", CryptoScheme::Ecdsa),
+		] {
+			assert_eq!(CryptoScheme::try_from(*name).unwrap(), *scheme);
+		}
+		assert!(CryptoScheme::try_from("rsa").is_err());
+	}
+
+	#[test]
+	fn crypto_scheme_follows_the_flags() {
+		let usage = get_usage();
+		let scheme = |args: &[&str]| crypto_scheme(&get_app(&usage).get_matches_from(args));
+
+		assert_eq!(scheme(&["subkey", "generate"]).unwrap(), CryptoScheme::Sr25519);
+		assert_eq!(scheme(&["subkey", "--ed25519", "generate"]).unwrap(), CryptoScheme::Ed25519);
+		assert_eq!(scheme(&["subkey", "--scheme", "Secp256k1", "generate"]).unwrap(), CryptoScheme::Ecdsa);
+		assert!(scheme(&["subkey", "--scheme", "ecdsa", "--ed25519", "generate"]).is_err());
+		assert!(scheme(&["subkey", "--scheme", "unknown", "generate"]).is_err());
+	}
+
+	#[test]
+	fn format_value_follows_the_output_type() {
+		assert_eq!(format_value(OutputType::Text, "signature", "0x01"), "0x01");
+		assert_eq!(
+			serde_json::from_str::<serde_json::Value>(&format_value(OutputType::Json, "signature", "0x01")).unwrap(),
+			json!({ "signature": "0x01" }),
+		);
+	}
+
+	#[test]
+	fn insert_into_in_memory_keystore() {
+		let pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let key_type = KeyTypeId::try_from("gran").unwrap();
+		let store = sc_keystore::Store::new_in_memory();
+
+		insert_into_keystore(&mut *store.write(), key_type, "//Alice", pair.public().as_ref()).unwrap();
+
+		let inserted = store.read().key_pair_by_type::<sr25519::Pair>(&pair.public(), key_type).unwrap();
+		assert_eq!(inserted.public(), pair.public());
+	}
+
+	#[test]
+	fn insert_into_local_keystore_writes_the_key_file() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("keystore");
+		let public = ed25519::Pair::from_string("//Alice", None).unwrap().public();
+		let key_type = KeyTypeId::try_from("gran").unwrap();
+
+		let file = insert_into_local_keystore(
+			path.to_str().unwrap(),
+			None,
+			key_type,
+			"//Alice",
+			public.as_ref(),
+		).unwrap();
+
+		assert_eq!(file.parent(), Some(path.as_path()));
+		assert_eq!(fs::read_to_string(&file).unwrap(), "\"//Alice\"");
+	}
+
+	#[test]
+	fn has_key_reads_back_a_key_inserted_into_a_local_keystore() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().to_str().unwrap();
+		let pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let gran = KeyTypeId::try_from("gran").unwrap();
+		let babe = KeyTypeId::try_from("babe").unwrap();
+
+		insert_into_local_keystore(path, None, gran, "//Alice", pair.public().as_ref()).unwrap();
+
+		let store = open_local_keystore(path, None).unwrap();
+		assert!(has_key(&*store.read(), gran, pair.public().as_ref()));
+		assert!(!has_key(&*store.read(), babe, pair.public().as_ref()));
+	}
+
+	#[test]
+	fn insert_into_local_keystore_uses_the_keystore_password() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().to_str().unwrap();
+		let public = sr25519::Pair::from_string("//Alice", Some("secret")).unwrap().public();
+		let key_type = KeyTypeId::try_from("gran").unwrap();
+		let secret = Some("secret");
+
+		insert_into_local_keystore(path, secret, key_type, "//Alice", public.as_ref()).unwrap();
+
+		let read_back = |password| open_local_keystore(path, password).unwrap().read()
+			.key_pair_by_type::<sr25519::Pair>(&public, key_type)
+			.map(|pair| pair.public());
+		assert_eq!(read_back(secret).unwrap(), public);
+		assert!(read_back(None).is_err());
+	}
+
 	#[test]
 	fn should_work() {
 		let s = "0123456789012345678901234567890123456789012345678901234567890123";
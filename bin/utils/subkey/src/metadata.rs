@@ -0,0 +1,139 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Runtime metadata loaded from a file, used to name the calls of offline transactions.
+
+use codec::Decode;
+use frame_metadata::{DecodeDifferent, RuntimeMetadata, RuntimeMetadataPrefixed, META_RESERVED};
+use std::{fs, path::Path};
+
+use super::{decode_hex, Error};
+
+/// The only metadata version that can be read.
+const SUPPORTED_VERSION: u8 = 11;
+
+/// The calls of a module, in the order of their index.
+struct ModuleCalls {
+	name: String,
+	calls: Vec<String>,
+}
+
+/// The call names of a runtime, indexed like the encoded calls.
+pub struct Metadata {
+	modules: Vec<ModuleCalls>,
+}
+
+impl Metadata {
+	/// Load SCALE or hex-encoded metadata from the file at `path`.
+	pub fn from_file(path: &Path) -> Result<Self, Error> {
+		let content = fs::read(path)?;
+		let text = String::from_utf8_lossy(&content);
+		let text = text.trim();
+
+		let data = if text.starts_with("0x") {
+			decode_hex(&text[2..])?
+		} else if !text.is_empty() && text.bytes().all(|b| b.is_ascii_hexdigit()) {
+			decode_hex(text)?
+		} else {
+			content
+		};
+
+		Self::from_bytes(&data).map_err(|e| Error::Formatted(format!(
+			"Failed to load metadata {}: {}",
+			path.display(),
+			e,
+		)))
+	}
+
+	/// Decode SCALE-encoded metadata, checking its magic number and version first.
+	pub fn from_bytes(data: &[u8]) -> Result<Self, Error> {
+		if data.len() < 5 || data[..4] != META_RESERVED.to_le_bytes() {
+			return Err(Error::Static("Invalid metadata: the `meta` magic number is missing"));
+		}
+		if data[4] != SUPPORTED_VERSION {
+			return Err(Error::Formatted(format!(
+				"Unsupported metadata version V{}, only V{} is supported",
+				data[4],
+				SUPPORTED_VERSION,
+			)));
+		}
+
+		let metadata = match RuntimeMetadataPrefixed::decode(&mut &data[..]) {
+			Ok(RuntimeMetadataPrefixed(_, RuntimeMetadata::V11(metadata))) => metadata,
+			Ok(_) => unreachable!("the version is checked above; qed"),
+			Err(e) => return Err(Error::Formatted(format!("Invalid metadata: {}", e))),
+		};
+
+		let modules = decoded(metadata.modules)
+			.into_iter()
+			// Only the modules with calls get an index in the `Call` enum.
+			.filter_map(|module| {
+				let calls = decoded(module.calls?)
+					.into_iter()
+					.map(|call| decoded(call.name))
+					.collect();
+				Some(ModuleCalls { name: decoded(module.name), calls })
+			})
+			.collect();
+
+		Ok(Metadata { modules })
+	}
+
+	/// Returns the module and call names of an encoded call.
+	pub fn call_name(&self, call: &[u8]) -> Option<(&str, &str)> {
+		let module = self.modules.get(*call.get(0)? as usize)?;
+		let name = module.calls.get(*call.get(1)? as usize)?;
+		Some((&module.name, name))
+	}
+}
+
+/// Unwrap a value of decoded metadata.
+fn decoded<B, O>(value: DecodeDifferent<B, O>) -> O {
+	match value {
+		DecodeDifferent::Decoded(value) => value,
+		DecodeDifferent::Encode(_) => unreachable!("decoded metadata only contains decoded values; qed"),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use node_runtime::{BalancesCall, Call, Runtime};
+
+	#[test]
+	fn call_names_are_resolved() {
+		let metadata = Metadata::from_bytes(&Runtime::metadata().encode()).unwrap();
+		let call = Call::Balances(BalancesCall::transfer(Default::default(), 100)).encode();
+
+		assert_eq!(metadata.call_name(&call), Some(("Balances", "transfer")));
+		assert_eq!(metadata.call_name(&[0xff, 0]), None);
+	}
+
+	#[test]
+	fn unsupported_version_is_reported() {
+		let mut data = Runtime::metadata().encode();
+		data[4] = 10;
+
+		match Metadata::from_bytes(&data) {
+			Err(Error::Formatted(msg)) => assert!(msg.contains("V10")),
+			_ => panic!("metadata V10 must not be loaded"),
+		}
+		assert!(Metadata::from_bytes(b"not metadata").is_err());
+	}
+}
@@ -18,7 +18,7 @@
 
 use super::{PublicOf, PublicT, Crypto};
 use sp_core::Pair;
-use rand::{rngs::OsRng, RngCore};
+use rand::RngCore;
 
 fn good_waypoint(done: u64) -> u64 {
 	match done {
@@ -70,7 +70,8 @@ fn validate_base58(c :char) -> bool {
 	c.is_alphanumeric() && !"0lIO".contains(c)
 }
 
-pub(super) fn generate_key<C: Crypto>(desired: &str) -> Result<KeyPair<C>, &'static str> where
+/// Search a key whose SS58 address contains `desired`, starting from seeds drawn from `rng`.
+pub(super) fn generate_key<C: Crypto>(desired: &str, rng: &mut dyn RngCore) -> Result<KeyPair<C>, &'static str> where
 		PublicOf<C>: PublicT,
 {
 	if desired.is_empty() {
@@ -91,7 +92,7 @@ pub(super) fn generate_key<C: Crypto>(desired: &str) -> Result<KeyPair<C>, &'sta
 
 	loop {
 		if done % 100000 == 0 {
-			OsRng.fill_bytes(seed.as_mut());
+			rng.fill_bytes(seed.as_mut());
 		} else {
 			next_seed(seed.as_mut());
 		}
@@ -123,13 +124,14 @@ pub(super) fn generate_key<C: Crypto>(desired: &str) -> Result<KeyPair<C>, &'sta
 mod tests {
 	use super::super::Ed25519;
 	use super::*;
+	use rand::{rngs::{OsRng, StdRng}, SeedableRng};
 	use sp_core::{crypto::Ss58Codec, Pair};
 	#[cfg(feature = "bench")]
 	use test::Bencher;
 
 	#[test]
 	fn test_generation_with_single_char() {
-		assert!(generate_key::<Ed25519>("j")
+		assert!(generate_key::<Ed25519>("j", &mut OsRng)
 			.unwrap()
 			.pair
 			.public()
@@ -137,6 +139,13 @@ mod tests {
 			.contains("j"));
 	}
 
+	#[test]
+	fn test_generation_with_seeded_rng_is_deterministic() {
+		let generate = || generate_key::<Ed25519>("j", &mut StdRng::from_seed([7; 32])).unwrap().seed;
+
+		assert_eq!(generate(), generate());
+	}
+
 	#[test]
 	fn test_score_1_char_100() {
 		let score = calculate_score("j", "5jolkadotwHY5k9GpdTgpqs9xjuNvtv8EcwCFpEeyEf3KHim");
@@ -177,29 +186,29 @@ mod tests {
 
 	#[test]
 	fn test_invalid_pattern() {
-		assert!(generate_key::<Ed25519>("").is_err());
-		assert!(generate_key::<Ed25519>("0").is_err());
-		assert!(generate_key::<Ed25519>("l").is_err());
-		assert!(generate_key::<Ed25519>("I").is_err());
-		assert!(generate_key::<Ed25519>("O").is_err());
-		assert!(generate_key::<Ed25519>("!").is_err());
+		assert!(generate_key::<Ed25519>("", &mut OsRng).is_err());
+		assert!(generate_key::<Ed25519>("0", &mut OsRng).is_err());
+		assert!(generate_key::<Ed25519>("l", &mut OsRng).is_err());
+		assert!(generate_key::<Ed25519>("I", &mut OsRng).is_err());
+		assert!(generate_key::<Ed25519>("O", &mut OsRng).is_err());
+		assert!(generate_key::<Ed25519>("!", &mut OsRng).is_err());
 	}
 
 	#[test]
 	fn test_valid_pattern() {
-		assert!(generate_key::<Ed25519>("o").is_ok());
-		assert!(generate_key::<Ed25519>("L").is_ok());
+		assert!(generate_key::<Ed25519>("o", &mut OsRng).is_ok());
+		assert!(generate_key::<Ed25519>("L", &mut OsRng).is_ok());
 	}
 
 	#[cfg(feature = "bench")]
 	#[bench]
 	fn bench_paranoiac(b: &mut Bencher) {
-		b.iter(|| generate_key("polk"));
+		b.iter(|| generate_key::<Ed25519>("polk", &mut OsRng));
 	}
 
 	#[cfg(feature = "bench")]
 	#[bench]
 	fn bench_not_paranoiac(b: &mut Bencher) {
-		b.iter(|| generate_key("polk"));
+		b.iter(|| generate_key::<Ed25519>("polk", &mut OsRng));
 	}
 }
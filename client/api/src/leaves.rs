@@ -55,6 +55,11 @@ impl<H, N: Ord> FinalizationDisplaced<H, N> {
 		// one transaction, then there will be no overlap in the keys.
 		self.leaves.append(&mut other.leaves);
 	}
+
+	/// Iterate over all displaced leaves.
+	pub fn leaves(&self) -> impl Iterator<Item=&H> {
+		self.leaves.values().flat_map(|h| h.iter())
+	}
 }
 
 /// list of leaf hashes ordered by number (descending).
@@ -18,9 +18,12 @@
 
 //! Substrate chain configurations.
 
-use std::{borrow::Cow, fs::File, path::PathBuf, sync::Arc, collections::HashMap};
+use std::{borrow::Cow, fmt, fs::File, io::Read, path::PathBuf, sync::Arc, collections::HashMap};
 use serde::{Serialize, Deserialize};
-use sp_core::storage::{StorageKey, StorageData, ChildInfo, Storage, StorageChild};
+use serde::de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use sp_core::{
+	Bytes, ed25519, sr25519, storage::{StorageKey, StorageData, ChildInfo, Storage, StorageChild},
+};
 use sp_runtime::BuildStorage;
 use serde_json as json;
 use crate::{RuntimeGenesis, ChainType, extension::GetExtension, Properties};
@@ -31,7 +34,8 @@ enum GenesisSource<G> {
 	File(PathBuf),
 	Binary(Cow<'static, [u8]>),
 	Factory(Arc<dyn Fn() -> G + Send + Sync>),
-	Storage(Storage),
+	/// The storage, with the state root it is expected to have.
+	Storage(Storage, Option<Vec<u8>>),
 }
 
 impl<G> Clone for GenesisSource<G> {
@@ -40,7 +44,7 @@ impl<G> Clone for GenesisSource<G> {
 			Self::File(ref path) => Self::File(path.clone()),
 			Self::Binary(ref d) => Self::Binary(d.clone()),
 			Self::Factory(ref f) => Self::Factory(f.clone()),
-			Self::Storage(ref s) => Self::Storage(s.clone()),
+			Self::Storage(ref s, ref root) => Self::Storage(s.clone(), root.clone()),
 		}
 	}
 }
@@ -66,7 +70,7 @@ impl<G: RuntimeGenesis> GenesisSource<G> {
 				Ok(genesis.genesis)
 			},
 			Self::Factory(f) => Ok(Genesis::Runtime(f())),
-			Self::Storage(storage) => {
+			Self::Storage(storage, state_root) => {
 				let top = storage.top
 					.iter()
 					.map(|(k, v)| (StorageKey(k.clone()), StorageData(v.clone())))
@@ -85,7 +89,11 @@ impl<G: RuntimeGenesis> GenesisSource<G> {
 					)
 					.collect();
 
-				Ok(Genesis::Raw(RawGenesis { top, children_default }))
+				Ok(Genesis::Raw(RawGenesis {
+					top,
+					children_default,
+					state_root: state_root.clone().map(Bytes),
+				}))
 			},
 		}
 	}
@@ -95,7 +103,7 @@ impl<G: RuntimeGenesis, E> BuildStorage for ChainSpec<G, E> {
 	fn build_storage(&self) -> Result<Storage, String> {
 		match self.genesis.resolve()? {
 			Genesis::Runtime(gc) => gc.build_storage(),
-			Genesis::Raw(RawGenesis { top: map, children_default: children_map }) => Ok(Storage {
+			Genesis::Raw(RawGenesis { top: map, children_default: children_map, .. }) => Ok(Storage {
 				top: map.into_iter().map(|(k, v)| (k.0, v.0)).collect(),
 				children_default: children_map.into_iter().map(|(storage_key, child_content)| {
 					let child_info = ChildInfo::new_default(storage_key.0.as_slice());
@@ -128,6 +136,136 @@ pub type GenesisStorage = HashMap<StorageKey, StorageData>;
 pub struct RawGenesis {
 	pub top: GenesisStorage,
 	pub children_default: HashMap<StorageKey, GenesisStorage>,
+	/// State root the storage is expected to have, recorded when the state is exported.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub state_root: Option<Bytes>,
+}
+
+/// A storage entry of a raw genesis, read by `read_raw_genesis`.
+#[derive(Debug, PartialEq)]
+pub enum RawGenesisEntry {
+	/// A key and value of the top storage.
+	Top(Vec<u8>, Vec<u8>),
+	/// The storage key of a default child storage, and a key and value of it.
+	Child(Vec<u8>, Vec<u8>, Vec<u8>),
+}
+
+/// Read the raw genesis of the JSON chain spec in `reader` without holding its storage in memory.
+///
+/// `visit` is called with each storage entry as it is read. Returns the state root recorded in
+/// the raw genesis, if any.
+pub fn read_raw_genesis<R: Read>(
+	reader: R,
+	visit: &mut dyn FnMut(RawGenesisEntry) -> Result<(), String>,
+) -> Result<Option<Vec<u8>>, String> {
+	let mut state = RawGenesisState { visit, found: false, state_root: None };
+	let mut deserializer = json::Deserializer::from_reader(std::io::BufReader::new(reader));
+	RawGenesisSeed { state: &mut state, part: RawGenesisPart::Spec }
+		.deserialize(&mut deserializer)
+		.and_then(|()| deserializer.end())
+		.map_err(|e| format!("Error parsing spec file: {}", e))?;
+
+	if !state.found {
+		return Err("The chain spec has no raw genesis".into());
+	}
+	Ok(state.state_root)
+}
+
+struct RawGenesisState<'a> {
+	visit: &'a mut dyn FnMut(RawGenesisEntry) -> Result<(), String>,
+	found: bool,
+	state_root: Option<Vec<u8>>,
+}
+
+/// The object of the chain spec a `RawGenesisSeed` reads.
+enum RawGenesisPart {
+	Spec,
+	Genesis,
+	Raw,
+	Children,
+	/// The top storage, or the child storage with the given storage key.
+	Storage(Option<Vec<u8>>),
+}
+
+struct RawGenesisSeed<'s, 'a> {
+	state: &'s mut RawGenesisState<'a>,
+	part: RawGenesisPart,
+}
+
+impl<'de, 's, 'a> DeserializeSeed<'de> for RawGenesisSeed<'s, 'a> {
+	type Value = ();
+
+	fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+		deserializer.deserialize_any(self)
+	}
+}
+
+impl<'de, 's, 'a> Visitor<'de> for RawGenesisSeed<'s, 'a> {
+	type Value = ();
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("a chain spec with a raw genesis")
+	}
+
+	fn visit_map<M: MapAccess<'de>>(self, mut map: M) -> Result<(), M::Error> {
+		let RawGenesisSeed { state, part } = self;
+		let parse_key = |key: &str| sp_core::bytes::from_hex(key).map_err(|e| {
+			<M::Error as de::Error>::custom(format!("Invalid storage key {}: {:?}", key, e))
+		});
+
+		while let Some(key) = map.next_key::<String>()? {
+			let inner = match (&part, key.as_str()) {
+				(RawGenesisPart::Spec, "genesis") => RawGenesisPart::Genesis,
+				(RawGenesisPart::Genesis, "raw") => {
+					state.found = true;
+					RawGenesisPart::Raw
+				},
+				(RawGenesisPart::Raw, "top") => RawGenesisPart::Storage(None),
+				(RawGenesisPart::Raw, "childrenDefault") => RawGenesisPart::Children,
+				(RawGenesisPart::Raw, "stateRoot") => {
+					state.state_root = map.next_value::<Option<Bytes>>()?.map(|root| root.0);
+					continue;
+				},
+				(RawGenesisPart::Children, _) => RawGenesisPart::Storage(Some(parse_key(&key)?)),
+				(RawGenesisPart::Storage(child), _) => {
+					let key = parse_key(&key)?;
+					let value = map.next_value::<StorageData>()?.0;
+					let entry = match child {
+						Some(child) => RawGenesisEntry::Child(child.clone(), key, value),
+						None => RawGenesisEntry::Top(key, value),
+					};
+					(state.visit)(entry).map_err(<M::Error as de::Error>::custom)?;
+					continue;
+				},
+				_ => {
+					map.next_value::<IgnoredAny>()?;
+					continue;
+				},
+			};
+			map.next_value_seed(RawGenesisSeed { state: &mut *state, part: inner })?;
+		}
+
+		Ok(())
+	}
+
+	/// Raw genesis written before it was a map, as `[top, children_default]`.
+	fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+		match self.part {
+			RawGenesisPart::Raw => {},
+			_ => return Err(de::Error::invalid_type(de::Unexpected::Seq, &self)),
+		}
+
+		let state = self.state;
+		let top = RawGenesisSeed { state: &mut *state, part: RawGenesisPart::Storage(None) };
+		seq.next_element_seed(top)?;
+		let children = RawGenesisSeed { state: &mut *state, part: RawGenesisPart::Children };
+		seq.next_element_seed(children)?;
+		if let Some(state_root) = seq.next_element::<Option<Bytes>>()? {
+			state.state_root = state_root.map(|root| root.0);
+		}
+
+		Ok(())
+	}
 }
 
 #[derive(Serialize, Deserialize)]
@@ -164,10 +302,15 @@ struct ClientSpec<E> {
 /// We use `Option` here since `()` is not flattenable by serde.
 pub type NoExtension = Option<()>;
 
+/// Sets the initial authorities of a runtime genesis configuration, from their sr25519 and ed25519
+/// public keys.
+pub type AuthoritiesHook<G> = fn(&mut G, &[(sr25519::Public, ed25519::Public)]);
+
 /// A configuration of a chain. Can be used to build a genesis block.
 pub struct ChainSpec<G, E = NoExtension> {
 	client_spec: ClientSpec<E>,
 	genesis: GenesisSource<G>,
+	authorities_hook: Option<AuthoritiesHook<G>>,
 }
 
 impl<G, E: Clone> Clone for ChainSpec<G, E> {
@@ -175,6 +318,7 @@ impl<G, E: Clone> Clone for ChainSpec<G, E> {
 		ChainSpec {
 			client_spec: self.client_spec.clone(),
 			genesis: self.genesis.clone(),
+			authorities_hook: self.authorities_hook,
 		}
 	}
 }
@@ -217,11 +361,31 @@ impl<G, E> ChainSpec<G, E> {
 		self.client_spec.boot_nodes.push(addr)
 	}
 
+	/// Remove all the bootnodes from the list.
+	pub fn clear_boot_nodes(&mut self) {
+		self.client_spec.boot_nodes.clear()
+	}
+
+	/// Set the spec name and id.
+	pub fn set_name_and_id(&mut self, name: String, id: String) {
+		self.client_spec.name = name;
+		self.client_spec.id = id;
+	}
+
 	/// Returns a reference to defined chain spec extensions.
 	pub fn extensions(&self) -> &E {
 		&self.client_spec.extensions
 	}
 
+	/// Set the hook used to set the initial authorities of the genesis configuration, e.g. by
+	/// `build-spec --authority`.
+	///
+	/// Without it, the initial authorities can't be set for this chain specification.
+	pub fn with_authorities_hook(mut self, hook: AuthoritiesHook<G>) -> Self {
+		self.authorities_hook = Some(hook);
+		self
+	}
+
 	/// Create hardcoded spec.
 	pub fn from_genesis<F: Fn() -> G + 'static + Send + Sync>(
 		name: &str,
@@ -250,6 +414,7 @@ impl<G, E> ChainSpec<G, E> {
 		ChainSpec {
 			client_spec,
 			genesis: GenesisSource::Factory(Arc::new(constructor)),
+			authorities_hook: None,
 		}
 	}
 
@@ -268,6 +433,7 @@ impl<G, E: serde::de::DeserializeOwned> ChainSpec<G, E> {
 		Ok(ChainSpec {
 			client_spec,
 			genesis: GenesisSource::Binary(json),
+			authorities_hook: None,
 		})
 	}
 
@@ -280,6 +446,7 @@ impl<G, E: serde::de::DeserializeOwned> ChainSpec<G, E> {
 		Ok(ChainSpec {
 			client_spec,
 			genesis: GenesisSource::File(path),
+			authorities_hook: None,
 		})
 	}
 }
@@ -309,7 +476,7 @@ impl<G: RuntimeGenesis, E: serde::Serialize + Clone + 'static> ChainSpec<G, E> {
 					))
 					.collect();
 
-				Genesis::Raw(RawGenesis { top, children_default })
+				Genesis::Raw(RawGenesis { top, children_default, state_root: None })
 			},
 			(_, genesis) => genesis,
 		};
@@ -359,6 +526,14 @@ where
 		ChainSpec::add_boot_node(self, addr)
 	}
 
+	fn clear_boot_nodes(&mut self) {
+		ChainSpec::clear_boot_nodes(self)
+	}
+
+	fn set_name_and_id(&mut self, name: String, id: String) {
+		ChainSpec::set_name_and_id(self, name, id)
+	}
+
 	fn extensions(&self) -> &dyn GetExtension {
 		ChainSpec::extensions(self) as &dyn GetExtension
 	}
@@ -376,7 +551,36 @@ where
 	}
 
 	fn set_storage(&mut self, storage: Storage) {
-		self.genesis = GenesisSource::Storage(storage);
+		self.genesis = GenesisSource::Storage(storage, None);
+	}
+
+	fn set_storage_with_root(&mut self, storage: Storage, state_root: Vec<u8>) {
+		self.genesis = GenesisSource::Storage(storage, Some(state_root));
+	}
+
+	fn genesis_state_root(&self) -> Result<Option<Vec<u8>>, String> {
+		match self.genesis.resolve()? {
+			Genesis::Raw(RawGenesis { state_root, .. }) => Ok(state_root.map(|root| root.0)),
+			Genesis::Runtime(_) => Ok(None),
+		}
+	}
+
+	fn set_genesis_authorities(
+		&self,
+		genesis: &mut json::Value,
+		authorities: &[(sr25519::Public, ed25519::Public)],
+	) -> Result<bool, String> {
+		let hook = match self.authorities_hook {
+			Some(hook) => hook,
+			None => return Ok(false),
+		};
+
+		let mut runtime: G = json::from_value(genesis.clone())
+			.map_err(|e| format!("Invalid genesis runtime configuration: {}", e))?;
+		hook(&mut runtime, authorities);
+		*genesis = json::to_value(&runtime)
+			.map_err(|e| format!("Error generating genesis runtime configuration: {}", e))?;
+		Ok(true)
 	}
 }
 
@@ -430,4 +634,73 @@ mod tests {
 
 		assert_eq!(spec.extensions().my_property, "Test Extension");
 	}
+
+	#[test]
+	fn genesis_authorities_are_set_by_the_hook() {
+		use crate::ChainSpec as _;
+
+		fn set_authorities(
+			genesis: &mut Genesis,
+			authorities: &[(sr25519::Public, ed25519::Public)],
+		) {
+			genesis.0.insert("authorities".into(), authorities.len().to_string());
+		}
+
+		let spec = TestSpec::from_json_file(PathBuf::from("./res/chain_spec.json")).unwrap();
+		let authorities = [(sr25519::Public::default(), ed25519::Public::default())];
+		let mut genesis = json::json!({});
+		assert_eq!(spec.set_genesis_authorities(&mut genesis, &authorities), Ok(false));
+
+		let spec = spec.with_authorities_hook(set_authorities);
+		assert_eq!(spec.set_genesis_authorities(&mut genesis, &authorities), Ok(true));
+		assert_eq!(genesis, json::json!({ "authorities": "1" }));
+	}
+
+	#[test]
+	fn read_raw_genesis_visits_every_entry() {
+		let spec = br#"{
+			"name": "Test",
+			"genesis": { "raw": {
+				"top": { "0x01": "0x0a", "0x02": "0x0b" },
+				"childrenDefault": { "0x03": { "0x04": "0x0c" } },
+				"stateRoot": "0x0d"
+			} },
+			"id": "test"
+		}"#;
+
+		let mut entries = Vec::new();
+		let state_root = read_raw_genesis(&spec[..], &mut |entry| {
+			entries.push(entry);
+			Ok(())
+		}).unwrap();
+
+		assert_eq!(state_root, Some(vec![0x0d]));
+		assert_eq!(entries, vec![
+			RawGenesisEntry::Top(vec![1], vec![0x0a]),
+			RawGenesisEntry::Top(vec![2], vec![0x0b]),
+			RawGenesisEntry::Child(vec![3], vec![4], vec![0x0c]),
+		]);
+	}
+
+	#[test]
+	fn read_raw_genesis_reads_the_storage_of_the_spec() {
+		let spec = TestSpec::from_json_file(PathBuf::from("./res/chain_spec.json")).unwrap();
+		let mut top = std::collections::BTreeMap::new();
+		read_raw_genesis(&include_bytes!("../res/chain_spec.json")[..], &mut |entry| match entry {
+			RawGenesisEntry::Top(key, value) => {
+				top.insert(key, value);
+				Ok(())
+			},
+			RawGenesisEntry::Child(..) => Err("unexpected child storage".into()),
+		}).unwrap();
+
+		assert_eq!(top, spec.build_storage().unwrap().top);
+	}
+
+	#[test]
+	fn read_raw_genesis_needs_a_raw_genesis() {
+		let spec = br#"{ "name": "Test", "genesis": { "runtime": {} } }"#;
+
+		assert!(read_raw_genesis(&spec[..], &mut |_| Ok(())).is_err());
+	}
 }
@@ -110,7 +110,9 @@
 mod chain_spec;
 mod extension;
 
-pub use chain_spec::{ChainSpec as GenericChainSpec, NoExtension};
+pub use chain_spec::{
+	ChainSpec as GenericChainSpec, AuthoritiesHook, NoExtension, RawGenesisEntry, read_raw_genesis,
+};
 pub use extension::{Group, Fork, Forks, Extension, GetExtension, get_extension};
 pub use sc_chain_spec_derive::{ChainSpecExtension, ChainSpecGroup};
 pub use sp_chain_spec::{Properties, ChainType};
@@ -119,7 +121,7 @@ use serde::{Serialize, de::DeserializeOwned};
 use sp_runtime::BuildStorage;
 use sc_network::config::MultiaddrWithPeerId;
 use sc_telemetry::TelemetryEndpoints;
-use sp_core::storage::Storage;
+use sp_core::{ed25519, sr25519, storage::Storage};
 
 /// A set of traits for the runtime genesis config.
 pub trait RuntimeGenesis: Serialize + DeserializeOwned + BuildStorage {}
@@ -147,6 +149,10 @@ pub trait ChainSpec: BuildStorage + Send {
 	fn extensions(&self) -> &dyn GetExtension;
 	/// Add a bootnode to the list.
 	fn add_boot_node(&mut self, addr: MultiaddrWithPeerId);
+	/// Remove all the bootnodes from the list.
+	fn clear_boot_nodes(&mut self);
+	/// Set the spec name and id.
+	fn set_name_and_id(&mut self, name: String, id: String);
 	/// Return spec as JSON.
 	fn as_json(&self, raw: bool) -> Result<String, String>;
 	/// Return StorageBuilder for this spec.
@@ -157,4 +163,28 @@ pub trait ChainSpec: BuildStorage + Send {
 	///
 	/// This will be used as storage at genesis.
 	fn set_storage(&mut self, storage: Storage);
+	/// Set the storage that should be used by this chain spec, with the state root it is
+	/// expected to have.
+	///
+	/// The root is recorded in the raw genesis, to be checked when the state is imported. By
+	/// default the root is not recorded.
+	fn set_storage_with_root(&mut self, storage: Storage, _state_root: Vec<u8>) {
+		self.set_storage(storage)
+	}
+	/// The state root recorded in the raw genesis, if any.
+	///
+	/// By default no state root is recorded.
+	fn genesis_state_root(&self) -> Result<Option<Vec<u8>>, String> {
+		Ok(None)
+	}
+	/// Set the initial authorities of the genesis runtime configuration `genesis`, given as JSON,
+	/// from their sr25519 and ed25519 public keys.
+	///
+	/// Returns `false` if the chain specification has no hook to set them, see
+	/// `GenericChainSpec::with_authorities_hook`.
+	fn set_genesis_authorities(
+		&self,
+		genesis: &mut serde_json::Value,
+		authorities: &[(sr25519::Public, ed25519::Public)],
+	) -> Result<bool, String>;
 }
@@ -151,7 +151,7 @@ impl Into<sc_service::config::RpcMethods> for RpcMethods {
 arg_enum! {
 	/// Database backend
 	#[allow(missing_docs)]
-	#[derive(Debug, Clone, Copy)]
+	#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 	pub enum Database {
 		// Facebooks RocksDB
 		RocksDb,
@@ -159,9 +159,58 @@ arg_enum! {
 		SubDb,
 		// ParityDb. https://github.com/paritytech/parity-db/
 		ParityDb,
+		// The backend of the existing database, RocksDB if there is none.
+		Auto,
+	}
+}
+
+impl Database {
+	/// Whether the backend is compiled in. `Auto` always is.
+	pub fn is_enabled(self) -> bool {
+		use sc_service::config::DatabaseConfig;
+
+		let path = Default::default();
+		match self {
+			Database::RocksDb => DatabaseConfig::RocksDb { path, cache_size: 0 }.is_enabled(),
+			Database::SubDb => DatabaseConfig::SubDb { path }.is_enabled(),
+			Database::ParityDb => DatabaseConfig::ParityDb { path }.is_enabled(),
+			Database::Auto => true,
+		}
+	}
+
+	/// Returns list of variants that are compiled in.
+	pub fn enabled_variants() -> Vec<&'static str> {
+		Self::variants()
+			.iter()
+			.cloned()
+			.filter(|name| name.parse::<Self>().map_or(false, Self::is_enabled))
+			.collect()
 	}
 }
 
+arg_enum! {
+	/// Format of exported blocks.
+	#[allow(missing_docs)]
+	#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+	pub enum BlocksFormat {
+		// A stream of JSON encoded blocks.
+		Json,
+		// One JSON object per line, holding the SCALE encoded block.
+		Jsonl,
+		// SCALE encoded blocks.
+		Binary,
+	}
+}
+
+impl Into<sc_service::chain_ops::BlocksFormat> for BlocksFormat {
+	fn into(self) -> sc_service::chain_ops::BlocksFormat {
+		match self {
+			BlocksFormat::Json => sc_service::chain_ops::BlocksFormat::Json,
+			BlocksFormat::Jsonl => sc_service::chain_ops::BlocksFormat::JsonLines,
+			BlocksFormat::Binary => sc_service::chain_ops::BlocksFormat::Binary,
+		}
+	}
+}
 
 arg_enum! {
 	/// Whether off-chain workers are enabled.
@@ -0,0 +1,457 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Resolution of the chain specifications given with `--chain` that are not built into the node.
+
+use log::info;
+use sc_service::{ChainSpec, ChainSpecExtension, GenericChainSpec, RuntimeGenesis};
+use sp_core::hashing::{blake2_256, sha2_256};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Magic bytes at the start of gzip compressed files.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Top-level fields of a chain specification that aren't extensions.
+const SPEC_FIELDS: &[&str] = &[
+	"name",
+	"id",
+	"chainType",
+	"bootNodes",
+	"telemetryEndpoints",
+	"protocolId",
+	"properties",
+	"consensusEngine",
+	"genesis",
+];
+
+/// Options of the download of a chain specification given as URL.
+#[derive(Debug, Clone, Default)]
+pub struct ChainSpecDownload {
+	/// Download the chain specification even if it is cached.
+	pub refresh: bool,
+	/// Expected SHA-256 hash of the chain specification, hex-encoded.
+	pub sha256: Option<String>,
+}
+
+/// Where a chain specification that isn't built into the node comes from.
+#[derive(Debug, PartialEq)]
+pub enum ChainSpecSource<'a> {
+	/// A chain specification given inline as JSON.
+	Json(&'a str),
+	/// The path of a JSON chain specification file.
+	File(&'a Path),
+}
+
+impl<'a> ChainSpecSource<'a> {
+	/// Determine the source of the chain specification `id`.
+	///
+	/// An `id` starting with `{` is an inline JSON chain specification, anything else is a path.
+	pub fn from_id(id: &'a str) -> Self {
+		if id.trim_start().starts_with('{') {
+			ChainSpecSource::Json(id)
+		} else {
+			ChainSpecSource::File(Path::new(id))
+		}
+	}
+}
+
+/// Load a chain specification given with `--chain` that is not one of the built-in chains.
+///
+/// The errors name the path of the chain specification file that couldn't be read or parsed.
+pub fn load_chain_spec<G, E>(id: &str) -> Result<GenericChainSpec<G, E>, String>
+where
+	G: RuntimeGenesis,
+	E: ChainSpecExtension,
+{
+	match ChainSpecSource::from_id(id) {
+		ChainSpecSource::Json(json) => {
+			info!("📋 Loading chain specification from inline JSON");
+			GenericChainSpec::from_json_bytes(json.as_bytes().to_vec())
+				.map_err(|e| format!("Invalid inline chain specification: {}", e))
+		},
+		ChainSpecSource::File(path) => {
+			if !path.is_file() {
+				return Err("Unknown chain, it is neither a built-in chain nor a chain specification file".into());
+			}
+
+			if is_gzip(path)? {
+				info!("📋 Loading gzip compressed chain specification from {}", path.display());
+				return GenericChainSpec::from_json_bytes(read_gzip(path)?)
+					.map_err(|e| format!("Failed to load chain specification {}: {}", path.display(), e));
+			}
+
+			info!("📋 Loading chain specification from {}", path.display());
+			GenericChainSpec::from_json_file(path.to_path_buf())
+				.map_err(|e| format!("Failed to load chain specification {}: {}", path.display(), e))
+		},
+	}
+}
+
+/// Returns true if the `--chain` value `id` is the URL of a chain specification.
+pub(crate) fn is_chain_spec_url(id: &str) -> bool {
+	id.starts_with("http://") || id.starts_with("https://")
+}
+
+/// Download the chain specification at `url` into `cache_dir` and return the path of the file.
+///
+/// A cached copy is reused unless a refresh is requested or it doesn't match the expected hash.
+pub(crate) fn fetch_chain_spec(
+	url: &str,
+	cache_dir: &Path,
+	options: &ChainSpecDownload,
+) -> Result<PathBuf, String> {
+	let expected = options.sha256.as_ref().map(|hash| parse_sha256(hash)).transpose()?;
+	let path = cache_dir.join(format!("{}.json", to_hex(&blake2_256(url.as_bytes())[..16])));
+
+	if !options.refresh && path.is_file() {
+		let cached = fs::read(&path)
+			.map_err(|e| format!("Failed to read chain specification {}: {}", path.display(), e))?;
+		if expected.map_or(true, |expected| sha2_256(&cached) == expected) {
+			info!("📋 Using the chain specification from {} cached in {}", url, path.display());
+			return Ok(path);
+		}
+		info!("📋 The cached chain specification doesn't match `--chain-spec-sha256`, downloading it again");
+	}
+
+	info!("📋 Downloading chain specification from {}", url);
+	let data = download(url)
+		.map_err(|e| format!("Failed to download chain specification from {}: {}", url, e))?;
+
+	if let Some(expected) = expected {
+		let hash = sha2_256(&data);
+		if hash != expected {
+			return Err(format!(
+				"The chain specification downloaded from {} has the SHA-256 hash {}, but {} is expected",
+				url,
+				to_hex(&hash),
+				to_hex(&expected),
+			));
+		}
+	}
+
+	let json = if data.starts_with(&GZIP_MAGIC) {
+		gunzip(&data[..])
+			.map_err(|e| format!("Failed to decompress chain specification from {}: {}", url, e))?
+	} else {
+		data.clone()
+	};
+	let spec: serde_json::Value = serde_json::from_slice(&json)
+		.map_err(|e| format!("The chain specification downloaded from {} is invalid: {}", url, e))?;
+	if spec.get("id").map_or(true, |id| !id.is_string()) {
+		return Err(format!("The file downloaded from {} is not a chain specification", url));
+	}
+
+	let tmp_path = path.with_extension("json.tmp");
+	fs::create_dir_all(cache_dir)
+		.and_then(|_| fs::write(&tmp_path, &data))
+		.and_then(|_| fs::rename(&tmp_path, &path))
+		.map_err(|e| format!("Failed to cache chain specification in {}: {}", path.display(), e))?;
+
+	Ok(path)
+}
+
+/// Fetch the content of `url`.
+#[cfg(feature = "db")]
+fn download(url: &str) -> Result<Vec<u8>, String> {
+	let uri: hyper::Uri = url.parse().map_err(|e| format!("Invalid URL: {}", e))?;
+	let mut runtime = tokio::runtime::Builder::new()
+		.basic_scheduler()
+		.enable_all()
+		.build()
+		.map_err(|e| e.to_string())?;
+
+	runtime.block_on(async {
+		let client = hyper::Client::builder()
+			.build::<_, hyper::Body>(hyper_rustls::HttpsConnector::new());
+		let response = client.get(uri).await.map_err(|e| e.to_string())?;
+		if !response.status().is_success() {
+			return Err(format!("HTTP status {}", response.status()));
+		}
+		let gzip_encoded = response.headers()
+			.get(hyper::header::CONTENT_ENCODING)
+			.map_or(false, |encoding| encoding == "gzip");
+
+		let body = hyper::body::to_bytes(response.into_body())
+			.await
+			.map_err(|e| e.to_string())?;
+		if !gzip_encoded {
+			return Ok(body.to_vec());
+		}
+
+		gunzip(&body[..]).map_err(|e| format!("Invalid gzip content encoding: {}", e))
+	})
+}
+
+/// Fetch the content of `url`.
+#[cfg(not(feature = "db"))]
+fn download(_url: &str) -> Result<Vec<u8>, String> {
+	Err("downloading requires the `db` feature, which this node was compiled without".into())
+}
+
+/// Decompress the gzip compressed data read from `reader`.
+#[cfg(feature = "db")]
+fn gunzip<R: Read>(reader: R) -> std::io::Result<Vec<u8>> {
+	let mut data = Vec::new();
+	flate2::read::GzDecoder::new(reader).read_to_end(&mut data)?;
+	Ok(data)
+}
+
+/// Decompress the gzip compressed data read from `reader`.
+#[cfg(not(feature = "db"))]
+fn gunzip<R: Read>(_reader: R) -> std::io::Result<Vec<u8>> {
+	Err(std::io::Error::new(
+		std::io::ErrorKind::Other,
+		"gzip requires the `db` feature, which this node was compiled without",
+	))
+}
+
+/// Parse a hex-encoded SHA-256 hash.
+fn parse_sha256(hash: &str) -> Result<[u8; 32], String> {
+	let hash = hash.trim_start_matches("0x");
+	let invalid = || format!("Invalid SHA-256 hash `{}`, expected 64 hex characters", hash);
+	if hash.len() != 64 {
+		return Err(invalid());
+	}
+
+	let mut bytes = [0; 32];
+	for (i, byte) in bytes.iter_mut().enumerate() {
+		*byte = u8::from_str_radix(&hash[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+	}
+	Ok(bytes)
+}
+
+/// Encode `bytes` as lowercase hex.
+fn to_hex(bytes: &[u8]) -> String {
+	bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Check that the chain specification given with `--chain id` has no field unknown to `spec`, the
+/// chain specification loaded from it.
+///
+/// The unknown fields are reported with their JSON pointer. Built-in chains are not checked.
+pub(crate) fn check_unknown_fields(id: &str, spec: &dyn ChainSpec) -> Result<(), String> {
+	let (source, json) = match ChainSpecSource::from_id(id) {
+		ChainSpecSource::Json(json) => ("inline chain specification".to_string(), json.as_bytes().to_vec()),
+		ChainSpecSource::File(path) if path.is_file() => {
+			let json = if is_gzip(path)? {
+				read_gzip(path)?
+			} else {
+				std::fs::read(path)
+					.map_err(|e| format!("Failed to read chain specification {}: {}", path.display(), e))?
+			};
+			(format!("chain specification {}", path.display()), json)
+		},
+		ChainSpecSource::File(_) => return Ok(()),
+	};
+
+	// The serde errors include the line and column of syntax errors.
+	let given: serde_json::Value = serde_json::from_slice(&json)
+		.map_err(|e| format!("Failed to parse {}: {}", source, e))?;
+	let known: serde_json::Value = serde_json::from_str(&spec.as_json(false)?)
+		.map_err(|e| format!("Failed to parse {}: {}", source, e))?;
+
+	let mut unknown = Vec::new();
+	if let Some(fields) = given.as_object() {
+		for (key, value) in fields {
+			if SPEC_FIELDS.contains(&key.as_str()) {
+				continue;
+			}
+			match known.get(key) {
+				Some(known) => collect_unknown_fields(value, known, format!("/{}", key), &mut unknown),
+				None => unknown.push(format!("/{}", key)),
+			}
+		}
+	}
+
+	if unknown.is_empty() {
+		Ok(())
+	} else {
+		Err(format!("Unknown fields in {}: {}", source, unknown.join(", ")))
+	}
+}
+
+/// Collect the paths of the fields of the `given` object that are missing from `known`.
+fn collect_unknown_fields(
+	given: &serde_json::Value,
+	known: &serde_json::Value,
+	path: String,
+	unknown: &mut Vec<String>,
+) {
+	if let (Some(given), Some(known)) = (given.as_object(), known.as_object()) {
+		for (key, value) in given {
+			let key_path = format!("{}/{}", path, key);
+			match known.get(key) {
+				Some(known) => collect_unknown_fields(value, known, key_path, unknown),
+				None => unknown.push(key_path),
+			}
+		}
+	}
+}
+
+/// Read and decompress the gzip compressed chain specification at `path`.
+fn read_gzip(path: &Path) -> Result<Vec<u8>, String> {
+	gunzip(open(path)?)
+		.map_err(|e| format!("Failed to decompress chain specification {}: {}", path.display(), e))
+}
+
+/// Open the chain specification file at `path`.
+fn open(path: &Path) -> Result<File, String> {
+	File::open(path).map_err(|e| format!("Failed to read chain specification {}: {}", path.display(), e))
+}
+
+/// Returns true if the file at `path` is gzip compressed, based on its extension or its first bytes.
+fn is_gzip(path: &Path) -> Result<bool, String> {
+	if path.extension().map_or(false, |ext| ext == "gz") {
+		return Ok(true);
+	}
+
+	let mut magic = [0; 2];
+	match open(path)?.read_exact(&mut magic) {
+		Ok(()) => Ok(magic == GZIP_MAGIC),
+		// Too short to be compressed, the JSON parser reports the error.
+		Err(_) => Ok(false),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_service::{ChainSpec, NoExtension};
+	use std::io::Write;
+
+	const SPEC: &str = r#"{
+		"name": "Test",
+		"id": "test",
+		"bootNodes": [],
+		"telemetryEndpoints": null,
+		"protocolId": null,
+		"properties": null,
+		"consensusEngine": null,
+		"genesis": { "raw": { "top": {}, "childrenDefault": {} } }
+	}"#;
+
+	type TestSpec = GenericChainSpec<(), NoExtension>;
+
+	#[test]
+	fn resolves_source_from_id() {
+		assert_eq!(ChainSpecSource::from_id(SPEC), ChainSpecSource::Json(SPEC));
+		assert_eq!(
+			ChainSpecSource::from_id("specs/test.json"),
+			ChainSpecSource::File(Path::new("specs/test.json")),
+		);
+	}
+
+	#[test]
+	fn loads_inline_json() {
+		let spec = load_chain_spec::<(), NoExtension>(SPEC).unwrap();
+		assert_eq!(spec.id(), "test");
+
+		let err = load_chain_spec::<(), NoExtension>("{ \"name\": \"Test\" }").err().unwrap();
+		assert!(err.starts_with("Invalid inline chain specification"));
+	}
+
+	#[test]
+	fn loads_file() {
+		let mut file = tempfile::NamedTempFile::new().unwrap();
+		file.write_all(SPEC.as_bytes()).unwrap();
+
+		let spec: TestSpec = load_chain_spec(file.path().to_str().unwrap()).unwrap();
+		assert_eq!(spec.id(), "test");
+	}
+
+	#[test]
+	fn reports_invalid_file_with_path() {
+		let mut file = tempfile::NamedTempFile::new().unwrap();
+		file.write_all(b"not json").unwrap();
+		let path = file.path().to_str().unwrap();
+
+		let err = load_chain_spec::<(), NoExtension>(path).err().unwrap();
+		assert!(err.contains(&format!("Failed to load chain specification {}", path)));
+	}
+
+	#[test]
+	#[cfg(feature = "db")]
+	fn loads_gzip_file() {
+		use flate2::{write::GzEncoder, Compression};
+
+		// Detected from the magic bytes even without the `.gz` extension.
+		let file = tempfile::NamedTempFile::new().unwrap();
+		let mut encoder = GzEncoder::new(file.reopen().unwrap(), Compression::default());
+		encoder.write_all(SPEC.as_bytes()).unwrap();
+		encoder.finish().unwrap();
+
+		let spec: TestSpec = load_chain_spec(file.path().to_str().unwrap()).unwrap();
+		assert_eq!(spec.id(), "test");
+	}
+
+	#[test]
+	#[cfg(feature = "db")]
+	fn reports_invalid_gzip_file() {
+		let mut file = tempfile::Builder::new().suffix(".json.gz").tempfile().unwrap();
+		file.write_all(SPEC.as_bytes()).unwrap();
+		let path = file.path().to_str().unwrap();
+
+		let err = load_chain_spec::<(), NoExtension>(path).err().unwrap();
+		assert!(err.contains(&format!("Failed to decompress chain specification {}", path)));
+	}
+
+	#[test]
+	fn reports_unknown_fields() {
+		let spec: TestSpec = load_chain_spec(SPEC).unwrap();
+		assert!(check_unknown_fields(SPEC, &spec).is_ok());
+
+		let typo = SPEC.replace("\"bootNodes\"", "\"bootnodes\": [], \"bootNodes\"");
+		let spec: TestSpec = load_chain_spec(&typo).unwrap();
+		let err = check_unknown_fields(&typo, &spec).err().unwrap();
+		assert!(err.contains("Unknown fields in inline chain specification: /bootnodes"));
+
+		// Built-in chains are not checked.
+		assert!(check_unknown_fields("dev", &spec).is_ok());
+	}
+
+	#[test]
+	fn uses_cached_chain_spec() {
+		let cache_dir = tempfile::tempdir().unwrap();
+		let url = "http://localhost:1/spec.json";
+		let mut options = ChainSpecDownload::default();
+
+		// Nothing is listening on port 1, so the spec can only come from the cache.
+		let err = fetch_chain_spec(url, cache_dir.path(), &options).err().unwrap();
+		assert!(err.starts_with("Failed to download chain specification"));
+
+		let path = cache_dir.path().join(format!("{}.json", to_hex(&blake2_256(url.as_bytes())[..16])));
+		fs::write(&path, SPEC).unwrap();
+		assert_eq!(fetch_chain_spec(url, cache_dir.path(), &options).unwrap(), path);
+
+		options.sha256 = Some(to_hex(&sha2_256(SPEC.as_bytes())));
+		assert_eq!(fetch_chain_spec(url, cache_dir.path(), &options).unwrap(), path);
+
+		options.sha256 = Some("00".repeat(32));
+		assert!(fetch_chain_spec(url, cache_dir.path(), &options).is_err());
+		options.sha256 = Some("not a hash".into());
+		assert!(fetch_chain_spec(url, cache_dir.path(), &options).err().unwrap().starts_with("Invalid SHA-256"));
+	}
+
+	#[test]
+	fn reports_unknown_chain() {
+		let err = load_chain_spec::<(), NoExtension>("does-not-exist").err().unwrap();
+		assert!(err.starts_with("Unknown chain"));
+	}
+}
@@ -0,0 +1,353 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(feature = "db")]
+use crate::commands::compact_db_cmd::{open_error, rocksdb_error};
+#[cfg(not(feature = "db"))]
+use crate::commands::utils::db_feature_error;
+use crate::error;
+use crate::params::{DatabaseParams, SharedParams};
+use crate::CliConfiguration;
+#[cfg(feature = "db")]
+use crate::config::database_parent_dir;
+#[cfg(feature = "db")]
+use sc_client_db::{column_family_name, columns, meta_keys};
+use sc_service::{config::DatabaseConfig, Configuration, Role};
+use sp_core::hexdisplay::HexDisplay;
+#[cfg(feature = "db")]
+use sp_runtime::codec::Decode;
+#[cfg(feature = "db")]
+use sp_runtime::traits::Header as HeaderT;
+use sp_runtime::traits::Block as BlockT;
+#[cfg(feature = "db")]
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+/// Name of the manifest file written next to the database of a backup.
+const MANIFEST_FILE: &str = "backup.json";
+
+/// The `backup` command used to take a consistent snapshot of the database of a stopped node.
+#[derive(Debug, StructOpt, Clone)]
+pub struct BackupCmd {
+	/// Directory to write the backup to. It must not exist yet.
+	///
+	/// The backup uses the layout of a base path, so it can be restored by passing this directory
+	/// to `--base-path`. The node using the database must be stopped.
+	#[structopt(long, short = "o", value_name = "DIR", parse(from_os_str))]
+	pub output: PathBuf,
+
+	/// Open the backup once it is written and check that its best block header decodes.
+	#[structopt(long)]
+	pub verify: bool,
+
+	/// Back up the database of a light client.
+	#[structopt(long)]
+	pub light: bool,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl BackupCmd {
+	/// Run the backup command
+	#[cfg(feature = "db")]
+	pub fn run<B: BlockT>(&self, config: Configuration) -> error::Result<()> {
+		let db_path = match &config.database {
+			DatabaseConfig::RocksDb { path, .. } => path.clone(),
+			other => return Err(error::Error::Input(format!(
+				"Backups are not supported for the {} backend",
+				other,
+			))),
+		};
+		let chain_id = config.chain_spec.id();
+
+		if self.output.exists() {
+			return Err(error::Error::Input(format!("{:?} already exists", self.output)));
+		}
+
+		let chain_dir = self.output.join("chains").join(chain_id);
+		let backup_dir = database_parent_dir(&chain_dir, &config.role);
+		let backup_path = backup_dir.join(db_path.file_name().unwrap_or_else(|| OsStr::new("db")));
+		fs::create_dir_all(&backup_dir)?;
+
+		// A checkpoint is only consistent when created by the process owning the database lock,
+		// so opening the database fails if a node is still running.
+		let db = open_database(&db_path)?;
+		let (best, genesis_hash) = read_manifest_meta(&db)?;
+		rocksdb::checkpoint::Checkpoint::new(&db)
+			.and_then(|checkpoint| checkpoint.create_checkpoint(&backup_path))
+			.map_err(rocksdb_error)?;
+		let (best_number, best_hash) = split_lookup_key(&best)?;
+
+		let manifest = serde_json::json!({
+			"chain_id": chain_id,
+			"best_number": best_number,
+			"best_hash": format!("0x{}", HexDisplay::from(&best_hash)),
+			"genesis_hash": format!("0x{}", HexDisplay::from(&genesis_hash)),
+		});
+		fs::write(
+			chain_dir.join(MANIFEST_FILE),
+			serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?,
+		)?;
+
+		println!(
+			"Backup of {:?} at block #{} written to {:?}",
+			db_path,
+			best_number,
+			self.output,
+		);
+
+		if self.verify {
+			verify_backup::<B>(&backup_path, &best)?;
+			println!("Backup verified: best block #{} decodes", best_number);
+		}
+
+		Ok(())
+	}
+
+	/// Run the backup command
+	#[cfg(not(feature = "db"))]
+	pub fn run<B: BlockT>(&self, _config: Configuration) -> error::Result<()> {
+		Err(db_feature_error("`backup`"))
+	}
+}
+
+impl CliConfiguration for BackupCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+
+	fn role(&self, _is_dev: bool) -> error::Result<Role> {
+		Ok(if self.light { Role::Light } else { Role::Full })
+	}
+}
+
+/// Check that a backup restored in `config_dir` was taken from the chain `chain_id`, and that
+/// `database` has the genesis block the backup was taken with.
+///
+/// Does nothing if `config_dir` doesn't come from a backup.
+pub(crate) fn check_backup_manifest(
+	config_dir: &Path,
+	chain_id: &str,
+	database: &DatabaseConfig,
+) -> error::Result<()> {
+	let path = config_dir.join(MANIFEST_FILE);
+	if !path.exists() {
+		return Ok(());
+	}
+
+	let manifest: serde_json::Value = serde_json::from_slice(&fs::read(&path)?)
+		.map_err(|e| format!("Invalid backup manifest {:?}: {}", path, e))?;
+
+	match manifest.get("chain_id").and_then(|id| id.as_str()) {
+		Some(id) if id == chain_id => {},
+		Some(id) => return Err(error::Error::Input(format!(
+			"The backup in {:?} was taken from chain `{}`, not `{}`",
+			config_dir,
+			id,
+			chain_id,
+		))),
+		None => return Err(error::Error::Input(format!(
+			"Missing chain id in backup manifest {:?}",
+			path,
+		))),
+	}
+
+	let expected = manifest.get("genesis_hash")
+		.and_then(|hash| hash.as_str())
+		.ok_or_else(|| error::Error::Input(format!(
+			"Missing genesis hash in backup manifest {:?}",
+			path,
+		)))?;
+	let db_path = match database {
+		// The database is created from the chain specification if it doesn't exist yet.
+		DatabaseConfig::RocksDb { path, .. } if path.join("CURRENT").exists() => path,
+		_ => return Ok(()),
+	};
+	let genesis_hash = format!("0x{}", HexDisplay::from(&read_genesis_hash(db_path)?));
+	if genesis_hash != expected {
+		return Err(error::Error::Input(format!(
+			"The backup in {:?} was taken from a chain with genesis {}, but the database {:?} has \
+			genesis {}",
+			config_dir,
+			expected,
+			db_path,
+			genesis_hash,
+		)));
+	}
+
+	Ok(())
+}
+
+/// Open the RocksDB database at `path` with all its column families.
+///
+/// This takes the lock file of the database, so it fails if a node is running on it.
+#[cfg(feature = "db")]
+pub(crate) fn open_database(path: &Path) -> error::Result<rocksdb::DB> {
+	let opts = rocksdb::Options::default();
+	let columns = rocksdb::DB::list_cf(&opts, path).map_err(rocksdb_error)?;
+	rocksdb::DB::open_cf(&opts, path, &columns).map_err(|e| open_error(path, e))
+}
+
+/// Open the RocksDB database at `path` read-only, which works while a node is running on it.
+#[cfg(feature = "db")]
+pub(crate) fn open_database_read_only(path: &Path) -> error::Result<rocksdb::DB> {
+	let opts = rocksdb::Options::default();
+	let columns = rocksdb::DB::list_cf(&opts, path).map_err(rocksdb_error)?;
+	rocksdb::DB::open_cf_for_read_only(&opts, path, &columns, false).map_err(rocksdb_error)
+}
+
+/// Read the best block lookup key and the genesis hash written to the manifest.
+#[cfg(feature = "db")]
+fn read_manifest_meta(db: &rocksdb::DB) -> error::Result<(Vec<u8>, Vec<u8>)> {
+	Ok((read_meta(db, meta_keys::BEST_BLOCK)?, read_meta(db, meta_keys::GENESIS_HASH)?))
+}
+
+/// Read the hash of the genesis block recorded in the database at `path`.
+#[cfg(feature = "db")]
+pub(crate) fn read_genesis_hash(path: &Path) -> error::Result<Vec<u8>> {
+	read_meta(&open_database_read_only(path)?, meta_keys::GENESIS_HASH)
+}
+
+/// Read the hash of the genesis block recorded in the database at `path`.
+#[cfg(not(feature = "db"))]
+pub(crate) fn read_genesis_hash(_path: &Path) -> error::Result<Vec<u8>> {
+	Err(db_feature_error("Reading the genesis hash of the database"))
+}
+
+/// Whether the database at `path` was created by a light client.
+#[cfg(feature = "db")]
+pub(crate) fn is_light_database(path: &Path) -> error::Result<bool> {
+	Ok(read_meta(&open_database_read_only(path)?, meta_keys::TYPE)? == b"light")
+}
+
+/// Whether the database at `path` was created by a light client.
+#[cfg(not(feature = "db"))]
+pub(crate) fn is_light_database(_path: &Path) -> error::Result<bool> {
+	Err(db_feature_error("Reading the type of the database"))
+}
+
+/// Read a value of the meta column, which must be present.
+#[cfg(feature = "db")]
+pub(crate) fn read_meta(db: &rocksdb::DB, key: &[u8]) -> error::Result<Vec<u8>> {
+	let column = db.cf_handle(&column_family_name(columns::META))
+		.ok_or_else(|| error::Error::Input("The database has no meta column".into()))?;
+
+	db.get_cf(column, key)
+		.map_err(rocksdb_error)?
+		.ok_or_else(|| error::Error::Input(format!(
+			"Missing `{}` in the database meta, is it the database of a full node?",
+			String::from_utf8_lossy(key),
+		)))
+}
+
+/// Split a block lookup key into the block number and hash.
+#[cfg(feature = "db")]
+pub(crate) fn split_lookup_key(key: &[u8]) -> error::Result<(u32, &[u8])> {
+	if key.len() < 4 {
+		return Err(error::Error::Input("Invalid best block lookup key".into()));
+	}
+
+	let mut number = [0; 4];
+	number.copy_from_slice(&key[..4]);
+	Ok((u32::from_be_bytes(number), &key[4..]))
+}
+
+/// Check that the best block header of the backup at `path` decodes and matches `best`.
+#[cfg(feature = "db")]
+fn verify_backup<B: BlockT>(path: &Path, best: &[u8]) -> error::Result<()> {
+	let db = open_database(path)?;
+
+	if read_meta(&db, meta_keys::BEST_BLOCK)? != best {
+		return Err(error::Error::Input("The best block of the backup doesn't match".into()));
+	}
+
+	let column = db.cf_handle(&column_family_name(columns::HEADER))
+		.ok_or_else(|| error::Error::Input("The backup has no header column".into()))?;
+	let encoded = db.get_cf(column, best)
+		.map_err(rocksdb_error)?
+		.ok_or_else(|| error::Error::Input("The best block header is missing from the backup".into()))?;
+	let header = B::Header::decode(&mut &encoded[..])
+		.map_err(|e| format!("Failed to decode the best block header of the backup: {:?}", e))?;
+
+	let (_, best_hash) = split_lookup_key(best)?;
+	if header.hash().as_ref() != best_hash {
+		return Err(error::Error::Input("The best block header of the backup has a wrong hash".into()));
+	}
+
+	Ok(())
+}
+
+#[cfg(all(test, feature = "db"))]
+mod tests {
+	use super::*;
+
+	fn restored_backup(genesis_hash: &[u8]) -> (tempfile::TempDir, DatabaseConfig) {
+		let dir = tempfile::tempdir().unwrap();
+		let db_path = dir.path().join("db");
+		{
+			let mut opts = rocksdb::Options::default();
+			opts.create_if_missing(true);
+			opts.create_missing_column_families(true);
+			let meta = column_family_name(columns::META);
+			let db = rocksdb::DB::open_cf(&opts, &db_path, &[&meta]).unwrap();
+			db.put_cf(db.cf_handle(&meta).unwrap(), meta_keys::GENESIS_HASH, genesis_hash).unwrap();
+		}
+
+		let manifest = serde_json::json!({
+			"chain_id": "dev",
+			"genesis_hash": "0x0101",
+		});
+		fs::write(dir.path().join(MANIFEST_FILE), manifest.to_string()).unwrap();
+
+		let database = DatabaseConfig::RocksDb { path: db_path, cache_size: 128 };
+		(dir, database)
+	}
+
+	#[test]
+	fn check_backup_manifest_accepts_the_backed_up_chain() {
+		let (dir, database) = restored_backup(&[1, 1]);
+
+		assert!(check_backup_manifest(dir.path(), "dev", &database).is_ok());
+	}
+
+	#[test]
+	fn check_backup_manifest_rejects_another_chain() {
+		let (dir, database) = restored_backup(&[1, 1]);
+
+		assert!(check_backup_manifest(dir.path(), "local", &database).is_err());
+	}
+
+	#[test]
+	fn check_backup_manifest_rejects_another_genesis() {
+		let (dir, database) = restored_backup(&[2, 2]);
+
+		assert!(check_backup_manifest(dir.path(), "dev", &database).is_err());
+	}
+}
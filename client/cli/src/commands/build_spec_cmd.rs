@@ -16,15 +16,32 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::commands::utils::{parse_authority_keys, parse_sr25519_public, ss58_format};
+#[cfg(not(feature = "db"))]
+use crate::commands::utils::db_feature_error;
 use crate::error;
 use crate::params::NodeKeyParams;
 use crate::params::SharedParams;
 use crate::CliConfiguration;
+use lazy_static::lazy_static;
 use log::info;
 use sc_network::config::build_multiaddr;
-use sc_service::{config::MultiaddrWithPeerId, Configuration};
+use sc_service::{config::MultiaddrWithPeerId, ChainSpec, Configuration};
+use sp_core::crypto::{Ss58AddressFormat, Ss58Codec};
 use structopt::StructOpt;
+use std::fs;
 use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// TCP port of the default bootnode when `--bootnode-port` isn't given.
+const DEFAULT_BOOTNODE_PORT: u16 = 30333;
+
+lazy_static! {
+	static ref BOOTNODE_PORT_HELP: String = format!(
+		"TCP port of the default bootnode added to the specification [default: {}]",
+		DEFAULT_BOOTNODE_PORT,
+	);
+}
 
 /// The `build-spec` command used to build a specification.
 #[derive(Debug, StructOpt, Clone)]
@@ -35,11 +52,55 @@ pub struct BuildSpecCmd {
 
 	/// Disable adding the default bootnode to the specification.
 	///
-	/// By default the `/ip4/127.0.0.1/tcp/30333/p2p/NODE_PEER_ID` bootnode is added to the
-	/// specification when no bootnode exists.
+	/// By default the `/ip4/127.0.0.1/tcp/PORT/p2p/NODE_PEER_ID` bootnode, with the port of
+	/// `--bootnode-port`, is added to the specification when no bootnode exists. With this flag,
+	/// the bootnodes inherited from the base specification are removed as well.
 	#[structopt(long = "disable-default-bootnode")]
 	pub disable_default_bootnode: bool,
 
+	/// TCP port of the default bootnode added to the specification.
+	#[structopt(
+		long = "bootnode-port",
+		value_name = "PORT",
+		conflicts_with = "disable-default-bootnode",
+		help = BOOTNODE_PORT_HELP.as_str()
+	)]
+	pub bootnode_port: Option<u16>,
+
+	/// Deep-merge the JSON object of the given file into the genesis runtime configuration.
+	///
+	/// Objects are merged key by key and any other value, including arrays, replaces the value of
+	/// the specification. Every key of the patch must exist in the genesis configuration.
+	/// With `--dry-run`, only the merged genesis configuration is printed.
+	#[structopt(long, value_name = "FILE", parse(from_os_str), conflicts_with = "raw")]
+	pub genesis_patch: Option<PathBuf>,
+
+	/// Set the sudo key of the genesis configuration.
+	///
+	/// The value is an SS58 address in the format of the chain, or a development URI like `//Alice`.
+	#[structopt(long, value_name = "ACCOUNT", conflicts_with = "raw")]
+	pub sudo_key: Option<String>,
+
+	/// Replace the initial authorities of the genesis configuration. Can be passed multiple times.
+	///
+	/// The value is a development URI like `//Alice`, from which the sr25519 and ed25519 authority
+	/// keys are derived, or the SS58 addresses of both keys as
+	/// `<sr25519 address>:<ed25519 address>`.
+	#[structopt(long = "authority", value_name = "KEYS", conflicts_with = "raw")]
+	pub authorities: Vec<String>,
+
+	/// Write the specification to the given file instead of stdout.
+	///
+	/// The file is replaced atomically, so it is never left half written.
+	#[structopt(long, short = "o", value_name = "PATH", parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	/// Compress the specification written with `--output` with gzip.
+	///
+	/// This is the default when the output file name ends with `.gz`.
+	#[structopt(long, requires = "output")]
+	pub compress: bool,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub shared_params: SharedParams,
@@ -56,22 +117,261 @@ impl BuildSpecCmd {
 		let mut spec = config.chain_spec;
 		let raw_output = self.raw;
 
-		if spec.boot_nodes().is_empty() && !self.disable_default_bootnode {
+		if self.disable_default_bootnode {
+			spec.clear_boot_nodes();
+		} else if spec.boot_nodes().is_empty() {
+			// When the key is read from a file that doesn't exist yet, a new key is generated and
+			// written to it, so a node started with the same parameters gets this peer id.
 			let keys = config.network.node_key.into_keypair()?;
 			let peer_id = keys.public().into_peer_id();
 			let addr = MultiaddrWithPeerId {
-				multiaddr: build_multiaddr![Ip4([127, 0, 0, 1]), Tcp(30333u16)],
+				multiaddr: build_multiaddr![
+					Ip4([127, 0, 0, 1]),
+					Tcp(self.bootnode_port.unwrap_or(DEFAULT_BOOTNODE_PORT))
+				],
 				peer_id,
 			};
+			info!("Adding bootnode {}", addr);
 			spec.add_boot_node(addr)
 		}
 
-		let json = sc_service::chain_ops::build_spec(&*spec, raw_output)?;
-		if std::io::stdout().write_all(json.as_bytes()).is_err() {
-			let _ = std::io::stderr().write_all(b"Error writing to stdout\n");
+		let mut json = sc_service::chain_ops::build_spec(&*spec, raw_output)?;
+
+		if self.edits_genesis() {
+			let format = ss58_format(&spec.properties())?;
+			let mut value: serde_json::Value = serde_json::from_str(&json)
+				.map_err(|e| format!("Invalid chain spec: {}", e))?;
+			let genesis = value.pointer_mut("/genesis/runtime")
+				.ok_or("The chain spec has no genesis runtime configuration")?;
+
+			let mut edited = Vec::new();
+			if let Some(sudo_key) = &self.sudo_key {
+				edited.push(set_sudo_key(genesis, sudo_key, format)?);
+			}
+			if !self.authorities.is_empty() {
+				edited.extend(set_authorities(genesis, &self.authorities, format, &*spec)?);
+			}
+
+			if let Some(patch_path) = &self.genesis_patch {
+				let patch: serde_json::Value = serde_json::from_slice(&fs::read(patch_path)?)
+					.map_err(|e| format!("Invalid genesis patch {}: {}", patch_path.display(), e))?;
+				if let Some(path) = edited.iter().find(|path| patch.pointer(path).is_some()) {
+					return Err(error::Error::Input(format!(
+						"The genesis patch sets {}, which is already set by `--sudo-key` or `--authority`",
+						path,
+					)));
+				}
+
+				patch_genesis(genesis, &patch)?;
+			}
+
+			if self.shared_params.is_dry_run() {
+				let genesis = serde_json::to_string_pretty(genesis).map_err(|e| e.to_string())?;
+				println!("{}", genesis);
+				return Ok(());
+			}
+
+			json = serde_json::to_string_pretty(&value).map_err(|e| e.to_string())?;
+		}
+
+		match &self.output {
+			Some(path) => {
+				let data = if self.compress || path.extension().map_or(false, |ext| ext == "gz") {
+					compress(json.as_bytes())?
+				} else {
+					json.into_bytes()
+				};
+				write_atomically(path, &data)?;
+				eprintln!("Chain spec written to {} ({} bytes)", path.display(), data.len());
+			},
+			None => if std::io::stdout().write_all(json.as_bytes()).is_err() {
+				let _ = std::io::stderr().write_all(b"Error writing to stdout\n");
+			},
 		}
 		Ok(())
 	}
+
+	/// Returns whether the genesis configuration is modified by the command line.
+	pub fn edits_genesis(&self) -> bool {
+		self.genesis_patch.is_some() || self.sudo_key.is_some() || !self.authorities.is_empty()
+	}
+}
+
+/// Compress the chain specification `json` with gzip.
+#[cfg(feature = "db")]
+fn compress(json: &[u8]) -> error::Result<Vec<u8>> {
+	let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+	encoder.write_all(json)?;
+	Ok(encoder.finish()?)
+}
+
+/// Compress the chain specification `json` with gzip.
+#[cfg(not(feature = "db"))]
+fn compress(_json: &[u8]) -> error::Result<Vec<u8>> {
+	Err(db_feature_error("Compressing the chain specification"))
+}
+
+/// Returns the key of the genesis configuration of a pallet, e.g. `sudo` or `palletSudo`.
+fn pallet_key(genesis: &serde_json::Value, name: &str) -> Option<String> {
+	let prefixed = format!("pallet{}{}", name[..1].to_uppercase(), &name[1..]);
+	vec![name.to_string(), prefixed].into_iter().find(|key| genesis.get(key).is_some())
+}
+
+/// Set the sudo key of `genesis`, returning the path of the modified value.
+fn set_sudo_key(
+	genesis: &mut serde_json::Value,
+	sudo_key: &str,
+	format: Option<Ss58AddressFormat>,
+) -> error::Result<String> {
+	let public = parse_sr25519_public(sudo_key, format)?;
+	let pallet = pallet_key(genesis, "sudo")
+		.ok_or("`--sudo-key` is given, but the genesis has no sudo configuration")?;
+
+	genesis[&pallet]["key"] = public.to_ss58check_with_version(format.unwrap_or_default()).into();
+	Ok(format!("/{}/key", pallet))
+}
+
+/// Replace the initial authorities of `genesis`, returning the paths of the modified values.
+///
+/// They are set by the hook of the chain specification `spec` if it has one, which knows the
+/// session keys of its runtime. Otherwise only the authorities of the aura, babe and grandpa
+/// pallets are replaced.
+fn set_authorities(
+	genesis: &mut serde_json::Value,
+	authorities: &[String],
+	format: Option<Ss58AddressFormat>,
+	spec: &dyn ChainSpec,
+) -> error::Result<Vec<String>> {
+	let format = format.unwrap_or_default();
+	let keys = authorities.iter()
+		.map(|authority| parse_authority_keys(authority, Some(format)))
+		.collect::<error::Result<Vec<_>>>()?;
+
+	let before = genesis.clone();
+	if spec.set_genesis_authorities(genesis, &keys).map_err(error::Error::Input)? {
+		let mut edited = Vec::new();
+		changed_paths(&before, genesis, String::new(), &mut edited);
+		return Ok(edited);
+	}
+
+	// The authorities of a session based runtime come from the session keys, whose layout is
+	// only known to the hook.
+	if pallet_key(genesis, "session").is_some() {
+		return Err(error::Error::Input(
+			"`--authority` is not supported by this chain specification, which has no hook to set \
+			the session keys of its runtime".into()
+		));
+	}
+
+	let mut edited = Vec::new();
+	if let Some(pallet) = pallet_key(genesis, "aura") {
+		genesis[&pallet]["authorities"] = keys.iter()
+			.map(|(sr25519, _)| sr25519.to_ss58check_with_version(format))
+			.collect();
+		edited.push(format!("/{}/authorities", pallet));
+	}
+	if let Some(pallet) = pallet_key(genesis, "babe") {
+		genesis[&pallet]["authorities"] = keys.iter()
+			.map(|(sr25519, _)| serde_json::json!([sr25519.to_ss58check_with_version(format), 1]))
+			.collect();
+		edited.push(format!("/{}/authorities", pallet));
+	}
+	if let Some(pallet) = pallet_key(genesis, "grandpa") {
+		genesis[&pallet]["authorities"] = keys.iter()
+			.map(|(_, ed25519)| serde_json::json!([ed25519.to_ss58check_with_version(format), 1]))
+			.collect();
+		edited.push(format!("/{}/authorities", pallet));
+	}
+
+	if edited.is_empty() {
+		return Err(error::Error::Input(
+			"`--authority` is given, but the genesis has no aura, babe or grandpa configuration".into()
+		));
+	}
+
+	Ok(edited)
+}
+
+/// Deep-merge `patch` into `genesis`, failing with the list of the unknown keys of `patch`.
+fn patch_genesis(genesis: &mut serde_json::Value, patch: &serde_json::Value) -> error::Result<()> {
+	if !patch.is_object() {
+		return Err(error::Error::Input("The genesis patch must be a JSON object".into()));
+	}
+
+	let mut unknown = Vec::new();
+	merge_json(genesis, patch, String::new(), &mut unknown);
+
+	if unknown.is_empty() {
+		Ok(())
+	} else {
+		Err(error::Error::Input(format!(
+			"Unknown keys in the genesis patch: {}",
+			unknown.join(", "),
+		)))
+	}
+}
+
+/// Merge `patch` into `target`, collecting the paths of the keys missing from `target`.
+fn merge_json(
+	target: &mut serde_json::Value,
+	patch: &serde_json::Value,
+	path: String,
+	unknown: &mut Vec<String>,
+) {
+	match (target, patch) {
+		(serde_json::Value::Object(target), serde_json::Value::Object(patch)) => {
+			for (key, value) in patch {
+				let key_path = format!("{}/{}", path, key);
+				match target.get_mut(key) {
+					Some(target) => merge_json(target, value, key_path, unknown),
+					None => unknown.push(key_path),
+				}
+			}
+		},
+		(target, patch) => *target = patch.clone(),
+	}
+}
+
+/// Collect the paths of the values of `after` that differ from `before`, comparing objects key by
+/// key.
+fn changed_paths(
+	before: &serde_json::Value,
+	after: &serde_json::Value,
+	path: String,
+	changed: &mut Vec<String>,
+) {
+	match (before, after) {
+		(serde_json::Value::Object(before), serde_json::Value::Object(after)) => {
+			for (key, value) in after {
+				let key_path = format!("{}/{}", path, key);
+				match before.get(key) {
+					Some(before) => changed_paths(before, value, key_path, changed),
+					None => changed.push(key_path),
+				}
+			}
+		},
+		(before, after) => if before != after {
+			changed.push(path);
+		},
+	}
+}
+
+/// Write `data` to a temporary file next to `path` and rename it to `path`.
+fn write_atomically(path: &Path, data: &[u8]) -> std::io::Result<()> {
+	let file_name = path.file_name().ok_or_else(|| std::io::Error::new(
+		std::io::ErrorKind::InvalidInput,
+		format!("{} is not a file path", path.display()),
+	))?;
+	let mut tmp_name = std::ffi::OsString::from(".");
+	tmp_name.push(file_name);
+	tmp_name.push(".tmp");
+	let tmp_path = path.with_file_name(tmp_name);
+
+	let result = fs::write(&tmp_path, data).and_then(|_| fs::rename(&tmp_path, path));
+	if result.is_err() {
+		let _ = fs::remove_file(&tmp_path);
+	}
+	result
 }
 
 impl CliConfiguration for BuildSpecCmd {
@@ -83,3 +383,90 @@ impl CliConfiguration for BuildSpecCmd {
 		Some(&self.node_key_params)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_service::{ChainType, GenericChainSpec, NoExtension};
+	use serde_json::json;
+
+	#[test]
+	fn genesis_patch_is_merged() {
+		let mut genesis = json!({
+			"balances": { "balances": [["a", 1]] },
+			"sudo": { "key": "a" },
+		});
+
+		patch_genesis(&mut genesis, &json!({
+			"balances": { "balances": [["b", 2], ["c", 3]] },
+			"sudo": { "key": "b" },
+		})).unwrap();
+
+		assert_eq!(genesis, json!({
+			"balances": { "balances": [["b", 2], ["c", 3]] },
+			"sudo": { "key": "b" },
+		}));
+	}
+
+	#[test]
+	fn genesis_patch_rejects_unknown_keys() {
+		let mut genesis = json!({ "sudo": { "key": "a" } });
+
+		let err = patch_genesis(&mut genesis, &json!({
+			"sudo": { "kye": "b" },
+			"sudoo": {},
+		})).unwrap_err();
+
+		assert_eq!(err.to_string(), "Unknown keys in the genesis patch: /sudo/kye, /sudoo");
+	}
+
+	#[test]
+	fn sudo_key_and_authorities_are_set() {
+		let mut genesis = json!({
+			"aura": { "authorities": [] },
+			"grandpa": { "authorities": [] },
+			"sudo": { "key": "" },
+		});
+
+		let format = Some(Ss58AddressFormat::SubstrateAccount);
+		let spec = GenericChainSpec::<(), NoExtension>::from_genesis(
+			"Test",
+			"test",
+			ChainType::Development,
+			|| (),
+			vec![],
+			None,
+			None,
+			None,
+			None,
+		);
+		assert_eq!(set_sudo_key(&mut genesis, "//Alice", format).unwrap(), "/sudo/key");
+		set_authorities(&mut genesis, &["//Alice".into(), "//Bob".into()], format, &spec).unwrap();
+
+		assert_eq!(genesis["sudo"]["key"], "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY");
+		assert_eq!(genesis["aura"]["authorities"].as_array().unwrap().len(), 2);
+		assert_eq!(genesis["grandpa"]["authorities"][0][1], 1);
+
+		let mut genesis = json!({ "palletSession": { "keys": [] } });
+		assert!(set_authorities(&mut genesis, &["//Alice".into()], format, &spec).is_err());
+	}
+
+	#[test]
+	fn changed_paths_are_listed() {
+		let before = json!({ "staking": { "stakers": [1], "count": 1 }, "sudo": { "key": "a" } });
+		let after = json!({ "staking": { "stakers": [2], "count": 1 }, "sudo": { "key": "a" } });
+
+		let mut changed = Vec::new();
+		changed_paths(&before, &after, String::new(), &mut changed);
+		assert_eq!(changed, vec!["/staking/stakers"]);
+	}
+
+	#[test]
+	fn bootnode_port_help_shows_the_default() {
+		let mut help = Vec::new();
+		BuildSpecCmd::clap().write_help(&mut help).unwrap();
+
+		let help = String::from_utf8(help).unwrap();
+		assert!(help.contains(&format!("[default: {}]", DEFAULT_BOOTNODE_PORT)));
+	}
+}
@@ -17,9 +17,10 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
-	CliConfiguration, error, params::{ImportParams, SharedParams, BlockNumberOrHash},
+	CliConfiguration, error, params::{ImportParams, SharedParams, BlockNumber, BlockNumberOrHash},
 };
 use sc_service::{Configuration, ServiceBuilderCommand};
+use sp_runtime::generic::BlockId;
 use sp_runtime::traits::{Block as BlockT, NumberFor};
 use std::{fmt::Debug, str::FromStr};
 use structopt::StructOpt;
@@ -31,6 +32,10 @@ pub struct CheckBlockCmd {
 	#[structopt(value_name = "HASH or NUMBER")]
 	pub input: BlockNumberOrHash,
 
+	/// Check all the blocks from the block number given as input up to this one.
+	#[structopt(long, value_name = "NUMBER")]
+	pub to: Option<BlockNumber>,
+
 	/// The default number of 64KB pages to ever allocate for Wasm execution.
 	///
 	/// Don't alter this unless you know what you're doing.
@@ -62,8 +67,50 @@ impl CheckBlockCmd {
 		<BB::Hash as FromStr>::Err: std::fmt::Debug,
 	{
 		let start = std::time::Instant::now();
-		builder(config)?.check_block(self.input.parse()?).await?;
-		println!("Completed in {} ms.", start.elapsed().as_millis());
+		let to = match &self.to {
+			Some(to) => to.parse()?,
+			None => {
+				builder(config)?.check_block(self.input.parse()?).await?;
+				println!("Completed in {} ms.", start.elapsed().as_millis());
+				return Ok(());
+			},
+		};
+		let from = match self.input.parse::<BB>()? {
+			BlockId::Number(from) => from,
+			BlockId::Hash(_) => {
+				return Err(error::Error::Input("A block range must start at a block number".into()));
+			},
+		};
+
+		if to < from {
+			return Err(error::Error::Input(format!(
+				"The block range ends at #{}, before its first block #{}",
+				to,
+				from,
+			)));
+		}
+
+		let results = builder(config)?.check_blocks(from, to).await?;
+
+		let mut failed = 0;
+		for (number, result) in &results {
+			match result {
+				Ok(()) => println!("#{}: ok", number),
+				Err(e) => {
+					failed += 1;
+					println!("#{}: {}", number, e);
+				},
+			}
+		}
+		println!(
+			"Checked {} blocks in {} ms.",
+			results.len(),
+			start.elapsed().as_millis(),
+		);
+
+		if failed > 0 {
+			return Err(error::Error::Input(format!("{} blocks failed the check", failed)));
+		}
 
 		Ok(())
 	}
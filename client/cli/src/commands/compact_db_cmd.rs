@@ -0,0 +1,169 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(feature = "db")]
+use crate::commands::utils::dir_usage;
+#[cfg(not(feature = "db"))]
+use crate::commands::utils::db_feature_error;
+use crate::error;
+use crate::params::{DatabaseParams, SharedParams};
+use crate::CliConfiguration;
+#[cfg(feature = "db")]
+use sc_service::config::DatabaseConfig;
+use sc_service::{Configuration, Role};
+#[cfg(feature = "db")]
+use std::path::Path;
+#[cfg(feature = "db")]
+use std::time::Instant;
+use structopt::StructOpt;
+
+/// The `compact-db` command used to compact the database of a stopped node.
+#[derive(Debug, StructOpt, Clone)]
+pub struct CompactDbCmd {
+	/// Only compact the column with the given index. Can be passed multiple times.
+	///
+	/// By default all the columns of the database are compacted.
+	#[structopt(long = "column", value_name = "INDEX")]
+	pub columns: Vec<u32>,
+
+	/// Compact the database of a light client.
+	#[structopt(long)]
+	pub light: bool,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl CompactDbCmd {
+	/// Run the compact-db command
+	#[cfg(feature = "db")]
+	pub fn run(&self, config: Configuration) -> error::Result<()> {
+		let db_path = match &config.database {
+			DatabaseConfig::RocksDb { path, .. } => path.clone(),
+			other => return Err(error::Error::Input(format!(
+				"Database compaction is not supported for the {} backend",
+				other,
+			))),
+		};
+
+		if !db_path.exists() {
+			return Err(error::Error::Input(format!("{:?} does not exist", db_path)));
+		}
+
+		let opts = rocksdb::Options::default();
+		let all_columns = rocksdb::DB::list_cf(&opts, &db_path).map_err(rocksdb_error)?;
+		let columns = self.selected_columns(&all_columns)?;
+
+		// Opening the database takes its lock file, so this fails if a node is still running.
+		let db = rocksdb::DB::open_cf(&opts, &db_path, &all_columns)
+			.map_err(|e| open_error(&db_path, e))?;
+
+		let (size_before, _) = dir_usage(&db_path)?;
+		println!("Compacting {:?} ({} bytes)", db_path, size_before);
+
+		let started = Instant::now();
+		for (i, column) in columns.iter().enumerate() {
+			let handle = db.cf_handle(column)
+				.ok_or_else(|| error::Error::Other(format!("Missing column family {}", column)))?;
+
+			println!("[{}/{}] Compacting column {}...", i + 1, columns.len(), column);
+			let column_started = Instant::now();
+			db.compact_range_cf(handle, None::<&[u8]>, None::<&[u8]>);
+			println!(
+				"[{}/{}] Compacted column {} in {:.2}s",
+				i + 1,
+				columns.len(),
+				column,
+				column_started.elapsed().as_secs_f32(),
+			);
+		}
+		drop(db);
+
+		let (size_after, _) = dir_usage(&db_path)?;
+		println!(
+			"Compaction finished in {:.2}s: {} bytes before, {} bytes after",
+			started.elapsed().as_secs_f32(),
+			size_before,
+			size_after,
+		);
+
+		Ok(())
+	}
+
+	/// Run the compact-db command
+	#[cfg(not(feature = "db"))]
+	pub fn run(&self, _config: Configuration) -> error::Result<()> {
+		Err(db_feature_error("`compact-db`"))
+	}
+
+	/// Returns the names of the column families to compact.
+	#[cfg(feature = "db")]
+	fn selected_columns(&self, all_columns: &[String]) -> error::Result<Vec<String>> {
+		if self.columns.is_empty() {
+			// The `default` column family is never used by the client.
+			return Ok(all_columns.iter().filter(|c| *c != "default").cloned().collect());
+		}
+
+		self.columns.iter().map(|index| {
+			let name = sc_client_db::column_family_name(*index);
+			if *index < sc_client_db::NUM_COLUMNS && all_columns.contains(&name) {
+				Ok(name)
+			} else {
+				Err(error::Error::Input(format!("Column {} does not exist in the database", index)))
+			}
+		}).collect()
+	}
+}
+
+impl CliConfiguration for CompactDbCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+
+	fn role(&self, _is_dev: bool) -> error::Result<Role> {
+		Ok(if self.light { Role::Light } else { Role::Full })
+	}
+}
+
+/// Convert a RocksDB error.
+#[cfg(feature = "db")]
+pub(crate) fn rocksdb_error(err: rocksdb::Error) -> error::Error {
+	error::Error::Other(format!("Database error: {}", err))
+}
+
+/// Convert the error of opening the database at `path` for writing.
+///
+/// RocksDB doesn't tell a held lock file apart from other errors, so the most likely cause, a
+/// node still running on the database, is always mentioned.
+#[cfg(feature = "db")]
+pub(crate) fn open_error(path: &Path, err: rocksdb::Error) -> error::Error {
+	error::Error::Input(format!(
+		"Failed to open the database {:?}, make sure the node using it is stopped: {}",
+		path,
+		err,
+	))
+}
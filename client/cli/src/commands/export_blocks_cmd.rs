@@ -16,6 +16,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::arg_enums::BlocksFormat;
 use crate::error;
 use crate::params::{BlockNumber, DatabaseParams, PruningParams, SharedParams};
 use crate::CliConfiguration;
@@ -40,7 +41,7 @@ pub struct ExportBlocksCmd {
 	/// Specify starting block number.
 	///
 	/// Default is 1.
-	#[structopt(long = "from", value_name = "BLOCK")]
+	#[structopt(long = "from", value_name = "BLOCK", conflicts_with = "append")]
 	pub from: Option<BlockNumber>,
 
 	/// Specify last block number.
@@ -53,6 +54,26 @@ pub struct ExportBlocksCmd {
 	#[structopt(long)]
 	pub binary: bool,
 
+	/// Format of the exported blocks.
+	///
+	/// `Jsonl` writes one JSON object per line with the number, hash, parent hash and SCALE encoded
+	/// block, which can be processed as a stream.
+	#[structopt(
+		long,
+		value_name = "FORMAT",
+		possible_values = &BlocksFormat::variants(),
+		case_insensitive = true,
+		conflicts_with = "binary"
+	)]
+	pub format: Option<BlocksFormat>,
+
+	/// Append the new blocks to an existing `Jsonl` export.
+	///
+	/// The export resumes after the last block of the output file, which must have been exported
+	/// from the same chain.
+	#[structopt(long, requires = "output")]
+	pub append: bool,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub shared_params: SharedParams,
@@ -87,15 +108,42 @@ impl ExportBlocksCmd {
 		let from = self.from.as_ref().and_then(|f| f.parse().ok()).unwrap_or(1);
 		let to = self.to.as_ref().and_then(|t| t.parse().ok());
 
-		let binary = self.binary;
+		let format = match self.format {
+			Some(format) => format,
+			None if self.binary => BlocksFormat::Binary,
+			None => BlocksFormat::Json,
+		};
 
-		let file: Box<dyn io::Write> = match &self.output {
-			Some(filename) => Box::new(fs::File::create(filename)?),
-			None => Box::new(io::stdout()),
+		let builder = builder(config)?;
+
+		let (from, file): (_, Box<dyn io::Write>) = match &self.output {
+			Some(filename) if self.append && filename.exists() => {
+				// Only the JSON lines format carries the block numbers and hashes needed to
+				// resume an export.
+				if format != BlocksFormat::Jsonl {
+					return Err(error::Error::Input(
+						"`--append` is only supported with `--format jsonl`".into()
+					));
+				}
+
+				let exported = io::BufReader::new(fs::File::open(filename)?);
+				match builder.next_block_to_export(exported, to)? {
+					Some(from) => {
+						info!("Appending blocks from #{} to {}", from, filename.display());
+						(from, Box::new(fs::OpenOptions::new().append(true).open(filename)?))
+					},
+					None => {
+						info!("{} is up to date", filename.display());
+						return Ok(());
+					},
+				}
+			},
+			Some(filename) => (from.into(), Box::new(fs::File::create(filename)?)),
+			None => (from.into(), Box::new(io::stdout())),
 		};
 
-		builder(config)?
-			.export_blocks(file, from.into(), to, binary)
+		builder
+			.export_blocks(file, from, to, format.into())
 			.await
 			.map_err(Into::into)
 	}
@@ -21,7 +21,7 @@ use crate::{
 };
 use log::info;
 use sc_service::{Configuration, ServiceBuilderCommand};
-use sp_runtime::traits::{Block as BlockT, NumberFor};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT, NumberFor};
 use std::{fmt::Debug, str::FromStr, io::Write};
 use structopt::StructOpt;
 
@@ -33,6 +33,10 @@ pub struct ExportStateCmd {
 	#[structopt(value_name = "HASH or NUMBER")]
 	pub input: Option<BlockNumberOrHash>,
 
+	/// Block hash or number to export the state at, the best block by default.
+	#[structopt(long, value_name = "HASH or NUMBER", conflicts_with = "input")]
+	pub at: Option<BlockNumberOrHash>,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub shared_params: SharedParams,
@@ -59,9 +63,14 @@ impl ExportStateCmd {
 	{
 		info!("Exporting raw state...");
 		let mut input_spec = config.chain_spec.cloned_box();
-		let block_id = self.input.clone().map(|b| b.parse()).transpose()?;
-		let raw_state = builder(config)?.export_raw_state(block_id)?;
-		input_spec.set_storage(raw_state);
+		let block_id = self.input.as_ref().or(self.at.as_ref()).map(|b| b.parse()).transpose()?;
+		let (raw_state, header) = builder(config)?.export_raw_state(block_id)?;
+		input_spec.set_storage_with_root(raw_state, header.state_root().as_ref().to_vec());
+
+		// Specs exported at different heights must be told apart.
+		let name = format!("{} at #{} ({:?})", input_spec.name(), header.number(), header.hash());
+		let id = format!("{}_{}", input_spec.id(), header.number());
+		input_spec.set_name_and_id(name, id);
 
 		info!("Generating new chain spec...");
 		let json = sc_service::chain_ops::build_spec(&*input_spec, true)?;
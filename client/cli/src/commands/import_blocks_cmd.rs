@@ -16,18 +16,23 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::commands::utils::{describe_genesis_hash, print_chain};
 use crate::error;
 use crate::params::ImportParams;
 use crate::params::SharedParams;
 use crate::CliConfiguration;
+use log::info;
 use sc_service::{Configuration, ServiceBuilderCommand};
 use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
 use std::fmt::Debug;
 use std::fs;
-use std::io::{self, Read, Seek};
+use std::io::{self, Read};
 use std::path::PathBuf;
 use structopt::StructOpt;
 
+/// Smallest memory budget in MiB, below which the import would mostly wait for the queue.
+const MIN_MEMORY_BUDGET: u64 = 64;
+
 /// The `import-blocks` command used to import blocks.
 #[derive(Debug, StructOpt, Clone)]
 pub struct ImportBlocksCmd {
@@ -42,9 +47,25 @@ pub struct ImportBlocksCmd {
 	pub default_heap_pages: Option<u32>,
 
 	/// Try importing blocks from binary format rather than JSON.
+	///
+	/// Without this flag, both the `Json` and `Jsonl` export formats are accepted.
 	#[structopt(long)]
 	pub binary: bool,
 
+	/// Record the last imported block in the given file and resume from it when restarted.
+	///
+	/// The blocks of the input up to the recorded block are skipped.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	pub checkpoint_file: Option<PathBuf>,
+
+	/// Limit the memory used by the blocks waiting to be imported, in MiB.
+	///
+	/// The number of blocks queued for import is reduced so that their estimated size stays within
+	/// the budget. By default up to 1024 blocks are queued, whatever their size. The minimum
+	/// budget is 64 MiB.
+	#[structopt(long, value_name = "MB")]
+	pub memory_budget: Option<u64>,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub shared_params: SharedParams,
@@ -54,11 +75,6 @@ pub struct ImportBlocksCmd {
 	pub import_params: ImportParams,
 }
 
-/// Internal trait used to cast to a dynamic type that implements Read and Seek.
-trait ReadPlusSeek: Read + Seek {}
-
-impl<T: Read + Seek> ReadPlusSeek for T {}
-
 impl ImportBlocksCmd {
 	/// Run the import-blocks command
 	pub async fn run<B, BC, BB>(
@@ -73,17 +89,29 @@ impl ImportBlocksCmd {
 		<<<BB as BlockT>::Header as HeaderT>::Number as std::str::FromStr>::Err: std::fmt::Debug,
 		<BB as BlockT>::Hash: std::str::FromStr,
 	{
-		let file: Box<dyn ReadPlusSeek + Send> = match &self.input {
+		let memory_budget = match self.memory_budget {
+			Some(budget) if budget < MIN_MEMORY_BUDGET => return Err(error::Error::Input(format!(
+				"The memory budget must be at least {} MiB",
+				MIN_MEMORY_BUDGET,
+			))),
+			budget => budget.map(|budget| budget * 1024 * 1024),
+		};
+
+		// Stdin is streamed rather than buffered, so that blocks can be piped from `export-blocks`.
+		let file: Box<dyn Read + Send> = match &self.input {
 			Some(filename) => Box::new(fs::File::open(filename)?),
 			None => {
-				let mut buffer = Vec::new();
-				io::stdin().read_to_end(&mut buffer)?;
-				Box::new(io::Cursor::new(buffer))
+				info!("Reading blocks from stdin");
+				Box::new(io::stdin())
 			}
 		};
 
-		builder(config)?
-			.import_blocks(file, false, self.binary)
+		let chain_spec = config.chain_spec.cloned_box();
+		let builder = builder(config)?;
+		print_chain(&*chain_spec, &describe_genesis_hash(builder.genesis_hash()));
+
+		builder
+			.import_blocks(file, false, self.binary, self.checkpoint_file.clone(), memory_budget)
 			.await
 			.map_err(Into::into)
 	}
@@ -0,0 +1,157 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::error;
+use crate::params::{ImportParams, SharedParams};
+use crate::CliConfiguration;
+use log::info;
+use sc_service::{Configuration, RawGenesisEntry, ServiceBuilderCommand};
+use sp_core::hexdisplay::HexDisplay;
+use sp_runtime::traits::Block as BlockT;
+use std::fs;
+use std::path::Path;
+use structopt::StructOpt;
+
+/// The `import-state` command used to start a new database from a state exported with
+/// `export-state`.
+///
+/// The state is read from the chain spec file given with `--chain`.
+#[derive(Debug, StructOpt, Clone)]
+pub struct ImportStateCmd {
+	/// State root the imported state must have, as a hex string.
+	///
+	/// Required if the chain spec given with `--chain` doesn't record the root of its state.
+	#[structopt(long, value_name = "HASH")]
+	pub state_root: Option<String>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub import_params: ImportParams,
+}
+
+impl ImportStateCmd {
+	/// Run the import-state command
+	///
+	/// The storage of the chain spec is read and written to the new database in bounded batches,
+	/// so it doesn't need to fit in memory.
+	pub fn run<B, BC, BB>(
+		&self,
+		config: Configuration,
+		builder: B,
+	) -> error::Result<()>
+	where
+		B: FnOnce(Configuration) -> Result<BC, sc_service::error::Error>,
+		BC: ServiceBuilderCommand<Block = BB> + Unpin,
+		BB: BlockT,
+	{
+		let spec_path = match &self.shared_params.chain {
+			Some(chain) if Path::new(chain).is_file() => Path::new(chain).to_path_buf(),
+			_ => return Err(error::Error::Input(
+				"The state is imported from the chain spec file given with `--chain`".into(),
+			)),
+		};
+		let state_root = match &self.state_root {
+			Some(root) => Some(sp_core::bytes::from_hex(root)
+				.map_err(|e| format!("Invalid state root {}: {:?}", root, e))?),
+			None => None,
+		};
+
+		let db_path = config.database.path().map(|path| path.to_path_buf());
+		if let Some(path) = &db_path {
+			if path.exists() {
+				return Err(error::Error::Input(format!(
+					"{:?} already exists, the state can only be imported in a new database",
+					path,
+				)));
+			}
+		}
+
+		info!("Importing the state of {}...", config.chain_spec.name());
+		let imported = import_state::<BB>(&config, &spec_path).and_then(|(root, recorded)| {
+			let expected = state_root.or(recorded).ok_or_else(|| error::Error::Input(
+				"The chain spec doesn't record the root of its state, pass it with `--state-root`"
+					.into(),
+			))?;
+			if root.as_ref() != &expected[..] {
+				return Err(error::Error::Input(format!(
+					"State root mismatch: expected 0x{}, the imported state has 0x{}",
+					HexDisplay::from(&expected),
+					HexDisplay::from(&root.as_ref()),
+				)));
+			}
+
+			Ok(root)
+		});
+		let root = match imported {
+			Ok(root) => root,
+			Err(err) => {
+				// Don't leave a database behind that would start a diverging chain.
+				if let Some(path) = &db_path {
+					if path.exists() {
+						fs::remove_dir_all(path)?;
+					}
+				}
+				return Err(err);
+			},
+		};
+
+		// The node opens the new database with the imported genesis block.
+		builder(config)?.genesis_hash()?;
+		println!(
+			"Imported the state with root 0x{} as the genesis of a new database",
+			HexDisplay::from(&root.as_ref()),
+		);
+
+		Ok(())
+	}
+}
+
+/// Write the state of the chain spec at `spec_path` as the genesis state of the database of
+/// `config`. Returns its root and the root recorded in the chain spec, if any.
+fn import_state<B: BlockT>(
+	config: &Configuration,
+	spec_path: &Path,
+) -> error::Result<(B::Hash, Option<Vec<u8>>)> {
+	let spec = fs::File::open(spec_path)?;
+	let mut recorded = None;
+	let root = sc_service::new_database_with_genesis_state::<B>(config, |import| {
+		recorded = sc_service::read_raw_genesis(spec, &mut |entry| match entry {
+			RawGenesisEntry::Top(key, value) => import.insert(key, value),
+			RawGenesisEntry::Child(storage_key, key, value) => {
+				import.insert_child(storage_key, key, value)
+			},
+		}.map_err(|e| e.to_string()))?;
+		Ok(())
+	})?;
+
+	Ok((root, recorded))
+}
+
+impl CliConfiguration for ImportStateCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
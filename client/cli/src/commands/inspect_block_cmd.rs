@@ -0,0 +1,254 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::error;
+use crate::params::{BlockNumberOrHash, DatabaseParams, SharedParams};
+use crate::CliConfiguration;
+use sc_service::{config::DatabaseConfig, Configuration};
+use sp_core::hexdisplay::HexDisplay;
+use sp_runtime::codec::Encode;
+use sp_runtime::generic::{BlockId, DigestItem};
+use sp_runtime::traits::{Block as BlockT, Extrinsic as ExtrinsicT, Header as HeaderT, NumberFor};
+use std::fmt::Debug;
+use std::path::Path;
+use std::str::FromStr;
+use structopt::StructOpt;
+#[cfg(feature = "db")]
+use {
+	crate::commands::compact_db_cmd::rocksdb_error,
+	sc_client_db::{column_family_name, columns},
+	sp_runtime::codec::Decode,
+	sp_runtime::traits::SaturatedConversion,
+	std::convert::TryFrom,
+};
+#[cfg(not(feature = "db"))]
+use crate::commands::utils::db_feature_error;
+
+/// The `inspect-block` command used to print a block stored in the database.
+#[derive(Debug, StructOpt, Clone)]
+pub struct InspectBlockCmd {
+	/// Block hash or number
+	#[structopt(value_name = "HASH or NUMBER")]
+	pub input: BlockNumberOrHash,
+
+	/// Print the block as JSON.
+	#[structopt(long)]
+	pub json: bool,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl InspectBlockCmd {
+	/// Run the inspect-block command
+	pub fn run<B>(&self, config: Configuration) -> error::Result<()>
+	where
+		B: BlockT,
+		B::Hash: FromStr,
+		<B::Hash as FromStr>::Err: Debug,
+		NumberFor<B>: FromStr,
+		<NumberFor<B> as FromStr>::Err: Debug,
+	{
+		let db_path = match &config.database {
+			DatabaseConfig::RocksDb { path, .. } => path.clone(),
+			other => return Err(error::Error::Input(format!(
+				"Inspecting blocks is not supported for the {} backend",
+				other,
+			))),
+		};
+
+		if !db_path.exists() {
+			return Err(error::Error::Input(format!("{:?} does not exist", db_path)));
+		}
+
+		let id = self.input.parse::<B>()?;
+		let (header, extrinsics) = read_block::<B>(&db_path, &id)?;
+
+		if self.json {
+			let block = block_json::<B>(&header, extrinsics.as_ref().map(|e| &e[..]));
+			println!("{}", serde_json::to_string_pretty(&block).map_err(|e| e.to_string())?);
+		} else {
+			print_block::<B>(&header, extrinsics.as_ref().map(|e| &e[..]));
+		}
+
+		Ok(())
+	}
+}
+
+impl CliConfiguration for InspectBlockCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// Read the header and, unless it was pruned, the body of the block `id` from the database at
+/// `db_path`.
+#[cfg(feature = "db")]
+fn read_block<B: BlockT>(
+	db_path: &Path,
+	id: &BlockId<B>,
+) -> error::Result<(B::Header, Option<Vec<B::Extrinsic>>)> {
+	// The database is opened read-only, so a running node can be inspected as well.
+	let db = open_read_only(db_path)?;
+
+	let lookup_key = lookup_key::<B>(&db, id)?
+		.ok_or_else(|| error::Error::Input(format!("Block {} not found", id)))?;
+	let header = read_column(&db, columns::HEADER, &lookup_key)?
+		.ok_or_else(|| error::Error::Input(format!("Block {} not found", id)))
+		.and_then(|encoded| B::Header::decode(&mut &encoded[..])
+			.map_err(|e| format!("Failed to decode the header of block {}: {:?}", id, e).into())
+		)?;
+	let extrinsics = match read_column(&db, columns::BODY, &lookup_key)? {
+		Some(encoded) => Some(
+			Vec::<B::Extrinsic>::decode(&mut &encoded[..])
+				.map_err(|e| format!("Failed to decode the body of block {}: {:?}", id, e))?
+		),
+		None => None,
+	};
+
+	Ok((header, extrinsics))
+}
+
+/// Read the header and, unless it was pruned, the body of the block `id` from the database at
+/// `db_path`.
+#[cfg(not(feature = "db"))]
+fn read_block<B: BlockT>(
+	_db_path: &Path,
+	_id: &BlockId<B>,
+) -> error::Result<(B::Header, Option<Vec<B::Extrinsic>>)> {
+	Err(db_feature_error("`inspect-block`"))
+}
+
+/// Open the RocksDB database at `path` read-only with all its column families.
+#[cfg(feature = "db")]
+fn open_read_only(path: &Path) -> error::Result<rocksdb::DB> {
+	let opts = rocksdb::Options::default();
+	let columns = rocksdb::DB::list_cf(&opts, path).map_err(rocksdb_error)?;
+	rocksdb::DB::open_cf_for_read_only(&opts, path, &columns, false).map_err(rocksdb_error)
+}
+
+/// Read `key` from the given column of the database.
+#[cfg(feature = "db")]
+fn read_column(db: &rocksdb::DB, column: u32, key: &[u8]) -> error::Result<Option<Vec<u8>>> {
+	let name = column_family_name(column);
+	let handle = db.cf_handle(&name)
+		.ok_or_else(|| error::Error::Input(format!("The database has no column {}", name)))?;
+
+	db.get_cf(handle, key).map_err(rocksdb_error)
+}
+
+/// Returns the key the header and body of the block `id` are stored under.
+///
+/// Numbers are looked up in the canonical chain.
+#[cfg(feature = "db")]
+fn lookup_key<B: BlockT>(db: &rocksdb::DB, id: &BlockId<B>) -> error::Result<Option<Vec<u8>>> {
+	match id {
+		BlockId::Hash(hash) => read_column(db, columns::KEY_LOOKUP, hash.as_ref()),
+		BlockId::Number(number) => {
+			let number = u32::try_from((*number).saturated_into::<u64>())
+				.map_err(|_| error::Error::Input(format!("Block number {} is too large", number)))?;
+			read_column(db, columns::KEY_LOOKUP, &number.to_be_bytes())
+		},
+	}
+}
+
+/// Format a hash as `0x` prefixed hex.
+fn to_hex(bytes: &[u8]) -> String {
+	format!("0x{}", HexDisplay::from(&bytes))
+}
+
+/// Returns the type, consensus engine and payload of a digest item.
+fn digest_item_parts<H: AsRef<[u8]>>(item: &DigestItem<H>) -> (&'static str, Option<String>, String) {
+	let engine = |id: &[u8; 4]| Some(String::from_utf8_lossy(id).into_owned());
+
+	match item {
+		DigestItem::ChangesTrieRoot(root) => ("ChangesTrieRoot", None, to_hex(root.as_ref())),
+		DigestItem::PreRuntime(id, data) => ("PreRuntime", engine(id), to_hex(data)),
+		DigestItem::Consensus(id, data) => ("Consensus", engine(id), to_hex(data)),
+		DigestItem::Seal(id, data) => ("Seal", engine(id), to_hex(data)),
+		DigestItem::ChangesTrieSignal(signal) => ("ChangesTrieSignal", None, format!("{:?}", signal)),
+		DigestItem::Other(data) => ("Other", None, to_hex(data)),
+	}
+}
+
+/// Build the JSON representation of a block. `extrinsics` is `None` if the body was pruned.
+fn block_json<B: BlockT>(header: &B::Header, extrinsics: Option<&[B::Extrinsic]>) -> serde_json::Value {
+	let digest = header.digest().logs().iter().map(|item| {
+		let (kind, engine, data) = digest_item_parts(item);
+		serde_json::json!({ "type": kind, "engine": engine, "data": data })
+	}).collect::<Vec<_>>();
+
+	let extrinsics = extrinsics.map(|extrinsics| extrinsics.iter().enumerate().map(|(index, xt)| {
+		serde_json::json!({
+			"index": index,
+			"signed": xt.is_signed(),
+			"data": to_hex(&xt.encode()),
+		})
+	}).collect::<Vec<_>>());
+
+	serde_json::json!({
+		"number": (*header.number()).saturated_into::<u64>(),
+		"hash": to_hex(header.hash().as_ref()),
+		"parentHash": to_hex(header.parent_hash().as_ref()),
+		"stateRoot": to_hex(header.state_root().as_ref()),
+		"extrinsicsRoot": to_hex(header.extrinsics_root().as_ref()),
+		"digest": digest,
+		"bodyPruned": extrinsics.is_none(),
+		"extrinsics": extrinsics,
+	})
+}
+
+/// Print a block in a human readable form. `extrinsics` is `None` if the body was pruned.
+fn print_block<B: BlockT>(header: &B::Header, extrinsics: Option<&[B::Extrinsic]>) {
+	println!("Block #{} ({})", header.number(), to_hex(header.hash().as_ref()));
+	println!("  parent hash: {}", to_hex(header.parent_hash().as_ref()));
+	println!("  state root: {}", to_hex(header.state_root().as_ref()));
+	println!("  extrinsics root: {}", to_hex(header.extrinsics_root().as_ref()));
+
+	println!("  digest:");
+	for item in header.digest().logs() {
+		match digest_item_parts(item) {
+			(kind, Some(engine), data) => println!("    {}({}): {}", kind, engine, data),
+			(kind, None, data) => println!("    {}: {}", kind, data),
+		}
+	}
+
+	match extrinsics {
+		Some(extrinsics) => {
+			println!("  extrinsics:");
+			for (index, xt) in extrinsics.iter().enumerate() {
+				let signed = match xt.is_signed() {
+					Some(true) => "signed ",
+					Some(false) => "unsigned ",
+					None => "",
+				};
+				println!("    {}: {}{}", index, signed, to_hex(&xt.encode()));
+			}
+		},
+		None => println!("  body pruned"),
+	}
+}
@@ -15,19 +15,31 @@
 
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
+mod backup_cmd;
 mod build_spec_cmd;
 mod check_block_cmd;
+mod compact_db_cmd;
 mod export_blocks_cmd;
 mod export_state_cmd;
 mod import_blocks_cmd;
+mod import_state_cmd;
+mod inspect_block_cmd;
+mod prune_cmd;
 mod purge_chain_cmd;
 mod revert_cmd;
 mod run_cmd;
+mod utils;
 
+pub use self::backup_cmd::BackupCmd;
+pub(crate) use self::backup_cmd::{check_backup_manifest, is_light_database};
 pub use self::build_spec_cmd::BuildSpecCmd;
 pub use self::check_block_cmd::CheckBlockCmd;
+pub use self::compact_db_cmd::CompactDbCmd;
 pub use self::export_blocks_cmd::ExportBlocksCmd;
 pub use self::import_blocks_cmd::ImportBlocksCmd;
+pub use self::import_state_cmd::ImportStateCmd;
+pub use self::inspect_block_cmd::InspectBlockCmd;
+pub use self::prune_cmd::PruneCmd;
 pub use self::purge_chain_cmd::PurgeChainCmd;
 pub use self::revert_cmd::RevertCmd;
 pub use self::run_cmd::RunCmd;
@@ -62,6 +74,41 @@ pub enum Subcommand {
 
 	/// Export state as raw chain spec.
 	ExportState(ExportStateCmd),
+
+	/// Start a new database from a state exported as raw chain spec.
+	ImportState(ImportStateCmd),
+
+	/// Compact the database of a stopped node.
+	CompactDb(CompactDbCmd),
+
+	/// Take a consistent copy of the database of a stopped node.
+	Backup(BackupCmd),
+
+	/// Print a block stored in the database.
+	InspectBlock(InspectBlockCmd),
+
+	/// Remove old state from the database of a stopped node.
+	Prune(PruneCmd),
+}
+
+impl Subcommand {
+	/// Parse a subcommand from `iter`, whose first item is the binary name, e.g.
+	/// `["node", "purge-chain", "--dev"]`.
+	///
+	/// Unlike `structopt`'s `from_iter`, an error is returned instead of exiting the process. This
+	/// also applies to `--help` and `--version`, which return a [`clap::Error`] with the
+	/// [`ErrorKind::HelpDisplayed`] or [`ErrorKind::VersionDisplayed`] kind.
+	///
+	/// [`clap::Error`]: structopt::clap::Error
+	/// [`ErrorKind::HelpDisplayed`]: structopt::clap::ErrorKind::HelpDisplayed
+	/// [`ErrorKind::VersionDisplayed`]: structopt::clap::ErrorKind::VersionDisplayed
+	pub fn from_iter_safe<I>(iter: I) -> structopt::clap::Result<Self>
+	where
+		I: IntoIterator,
+		I::Item: Into<std::ffi::OsString> + Clone,
+	{
+		<Self as StructOpt>::from_iter_safe(iter)
+	}
 }
 
 // TODO: move to config.rs?
@@ -99,7 +146,7 @@ pub enum Subcommand {
 ///
 /// ```ignore
 /// impl CliConfiguration for Subcommand {
-///	    fn base_path(&self) -> Result<Option<PathBuf>> {
+///	    fn base_path(&self) -> Result<Option<BasePath>> {
 ///	        match self {
 ///	            Subcommand::Variant1(cmd) => cmd.base_path(),
 ///	            Subcommand::Variant2(cmd) => cmd.base_path(),
@@ -156,13 +203,19 @@ macro_rules! substrate_cli_subcommands {
 				}
 			}
 
+			fn node_key_params(&self) -> Option<&$crate::NodeKeyParams> {
+				match self {
+					$($enum::$variant(cmd) => cmd.node_key_params()),*
+				}
+			}
+
 			fn database_params(&self) -> Option<&$crate::DatabaseParams> {
 				match self {
 					$($enum::$variant(cmd) => cmd.database_params()),*
 				}
 			}
 
-			fn base_path(&self) -> $crate::Result<::std::option::Option<::std::path::PathBuf>> {
+			fn base_path(&self) -> $crate::Result<::std::option::Option<::sc_service::config::BasePath>> {
 				match self {
 					$($enum::$variant(cmd) => cmd.base_path()),*
 				}
@@ -247,10 +300,16 @@ macro_rules! substrate_cli_subcommands {
 				}
 			}
 
-			fn pruning(&self, unsafe_pruning: bool, role: &::sc_service::Role)
+			fn state_pruning(&self, unsafe_pruning: bool, role: &::sc_service::Role)
 			-> $crate::Result<::sc_service::config::PruningMode> {
 				match self {
-					$($enum::$variant(cmd) => cmd.pruning(unsafe_pruning, role)),*
+					$($enum::$variant(cmd) => cmd.state_pruning(unsafe_pruning, role)),*
+				}
+			}
+
+			fn keep_blocks(&self) -> $crate::Result<::sc_service::config::KeepBlocks> {
+				match self {
+					$($enum::$variant(cmd) => cmd.keep_blocks()),*
 				}
 			}
 
@@ -278,6 +337,18 @@ macro_rules! substrate_cli_subcommands {
 				}
 			}
 
+			fn allow_dangerous_flags(&self) -> $crate::Result<bool> {
+				match self {
+					$($enum::$variant(cmd) => cmd.allow_dangerous_flags()),*
+				}
+			}
+
+			fn wasm_runtime_overrides(&self) -> $crate::Result<::std::option::Option<::std::path::PathBuf>> {
+				match self {
+					$($enum::$variant(cmd) => cmd.wasm_runtime_overrides()),*
+				}
+			}
+
 			fn execution_strategies(&self, is_dev: bool)
 			-> $crate::Result<::sc_client_api::execution_extensions::ExecutionStrategies> {
 				match self {
@@ -303,12 +374,24 @@ macro_rules! substrate_cli_subcommands {
 				}
 			}
 
+			fn rpc_ipc(&self) -> $crate::Result<::std::option::Option<::std::path::PathBuf>> {
+				match self {
+					$($enum::$variant(cmd) => cmd.rpc_ipc()),*
+				}
+			}
+
 			fn rpc_ws_max_connections(&self) -> $crate::Result<::std::option::Option<usize>> {
 				match self {
 					$($enum::$variant(cmd) => cmd.rpc_ws_max_connections()),*
 				}
 			}
 
+			fn rpc_max_payload(&self) -> $crate::Result<::std::option::Option<usize>> {
+				match self {
+					$($enum::$variant(cmd) => cmd.rpc_max_payload()),*
+				}
+			}
+
 			fn rpc_cors(&self, is_dev: bool)
 			-> $crate::Result<::std::option::Option<::std::vec::Vec<String>>> {
 				match self {
@@ -402,11 +485,79 @@ macro_rules! substrate_cli_subcommands {
 					$($enum::$variant(cmd) => cmd.log_filters()),*
 				}
 			}
+
+			fn announce_block(&self) -> $crate::Result<bool> {
+				match self {
+					$($enum::$variant(cmd) => cmd.announce_block()),*
+				}
+			}
 		}
 	}
 }
 
 substrate_cli_subcommands!(
-	Subcommand => BuildSpec, ExportBlocks, ImportBlocks, CheckBlock, Revert, PurgeChain, ExportState
+	Subcommand => BuildSpec, ExportBlocks, ImportBlocks, CheckBlock, Revert, PurgeChain, ExportState,
+	ImportState, CompactDb, Backup, InspectBlock, Prune
 );
 
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn subcommand_from_iter_safe() {
+		let subcommand = Subcommand::from_iter_safe(vec!["node", "purge-chain", "-y"]).unwrap();
+		match subcommand {
+			Subcommand::PurgeChain(cmd) => assert!(cmd.yes),
+			other => panic!("Unexpected subcommand {:?}", other),
+		}
+	}
+
+	#[test]
+	fn subcommand_from_iter_safe_returns_errors() {
+		let err = Subcommand::from_iter_safe(vec!["node", "purge-chain", "--unknown"]).unwrap_err();
+		assert_eq!(err.kind, structopt::clap::ErrorKind::UnknownArgument);
+
+		let err = Subcommand::from_iter_safe(vec!["node", "--help"]).unwrap_err();
+		assert_eq!(err.kind, structopt::clap::ErrorKind::HelpDisplayed);
+	}
+
+	#[test]
+	fn every_subcommand_has_shared_params() {
+		use crate::CliConfiguration;
+
+		let subcommands: &[&[&str]] = &[
+			&["build-spec"],
+			&["export-blocks"],
+			&["import-blocks"],
+			&["check-block", "1"],
+			&["revert"],
+			&["purge-chain"],
+			&["export-state"],
+			&["import-state"],
+			&["compact-db"],
+			&["backup", "--output", "backup"],
+			&["inspect-block", "1"],
+			&["prune", "--keep", "256"],
+		];
+
+		for args in subcommands {
+			let args = std::iter::once("node").chain(args.iter().cloned()).chain(vec!["--chain", "local"]);
+			let subcommand = Subcommand::from_iter_safe(args).unwrap();
+			assert_eq!(subcommand.shared_params().chain_id(false), "local", "{:?}", subcommand);
+		}
+	}
+
+	#[test]
+	fn subcommand_forwards_the_params_of_its_command() {
+		use crate::CliConfiguration;
+
+		let subcommand = Subcommand::from_iter_safe(vec!["node", "build-spec"]).unwrap();
+		assert!(subcommand.node_key_params().is_some());
+		assert!(subcommand.announce_block().unwrap());
+
+		let subcommand = Subcommand::from_iter_safe(vec!["node", "purge-chain"]).unwrap();
+		assert!(subcommand.node_key_params().is_none());
+	}
+}
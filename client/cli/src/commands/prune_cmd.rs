@@ -0,0 +1,590 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(feature = "db")]
+use crate::commands::backup_cmd::{open_database, read_meta, split_lookup_key};
+#[cfg(feature = "db")]
+use crate::commands::compact_db_cmd::rocksdb_error;
+#[cfg(not(feature = "db"))]
+use crate::commands::utils::db_feature_error;
+use crate::error;
+use crate::params::{DatabaseParams, SharedParams};
+use crate::CliConfiguration;
+use sc_service::{config::DatabaseConfig, Configuration};
+use sp_runtime::traits::Block as BlockT;
+use std::path::Path;
+use structopt::StructOpt;
+#[cfg(feature = "db")]
+use {
+	hash_db::{HashDBRef, Hasher, Prefix, EMPTY_PREFIX},
+	sc_client_db::{column_family_name, columns, meta_keys, state_meta_keys},
+	sc_service::PruningMode,
+	sp_core::storage::well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX,
+	sp_runtime::codec::{Decode, Encode},
+	sp_runtime::traits::{HashFor, Header as HeaderT},
+	sp_trie::{prefixed_key, trie_types::TrieDB, DBValue, Trie},
+	std::collections::HashSet,
+	std::path::PathBuf,
+	std::time::Instant,
+	trie_db::node::{Node, NodeHandle},
+	trie_db::{NibbleVec, NodeCodec as _, TrieIterator},
+};
+
+/// Number of trie nodes deleted or marked per database write.
+#[cfg(feature = "db")]
+const BATCH_SIZE: usize = 10_000;
+
+/// The `prune` command used to remove old state from the database of a stopped node.
+#[derive(Debug, StructOpt, Clone)]
+pub struct PruneCmd {
+	/// Number of blocks below the best block to keep the state of.
+	///
+	/// The state of the finalized block and of all the blocks above it is always kept.
+	#[structopt(long, value_name = "COUNT")]
+	pub keep: u32,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl PruneCmd {
+	/// Run the prune command
+	///
+	/// With `--dry-run`, only the size of the state that would be removed is printed.
+	pub fn run<B: BlockT>(&self, config: Configuration) -> error::Result<()> {
+		let db_path = match &config.database {
+			DatabaseConfig::RocksDb { path, .. } => path.clone(),
+			other => return Err(error::Error::Input(format!(
+				"Pruning is not supported for the {} backend",
+				other,
+			))),
+		};
+
+		if !db_path.exists() {
+			return Err(error::Error::Input(format!("{:?} does not exist", db_path)));
+		}
+
+		prune::<B>(&db_path, self.keep, self.shared_params.is_dry_run())
+	}
+}
+
+impl CliConfiguration for PruneCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// Remove the state of the blocks more than `keep` blocks below the best block from the database
+/// at `db_path`, or only print its size on a dry run.
+#[cfg(not(feature = "db"))]
+fn prune<B: BlockT>(_db_path: &Path, _keep: u32, _dry_run: bool) -> error::Result<()> {
+	Err(db_feature_error("`prune`"))
+}
+
+/// Remove the state of the blocks more than `keep` blocks below the best block from the database
+/// at `db_path`, or only print its size on a dry run.
+#[cfg(feature = "db")]
+fn prune<B: BlockT>(db_path: &Path, keep: u32, dry_run: bool) -> error::Result<()> {
+	// Opening the database takes its lock file, so this fails if a node is still running.
+	let db = open_database(db_path)?;
+
+	let (best, _) = split_lookup_key(&read_meta(&db, meta_keys::BEST_BLOCK)?)?;
+	let (finalized, _) = split_lookup_key(&read_meta(&db, meta_keys::FINALIZED_BLOCK)?)?;
+	let keep_from = best.saturating_sub(keep).min(finalized);
+	if keep_from == 0 {
+		println!("Nothing to prune: the state of all the blocks is kept");
+		return Ok(());
+	}
+
+	println!(
+		"Keeping the state of the blocks from #{} (best: #{}, finalized: #{})",
+		keep_from,
+		best,
+		finalized,
+	);
+
+	let started = Instant::now();
+	let roots = state_roots::<B>(&db, keep_from)?;
+	let marks = Marks::open(db_path)?;
+	let mut reachable = Reachable::<HashFor<B>>::new(&db, &marks)?;
+	for (i, root) in roots.iter().enumerate() {
+		reachable.mark(root)?;
+		if (i + 1) % 100 == 0 || i + 1 == roots.len() {
+			println!(
+				"[{}/{}] Marked {} reachable trie nodes",
+				i + 1,
+				roots.len(),
+				reachable.marked,
+			);
+		}
+	}
+	reachable.flush()?;
+
+	if !dry_run {
+		reset_state_meta(&db, keep)?;
+	}
+	let (removed, removed_bytes) = sweep(&db, &marks, dry_run)?;
+
+	if dry_run {
+		println!(
+			"Pruning would remove {} trie nodes ({} bytes)",
+			removed,
+			removed_bytes,
+		);
+	} else {
+		println!(
+			"Pruning finished in {:.2}s: removed {} trie nodes ({} bytes). \
+			Run `compact-db` to reclaim the disk space and restart the node with `--pruning`.",
+			started.elapsed().as_secs_f32(),
+			removed,
+			removed_bytes,
+		);
+	}
+
+	Ok(())
+}
+
+/// Returns a handle to the given column, which must exist.
+#[cfg(feature = "db")]
+fn column(db: &rocksdb::DB, column: u32) -> error::Result<&rocksdb::ColumnFamily> {
+	let name = column_family_name(column);
+	db.cf_handle(&name)
+		.ok_or_else(|| error::Error::Input(format!("The database has no column {}", name)))
+}
+
+/// Returns the state roots of all the blocks, canonical or not, from `keep_from` onwards.
+#[cfg(feature = "db")]
+fn state_roots<B: BlockT>(db: &rocksdb::DB, keep_from: u32) -> error::Result<Vec<B::Hash>> {
+	let from = keep_from.to_be_bytes();
+	let iter = db.iterator_cf(
+		column(db, columns::HEADER)?,
+		rocksdb::IteratorMode::From(&from, rocksdb::Direction::Forward),
+	);
+
+	iter.map(|(key, encoded)| {
+		let header = B::Header::decode(&mut &encoded[..])
+			.map_err(|e| format!("Failed to decode block header {:?}: {:?}", key, e))?;
+		Ok(*header.state_root())
+	}).collect()
+}
+
+/// The database keys of the reachable trie nodes.
+///
+/// They are kept in a database next to the client database, so that the memory used doesn't grow
+/// with the size of the state. It is removed when dropped.
+#[cfg(feature = "db")]
+struct Marks {
+	db: Option<rocksdb::DB>,
+	path: PathBuf,
+}
+
+#[cfg(feature = "db")]
+impl Marks {
+	/// Open an empty set next to the database at `db_path`, replacing the one left behind by an
+	/// interrupted run.
+	fn open(db_path: &Path) -> error::Result<Self> {
+		let mut path = db_path.as_os_str().to_owned();
+		path.push(".prune-marks");
+		let path = PathBuf::from(path);
+		if path.exists() {
+			std::fs::remove_dir_all(&path)?;
+		}
+
+		let mut opts = rocksdb::Options::default();
+		opts.create_if_missing(true);
+		let db = rocksdb::DB::open(&opts, &path).map_err(rocksdb_error)?;
+		Ok(Marks { db: Some(db), path })
+	}
+
+	fn db(&self) -> &rocksdb::DB {
+		self.db.as_ref().expect("only taken when dropped; qed")
+	}
+
+	fn contains(&self, key: &[u8]) -> error::Result<bool> {
+		Ok(self.db().get(key).map_err(rocksdb_error)?.is_some())
+	}
+}
+
+#[cfg(feature = "db")]
+impl Drop for Marks {
+	fn drop(&mut self) {
+		// The database has to be closed before its files are removed.
+		self.db.take();
+		let _ = std::fs::remove_dir_all(&self.path);
+	}
+}
+
+/// The state column of the client database, read as a database of trie nodes.
+#[cfg(feature = "db")]
+struct StateColumn<'a, H: Hasher> {
+	db: &'a rocksdb::DB,
+	column: &'a rocksdb::ColumnFamily,
+	_marker: std::marker::PhantomData<H>,
+}
+
+#[cfg(feature = "db")]
+impl<'a, H: Hasher> StateColumn<'a, H> {
+	fn new(db: &'a rocksdb::DB) -> error::Result<Self> {
+		Ok(StateColumn {
+			db,
+			column: column(db, columns::STATE)?,
+			_marker: Default::default(),
+		})
+	}
+
+	fn get_raw(&self, key: &[u8]) -> error::Result<Option<Vec<u8>>> {
+		self.db.get_cf(self.column, key).map_err(rocksdb_error)
+	}
+}
+
+#[cfg(feature = "db")]
+impl<'a, H: Hasher> HashDBRef<H, DBValue> for StateColumn<'a, H> {
+	fn get(&self, key: &H::Out, prefix: Prefix) -> Option<DBValue> {
+		self.get_raw(&prefixed_key::<H>(key, prefix)).ok().flatten()
+	}
+
+	fn contains(&self, key: &H::Out, prefix: Prefix) -> bool {
+		self.get(key, prefix).is_some()
+	}
+}
+
+/// Marks the trie nodes reachable from the kept state roots.
+#[cfg(feature = "db")]
+struct Reachable<'a, H: Hasher> {
+	state: StateColumn<'a, H>,
+	marks: &'a Marks,
+	/// Marks not written to `marks` yet, at most `BATCH_SIZE` of them.
+	pending: HashSet<Vec<u8>>,
+	/// Number of marked nodes.
+	marked: u64,
+}
+
+#[cfg(feature = "db")]
+impl<'a, H: Hasher> Reachable<'a, H> {
+	fn new(db: &'a rocksdb::DB, marks: &'a Marks) -> error::Result<Self> {
+		Ok(Reachable {
+			state: StateColumn::new(db)?,
+			marks,
+			pending: HashSet::new(),
+			marked: 0,
+		})
+	}
+
+	/// Mark all the nodes of the state with the given root, including its child tries.
+	fn mark(&mut self, root: &H::Out) -> error::Result<()> {
+		// The state of discarded forks and empty states have no root node in the database.
+		if !self.state.contains(root, EMPTY_PREFIX) {
+			return Ok(());
+		}
+		self.mark_trie(root, &[])?;
+
+		let mut child_roots = Vec::new();
+		{
+			let trie = TrieDB::<H>::new(&self.state, root).map_err(trie_error)?;
+			let mut iter = trie.iter().map_err(trie_error)?;
+			iter.seek(DEFAULT_CHILD_STORAGE_KEY_PREFIX).map_err(trie_error)?;
+			for item in iter {
+				let (key, value) = item.map_err(trie_error)?;
+				if !key.starts_with(DEFAULT_CHILD_STORAGE_KEY_PREFIX) {
+					break;
+				}
+				let mut child_root = H::Out::default();
+				if value.len() == child_root.as_ref().len() {
+					child_root.as_mut().copy_from_slice(&value);
+					child_roots.push((key[DEFAULT_CHILD_STORAGE_KEY_PREFIX.len()..].to_vec(), child_root));
+				}
+			}
+		}
+
+		for (keyspace, child_root) in child_roots {
+			self.mark_trie(&child_root, &keyspace)?;
+		}
+
+		Ok(())
+	}
+
+	/// Mark the nodes of a single trie stored under `keyspace`.
+	///
+	/// Subtrees already marked from another root are skipped, and so are nodes missing from the
+	/// database, like the state of discarded forks.
+	fn mark_trie(&mut self, root: &H::Out, keyspace: &[u8]) -> error::Result<()> {
+		let mut stack = vec![(NibbleVec::new(), root.clone())];
+
+		while let Some((path, hash)) = stack.pop() {
+			let key = db_key::<H>(keyspace, &hash, path.as_prefix());
+			if self.is_marked(&key)? {
+				continue;
+			}
+			let encoded = match self.state.get_raw(&key)? {
+				Some(encoded) => encoded,
+				None => continue,
+			};
+			self.insert(key)?;
+
+			let mut inline = vec![(path, encoded)];
+			while let Some((path, encoded)) = inline.pop() {
+				let node = sp_trie::NodeCodec::<H>::decode(&encoded)
+					.map_err(|e| format!("Failed to decode trie node: {:?}", e))?;
+
+				let (path, children) = match node {
+					Node::Empty | Node::Leaf(..) => continue,
+					Node::Extension(partial, child) => {
+						let mut path = path;
+						path.append_partial(partial.right());
+						stack_child::<H>(&mut stack, &mut inline, path, child);
+						continue;
+					},
+					Node::Branch(children, _) => (path, children),
+					Node::NibbledBranch(partial, children, _) => {
+						let mut path = path;
+						path.append_partial(partial.right());
+						(path, children)
+					},
+				};
+
+				for (index, child) in children.iter().enumerate() {
+					if let Some(child) = child {
+						let mut child_path = path.clone();
+						child_path.push(index as u8);
+						stack_child::<H>(&mut stack, &mut inline, child_path, *child);
+					}
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	fn is_marked(&self, key: &[u8]) -> error::Result<bool> {
+		Ok(self.pending.contains(key) || self.marks.contains(key)?)
+	}
+
+	fn insert(&mut self, key: Vec<u8>) -> error::Result<()> {
+		self.pending.insert(key);
+		self.marked += 1;
+		if self.pending.len() == BATCH_SIZE {
+			self.flush()?;
+		}
+		Ok(())
+	}
+
+	/// Write the pending marks to `marks`.
+	fn flush(&mut self) -> error::Result<()> {
+		let mut batch = rocksdb::WriteBatch::default();
+		for key in self.pending.drain() {
+			batch.put(&key, &[]).map_err(rocksdb_error)?;
+		}
+		self.marks.db().write(batch).map_err(rocksdb_error)
+	}
+}
+
+/// Queue a child node, stored in the database or inlined in its parent.
+#[cfg(feature = "db")]
+fn stack_child<H: Hasher>(
+	stack: &mut Vec<(NibbleVec, H::Out)>,
+	inline: &mut Vec<(NibbleVec, Vec<u8>)>,
+	path: NibbleVec,
+	child: NodeHandle,
+) {
+	match child {
+		NodeHandle::Hash(data) => {
+			let mut hash = H::Out::default();
+			if data.len() == hash.as_ref().len() {
+				hash.as_mut().copy_from_slice(data);
+				stack.push((path, hash));
+			}
+		},
+		NodeHandle::Inline(data) => inline.push((path, data.to_vec())),
+	}
+}
+
+/// Returns the database key of a trie node, as written by the client.
+#[cfg(feature = "db")]
+fn db_key<H: Hasher>(keyspace: &[u8], hash: &H::Out, prefix: Prefix) -> Vec<u8> {
+	if keyspace.is_empty() {
+		prefixed_key::<H>(hash, prefix)
+	} else {
+		let mut path = keyspace.to_vec();
+		path.extend_from_slice(prefix.0);
+		prefixed_key::<H>(hash, (&path, prefix.1))
+	}
+}
+
+/// Delete the state entries that are not marked, or only count them on a dry run.
+///
+/// Returns the number of entries and their size in bytes.
+#[cfg(feature = "db")]
+fn sweep(db: &rocksdb::DB, marks: &Marks, dry_run: bool) -> error::Result<(u64, u64)> {
+	let state = column(db, columns::STATE)?;
+	let mut batch = rocksdb::WriteBatch::default();
+	let mut pending = 0;
+	let (mut removed, mut removed_bytes) = (0u64, 0u64);
+
+	for (key, value) in db.iterator_cf(state, rocksdb::IteratorMode::Start) {
+		if marks.contains(&key)? {
+			continue;
+		}
+
+		removed += 1;
+		removed_bytes += (key.len() + value.len()) as u64;
+		if dry_run {
+			continue;
+		}
+
+		batch.delete_cf(state, &key).map_err(rocksdb_error)?;
+		pending += 1;
+		if pending == BATCH_SIZE {
+			db.write(std::mem::take(&mut batch)).map_err(rocksdb_error)?;
+			pending = 0;
+			println!("Removed {} trie nodes ({} bytes)", removed, removed_bytes);
+		}
+	}
+
+	if pending > 0 {
+		db.write(batch).map_err(rocksdb_error)?;
+	}
+
+	Ok((removed, removed_bytes))
+}
+
+/// Switch the state database to constrained pruning of `keep` blocks and drop its pruning
+/// journal.
+///
+/// The journal refers to state that was just removed, and pruning restarts from the last
+/// canonical block, so the node can be restarted with `--pruning`.
+#[cfg(feature = "db")]
+fn reset_state_meta(db: &rocksdb::DB, keep: u32) -> error::Result<()> {
+	let meta = column(db, columns::STATE_META)?;
+	let mut batch = rocksdb::WriteBatch::default();
+
+	for (key, _) in db.iterator_cf(meta, rocksdb::IteratorMode::Start) {
+		if key.ends_with(state_meta_keys::PRUNING_JOURNAL) {
+			batch.delete_cf(meta, &key).map_err(rocksdb_error)?;
+		}
+	}
+
+	let mode = PruningMode::keep_blocks(keep);
+	batch.put_cf(meta, state_meta_keys::PRUNING_MODE, mode.id()).map_err(rocksdb_error)?;
+	let last_canonical = db.get_cf(meta, state_meta_keys::LAST_CANONICAL).map_err(rocksdb_error)?;
+	if let Some(last_canonical) = last_canonical {
+		// The last canonical block is encoded as `(hash, number)`.
+		let number = u64::decode(&mut &last_canonical[last_canonical.len().saturating_sub(8)..])
+			.map_err(|e| format!("Invalid last canonical block in the state metadata: {:?}", e))?;
+		batch.put_cf(meta, state_meta_keys::LAST_PRUNED, number.encode()).map_err(rocksdb_error)?;
+	}
+
+	db.write(batch).map_err(rocksdb_error)
+}
+
+/// Convert a trie error to a CLI error.
+#[cfg(feature = "db")]
+fn trie_error<E: std::fmt::Debug>(err: E) -> error::Error {
+	error::Error::Other(format!("Failed to read the state: {:?}", err))
+}
+
+#[cfg(all(test, feature = "db"))]
+mod tests {
+	use super::*;
+	use sp_core::H256;
+	use sp_runtime::testing::{Block as RawBlock, ExtrinsicWrapper, Header};
+	use sp_runtime::traits::BlakeTwo256;
+	use sp_trie::{trie_types::TrieDBMut, MemoryDB, TrieMut};
+
+	type Block = RawBlock<ExtrinsicWrapper<u64>>;
+
+	/// A value long enough for its leaf to be stored as a node of its own.
+	const SHARED: &[u8] = b"a value shared by the state of all the blocks";
+
+	/// Write a client database at `path` with the given states for the blocks 1, 2, ..., the last
+	/// one being best and finalized. Returns the state roots.
+	fn database_with_states(path: &Path, states: &[&[(&[u8], &[u8])]]) -> Vec<H256> {
+		let mut opts = rocksdb::Options::default();
+		opts.create_if_missing(true);
+		opts.create_missing_column_families(true);
+		let names = (0..sc_client_db::NUM_COLUMNS).map(column_family_name).collect::<Vec<_>>();
+		let db = rocksdb::DB::open_cf(&opts, path, &names).unwrap();
+		let column = |index| db.cf_handle(&column_family_name(index)).unwrap();
+
+		let (mut roots, mut parent, mut lookup_key) = (Vec::new(), H256::default(), Vec::new());
+		for (number, state) in (1..).zip(states) {
+			let mut nodes = MemoryDB::<BlakeTwo256>::default();
+			let mut root = H256::default();
+			{
+				let mut trie = TrieDBMut::<BlakeTwo256>::new(&mut nodes, &mut root);
+				for (key, value) in state.iter() {
+					trie.insert(key, value).unwrap();
+				}
+			}
+			for (key, (value, _)) in nodes.drain() {
+				db.put_cf(column(columns::STATE), key, value).unwrap();
+			}
+
+			let header = Header::new(number, Default::default(), root, parent, Default::default());
+			parent = header.hash();
+			lookup_key = (number as u32).to_be_bytes().to_vec();
+			lookup_key.extend_from_slice(parent.as_ref());
+			db.put_cf(column(columns::HEADER), &lookup_key, header.encode()).unwrap();
+			roots.push(root);
+		}
+
+		db.put_cf(column(columns::META), meta_keys::BEST_BLOCK, &lookup_key).unwrap();
+		db.put_cf(column(columns::META), meta_keys::FINALIZED_BLOCK, &lookup_key).unwrap();
+		roots
+	}
+
+	#[test]
+	fn prune_removes_the_old_state_only() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("db");
+		let roots = database_with_states(&path, &[
+			&[(b"shared", SHARED), (b"old", b"1")],
+			&[(b"shared", SHARED), (b"new", b"2")],
+			&[(b"shared", SHARED), (b"new", b"3")],
+		]);
+		let read = |root: &H256, key: &[u8]| {
+			let db = open_database(&path).unwrap();
+			let state = StateColumn::<BlakeTwo256>::new(&db).unwrap();
+			let trie = TrieDB::<BlakeTwo256>::new(&state, root).map_err(|_| ())?;
+			trie.get(key).map_err(|_| ())
+		};
+
+		prune::<Block>(&path, 1, true).unwrap();
+		assert_eq!(read(&roots[0], b"old"), Ok(Some(b"1".to_vec())));
+
+		prune::<Block>(&path, 1, false).unwrap();
+		assert!(read(&roots[0], b"old").is_err());
+		assert_eq!(read(&roots[1], b"shared"), Ok(Some(SHARED.to_vec())));
+		assert_eq!(read(&roots[1], b"new"), Ok(Some(b"2".to_vec())));
+		assert_eq!(read(&roots[2], b"new"), Ok(Some(b"3".to_vec())));
+		assert!(!dir.path().join("db.prune-marks").exists());
+
+		let db = open_database(&path).unwrap();
+		let meta = db.cf_handle(&column_family_name(columns::STATE_META)).unwrap();
+		let mode = db.get_cf(meta, state_meta_keys::PRUNING_MODE).unwrap();
+		assert_eq!(mode.as_deref(), Some(PruningMode::keep_blocks(1).id()));
+	}
+}
@@ -16,13 +16,16 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::commands::backup_cmd::read_genesis_hash;
+use crate::commands::utils::{confirm, dir_usage, print_chain};
 use crate::error;
 use crate::params::{DatabaseParams, SharedParams};
 use crate::CliConfiguration;
-use sc_service::Configuration;
+use sc_service::{config::DatabaseConfig, Configuration, Role};
+use sp_core::hexdisplay::HexDisplay;
 use std::fmt::Debug;
 use std::fs;
-use std::io::{self, Write};
+use std::io;
 use structopt::StructOpt;
 
 /// The `purge-chain` command used to remove the whole chain.
@@ -32,6 +35,15 @@ pub struct PurgeChainCmd {
 	#[structopt(short = "y")]
 	pub yes: bool,
 
+	/// Only print the size and the number of the files that would be removed, without removing
+	/// anything.
+	#[structopt(long = "dry-run")]
+	pub dry_run: bool,
+
+	/// Remove the database of a light client.
+	#[structopt(long)]
+	pub light: bool,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub shared_params: SharedParams,
@@ -43,27 +55,42 @@ pub struct PurgeChainCmd {
 
 impl PurgeChainCmd {
 	/// Run the purge command
+	///
+	/// The chain and the genesis hash of its database are printed before the confirmation.
 	pub fn run(&self, config: Configuration) -> error::Result<()> {
 		let db_path = config.database.path()
 			.ok_or_else(||
 				error::Error::Input("Cannot purge custom database implementation".into())
 		)?;
 
-		if !self.yes {
-			print!("Are you sure to remove {:?}? [y/N]: ", &db_path);
-			io::stdout().flush().expect("failed to flush stdout");
+		// The genesis hash is only recorded by RocksDB databases that the CLI can read.
+		let genesis_hash = match &config.database {
+			DatabaseConfig::RocksDb { path, .. } if path.exists() => read_genesis_hash(path)
+				.map(|hash| format!("0x{}", HexDisplay::from(&hash)))
+				.unwrap_or_else(|e| format!("unknown ({})", e)),
+			_ => "unknown".into(),
+		};
+		print_chain(&*config.chain_spec, &genesis_hash);
 
-			let mut input = String::new();
-			io::stdin().read_line(&mut input)?;
-			let input = input.trim();
-
-			match input.chars().nth(0) {
-				Some('y') | Some('Y') => {},
-				_ => {
-					println!("Aborted");
-					return Ok(());
+		if self.dry_run {
+			match dir_usage(db_path) {
+				Ok((size, files)) => println!(
+					"Purging would remove {:?}: {} files, {} bytes.",
+					&db_path,
+					files,
+					size,
+				),
+				Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+					println!("{:?} does not exist, there is nothing to purge.", &db_path);
 				},
+				Err(err) => return Err(err.into()),
 			}
+			return Ok(());
+		}
+
+		if !self.yes && !confirm(&format!("Are you sure to remove {:?}?", &db_path))? {
+			println!("Aborted");
+			return Ok(());
 		}
 
 		match fs::remove_dir_all(&db_path) {
@@ -88,4 +115,8 @@ impl CliConfiguration for PurgeChainCmd {
 	fn database_params(&self) -> Option<&DatabaseParams> {
 		Some(&self.database_params)
 	}
+
+	fn role(&self, _is_dev: bool) -> error::Result<Role> {
+		Ok(if self.light { Role::Light } else { Role::Full })
+	}
 }
@@ -16,6 +16,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::commands::utils::{confirm, describe_genesis_hash, print_chain};
 use crate::error;
 use crate::params::{BlockNumber, PruningParams, SharedParams};
 use crate::CliConfiguration;
@@ -31,6 +32,10 @@ pub struct RevertCmd {
 	#[structopt(default_value = "256")]
 	pub num: BlockNumber,
 
+	/// Skip interactive prompt by answering yes automatically.
+	#[structopt(short = "y")]
+	pub yes: bool,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub shared_params: SharedParams,
@@ -42,6 +47,8 @@ pub struct RevertCmd {
 
 impl RevertCmd {
 	/// Run the revert command
+	///
+	/// The chain and its genesis hash are printed before the confirmation.
 	pub fn run<B, BC, BB>(&self, config: Configuration, builder: B) -> error::Result<()>
 	where
 		B: FnOnce(Configuration) -> Result<BC, sc_service::error::Error>,
@@ -51,7 +58,16 @@ impl RevertCmd {
 		<BB as BlockT>::Hash: std::str::FromStr,
 	{
 		let blocks = self.num.parse()?;
-		builder(config)?.revert_chain(blocks)?;
+		let chain_spec = config.chain_spec.cloned_box();
+		let builder = builder(config)?;
+
+		print_chain(&*chain_spec, &describe_genesis_hash(builder.genesis_hash()));
+		if !self.yes && !confirm(&format!("Are you sure to revert {} blocks?", blocks))? {
+			println!("Aborted");
+			return Ok(());
+		}
+
+		builder.revert_chain(blocks)?;
 
 		Ok(())
 	}
@@ -27,11 +27,15 @@ use crate::params::OffchainWorkerParams;
 use crate::CliConfiguration;
 use regex::Regex;
 use sc_service::{
-	config::{MultiaddrWithPeerId, PrometheusConfig, TransactionPoolOptions},
+	config::{
+		BasePath, KeystoreConfig, MultiaddrWithPeerId, NetworkConfiguration, NodeKeyConfig,
+		OffchainWorkerConfig, PrometheusConfig, PruningMode, TransactionPoolOptions,
+	},
 	ChainSpec, Role,
 };
 use sc_telemetry::TelemetryEndpoints;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
 use structopt::StructOpt;
 
 /// The `run` command used to run a node.
@@ -44,7 +48,7 @@ pub struct RunCmd {
 	/// availability of local keys).
 	#[structopt(
 		long = "validator",
-		conflicts_with_all = &[ "sentry" ]
+		conflicts_with_all = &[ "sentry", "light" ]
 	)]
 	pub validator: bool,
 
@@ -69,7 +73,7 @@ pub struct RunCmd {
 	pub no_grandpa: bool,
 
 	/// Experimental: Run in light client mode.
-	#[structopt(long = "light", conflicts_with = "sentry")]
+	#[structopt(long = "light", conflicts_with_all = &[ "sentry", "validator" ])]
 	pub light: bool,
 
 	/// Listen to all RPC interfaces.
@@ -126,20 +130,50 @@ pub struct RunCmd {
 	#[structopt(long = "rpc-port", value_name = "PORT")]
 	pub rpc_port: Option<u16>,
 
+	/// Specify the interface of the HTTP RPC server, like `127.0.0.1` or `::1`.
+	///
+	/// Overrides `--rpc-external`. Any interface but a loopback one exposes the server like
+	/// `--rpc-external` does.
+	#[structopt(long = "rpc-interface", value_name = "IP")]
+	pub rpc_interface: Option<IpAddr>,
+
 	/// Specify WebSockets RPC server TCP port.
 	#[structopt(long = "ws-port", value_name = "PORT")]
 	pub ws_port: Option<u16>,
 
+	/// Specify the interface of the WebSockets RPC server, like `127.0.0.1` or `::1`.
+	///
+	/// Overrides `--ws-external`. Any interface but a loopback one exposes the server like
+	/// `--ws-external` does.
+	#[structopt(long = "ws-interface", value_name = "IP")]
+	pub ws_interface: Option<IpAddr>,
+
+	/// Serve the RPC methods over a Unix domain socket, or a named pipe on Windows, at this path.
+	///
+	/// The socket is only accessible to its owner, and allows the unsafe RPC methods unless
+	/// `--rpc-methods safe` is given.
+	#[structopt(long = "ipc-path", value_name = "PATH", parse(from_os_str))]
+	pub ipc_path: Option<PathBuf>,
+
 	/// Maximum number of WS RPC server connections.
-	#[structopt(long = "ws-max-connections", value_name = "COUNT")]
+	///
+	/// The connections above it are refused. Default is 100.
+	#[structopt(long = "ws-max-connections", value_name = "COUNT", parse(try_from_str = parse_non_zero))]
 	pub ws_max_connections: Option<usize>,
 
+	/// Maximum size of the requests and responses of the HTTP & WS RPC servers, in MiB.
+	///
+	/// Default is 15 MiB.
+	#[structopt(long = "rpc-max-payload", value_name = "MiB", parse(try_from_str = parse_non_zero))]
+	pub rpc_max_payload: Option<usize>,
+
 	/// Specify browser Origins allowed to access the HTTP & WS RPC servers.
 	///
 	/// A comma-separated list of origins (protocol://domain or special `null`
-	/// value). Value of `all` will disable origin validation. Default is to
-	/// allow localhost and https://polkadot.js.org origins. When running in
-	/// --dev mode the default is to allow all origins.
+	/// value), where the domain and the port can be `*`. Value of `all` will
+	/// disable origin validation. Default is to allow localhost and
+	/// https://polkadot.js.org origins. When running in --dev mode the default
+	/// is to allow all origins.
 	#[structopt(long = "rpc-cors", value_name = "ORIGINS", parse(try_from_str = parse_cors))]
 	pub rpc_cors: Option<Cors>,
 
@@ -155,7 +189,8 @@ pub struct RunCmd {
 
 	/// The human-readable name for this node.
 	///
-	/// The node name will be reported to the telemetry server, if enabled.
+	/// The node name will be reported to the telemetry server, if enabled. It must not be longer
+	/// than 64 characters. A random name of two words is used if not given.
 	#[structopt(long = "name", value_name = "NAME")]
 	pub name: Option<String>,
 
@@ -169,8 +204,9 @@ pub struct RunCmd {
 	///
 	/// This flag can be passed multiple times as a means to specify multiple
 	/// telemetry endpoints. Verbosity levels range from 0-9, with 0 denoting
-	/// the least verbosity.
-	/// Expected format is 'URL VERBOSITY', e.g. `--telemetry-url 'wss://foo/bar 0'`.
+	/// the least verbosity. The endpoints replace the ones of the chain spec.
+	/// Expected format is 'URL VERBOSITY', e.g. `--telemetry-url 'wss://foo/bar 0'`,
+	/// where the URL uses the `ws` or `wss` scheme.
 	#[structopt(long = "telemetry-url", value_name = "URL VERBOSITY", parse(try_from_str = parse_telemetry_endpoints))]
 	pub telemetry_endpoints: Vec<(String, u8)>,
 
@@ -178,6 +214,14 @@ pub struct RunCmd {
 	#[structopt(flatten)]
 	pub offchain_worker_params: OffchainWorkerParams,
 
+	/// Specify the path of a directory of Wasm runtimes to execute instead of the on-chain runtimes.
+	///
+	/// Each `.wasm` file replaces the on-chain runtime with the same spec name and spec version, for
+	/// instance to run a fixed runtime on a chain stuck by a bug without waiting for an upgrade.
+	/// The files that aren't valid runtimes are reported at startup and ignored.
+	#[structopt(long = "wasm-runtime-overrides", value_name = "PATH", parse(from_os_str))]
+	pub wasm_runtime_overrides: Option<PathBuf>,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub shared_params: SharedParams,
@@ -226,10 +270,21 @@ pub struct RunCmd {
 	#[structopt(long, conflicts_with_all = &["alice", "bob", "charlie", "dave", "eve", "ferdie", "one"])]
 	pub two: bool,
 
+	/// Start even with flags that are dangerous on a live chain.
+	///
+	/// `--force-authoring` and the development keys like `--alice` are refused on chains whose
+	/// specification has the `Live` chain type, unless this is given.
+	#[structopt(long = "i-know-what-im-doing")]
+	pub i_know_what_im_doing: bool,
+
 	/// Enable authoring even when offline.
 	#[structopt(long = "force-authoring")]
 	pub force_authoring: bool,
 
+	/// Don't force authoring when offline, even with `--dev`.
+	#[structopt(long = "no-force-authoring", conflicts_with = "force-authoring")]
+	pub no_force_authoring: bool,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub keystore_params: KeystoreParams,
@@ -250,6 +305,13 @@ pub struct RunCmd {
 		conflicts_with_all = &[ "sentry", "public-addr" ]
 	)]
 	pub sentry_nodes: Vec<MultiaddrWithPeerId>,
+
+	/// Run a temporary node.
+	///
+	/// A temporary directory will be created to store the configuration and the data of the node,
+	/// and removed when the node stops.
+	#[structopt(long = "tmp", conflicts_with = "base-path")]
+	pub tmp: bool,
 }
 
 impl RunCmd {
@@ -277,6 +339,34 @@ impl RunCmd {
 			None
 		}
 	}
+
+	/// The addresses of the HTTP and WebSockets RPC servers, which can't overlap.
+	///
+	/// The servers may share a port on different interfaces, unless one of them listens on all
+	/// interfaces.
+	fn rpc_addrs(&self) -> Result<(SocketAddr, SocketAddr)> {
+		let http = SocketAddr::new(
+			rpc_interface(self.rpc_interface, self.rpc_external || self.unsafe_rpc_external),
+			self.rpc_port.unwrap_or(9933),
+		);
+		let ws = SocketAddr::new(
+			rpc_interface(self.ws_interface, self.ws_external || self.unsafe_ws_external),
+			self.ws_port.unwrap_or(9944),
+		);
+
+		let overlap = http.port() == ws.port()
+			&& (http.ip() == ws.ip() || http.ip().is_unspecified() || ws.ip().is_unspecified());
+		if overlap {
+			return Err(Error::Input(format!(
+				"The HTTP RPC server on {} and the WebSockets RPC server on {} can't both listen. \
+				Change `--rpc-port` or `--ws-port`, or their interfaces.",
+				http,
+				ws,
+			)));
+		}
+
+		Ok((http, ws))
+	}
 }
 
 impl CliConfiguration for RunCmd {
@@ -288,6 +378,14 @@ impl CliConfiguration for RunCmd {
 		Some(&self.import_params)
 	}
 
+	fn base_path(&self) -> Result<Option<BasePath>> {
+		Ok(if self.tmp {
+			Some(BasePath::new_temp_dir()?)
+		} else {
+			self.shared_params.base_path().map(Into::into)
+		})
+	}
+
 	fn network_params(&self) -> Option<&NetworkParams> {
 		Some(&self.network_params)
 	}
@@ -296,22 +394,93 @@ impl CliConfiguration for RunCmd {
 		Some(&self.keystore_params)
 	}
 
+	fn network_config(
+		&self,
+		chain_spec: &Box<dyn ChainSpec>,
+		is_dev: bool,
+		net_config_dir: PathBuf,
+		client_id: &str,
+		node_name: &str,
+		node_key: NodeKeyConfig,
+	) -> Result<NetworkConfiguration> {
+		let mut config = self.network_params.network_config(
+			chain_spec,
+			is_dev,
+			Some(net_config_dir),
+			client_id,
+			node_name,
+			node_key,
+		)?;
+		add_reserved_nodes(&mut config, &self.sentry);
+
+		// Light clients only fetch from full nodes and don't serve anything themselves.
+		if self.light {
+			config.in_peers = self.network_params.in_peers.unwrap_or(0);
+			config.out_peers = self.network_params.out_peers.unwrap_or(8);
+		}
+
+		Ok(config)
+	}
+
+	fn keystore_config(&self, base_path: &PathBuf) -> Result<KeystoreConfig> {
+		// The development keys of `--dev` don't need to outlive the node.
+		if self.shared_params.dev && !self.keystore_params.is_given() {
+			Ok(KeystoreConfig::InMemory)
+		} else {
+			self.keystore_params.keystore_config(base_path)
+		}
+	}
+
 	fn offchain_worker_params(&self) -> Option<&OffchainWorkerParams> {
 		Some(&self.offchain_worker_params)
 	}
 
+	fn offchain_worker(&self, role: &Role) -> Result<OffchainWorkerConfig> {
+		let mut config = self.offchain_worker_params.offchain_worker(role)?;
+		config.indexing_enabled = self.import_params.offchain_indexing();
+
+		if self.light && (config.enabled || config.indexing_enabled) {
+			log::warn!(
+				"Light clients can't run offchain workers or index blocks, ignoring `--offchain-worker` \
+				and `--enable-offchain-indexing`."
+			);
+			return Ok(OffchainWorkerConfig { enabled: false, indexing_enabled: false });
+		}
+
+		log::info!(
+			"Offchain workers {} (`--offchain-worker {}`){}",
+			if config.enabled { "enabled" } else { "disabled" },
+			self.offchain_worker_params.enabled,
+			if config.indexing_enabled { ", with offchain indexing" } else { "" },
+		);
+		Ok(config)
+	}
+
+	fn state_pruning(&self, unsafe_pruning: bool, role: &Role) -> Result<PruningMode> {
+		if self.light && self.import_params.pruning_params.pruning.is_some() {
+			log::warn!("Light clients don't keep any state, ignoring `--pruning`.");
+			return Ok(PruningMode::default());
+		}
+
+		self.import_params.pruning_params.state_pruning(unsafe_pruning, role)
+	}
+
 	fn node_name(&self) -> Result<String> {
 		let name: String = match (self.name.as_ref(), self.get_keyring()) {
 			(Some(name), _) => name.to_string(),
 			(_, Some(keyring)) => keyring.to_string(),
-			(None, None) => crate::generate_node_name(),
+			(None, None) => {
+				let name = crate::generate_node_name();
+				log::info!("No `--name` given, using the random node name {}", name);
+				name
+			},
 		};
 
 		is_node_name_valid(&name).map_err(|msg| {
 			Error::Input(format!(
 				"Invalid node name '{}'. Reason: {}. If unsure, use none.",
 				name, msg
-			));
+			))
 		})?;
 
 		Ok(name)
@@ -348,24 +517,33 @@ impl CliConfiguration for RunCmd {
 		let is_light = self.light;
 		let is_authority = (self.validator || is_dev || keyring.is_some()) && !is_light;
 
+		// A sentry never authors, whatever keys `--dev` or the shortcuts would provide.
 		Ok(if is_light {
 			sc_service::Role::Light
-		} else if is_authority {
-			sc_service::Role::Authority {
-				sentry_nodes: self.sentry_nodes.clone(),
-			}
 		} else if !self.sentry.is_empty() {
 			sc_service::Role::Sentry {
 				validators: self.sentry.clone(),
 			}
+		} else if is_authority {
+			sc_service::Role::Authority {
+				sentry_nodes: self.sentry_nodes.clone(),
+			}
 		} else {
 			sc_service::Role::Full
 		})
 	}
 
+	fn allow_dangerous_flags(&self) -> Result<bool> {
+		Ok(self.i_know_what_im_doing)
+	}
+
+	fn wasm_runtime_overrides(&self) -> Result<Option<PathBuf>> {
+		Ok(self.wasm_runtime_overrides.clone())
+	}
+
 	fn force_authoring(&self) -> Result<bool> {
 		// Imply forced authoring on --dev
-		Ok(self.shared_params.dev || self.force_authoring)
+		Ok((self.shared_params.dev || self.force_authoring) && !self.no_force_authoring)
 	}
 
 	fn prometheus_config(&self) -> Result<Option<PrometheusConfig>> {
@@ -388,11 +566,28 @@ impl CliConfiguration for RunCmd {
 		Ok(self.no_grandpa)
 	}
 
+	fn rpc_ipc(&self) -> Result<Option<PathBuf>> {
+		Ok(self.ipc_path.clone())
+	}
+
 	fn rpc_ws_max_connections(&self) -> Result<Option<usize>> {
 		Ok(self.ws_max_connections)
 	}
 
+	fn rpc_max_payload(&self) -> Result<Option<usize>> {
+		Ok(self.rpc_max_payload)
+	}
+
 	fn rpc_cors(&self, is_dev: bool) -> Result<Option<Vec<String>>> {
+		if let Some(Cors::All) = self.rpc_cors {
+			if !is_dev && matches!(self.role(is_dev)?, Role::Authority { .. }) {
+				log::warn!(
+					"`--rpc-cors all` lets any website call the RPC servers of this validator \
+					from the browser of its operator."
+				);
+			}
+		}
+
 		Ok(self
 			.rpc_cors
 			.clone()
@@ -414,25 +609,29 @@ impl CliConfiguration for RunCmd {
 	}
 
 	fn rpc_http(&self) -> Result<Option<SocketAddr>> {
-		let interface = rpc_interface(
-			self.rpc_external,
+		let (addr, _) = self.rpc_addrs()?;
+		check_rpc_interface(
+			"HTTP RPC",
+			addr.ip(),
 			self.unsafe_rpc_external,
 			self.rpc_methods,
 			self.validator
 		)?;
 
-		Ok(Some(SocketAddr::new(interface, self.rpc_port.unwrap_or(9933))))
+		Ok(Some(addr))
 	}
 
 	fn rpc_ws(&self) -> Result<Option<SocketAddr>> {
-		let interface = rpc_interface(
-			self.ws_external,
+		let (_, addr) = self.rpc_addrs()?;
+		check_rpc_interface(
+			"WebSockets RPC",
+			addr.ip(),
 			self.unsafe_ws_external,
 			self.rpc_methods,
 			self.validator
 		)?;
 
-		Ok(Some(SocketAddr::new(interface, self.ws_port.unwrap_or(9944))))
+		Ok(Some(addr))
 	}
 
 	fn rpc_methods(&self) -> Result<sc_service::config::RpcMethods> {
@@ -448,34 +647,67 @@ impl CliConfiguration for RunCmd {
 	}
 }
 
+/// Add `nodes` to the reserved nodes of `config`, leaving out the ones already there.
+fn add_reserved_nodes(config: &mut NetworkConfiguration, nodes: &[MultiaddrWithPeerId]) {
+	for node in nodes {
+		if !config.reserved_nodes.iter().any(|reserved| reserved.to_string() == node.to_string()) {
+			config.reserved_nodes.push(node.clone());
+		}
+	}
+}
+
 /// Check whether a node name is considered as valid.
-pub fn is_node_name_valid(_name: &str) -> std::result::Result<(), &str> {
+pub fn is_node_name_valid(_name: &str) -> std::result::Result<(), String> {
 	let name = _name.to_string();
-	if name.chars().count() >= crate::NODE_NAME_MAX_LENGTH {
-		return Err("Node name too long");
+	if name.chars().count() > crate::NODE_NAME_MAX_LENGTH {
+		return Err(format!(
+			"Node name should not be longer than {} characters",
+			crate::NODE_NAME_MAX_LENGTH,
+		));
+	}
+
+	if name.chars().any(char::is_control) {
+		return Err("Node name should not contain control characters".into());
 	}
 
 	let invalid_chars = r"[\\.@]";
 	let re = Regex::new(invalid_chars).unwrap();
 	if re.is_match(&name) {
-		return Err("Node name should not contain invalid chars such as '.' and '@'");
+		return Err("Node name should not contain invalid chars such as '.' and '@'".into());
 	}
 
 	let invalid_patterns = r"(https?:\\/+)?(www)+";
 	let re = Regex::new(invalid_patterns).unwrap();
 	if re.is_match(&name) {
-		return Err("Node name should not contain urls");
+		return Err("Node name should not contain urls".into());
 	}
 
 	Ok(())
 }
 
-fn rpc_interface(
-	is_external: bool,
+/// The interface of an RPC server.
+///
+/// This is `interface` if given, all interfaces if the server is external, and localhost otherwise.
+fn rpc_interface(interface: Option<IpAddr>, is_external: bool) -> IpAddr {
+	interface.unwrap_or(if is_external {
+		Ipv4Addr::UNSPECIFIED.into()
+	} else {
+		Ipv4Addr::LOCALHOST.into()
+	})
+}
+
+/// Check that the `server` RPC server may listen on `interface`, warning if that exposes the unsafe
+/// RPC methods.
+fn check_rpc_interface(
+	server: &str,
+	interface: IpAddr,
 	is_unsafe_external: bool,
 	rpc_methods: RpcMethods,
 	is_validator: bool,
-) -> Result<IpAddr> {
+) -> Result<()> {
+	let is_exposed = !interface.is_loopback();
+	let is_external = is_exposed && !is_unsafe_external;
+
 	if is_external && is_validator && rpc_methods != RpcMethods::Unsafe {
 		return Err(Error::Input(
 			"--rpc-external and --ws-external options shouldn't be \
@@ -486,24 +718,28 @@ fn rpc_interface(
 		));
 	}
 
-	if is_external || is_unsafe_external {
-		if rpc_methods == RpcMethods::Unsafe {
-			log::warn!(
-				"It isn't safe to expose RPC publicly without a proxy server that filters \
-			available set of RPC methods."
-			);
-		}
-
-		Ok(Ipv4Addr::UNSPECIFIED.into())
-	} else {
-		Ok(Ipv4Addr::LOCALHOST.into())
+	if is_exposed && rpc_methods == RpcMethods::Unsafe {
+		log::warn!(
+			"⚠️  The {} server listens on {} and exposes the unsafe RPC methods. \
+			It isn't safe to expose RPC publicly without a proxy server that filters \
+			available set of RPC methods. Use `--rpc-methods safe` to deny the unsafe ones.",
+			server,
+			interface,
+		);
 	}
+
+	Ok(())
 }
 
+/// Highest verbosity level of a telemetry endpoint.
+const MAX_TELEMETRY_VERBOSITY: u8 = 9;
+
 #[derive(Debug)]
 enum TelemetryParsingError {
 	MissingVerbosity,
 	VerbosityParsingError(std::num::ParseIntError),
+	VerbosityOutOfRange(u8),
+	InvalidScheme(String),
 }
 
 impl std::error::Error for TelemetryParsingError {}
@@ -513,6 +749,17 @@ impl std::fmt::Display for TelemetryParsingError {
 		match &*self {
 			TelemetryParsingError::MissingVerbosity => write!(f, "Verbosity level missing"),
 			TelemetryParsingError::VerbosityParsingError(e) => write!(f, "{}", e),
+			TelemetryParsingError::VerbosityOutOfRange(verbosity) => write!(
+				f,
+				"Verbosity level {} is out of range, expected 0-{}",
+				verbosity,
+				MAX_TELEMETRY_VERBOSITY,
+			),
+			TelemetryParsingError::InvalidScheme(url) => write!(
+				f,
+				"Invalid telemetry URL `{}`, expected a `ws://` or `wss://` URL",
+				url,
+			),
 		}
 	}
 }
@@ -523,9 +770,18 @@ fn parse_telemetry_endpoints(s: &str) -> std::result::Result<(String, u8), Telem
 		None => Err(TelemetryParsingError::MissingVerbosity),
 		Some(pos_) => {
 			let url = s[..pos_].to_string();
+			// Multiaddresses are accepted as well, like in the chain spec.
+			if !(url.starts_with("ws://") || url.starts_with("wss://") || url.starts_with('/')) {
+				return Err(TelemetryParsingError::InvalidScheme(url));
+			}
+
 			let verbosity = s[pos_ + 1..]
 				.parse()
 				.map_err(TelemetryParsingError::VerbosityParsingError)?;
+			if verbosity > MAX_TELEMETRY_VERBOSITY {
+				return Err(TelemetryParsingError::VerbosityOutOfRange(verbosity));
+			}
+
 			Ok((url, verbosity))
 		}
 	}
@@ -552,6 +808,15 @@ impl From<Cors> for Option<Vec<String>> {
 	}
 }
 
+/// Parse a limit of the RPC servers, which must be at least 1.
+fn parse_non_zero(s: &str) -> std::result::Result<usize, String> {
+	match s.parse() {
+		Ok(0) => Err("must be at least 1".into()),
+		Ok(limit) => Ok(limit),
+		Err(e) => Err(format!("`{}` is not a positive integer: {}", s, e)),
+	}
+}
+
 /// Parse cors origins.
 fn parse_cors(s: &str) -> std::result::Result<Cors, Box<dyn std::error::Error>> {
 	let mut is_all = false;
@@ -562,7 +827,10 @@ fn parse_cors(s: &str) -> std::result::Result<Cors, Box<dyn std::error::Error>>
 				is_all = true;
 				break;
 			}
-			other => origins.push(other.to_owned()),
+			other => {
+				check_origin(other)?;
+				origins.push(other.to_owned());
+			},
 		}
 	}
 
@@ -573,22 +841,428 @@ fn parse_cors(s: &str) -> std::result::Result<Cors, Box<dyn std::error::Error>>
 	})
 }
 
+/// Check that `origin` is `null` or `protocol://domain[:port]`, with an optional `*` domain or port.
+fn check_origin(origin: &str) -> std::result::Result<(), String> {
+	if origin == "null" {
+		return Ok(());
+	}
+
+	let invalid = |reason: &str| Err(format!("Invalid origin `{}`: {}", origin, reason));
+	let (protocol, host) = match origin.find("://") {
+		Some(index) => (&origin[..index], &origin[index + 3..]),
+		None => return invalid("expected `protocol://domain`, like `https://polkadot.js.org`"),
+	};
+	if protocol.is_empty() || !protocol.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c)) {
+		return invalid("invalid protocol");
+	}
+	if host.contains('/') {
+		return invalid("an origin has no path");
+	}
+
+	let (domain, port) = match host.rfind(':') {
+		Some(index) if !host.ends_with(']') => (&host[..index], Some(&host[index + 1..])),
+		_ => (host, None),
+	};
+	let is_ipv6 = domain.starts_with('[') && domain.ends_with(']');
+	if domain.is_empty() || !(is_ipv6 || domain.chars().all(|c| c.is_ascii_alphanumeric() || "-.*".contains(c))) {
+		return invalid("invalid domain");
+	}
+	if port.map_or(false, |port| port != "*" && port.parse::<u16>().is_err()) {
+		return invalid("invalid port");
+	}
+
+	Ok(())
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 
+	fn run_cmd(args: &[&str]) -> RunCmd {
+		RunCmd::from_iter(std::iter::once("substrate").chain(args.iter().cloned()))
+	}
+
+	#[test]
+	fn dev_implies_a_development_node() {
+		let base_path = PathBuf::from("/base");
+		let cmd = run_cmd(&["--dev"]);
+		let is_dev = cmd.is_dev().unwrap();
+
+		assert!(is_dev);
+		assert_eq!(cmd.chain_id(is_dev).unwrap(), "dev");
+		assert_eq!(cmd.dev_key_seed(is_dev).unwrap(), Some("//Alice".into()));
+		assert!(matches!(cmd.role(is_dev).unwrap(), Role::Authority { .. }));
+		assert!(matches!(cmd.keystore_config(&base_path).unwrap(), KeystoreConfig::InMemory));
+		assert!(cmd.force_authoring().unwrap());
+		assert_eq!(cmd.rpc_cors(is_dev).unwrap(), None);
+
+		// The rest is left as without `--dev`.
+		let default = run_cmd(&[]);
+		assert_eq!(cmd.rpc_http().unwrap(), default.rpc_http().unwrap());
+		assert_eq!(cmd.rpc_ws().unwrap(), default.rpc_ws().unwrap());
+		assert_eq!(format!("{:?}", cmd.rpc_methods().unwrap()), format!("{:?}", default.rpc_methods().unwrap()));
+		assert_eq!(cmd.disable_grandpa().unwrap(), default.disable_grandpa().unwrap());
+		assert_eq!(cmd.max_runtime_instances().unwrap(), default.max_runtime_instances().unwrap());
+	}
+
+	#[test]
+	fn no_dev_leaves_the_development_settings_out() {
+		let base_path = PathBuf::from("/base");
+		let cmd = run_cmd(&[]);
+		let is_dev = cmd.is_dev().unwrap();
+
+		assert!(!is_dev);
+		assert_eq!(cmd.chain_id(is_dev).unwrap(), "");
+		assert_eq!(cmd.dev_key_seed(is_dev).unwrap(), None);
+		assert!(matches!(cmd.role(is_dev).unwrap(), Role::Full));
+		assert!(matches!(cmd.keystore_config(&base_path).unwrap(), KeystoreConfig::Path { .. }));
+		assert!(!cmd.force_authoring().unwrap());
+		assert!(cmd.rpc_cors(is_dev).unwrap().is_some());
+	}
+
+	#[test]
+	fn dev_settings_can_be_overridden() {
+		let base_path = PathBuf::from("/base");
+
+		let cmd = run_cmd(&["--dev", "--chain", "local"]);
+		assert_eq!(cmd.chain_id(true).unwrap(), "local");
+
+		let cmd = run_cmd(&["--dev", "--bob"]);
+		assert_eq!(cmd.dev_key_seed(true).unwrap(), Some("//Bob".into()));
+
+		let cmd = run_cmd(&["--dev", "--light"]);
+		assert_eq!(cmd.dev_key_seed(true).unwrap(), None);
+		assert!(matches!(cmd.role(true).unwrap(), Role::Light));
+
+		let cmd = run_cmd(&["--dev", "--no-force-authoring"]);
+		assert!(!cmd.force_authoring().unwrap());
+
+		let cmd = run_cmd(&["--dev", "--rpc-cors", "http://localhost:*"]);
+		assert_eq!(cmd.rpc_cors(true).unwrap(), Some(vec!["http://localhost:*".into()]));
+
+		let cmd = run_cmd(&["--dev", "--keystore-path", "/keys"]);
+		match cmd.keystore_config(&base_path).unwrap() {
+			KeystoreConfig::Path { path, .. } => assert_eq!(path, PathBuf::from("/keys")),
+			KeystoreConfig::InMemory => panic!("an explicit keystore path must be used"),
+		}
+	}
+
+	#[test]
+	fn keystore_in_memory_is_never_written() {
+		let base_path = PathBuf::from("/base");
+
+		let cmd = run_cmd(&["--keystore-in-memory", "--alice"]);
+		assert!(matches!(cmd.keystore_config(&base_path).unwrap(), KeystoreConfig::InMemory));
+		assert_eq!(cmd.dev_key_seed(false).unwrap(), Some("//Alice".into()));
+
+		let cmd = run_cmd(&["--dev", "--keystore-in-memory"]);
+		assert!(matches!(cmd.keystore_config(&base_path).unwrap(), KeystoreConfig::InMemory));
+
+		for contradiction in &["--keystore-path", "--password"] {
+			assert!(RunCmd::from_iter_safe(&["substrate", "--keystore-in-memory", contradiction, "x"]).is_err());
+		}
+	}
+
+	#[test]
+	fn role_follows_the_flags() {
+		assert!(matches!(run_cmd(&[]).role(false).unwrap(), Role::Full));
+		assert!(matches!(run_cmd(&["--validator"]).role(false).unwrap(), Role::Authority { .. }));
+		assert!(matches!(run_cmd(&["--light"]).role(false).unwrap(), Role::Light));
+		assert!(matches!(run_cmd(&[]).role(true).unwrap(), Role::Authority { .. }));
+
+		assert!(RunCmd::from_iter_safe(&["substrate", "--validator", "--light"]).is_err());
+	}
+
+	#[test]
+	fn tmp_uses_a_temporary_base_path() {
+		let path = match run_cmd(&["--tmp"]).base_path().unwrap() {
+			Some(base_path @ BasePath::Temporary(_)) => {
+				assert!(base_path.path().is_dir());
+				base_path.path().to_path_buf()
+			},
+			other => panic!("--tmp must give a temporary base path, got {:?}", other),
+		};
+		assert!(!path.exists(), "the temporary base path is removed once dropped");
+
+		assert!(matches!(run_cmd(&[]).base_path().unwrap(), None));
+		assert!(matches!(run_cmd(&["-d", "/base"]).base_path().unwrap(), Some(BasePath::Permanent(_))));
+		assert!(RunCmd::from_iter_safe(&["substrate", "--tmp", "--base-path", "/base"]).is_err());
+	}
+
+	#[test]
+	fn offchain_workers_follow_the_mode_and_the_role() {
+		let authority = Role::Authority { sentry_nodes: Vec::new() };
+		let enabled = |mode: &str, role: &Role| {
+			let light = matches!(role, Role::Light);
+			let args = if light { vec!["--light", "--offchain-worker", mode] } else { vec!["--offchain-worker", mode] };
+			run_cmd(&args).offchain_worker(role).unwrap().enabled
+		};
+
+		assert!(enabled("always", &authority));
+		assert!(enabled("always", &Role::Full));
+		assert!(!enabled("always", &Role::Light));
+		assert!(!enabled("never", &authority));
+		assert!(!enabled("never", &Role::Full));
+		assert!(enabled("whenvalidating", &authority));
+		assert!(!enabled("whenvalidating", &Role::Full));
+		assert!(!enabled("whenvalidating", &Role::Light));
+		assert!(!run_cmd(&[]).offchain_worker(&Role::Full).unwrap().enabled);
+		assert!(run_cmd(&["--validator"]).offchain_worker(&authority).unwrap().enabled);
+	}
+
+	#[test]
+	fn offchain_indexing_is_independent_of_the_offchain_workers() {
+		let indexing = |args: &[&str], role: &Role| run_cmd(args).offchain_worker(role).unwrap().indexing_enabled;
+
+		assert!(!indexing(&[], &Role::Full));
+		assert!(indexing(&["--enable-offchain-indexing", "true"], &Role::Full));
+		assert!(indexing(&["--enable-offchain-indexing", "true", "--offchain-worker", "never"], &Role::Full));
+		assert!(!indexing(&["--light", "--enable-offchain-indexing", "true"], &Role::Light));
+	}
+
+	#[test]
+	fn light_client_ignores_the_full_node_settings() {
+		let cmd = run_cmd(&["--light", "--pruning", "archive", "--offchain-worker", "always"]);
+		assert_eq!(cmd.state_pruning(false, &Role::Light).unwrap(), PruningMode::default());
+		assert!(!cmd.offchain_worker(&Role::Light).unwrap().enabled);
+
+		let cmd = run_cmd(&["--pruning", "archive", "--offchain-worker", "always"]);
+		assert_eq!(cmd.state_pruning(false, &Role::Full).unwrap(), PruningMode::ArchiveAll);
+		assert!(cmd.offchain_worker(&Role::Full).unwrap().enabled);
+	}
+
+	#[test]
+	fn sentry_reserves_the_validators() {
+		let validator = "/ip4/198.51.100.19/tcp/30333/p2p/QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV";
+		let cmd = run_cmd(&["--sentry", validator, "--reserved-nodes", validator]);
+
+		match cmd.role(false).unwrap() {
+			Role::Sentry { validators } => assert_eq!(validators[0].to_string(), validator),
+			_ => panic!("--sentry must give the sentry role"),
+		}
+		// Even with the keys of `--dev` or a shortcut.
+		assert!(matches!(run_cmd(&["--sentry", validator, "--alice"]).role(true).unwrap(), Role::Sentry { .. }));
+
+		let mut config = NetworkConfiguration::new_local();
+		config.reserved_nodes = cmd.network_params.reserved_nodes.clone();
+		add_reserved_nodes(&mut config, &cmd.sentry);
+		assert_eq!(
+			config.reserved_nodes.iter().map(ToString::to_string).collect::<Vec<_>>(),
+			vec![validator.to_string()],
+		);
+
+		let mut config = NetworkConfiguration::new_local();
+		add_reserved_nodes(&mut config, &cmd.sentry);
+		assert_eq!(config.reserved_nodes.len(), 1);
+
+		assert!(RunCmd::from_iter_safe(&["substrate", "--sentry", validator, "--validator"]).is_err());
+	}
+
+	#[test]
+	fn keyring_shortcuts_make_a_named_validator() {
+		let cmd = run_cmd(&["--charlie"]);
+
+		assert_eq!(cmd.node_name().unwrap(), "Charlie");
+		assert_eq!(cmd.dev_key_seed(false).unwrap(), Some("//Charlie".into()));
+		assert!(matches!(cmd.role(false).unwrap(), Role::Authority { .. }));
+
+		// An explicit name wins, and `--validator` changes nothing.
+		assert_eq!(run_cmd(&["--charlie", "--name", "node"]).node_name().unwrap(), "node");
+		let cmd = run_cmd(&["--validator", "--charlie"]);
+		assert_eq!(cmd.dev_key_seed(false).unwrap(), Some("//Charlie".into()));
+		assert!(matches!(cmd.role(false).unwrap(), Role::Authority { .. }));
+	}
+
+	#[test]
+	fn keyring_shortcuts_are_exclusive() {
+		let args = ["alice", "bob", "charlie", "dave", "eve", "ferdie"];
+		for first in &args {
+			for second in args.iter().filter(|second| second != &first) {
+				let result = RunCmd::from_iter_safe(&[
+					"substrate",
+					&format!("--{}", first),
+					&format!("--{}", second),
+				]);
+				assert!(result.is_err(), "--{} and --{} must conflict", first, second);
+			}
+		}
+	}
+
+	#[test]
+	fn rpc_servers_listen_on_localhost_by_default() {
+		let cmd = run_cmd(&[]);
+		assert_eq!(cmd.rpc_http().unwrap(), Some("127.0.0.1:9933".parse().unwrap()));
+		assert_eq!(cmd.rpc_ws().unwrap(), Some("127.0.0.1:9944".parse().unwrap()));
+
+		let cmd = run_cmd(&["--rpc-port", "8000", "--ws-port", "8001"]);
+		assert_eq!(cmd.rpc_http().unwrap(), Some("127.0.0.1:8000".parse().unwrap()));
+		assert_eq!(cmd.rpc_ws().unwrap(), Some("127.0.0.1:8001".parse().unwrap()));
+	}
+
+	#[test]
+	fn rpc_servers_follow_the_external_and_interface_flags() {
+		let cmd = run_cmd(&["--rpc-external", "--unsafe-ws-external"]);
+		assert_eq!(cmd.rpc_http().unwrap(), Some("0.0.0.0:9933".parse().unwrap()));
+		assert_eq!(cmd.rpc_ws().unwrap(), Some("0.0.0.0:9944".parse().unwrap()));
+
+		let cmd = run_cmd(&["--rpc-interface", "::1", "--ws-interface", "::", "--ws-port", "9000"]);
+		assert_eq!(cmd.rpc_http().unwrap(), Some("[::1]:9933".parse().unwrap()));
+		assert_eq!(cmd.rpc_ws().unwrap(), Some("[::]:9000".parse().unwrap()));
+
+		let cmd = run_cmd(&["--rpc-external", "--rpc-interface", "192.0.2.1"]);
+		assert_eq!(cmd.rpc_http().unwrap(), Some("192.0.2.1:9933".parse().unwrap()));
+
+		// A validator only exposes the unsafe methods on purpose.
+		assert!(run_cmd(&["--validator", "--ws-interface", "::"]).rpc_ws().is_err());
+		assert!(run_cmd(&["--validator", "--ws-interface", "::1"]).rpc_ws().is_ok());
+		assert!(run_cmd(&["--validator", "--ws-external", "--rpc-methods", "unsafe"]).rpc_ws().is_ok());
+		assert!(run_cmd(&["--validator", "--unsafe-rpc-external"]).rpc_http().is_ok());
+	}
+
+	#[test]
+	fn rpc_methods_combine_with_the_external_flags() {
+		let methods = |cmd: &RunCmd| format!("{:?}", cmd.rpc_methods().unwrap());
+
+		for (args, expected) in &[
+			(&[][..], "Auto"),
+			(&["--rpc-methods", "safe"][..], "Safe"),
+			(&["--rpc-methods", "Unsafe"][..], "Unsafe"),
+		] {
+			for external in &["--rpc-external", "--unsafe-rpc-external", "--ws-external"] {
+				let cmd = run_cmd(&[*args, &[*external][..]].concat());
+				assert_eq!(methods(&cmd), *expected);
+
+				let exposed: Option<SocketAddr> = if external.contains("rpc") {
+					cmd.rpc_http().unwrap()
+				} else {
+					cmd.rpc_ws().unwrap()
+				};
+				assert!(!exposed.unwrap().ip().is_loopback(), "{} must expose the server", external);
+			}
+
+			let cmd = run_cmd(args);
+			assert_eq!(methods(&cmd), *expected);
+			assert!(cmd.rpc_http().unwrap().unwrap().ip().is_loopback());
+		}
+
+		assert!(RunCmd::from_iter_safe(&["substrate", "--rpc-methods", "some"]).is_err());
+	}
+
+	#[test]
+	fn rpc_limits_are_at_least_one() {
+		let cmd = run_cmd(&["--ws-max-connections", "1", "--rpc-max-payload", "32"]);
+		assert_eq!(cmd.rpc_ws_max_connections().unwrap(), Some(1));
+		assert_eq!(cmd.rpc_max_payload().unwrap(), Some(32));
+		assert_eq!(run_cmd(&[]).rpc_max_payload().unwrap(), None);
+
+		for args in &[
+			["--ws-max-connections", "0"],
+			["--rpc-max-payload", "0"],
+			["--ws-max-connections", "-1"],
+			["--rpc-max-payload", "15MiB"],
+		] {
+			assert!(RunCmd::from_iter_safe(std::iter::once("substrate").chain(args.iter().cloned())).is_err());
+		}
+	}
+
+	#[test]
+	fn rpc_servers_cannot_share_a_port() {
+		assert!(run_cmd(&["--rpc-port", "9944"]).rpc_http().is_err());
+		assert!(run_cmd(&["--ws-port", "9933"]).rpc_ws().is_err());
+		assert!(run_cmd(&["--rpc-port", "9000", "--ws-port", "9000"]).rpc_ws().is_err());
+
+		let ipv6 = run_cmd(&["--ws-port", "9933", "--ws-interface", "::1"]);
+		assert_eq!(ipv6.rpc_ws().unwrap(), Some("[::1]:9933".parse().unwrap()));
+		assert_eq!(ipv6.rpc_http().unwrap(), Some("127.0.0.1:9933".parse().unwrap()));
+		assert!(run_cmd(&["--ws-port", "9933", "--ws-interface", "::1", "--rpc-external"])
+			.rpc_ws()
+			.is_err());
+	}
+
+	#[test]
+	fn rpc_cors_origins_are_validated() {
+		for origins in &[
+			"null",
+			"all",
+			"*",
+			"https://polkadot.js.org",
+			"http://localhost:*,http://127.0.0.1:9933",
+			"chrome-extension://abcdef",
+			"http://[::1]:8000",
+			"https://*.example.com",
+		] {
+			assert!(parse_cors(origins).is_ok(), "{}", origins);
+		}
+
+		for origins in &[
+			"localhost:8000",
+			"https://polkadot.js.org/apps",
+			"http://localhost:99999",
+			"http://",
+			"http://local host",
+			"https://polkadot.js.org,",
+		] {
+			assert!(parse_cors(origins).is_err(), "{}", origins);
+		}
+	}
+
+	#[test]
+	fn rpc_cors_defaults_to_a_safe_list_except_in_dev_mode() {
+		let list = run_cmd(&[]).rpc_cors(false).unwrap().unwrap();
+		assert!(list.contains(&"https://polkadot.js.org".to_string()));
+		assert!(list.contains(&"http://localhost:*".to_string()));
+		assert_eq!(run_cmd(&[]).rpc_cors(true).unwrap(), None);
+
+		assert_eq!(run_cmd(&["--validator", "--rpc-cors", "all"]).rpc_cors(false).unwrap(), None);
+		assert_eq!(
+			run_cmd(&["--rpc-cors", "http://localhost:*,null"]).rpc_cors(false).unwrap(),
+			Some(vec!["http://localhost:*".to_string(), "null".to_string()]),
+		);
+	}
+
 	#[test]
 	fn tests_node_name_good() {
 		assert!(is_node_name_valid("short name").is_ok());
+		assert!(is_node_name_valid(&"a".repeat(crate::NODE_NAME_MAX_LENGTH)).is_ok());
+		assert!(is_node_name_valid(&crate::generate_node_name()).is_ok());
 	}
 
 	#[test]
 	fn tests_node_name_bad() {
-		assert!(is_node_name_valid("long names are not very cool for the ui").is_err());
+		assert!(is_node_name_valid(&"a".repeat(crate::NODE_NAME_MAX_LENGTH + 1)).is_err());
+		assert!(is_node_name_valid("new\nline").is_err());
+		assert!(is_node_name_valid("tab\tname").is_err());
 		assert!(is_node_name_valid("Dots.not.Ok").is_err());
 		assert!(is_node_name_valid("http://visit.me").is_err());
 		assert!(is_node_name_valid("https://visit.me").is_err());
 		assert!(is_node_name_valid("www.visit.me").is_err());
 		assert!(is_node_name_valid("email@domain").is_err());
 	}
+
+	#[test]
+	fn tests_telemetry_endpoints() {
+		assert_eq!(
+			parse_telemetry_endpoints("wss://telemetry.example.com/submit 0").unwrap(),
+			("wss://telemetry.example.com/submit".to_string(), 0),
+		);
+		assert!(parse_telemetry_endpoints("ws://127.0.0.1:8000/submit 9").is_ok());
+		assert!(parse_telemetry_endpoints("/ip4/127.0.0.1/tcp/8000/ws 1").is_ok());
+
+		assert!(matches!(
+			parse_telemetry_endpoints("wss://telemetry.example.com/submit"),
+			Err(TelemetryParsingError::MissingVerbosity)
+		));
+		assert!(matches!(
+			parse_telemetry_endpoints("https://telemetry.example.com/submit 0"),
+			Err(TelemetryParsingError::InvalidScheme(_))
+		));
+		assert!(matches!(
+			parse_telemetry_endpoints("wss://telemetry.example.com/submit 10"),
+			Err(TelemetryParsingError::VerbosityOutOfRange(10))
+		));
+		assert!(matches!(
+			parse_telemetry_endpoints("wss://telemetry.example.com/submit x"),
+			Err(TelemetryParsingError::VerbosityParsingError(_))
+		));
+	}
 }
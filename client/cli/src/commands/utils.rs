@@ -0,0 +1,199 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Key and chain utilities shared by the commands.
+
+use crate::error;
+use sc_service::{ChainSpec, Properties};
+use sp_core::crypto::{Ss58AddressFormat, Ss58Codec};
+use sp_core::{ed25519, sr25519, Pair};
+use std::convert::TryFrom;
+use std::fmt::Debug;
+use std::io::{self, Write};
+use std::{fs, path::Path};
+
+/// Returns the SS58 format declared in the `ss58Format` property of a chain spec, if any.
+pub(crate) fn ss58_format(properties: &Properties) -> error::Result<Option<Ss58AddressFormat>> {
+	match properties.get("ss58Format") {
+		None => Ok(None),
+		Some(format) => format.as_u64()
+			.and_then(|format| u8::try_from(format).ok())
+			.and_then(|format| Ss58AddressFormat::try_from(format).ok())
+			.map(Some)
+			.ok_or_else(|| error::Error::Input(format!("Invalid `ss58Format` property: {}", format))),
+	}
+}
+
+/// Print the chain a data command acts on, so that acting on the wrong chain is noticed.
+pub(crate) fn print_chain(chain_spec: &dyn ChainSpec, genesis_hash: &str) {
+	println!(
+		"Chain: {} (id: {}), genesis hash: {}",
+		chain_spec.name(),
+		chain_spec.id(),
+		genesis_hash,
+	);
+}
+
+/// Format a genesis hash for `print_chain`, or why it is unknown.
+pub(crate) fn describe_genesis_hash<H: Debug>(hash: Result<H, sc_service::Error>) -> String {
+	hash.map(|hash| format!("{:?}", hash)).unwrap_or_else(|e| format!("unknown ({})", e))
+}
+
+/// Ask `question` on the terminal and return whether it was answered yes.
+pub(crate) fn confirm(question: &str) -> error::Result<bool> {
+	print!("{} [y/N]: ", question);
+	io::stdout().flush()?;
+
+	let mut input = String::new();
+	io::stdin().read_line(&mut input)?;
+
+	Ok(match input.trim().chars().nth(0) {
+		Some('y') | Some('Y') => true,
+		_ => false,
+	})
+}
+
+/// Returns the total size in bytes and the number of the files in `path`, recursively.
+pub(crate) fn dir_usage(path: &Path) -> io::Result<(u64, usize)> {
+	let (mut size, mut files) = (0, 0);
+	for entry in fs::read_dir(path)? {
+		let entry = entry?;
+		let metadata = entry.metadata()?;
+		if metadata.is_dir() {
+			let (dir_size, dir_files) = dir_usage(&entry.path())?;
+			size += dir_size;
+			files += dir_files;
+		} else {
+			size += metadata.len();
+			files += 1;
+		}
+	}
+	Ok((size, files))
+}
+
+/// The error of an access to the RocksDB database without the `db` feature, e.g.
+/// `db_feature_error("compact-db")`.
+#[cfg(not(feature = "db"))]
+pub(crate) fn db_feature_error(what: &str) -> error::Error {
+	error::Error::Input(format!(
+		"{} requires the `db` feature, which this node was compiled without",
+		what,
+	))
+}
+
+/// Parse an sr25519 public key given as an SS58 address or as a development URI like `//Alice`.
+///
+/// Addresses must use `format` when it is given.
+pub(crate) fn parse_sr25519_public(
+	value: &str,
+	format: Option<Ss58AddressFormat>,
+) -> error::Result<sr25519::Public> {
+	if value.starts_with("//") {
+		return sr25519::Pair::from_string(value, None)
+			.map(|pair| pair.public())
+			.map_err(|_| error::Error::Input(format!("Invalid development URI `{}`", value)));
+	}
+
+	parse_ss58(value, format)
+}
+
+/// Parse a public key given as an SS58 address, which must use `format` when it is given.
+fn parse_ss58<T: Ss58Codec>(value: &str, format: Option<Ss58AddressFormat>) -> error::Result<T> {
+	let (public, version) = T::from_ss58check_with_version(value)
+		.map_err(|e| error::Error::Input(format!("Invalid address `{}`: {:?}", value, e)))?;
+	match format {
+		Some(format) if format != version => Err(error::Error::Input(format!(
+			"Address `{}` uses the SS58 format {}, but the chain uses {}",
+			value,
+			u8::from(version),
+			u8::from(format),
+		))),
+		_ => Ok(public),
+	}
+}
+
+/// Parse the sr25519 and ed25519 authority keys of a validator.
+///
+/// The keys are derived from a development URI like `//Alice`, or given as two SS58 addresses
+/// separated by a colon, the sr25519 key first.
+pub(crate) fn parse_authority_keys(
+	value: &str,
+	format: Option<Ss58AddressFormat>,
+) -> error::Result<(sr25519::Public, ed25519::Public)> {
+	if value.starts_with("//") {
+		let ed25519 = ed25519::Pair::from_string(value, None)
+			.map(|pair| pair.public())
+			.map_err(|_| error::Error::Input(format!("Invalid development URI `{}`", value)))?;
+		return Ok((parse_sr25519_public(value, format)?, ed25519));
+	}
+
+	// An sr25519 key is not a valid ed25519 key, so both have to be given.
+	let mut keys = value.splitn(2, ':');
+	match (keys.next(), keys.next()) {
+		(Some(sr25519), Some(ed25519)) => {
+			Ok((parse_ss58(sr25519, format)?, parse_ss58(ed25519, format)?))
+		},
+		_ => Err(error::Error::Input(format!(
+			"Missing the ed25519 key of authority `{}`, use `<sr25519 address>:<ed25519 address>`",
+			value,
+		))),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn dev_uris_are_expanded() {
+		let alice = sr25519::Pair::from_string("//Alice", None).unwrap().public();
+		let address = alice.to_ss58check_with_version(Ss58AddressFormat::SubstrateAccount);
+
+		assert_eq!(parse_sr25519_public("//Alice", None).unwrap(), alice);
+		assert_eq!(parse_sr25519_public(&address, Some(Ss58AddressFormat::SubstrateAccount)).unwrap(), alice);
+		assert!(parse_sr25519_public(&address, Some(Ss58AddressFormat::PolkadotAccount)).is_err());
+
+		let (_, grandpa) = parse_authority_keys("//Alice", None).unwrap();
+		assert_eq!(grandpa, ed25519::Pair::from_string("//Alice", None).unwrap().public());
+	}
+
+	#[test]
+	fn authority_addresses_need_both_keys() {
+		let format = Ss58AddressFormat::SubstrateAccount;
+		let sr25519 = sr25519::Pair::from_string("//Alice", None).unwrap().public();
+		let ed25519 = ed25519::Pair::from_string("//Alice", None).unwrap().public();
+		let sr25519_address = sr25519.to_ss58check_with_version(format);
+		let both = format!("{}:{}", sr25519_address, ed25519.to_ss58check_with_version(format));
+
+		assert_eq!(parse_authority_keys(&both, Some(format)).unwrap(), (sr25519, ed25519));
+		assert!(parse_authority_keys(&sr25519_address, Some(format)).is_err());
+		assert!(parse_authority_keys(&format!("{}:", sr25519_address), Some(format)).is_err());
+	}
+
+	#[test]
+	fn dir_usage_counts_the_nested_files() {
+		let dir = tempfile::tempdir().unwrap();
+		fs::write(dir.path().join("CURRENT"), b"MANIFEST-000001\n").unwrap();
+		fs::create_dir(dir.path().join("nested")).unwrap();
+		fs::write(dir.path().join("nested/000003.log"), vec![0u8; 1000]).unwrap();
+		fs::create_dir(dir.path().join("empty")).unwrap();
+
+		assert_eq!(dir_usage(dir.path()).unwrap(), (1016, 2));
+		assert!(dir_usage(&dir.path().join("missing")).is_err());
+	}
+}
@@ -19,7 +19,10 @@
 //! Configuration trait for a CLI based on substrate
 
 use crate::arg_enums::Database;
-use crate::error::Result;
+use crate::chain_spec::{check_unknown_fields, fetch_chain_spec, is_chain_spec_url};
+use crate::commands::{check_backup_manifest, is_light_database};
+use crate::error::{Error, Result};
+use crate::params::{check_database_cache_size, database_dir, resolve_database};
 use crate::{
 	init_logger, DatabaseParams, ImportParams, KeystoreParams, NetworkParams, NodeKeyParams,
 	OffchainWorkerParams, PruningParams, SharedParams, SubstrateCli,
@@ -27,23 +30,30 @@ use crate::{
 use names::{Generator, Name};
 use sc_client_api::execution_extensions::ExecutionStrategies;
 use sc_service::config::{
-	Configuration, DatabaseConfig, ExtTransport, KeystoreConfig, NetworkConfiguration,
+	BasePath, Configuration, DatabaseConfig, ExtTransport, KeepBlocks, KeystoreConfig, NetworkConfiguration,
 	NodeKeyConfig, OffchainWorkerConfig, PrometheusConfig, PruningMode, Role, RpcMethods,
 	TaskType, TelemetryEndpoints, TransactionPoolOptions, WasmExecutionMethod,
 };
-use sc_service::{ChainSpec, TracingReceiver};
+use sc_service::{ChainSpec, ChainType, TracingReceiver};
+use std::fs;
 use std::future::Future;
 use std::net::SocketAddr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::sync::Arc;
 
 /// The maximum number of characters for a node name.
-pub(crate) const NODE_NAME_MAX_LENGTH: usize = 32;
+pub(crate) const NODE_NAME_MAX_LENGTH: usize = 64;
 
 /// default sub directory to store network config
 pub(crate) const DEFAULT_NETWORK_CONFIG_PATH: &'static str = "network";
 
+/// default sub directory to store the database of a light client, apart from the one of a full node
+pub(crate) const DEFAULT_LIGHT_DATABASE_PATH: &'static str = "light";
+
+/// default sub directory of the base path to cache the chain specifications given as URL
+pub(crate) const DEFAULT_CHAIN_SPEC_CACHE_PATH: &'static str = "chain-specs";
+
 /// A trait that allows converting an object to a Configuration
 pub trait CliConfiguration: Sized {
 	/// Get the SharedParams for this object
@@ -87,8 +97,8 @@ pub trait CliConfiguration: Sized {
 	/// Get the base path of the configuration (if any)
 	///
 	/// By default this is retrieved from `SharedParams`.
-	fn base_path(&self) -> Result<Option<PathBuf>> {
-		Ok(self.shared_params().base_path())
+	fn base_path(&self) -> Result<Option<BasePath>> {
+		Ok(self.shared_params().base_path().map(Into::into))
 	}
 
 	/// Returns `true` if the node is for development or not
@@ -126,7 +136,7 @@ pub trait CliConfiguration: Sized {
 		node_name: &str,
 		node_key: NodeKeyConfig,
 	) -> Result<NetworkConfiguration> {
-		Ok(if let Some(network_params) = self.network_params() {
+		if let Some(network_params) = self.network_params() {
 			network_params.network_config(
 				chain_spec,
 				is_dev,
@@ -136,13 +146,13 @@ pub trait CliConfiguration: Sized {
 				node_key,
 			)
 		} else {
-			NetworkConfiguration::new(
+			Ok(NetworkConfiguration::new(
 				node_name,
 				client_id,
 				node_key,
 				Some(net_config_dir),
-			)
-		})
+			))
+		}
 	}
 
 	/// Get the keystore configuration.
@@ -172,23 +182,29 @@ pub trait CliConfiguration: Sized {
 	}
 
 	/// Get the database configuration object for the parameters provided
+	///
+	/// `Database::Auto` selects the backend of the database existing under `base_path`.
 	fn database_config(
 		&self,
 		base_path: &PathBuf,
 		cache_size: usize,
 		database: Database,
 	) -> Result<DatabaseConfig> {
+		let database = resolve_database(base_path, database)?;
+		if !database.is_enabled() {
+			return Err(Error::Input(format!(
+				"This node wasn't compiled with the {} database backend, it supports: {}",
+				database,
+				Database::enabled_variants().join(", ").to_lowercase(),
+			)));
+		}
+		let path = database_dir(base_path, database);
+
 		Ok(match database {
-			Database::RocksDb => DatabaseConfig::RocksDb {
-				path: base_path.join("db"),
-				cache_size,
-			},
-			Database::SubDb => DatabaseConfig::SubDb {
-				path: base_path.join("subdb"),
-			},
-			Database::ParityDb => DatabaseConfig::ParityDb {
-				path: base_path.join("paritydb"),
-			},
+			Database::RocksDb => DatabaseConfig::RocksDb { path, cache_size },
+			Database::SubDb => DatabaseConfig::SubDb { path },
+			Database::ParityDb => DatabaseConfig::ParityDb { path },
+			Database::Auto => unreachable!("`resolve_database` resolves `Database::Auto`; qed"),
 		})
 	}
 
@@ -208,16 +224,26 @@ pub trait CliConfiguration: Sized {
 		Ok(Default::default())
 	}
 
-	/// Get the pruning mode.
+	/// Get the state pruning mode.
 	///
-	/// By default this is retrieved from `PruningMode` if it is available. Otherwise its
+	/// By default this is retrieved from `PruningParams` if it is available. Otherwise its
 	/// `PruningMode::default()`.
-	fn pruning(&self, unsafe_pruning: bool, role: &Role) -> Result<PruningMode> {
+	fn state_pruning(&self, unsafe_pruning: bool, role: &Role) -> Result<PruningMode> {
 		self.pruning_params()
-			.map(|x| x.pruning(unsafe_pruning, role))
+			.map(|x| x.state_pruning(unsafe_pruning, role))
 			.unwrap_or(Ok(Default::default()))
 	}
 
+	/// Get the block body pruning mode.
+	///
+	/// By default this is retrieved from `PruningParams` if it is available. Otherwise its
+	/// `KeepBlocks::All`.
+	fn keep_blocks(&self) -> Result<KeepBlocks> {
+		self.pruning_params()
+			.map(|x| x.keep_blocks())
+			.unwrap_or(Ok(KeepBlocks::All))
+	}
+
 	/// Get the chain ID (string).
 	///
 	/// By default this is retrieved from `SharedParams`.
@@ -237,9 +263,23 @@ pub trait CliConfiguration: Sized {
 	/// By default this is retrieved from `ImportParams` if it is available. Otherwise its
 	/// `WasmExecutionMethod::default()`.
 	fn wasm_method(&self) -> Result<WasmExecutionMethod> {
-		Ok(self.import_params()
+		self.import_params()
 			.map(|x| x.wasm_method())
-			.unwrap_or(Default::default()))
+			.unwrap_or_else(|| Ok(Default::default()))
+	}
+
+	/// Whether the flags that are dangerous on a live chain are allowed anyway.
+	///
+	/// By default this is `false`.
+	fn allow_dangerous_flags(&self) -> Result<bool> {
+		Ok(false)
+	}
+
+	/// Get the directory of the Wasm runtime overrides.
+	///
+	/// By default this is `None`.
+	fn wasm_runtime_overrides(&self) -> Result<Option<PathBuf>> {
+		Ok(None)
 	}
 
 	/// Get the execution strategies.
@@ -274,13 +314,27 @@ pub trait CliConfiguration: Sized {
 		Ok(Default::default())
 	}
 
-	/// Get the RPC websockets maximum connections (`None` if unlimited).
+	/// Get the path of the RPC IPC endpoint (`None` if disabled).
+	///
+	/// By default this is `None`.
+	fn rpc_ipc(&self) -> Result<Option<PathBuf>> {
+		Ok(Default::default())
+	}
+
+	/// Get the RPC websockets maximum connections (`None` if default).
 	///
 	/// By default this is `None`.
 	fn rpc_ws_max_connections(&self) -> Result<Option<usize>> {
 		Ok(Default::default())
 	}
 
+	/// Get the maximum size of the RPC requests and responses, in MiB (`None` if default).
+	///
+	/// By default this is `None`.
+	fn rpc_max_payload(&self) -> Result<Option<usize>> {
+		Ok(Default::default())
+	}
+
 	/// Get the RPC cors (`None` if disabled)
 	///
 	/// By default this is `None`.
@@ -321,11 +375,15 @@ pub trait CliConfiguration: Sized {
 
 	/// Returns an offchain worker config wrapped in `Ok(_)`
 	///
-	/// By default offchain workers are disabled.
+	/// By default offchain workers are disabled, and offchain indexing is retrieved from
+	/// `ImportParams` if it is available.
 	fn offchain_worker(&self, role: &Role) -> Result<OffchainWorkerConfig> {
-		self.offchain_worker_params()
+		let mut config = self.offchain_worker_params()
 			.map(|x| x.offchain_worker(role))
-			.unwrap_or_else(|| Ok(OffchainWorkerConfig::default()))
+			.unwrap_or_else(|| Ok(OffchainWorkerConfig::default()))?;
+		config.indexing_enabled = self.import_params().map_or(false, |x| x.offchain_indexing());
+
+		Ok(config)
 	}
 
 	/// Returns `Ok(true)` if authoring should be forced
@@ -401,29 +459,69 @@ pub trait CliConfiguration: Sized {
 	) -> Result<Configuration> {
 		let is_dev = self.is_dev()?;
 		let chain_id = self.chain_id(is_dev)?;
-		let chain_spec = cli.load_spec(chain_id.as_str())?;
-		let config_dir = self
-			.base_path()?
-			.unwrap_or_else(|| {
+		let base_path = match self.base_path()? {
+			Some(base_path) => base_path,
+			None => BasePath::from(PathBuf::from(
 				directories::ProjectDirs::from("", "", C::executable_name())
-					.expect("app directories exist on all supported platforms; qed")
+					.ok_or_else(|| Error::Input(
+						"Could not determine the default base path, use `--base-path` instead".into()
+					))?
 					.data_local_dir()
-					.into()
-			})
+			)),
+		};
+		// A chain specification given as URL is loaded from its cached copy.
+		let spec_id = if is_chain_spec_url(&chain_id) {
+			fetch_chain_spec(
+				&chain_id,
+				&base_path.path().join(DEFAULT_CHAIN_SPEC_CACHE_PATH),
+				&self.shared_params().chain_spec_download(),
+			)?.to_string_lossy().into_owned()
+		} else {
+			chain_id.clone()
+		};
+		let chain_spec = cli.load_spec(spec_id.as_str()).map_err(|e| format!(
+			"No chain specification could be resolved for `--chain {}`: {}",
+			chain_id,
+			e,
+		))?;
+		if self.shared_params().check_chain_spec() {
+			check_unknown_fields(&spec_id, &*chain_spec)?;
+		}
+		let config_dir = base_path
+			.path()
 			.join("chains")
 			.join(chain_spec.id());
 		let net_config_dir = config_dir.join(DEFAULT_NETWORK_CONFIG_PATH);
 		let client_id = C::client_id();
 		let database_cache_size = self.database_cache_size()?.unwrap_or(128);
-		let database = self.database()?.unwrap_or(Database::RocksDb);
+		check_database_cache_size(database_cache_size);
+		let database = self.database()?.unwrap_or(Database::Auto);
 		let node_key = self.node_key(&net_config_dir)?;
 		let role = self.role(is_dev)?;
+		let database_dir = database_parent_dir(&config_dir, &role);
+		if let Role::Light = role {
+			migrate_light_database(&config_dir, &database_dir)?;
+		}
 		let max_runtime_instances = self.max_runtime_instances()?.unwrap_or(8);
 
 		let unsafe_pruning = self
 			.import_params()
 			.map(|p| p.unsafe_pruning)
 			.unwrap_or(false);
+		let database = self.database_config(&database_dir, database_cache_size, database)?;
+		check_backup_manifest(&config_dir, chain_spec.id(), &database)?;
+		let pruning = self.state_pruning(unsafe_pruning, &role)?;
+		if self.import_params().is_some() {
+			sc_client_db::check_pruning_mode(&database, &pruning, unsafe_pruning)?;
+		}
+		let force_authoring = self.force_authoring()?;
+		let dev_key_seed = self.dev_key_seed(is_dev)?;
+		check_dangerous_flags(
+			&*chain_spec,
+			force_authoring,
+			dev_key_seed.is_some(),
+			self.allow_dangerous_flags()?,
+		)?;
 
 		Ok(Configuration {
 			impl_name: C::impl_name(),
@@ -439,30 +537,35 @@ pub trait CliConfiguration: Sized {
 				node_key,
 			)?,
 			keystore: self.keystore_config(&config_dir)?,
-			database: self.database_config(&config_dir, database_cache_size, database)?,
+			database,
 			state_cache_size: self.state_cache_size()?,
 			state_cache_child_ratio: self.state_cache_child_ratio()?,
-			pruning: self.pruning(unsafe_pruning, &role)?,
+			pruning,
+			keep_blocks: self.keep_blocks()?,
 			wasm_method: self.wasm_method()?,
+			wasm_runtime_overrides: self.wasm_runtime_overrides()?,
 			execution_strategies: self.execution_strategies(is_dev)?,
 			rpc_http: self.rpc_http()?,
 			rpc_ws: self.rpc_ws()?,
 			rpc_methods: self.rpc_methods()?,
+			rpc_ipc: self.rpc_ipc()?,
 			rpc_ws_max_connections: self.rpc_ws_max_connections()?,
+			rpc_max_payload: self.rpc_max_payload()?,
 			rpc_cors: self.rpc_cors(is_dev)?,
 			prometheus_config: self.prometheus_config()?,
 			telemetry_endpoints: self.telemetry_endpoints(&chain_spec)?,
 			telemetry_external_transport: self.telemetry_external_transport()?,
 			default_heap_pages: self.default_heap_pages()?,
 			offchain_worker: self.offchain_worker(&role)?,
-			force_authoring: self.force_authoring()?,
+			force_authoring,
 			disable_grandpa: self.disable_grandpa()?,
-			dev_key_seed: self.dev_key_seed(is_dev)?,
+			dev_key_seed,
 			tracing_targets: self.tracing_targets()?,
 			tracing_receiver: self.tracing_receiver()?,
 			chain_spec,
 			max_runtime_instances,
 			announce_block: self.announce_block()?,
+			base_path: Some(base_path),
 			role,
 		})
 	}
@@ -496,16 +599,154 @@ pub trait CliConfiguration: Sized {
 	}
 }
 
-/// Generate a valid random name for the node
+/// Generate a valid random name for the node, of two memorable words like `brave-penguin`
 pub fn generate_node_name() -> String {
 	loop {
-		let node_name = Generator::with_naming(Name::Numbered)
+		let node_name = Generator::with_naming(Name::Plain)
 			.next()
 			.expect("RNG is available on all supported platforms; qed");
 		let count = node_name.chars().count();
 
-		if count < NODE_NAME_MAX_LENGTH {
+		if count <= NODE_NAME_MAX_LENGTH {
 			return node_name;
 		}
 	};
 }
+
+/// The directory of `config_dir` holding the database of a node with `role`.
+///
+/// Light clients keep their database apart from the one of a full node of the same base path.
+pub(crate) fn database_parent_dir(config_dir: &Path, role: &Role) -> PathBuf {
+	match role {
+		Role::Light => config_dir.join(DEFAULT_LIGHT_DATABASE_PATH),
+		_ => config_dir.to_path_buf(),
+	}
+}
+
+/// Move the RocksDB database of a light client from `config_dir`, where light clients kept it
+/// before they had their own directory, to `light_dir`.
+///
+/// The database of a full node is left in place.
+fn migrate_light_database(config_dir: &Path, light_dir: &Path) -> Result<()> {
+	let old_path = database_dir(config_dir, Database::RocksDb);
+	let new_path = database_dir(light_dir, Database::RocksDb);
+	if !Database::RocksDb.is_enabled() || new_path.exists() || !old_path.join("CURRENT").exists() {
+		return Ok(());
+	}
+	if !is_light_database(&old_path)? {
+		return Ok(());
+	}
+
+	log::info!("Moving the database of the light client from {:?} to {:?}", old_path, new_path);
+	fs::create_dir_all(light_dir)?;
+	fs::rename(&old_path, &new_path)?;
+	Ok(())
+}
+
+/// Refuse the flags that are dangerous on a live chain, unless `allowed`, and warn about them.
+///
+/// Forced authoring produces blocks without peers and the development keys are public, which a
+/// validator of a live chain doesn't want.
+fn check_dangerous_flags(
+	chain_spec: &dyn ChainSpec,
+	force_authoring: bool,
+	dev_key: bool,
+	allowed: bool,
+) -> Result<()> {
+	if chain_spec.chain_type() != ChainType::Live {
+		return Ok(());
+	}
+
+	let flags = [
+		(force_authoring, "--force-authoring"),
+		(dev_key, "a development key like --alice"),
+	];
+	let dangerous = flags.iter()
+		.filter(|(given, _)| *given)
+		.map(|(_, flag)| *flag)
+		.collect::<Vec<_>>();
+	if dangerous.is_empty() {
+		return Ok(());
+	}
+
+	let message = format!(
+		"{} should not be used on the live chain {}",
+		dangerous.join(" and "),
+		chain_spec.name(),
+	);
+	if !allowed {
+		return Err(Error::Input(format!(
+			"{}. Pass --i-know-what-im-doing to start anyway.",
+			message,
+		)));
+	}
+
+	log::warn!("⚠️  {}, starting anyway as requested by --i-know-what-im-doing.", message);
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_service::{GenericChainSpec, NoExtension};
+
+	fn chain_spec(chain_type: &str) -> GenericChainSpec<(), NoExtension> {
+		GenericChainSpec::from_json_bytes(format!(r#"{{
+			"name": "Test",
+			"id": "test",
+			"chainType": "{}",
+			"bootNodes": [],
+			"telemetryEndpoints": null,
+			"protocolId": null,
+			"properties": null,
+			"consensusEngine": null,
+			"genesis": {{ "raw": {{ "top": {{}}, "childrenDefault": {{}} }} }}
+		}}"#, chain_type).into_bytes()).unwrap()
+	}
+
+	#[test]
+	fn dangerous_flags_are_refused_on_live_chains() {
+		let live = chain_spec("Live");
+
+		assert!(check_dangerous_flags(&live, false, false, false).is_ok());
+		assert!(check_dangerous_flags(&live, true, false, false).is_err());
+		assert!(check_dangerous_flags(&live, false, true, false).is_err());
+		assert!(check_dangerous_flags(&live, true, true, true).is_ok());
+	}
+
+	#[test]
+	fn dangerous_flags_are_allowed_on_other_chains() {
+		for chain_type in &["Development", "Local"] {
+			assert!(check_dangerous_flags(&chain_spec(chain_type), true, true, false).is_ok());
+		}
+	}
+
+	#[cfg(feature = "db")]
+	#[test]
+	fn only_the_database_of_a_light_client_is_moved() {
+		use sc_client_db::{column_family_name, columns, meta_keys};
+
+		let create = |path: &Path, db_type: &[u8]| {
+			let mut opts = rocksdb::Options::default();
+			opts.create_if_missing(true);
+			opts.create_missing_column_families(true);
+			let meta = column_family_name(columns::META);
+			let db = rocksdb::DB::open_cf(&opts, path, &[&meta]).unwrap();
+			db.put_cf(db.cf_handle(&meta).unwrap(), meta_keys::TYPE, db_type).unwrap();
+		};
+
+		let full = tempfile::tempdir().unwrap();
+		let light_dir = database_parent_dir(full.path(), &Role::Light);
+		create(&full.path().join("db"), b"full");
+		migrate_light_database(full.path(), &light_dir).unwrap();
+		assert!(full.path().join("db").exists());
+		assert!(!light_dir.exists());
+
+		let light = tempfile::tempdir().unwrap();
+		let light_dir = database_parent_dir(light.path(), &Role::Light);
+		create(&light.path().join("db"), b"light");
+		migrate_light_database(light.path(), &light_dir).unwrap();
+		assert!(!light.path().join("db").exists());
+		assert!(light_dir.join("db").join("CURRENT").exists());
+	}
+}
@@ -0,0 +1,355 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Default command line arguments loaded from a TOML file given with `--config`.
+//!
+//! The keys of the file are the long names of the flags of the command, e.g.:
+//!
+//! ```toml
+//! chain = "local"
+//! base-path = "/var/lib/substrate"
+//! log = ["sync=debug", "afg=trace"]
+//! ```
+//!
+//! The values are turned into command line arguments that are added to the ones given by the
+//! user, unless the user already provided the same flag (or a conflicting one).
+
+use std::{ffi::OsString, fs, path::Path};
+use structopt::clap::{self, App, ArgMatches, ArgSettings, ErrorKind};
+
+/// Name of the argument used to pass the configuration file.
+const CONFIG_ARG: &str = "config";
+
+/// Flags that can't be set from a configuration file.
+const EXCLUDED_KEYS: &[&str] = &[CONFIG_ARG, "help", "version"];
+
+/// The kind of value a configuration key expects.
+#[derive(Debug, Clone, Copy)]
+enum ValueKind {
+	/// A boolean switch, e.g. `dev = true`.
+	Flag,
+	/// A single string, e.g. `base-path = "/data"`.
+	Single,
+	/// A string or an array of strings, e.g. `log = ["sync=debug"]`.
+	Multiple,
+}
+
+/// A flag of a command that can be set from a configuration file.
+#[derive(Debug)]
+struct ConfigKey {
+	/// Name of the clap argument.
+	name: String,
+	/// Long name of the flag, which is the key in the file.
+	long: String,
+	kind: ValueKind,
+	/// Names of the arguments this flag conflicts with.
+	conflicts: Vec<String>,
+}
+
+impl ConfigKey {
+	fn new(
+		name: &str,
+		long: Option<&str>,
+		conflicts: &Option<Vec<&str>>,
+		kind: ValueKind,
+	) -> Option<Self> {
+		let long = long.filter(|long| !EXCLUDED_KEYS.contains(long))?;
+		Some(ConfigKey {
+			name: name.into(),
+			long: long.into(),
+			kind,
+			conflicts: conflicts.iter().flatten().map(|name| name.to_string()).collect(),
+		})
+	}
+}
+
+/// Returns the flags of `app` that can be set from a configuration file, without the ones of its
+/// subcommands.
+///
+/// clap only exposes the argument definitions through the fields of its parser.
+fn config_keys(app: &App) -> Vec<ConfigKey> {
+	let flags = app.p.flags.iter().filter_map(|flag| {
+		ConfigKey::new(flag.b.name, flag.s.long, &flag.b.blacklist, ValueKind::Flag)
+	});
+	let opts = app.p.opts.iter().filter_map(|opt| {
+		let kind = if opt.b.settings.is_set(ArgSettings::Multiple) {
+			ValueKind::Multiple
+		} else {
+			ValueKind::Single
+		};
+		ConfigKey::new(opt.b.name, opt.s.long, &opt.b.blacklist, kind)
+	});
+
+	flags.chain(opts).collect()
+}
+
+/// Returns `true` if `key` is the long name of a flag of `app` or of any of its subcommands.
+fn is_known_key(app: &App, key: &str) -> bool {
+	config_keys(app).iter().any(|k| k.long == key)
+		|| app.p.subcommands.iter().any(|app| is_known_key(app, key))
+}
+
+/// Convert the value of `key` in the configuration file to command line arguments.
+fn config_args(key: &ConfigKey, value: toml::Value, path: &Path) -> clap::Result<Vec<OsString>> {
+	let invalid = |expected: &str| clap::Error::with_description(
+		&format!(
+			"Invalid value for key `{}` in config file {}: expected {}",
+			key.long, path.display(), expected,
+		),
+		ErrorKind::InvalidValue,
+	);
+	let flag = OsString::from(format!("--{}", key.long));
+
+	Ok(match (key.kind, value) {
+		(ValueKind::Flag, toml::Value::Boolean(true)) => vec![flag],
+		(ValueKind::Flag, toml::Value::Boolean(false)) => Vec::new(),
+		(ValueKind::Flag, _) => return Err(invalid("a boolean")),
+		(ValueKind::Single, toml::Value::String(s))
+		| (ValueKind::Multiple, toml::Value::String(s)) => vec![flag, s.into()],
+		(ValueKind::Single, _) => return Err(invalid("a string")),
+		(ValueKind::Multiple, toml::Value::Array(values)) => values
+			.into_iter()
+			.map(|value| match value {
+				toml::Value::String(s) => Ok(vec![flag.clone(), s.into()]),
+				_ => Err(invalid("a string or an array of strings")),
+			})
+			.collect::<Result<Vec<_>, _>>()?
+			.concat(),
+		(ValueKind::Multiple, _) => return Err(invalid("a string or an array of strings")),
+	})
+}
+
+/// Returns the app and the matches of the deepest subcommand that was selected.
+fn selected_subcommand<'a, 'b, 'm>(
+	app: &'m App<'a, 'b>,
+	matches: &'m ArgMatches<'a>,
+) -> (&'m App<'a, 'b>, &'m ArgMatches<'a>) {
+	match matches.subcommand() {
+		(name, Some(sub_matches)) => {
+			match app.p.subcommands.iter().find(|sub_app| sub_app.p.meta.name == name) {
+				Some(sub_app) => selected_subcommand(sub_app, sub_matches),
+				None => (app, matches),
+			}
+		},
+		_ => (app, matches),
+	}
+}
+
+/// Returns `true` if `key`, or a flag conflicting with it, was given on the command line.
+fn is_overridden(key: &ConfigKey, keys: &[ConfigKey], matches: &ArgMatches) -> bool {
+	let given = |name: &str| matches.occurrences_of(name) > 0;
+
+	given(&key.name) || keys
+		.iter()
+		.filter(|other| given(&other.name))
+		.any(|other| other.conflicts.contains(&key.name) || key.conflicts.contains(&other.name))
+}
+
+/// Parse `args` with `app`, taking into account the configuration file given with `--config`.
+///
+/// Arguments given on the command line take precedence over the configuration file. Keys that are
+/// flags of another command than the selected one are skipped, so that a single file can be shared
+/// by all the commands.
+pub(crate) fn get_matches_from_safe<'a, 'b, I>(
+	app: App<'a, 'b>,
+	args: I,
+) -> clap::Result<ArgMatches<'a>>
+where
+	I: IntoIterator,
+	I::Item: Into<OsString> + Clone,
+{
+	let mut args = args.into_iter().map(Into::into).collect::<Vec<OsString>>();
+	let matches = app.clone().get_matches_from_safe(args.clone())?;
+	let (selected_app, selected_matches) = selected_subcommand(&app, &matches);
+
+	let path = match selected_matches.value_of_os(CONFIG_ARG) {
+		Some(path) => Path::new(path).to_path_buf(),
+		None => return Ok(matches),
+	};
+	let content = fs::read_to_string(&path).map_err(|e| clap::Error::with_description(
+		&format!("Failed to read config file {}: {}", path.display(), e),
+		ErrorKind::Io,
+	))?;
+	let table = toml::from_str::<toml::value::Table>(&content)
+		.map_err(|e| clap::Error::with_description(
+			&format!("Failed to parse config file {}: {}", path.display(), e),
+			ErrorKind::InvalidValue,
+		))?;
+
+	let keys = config_keys(selected_app);
+	let mut file_args = Vec::new();
+	for (name, value) in table {
+		match keys.iter().find(|key| key.long == name) {
+			Some(key) if is_overridden(key, &keys, selected_matches) => {},
+			Some(key) => file_args.extend(config_args(key, value, &path)?),
+			None if is_known_key(&app, &name) => {},
+			None => return Err(clap::Error::with_description(
+				&format!("Unknown key `{}` in config file {}", name, path.display()),
+				ErrorKind::InvalidValue,
+			)),
+		}
+	}
+
+	// The arguments after `--` are positional, the flags of the file go before them.
+	let end = args.iter().position(|arg| arg == "--").unwrap_or_else(|| args.len());
+	args.splice(end..end, file_args);
+
+	app.get_matches_from_safe(args)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{KeystoreParams, SharedParams};
+	use std::io::Write;
+	use structopt::StructOpt;
+
+	#[derive(Debug, StructOpt)]
+	struct Command {
+		#[structopt(flatten)]
+		shared_params: SharedParams,
+
+		#[structopt(flatten)]
+		keystore_params: KeystoreParams,
+	}
+
+	#[derive(Debug, StructOpt)]
+	struct SharedOnlyCommand {
+		#[structopt(flatten)]
+		shared_params: SharedParams,
+	}
+
+	#[derive(Debug, StructOpt)]
+	enum Subcommand {
+		Shared(SharedOnlyCommand),
+		Full(Command),
+	}
+
+	#[derive(Debug, StructOpt)]
+	struct TrailingArgsCommand {
+		#[structopt(flatten)]
+		shared_params: SharedParams,
+
+		#[structopt(last = true)]
+		args: Vec<String>,
+	}
+
+	fn config_file(content: &str) -> tempfile::NamedTempFile {
+		let mut file = tempfile::NamedTempFile::new().unwrap();
+		file.write_all(content.as_bytes()).unwrap();
+		file
+	}
+
+	fn parse<T: StructOpt>(file: &tempfile::NamedTempFile, args: &[&str]) -> clap::Result<T> {
+		let path = file.path().to_str().unwrap();
+		let mut args = std::iter::once("test").chain(args.iter().cloned()).collect::<Vec<_>>();
+		let end = args.iter().position(|arg| *arg == "--").unwrap_or_else(|| args.len());
+		args.splice(end..end, vec!["--config", path]);
+
+		get_matches_from_safe(T::clap(), args).map(|matches| T::from_clap(&matches))
+	}
+
+	#[test]
+	fn config_file_values_are_used_as_defaults() {
+		let file = config_file(r#"
+			chain = "local"
+			base-path = "/tmp/substrate"
+			log = ["sync=debug", "afg=trace"]
+			keystore-path = "/tmp/keystore"
+		"#);
+
+		let cmd = parse::<Command>(&file, &[]).unwrap();
+		assert_eq!(cmd.shared_params.chain, Some("local".into()));
+		assert_eq!(cmd.shared_params.base_path, Some("/tmp/substrate".into()));
+		assert_eq!(cmd.shared_params.log, vec!["sync=debug".to_string(), "afg=trace".into()]);
+		assert_eq!(cmd.keystore_params.keystore_path, Some("/tmp/keystore".into()));
+	}
+
+	#[test]
+	fn command_line_overrides_config_file() {
+		let file = config_file(r#"
+			chain = "local"
+			base-path = "/tmp/substrate"
+			password = "secret"
+		"#);
+
+		let cmd = parse::<Command>(
+			&file,
+			&["--dev", "--base-path", "/tmp/other", "--password-interactive"],
+		).unwrap();
+		assert_eq!(cmd.shared_params.chain, None);
+		assert!(cmd.shared_params.dev);
+		assert_eq!(cmd.shared_params.base_path, Some("/tmp/other".into()));
+		assert_eq!(cmd.keystore_params.password, None);
+		assert!(cmd.keystore_params.password_interactive);
+	}
+
+	#[test]
+	fn unsupported_keys_are_skipped() {
+		let file = config_file(r#"
+			base-path = "/tmp/substrate"
+			keystore-path = "/tmp/keystore"
+		"#);
+
+		match parse::<Subcommand>(&file, &["shared"]).unwrap() {
+			Subcommand::Shared(cmd) => {
+				assert_eq!(cmd.shared_params.base_path, Some("/tmp/substrate".into()));
+			},
+			cmd => panic!("Unexpected command {:?}", cmd),
+		}
+	}
+
+	#[test]
+	fn every_flag_of_the_command_is_accepted() {
+		let file = config_file(r#"
+			refresh-chain-spec = true
+			dry-run = true
+		"#);
+
+		let cmd = parse::<SharedOnlyCommand>(&file, &[]).unwrap();
+		assert!(cmd.shared_params.refresh_chain_spec);
+		assert!(cmd.shared_params.dry_run);
+	}
+
+	#[test]
+	fn config_file_arguments_go_before_trailing_arguments() {
+		let file = config_file("base-path = \"/tmp/substrate\"");
+
+		let cmd = parse::<TrailingArgsCommand>(&file, &["--", "extra"]).unwrap();
+		assert_eq!(cmd.shared_params.base_path, Some("/tmp/substrate".into()));
+		assert_eq!(cmd.args, vec!["extra".to_string()]);
+	}
+
+	#[test]
+	fn unknown_key_is_reported() {
+		let file = config_file("base_path = \"/tmp/substrate\"");
+
+		let err = parse::<Command>(&file, &[]).unwrap_err();
+		assert_eq!(err.kind, ErrorKind::InvalidValue);
+		assert!(err.message.contains("Unknown key `base_path`"));
+	}
+
+	#[test]
+	fn invalid_value_is_reported() {
+		let file = config_file("dev = \"yes\"");
+
+		let err = parse::<Command>(&file, &[]).unwrap_err();
+		assert!(err.message.contains("Invalid value for key `dev`"));
+		assert!(err.message.contains("expected a boolean"));
+	}
+}
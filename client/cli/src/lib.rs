@@ -22,13 +22,16 @@
 #![warn(unused_extern_crates)]
 
 mod arg_enums;
+mod chain_spec;
 mod commands;
 mod config;
+mod config_file;
 mod error;
 mod params;
 mod runner;
 
 pub use arg_enums::*;
+pub use chain_spec::*;
 pub use commands::*;
 pub use config::*;
 pub use error::*;
@@ -42,6 +45,8 @@ use std::future::Future;
 use std::io::Write;
 use std::pin::Pin;
 use std::sync::Arc;
+/// Re-exported so embedders parse arguments with the `structopt` and `clap` versions of the
+/// commands, e.g. to match on the errors of [`Subcommand::from_iter_safe`].
 pub use structopt;
 use structopt::{
 	clap::{self, AppSettings},
@@ -66,6 +71,16 @@ pub trait SubstrateCli: Sized {
 	/// short commit hash of the commit of in the Git repository.
 	fn impl_version() -> &'static str;
 
+	/// Details of the build, printed by `--version` after the implementation version, e.g. the
+	/// commit, the profile, the target or the runtime version.
+	///
+	/// By default there are none. `substrate_build_script_utils::generate_cargo_keys` provides the
+	/// commit, profile and target as the `SUBSTRATE_CLI_COMMIT`, `SUBSTRATE_CLI_PROFILE` and
+	/// `SUBSTRATE_CLI_TARGET` environment variables.
+	fn build_info() -> Vec<(&'static str, String)> {
+		Vec::new()
+	}
+
 	/// Executable file name.
 	fn executable_name() -> &'static str;
 
@@ -117,8 +132,7 @@ pub trait SubstrateCli: Sized {
 	{
 		let app = <Self as StructOpt>::clap();
 
-		let mut full_version = Self::impl_version().to_string();
-		full_version.push_str("\n");
+		let full_version = full_version(Self::impl_version(), &Self::build_info());
 
 		let app = app
 			.name(Self::executable_name())
@@ -131,7 +145,7 @@ pub trait SubstrateCli: Sized {
 				AppSettings::SubcommandsNegateReqs,
 			]);
 
-		let matches = match app.get_matches_from_safe(iter) {
+		let matches = match config_file::get_matches_from_safe(app, iter) {
 			Ok(matches) => matches,
 			Err(mut e) => {
 				// To support pipes, we can not use `writeln!` as any error
@@ -176,16 +190,16 @@ pub trait SubstrateCli: Sized {
 	{
 		let app = <Self as StructOpt>::clap();
 
-		let mut full_version = Self::impl_version().to_string();
-		full_version.push_str("\n");
+		let full_version = full_version(Self::impl_version(), &Self::build_info());
 
 		let app = app
 			.name(Self::executable_name())
 			.author(Self::author())
 			.about(Self::description())
-			.version(full_version.as_str());
+			.version(full_version.as_str())
+			.setting(AppSettings::GlobalVersion);
 
-		let matches = app.get_matches_from_safe(iter)?;
+		let matches = config_file::get_matches_from_safe(app, iter)?;
 
 		Ok(<Self as StructOpt>::from_clap(&matches))
 	}
@@ -212,6 +226,16 @@ pub trait SubstrateCli: Sized {
 	}
 }
 
+/// The version printed by `--version`: the implementation version followed by the build info,
+/// one `name: value` per line.
+fn full_version(impl_version: &str, build_info: &[(&str, String)]) -> String {
+	let mut full_version = format!("{}\n", impl_version);
+	for (name, value) in build_info {
+		full_version.push_str(&format!("{}: {}\n", name, value));
+	}
+	full_version
+}
+
 /// Initialize the logger
 pub fn init_logger(pattern: &str) {
 	use ansi_term::Colour;
@@ -287,3 +311,17 @@ fn kill_color(s: &str) -> String {
 	}
 	RE.replace_all(s, "").to_string()
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn full_version_lists_the_build_info() {
+		assert_eq!(full_version("2.0.0-abcdef-x86_64-linux-gnu", &[]), "2.0.0-abcdef-x86_64-linux-gnu\n");
+		assert_eq!(
+			full_version("2.0.0", &[("commit", "abcdef".into()), ("profile", "release".into())]),
+			"2.0.0\ncommit: abcdef\nprofile: release\n",
+		);
+	}
+}
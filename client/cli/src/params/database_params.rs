@@ -17,21 +17,42 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::arg_enums::Database;
+use crate::error::{Error, Result};
+use std::path::{Path, PathBuf};
 use structopt::StructOpt;
+use sysinfo::{RefreshKind, System, SystemExt};
+
+/// The backends with the directory of their database under the base path, the default one first.
+const DATABASE_DIRS: &[(Database, &str)] = &[
+	(Database::RocksDb, "db"),
+	(Database::ParityDb, "paritydb"),
+	(Database::SubDb, "subdb"),
+];
+
+/// The share of the system memory, in percent, above which the database cache is reported.
+const MAX_CACHE_MEMORY_SHARE: u64 = 80;
 
 /// Parameters for block import.
 #[derive(Debug, StructOpt, Clone)]
 pub struct DatabaseParams {
-	/// Select database backend to use.
+	/// Select database backend to use: rocksdb, paritydb, subdb or auto.
+	///
+	/// Default is auto, which uses the backend of the existing database, or RocksDB for a new
+	/// one if this node was compiled with it, else the first backend it was compiled with.
+	/// Selecting another backend than the one of the existing database is an error, as well as
+	/// selecting a backend this node wasn't compiled with.
 	#[structopt(
 		long,
 		alias = "db",
 		value_name = "DB",
-		case_insensitive = true,
+		parse(try_from_str = parse_database),
 	)]
 	pub database: Option<Database>,
 
 	/// Limit the memory the database cache can use.
+	///
+	/// 90% of the cache goes to the state column of RocksDB, the rest is split between the
+	/// other columns. Default is 128 MiB, archive nodes benefit from a larger cache.
 	#[structopt(long = "db-cache", value_name = "MiB")]
 	pub database_cache_size: Option<usize>,
 }
@@ -47,3 +68,125 @@ impl DatabaseParams {
 		self.database_cache_size
 	}
 }
+
+/// The directory of the database of `database` under `base_path`.
+///
+/// `Database::Auto` must have been resolved with `resolve_database`.
+pub(crate) fn database_dir(base_path: &Path, database: Database) -> PathBuf {
+	let dir = DATABASE_DIRS.iter()
+		.find(|(backend, _)| *backend == database)
+		.map_or("db", |(_, dir)| *dir);
+
+	base_path.join(dir)
+}
+
+/// Parse a database backend, listing the ones compiled in if it is unknown.
+fn parse_database(name: &str) -> std::result::Result<Database, String> {
+	name.parse().map_err(|_| format!(
+		"Unknown database backend `{}`, expected one of: {}",
+		name,
+		Database::enabled_variants().join(", ").to_lowercase(),
+	))
+}
+
+/// Resolve `Database::Auto` to the backend of the database existing under `base_path`, or to the
+/// first backend compiled in for a new database, and check that an explicitly selected backend is
+/// the one of the existing database.
+///
+/// The resolved backend is never `Database::Auto`.
+pub(crate) fn resolve_database(base_path: &Path, database: Database) -> Result<Database> {
+	let existing = DATABASE_DIRS.iter()
+		.filter(|(_, dir)| base_path.join(dir).exists())
+		.map(|(backend, _)| *backend)
+		.collect::<Vec<_>>();
+
+	match database {
+		Database::Auto => Ok(existing.first().cloned().unwrap_or_else(|| {
+			DATABASE_DIRS.iter()
+				.map(|(backend, _)| *backend)
+				.find(|backend| backend.is_enabled())
+				.unwrap_or(Database::RocksDb)
+		})),
+		selected if existing.is_empty() || existing.contains(&selected) => Ok(selected),
+		selected => Err(Error::Input(format!(
+			"`--database {}` was selected but {} already holds a {} database. Purge the chain with \
+			`purge-chain` or migrate the database to switch to another backend, or keep the \
+			existing one with `--database auto`.",
+			selected.to_string().to_lowercase(),
+			base_path.display(),
+			existing[0],
+		))),
+	}
+}
+
+/// Warn if a database cache of `cache_size` MiB would take most of the system memory.
+pub(crate) fn check_database_cache_size(cache_size: usize) {
+	let system = System::new_with_specifics(RefreshKind::new().with_memory());
+	let total_memory = system.get_total_memory();
+
+	if exceeds_memory_share(cache_size, total_memory) {
+		log::warn!(
+			"The database cache of {} MiB exceeds {}% of the {} MiB of system memory, \
+			the node may run out of memory.",
+			cache_size,
+			MAX_CACHE_MEMORY_SHARE,
+			total_memory / 1024,
+		);
+	}
+}
+
+/// Whether `cache_size` MiB exceed the share of `total_memory` KiB the cache should stay below.
+///
+/// An unknown total memory of `0` is never exceeded.
+fn exceeds_memory_share(cache_size: usize, total_memory: u64) -> bool {
+	total_memory > 0 && cache_size as u64 * 1024 * 100 > total_memory * MAX_CACHE_MEMORY_SHARE
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn resolve_database_detects_the_existing_backend() {
+		let base_path = tempfile::tempdir().unwrap();
+		let base_path = base_path.path();
+
+		// A new database uses the first backend compiled in, RocksDB by default.
+		let new = resolve_database(base_path, Database::Auto).unwrap();
+		assert!(new.is_enabled());
+		assert!(new == Database::RocksDb || !Database::RocksDb.is_enabled());
+		assert_eq!(resolve_database(base_path, Database::ParityDb).unwrap(), Database::ParityDb);
+
+		std::fs::create_dir(database_dir(base_path, Database::ParityDb)).unwrap();
+		assert_eq!(resolve_database(base_path, Database::Auto).unwrap(), Database::ParityDb);
+		assert_eq!(resolve_database(base_path, Database::ParityDb).unwrap(), Database::ParityDb);
+		assert!(resolve_database(base_path, Database::RocksDb).is_err());
+		assert!(resolve_database(base_path, Database::SubDb).is_err());
+	}
+
+	#[test]
+	fn database_parses_every_backend() {
+		let database = |name: &str| DatabaseParams::from_iter(&["test", "--database", name]).database();
+
+		assert_eq!(database("rocksdb"), Some(Database::RocksDb));
+		assert_eq!(database("ParityDb"), Some(Database::ParityDb));
+		assert_eq!(database("auto"), Some(Database::Auto));
+		assert_eq!(DatabaseParams::from_iter(&["test"]).database(), None);
+
+		let error = DatabaseParams::from_iter_safe(&["test", "--database", "mysql"]).unwrap_err();
+		assert!(error.message.contains("Unknown database backend `mysql`, expected one of: "));
+		assert!(error.message.contains("auto"));
+	}
+
+	#[test]
+	fn exceeds_memory_share_works() {
+		// 10 GiB of memory.
+		let total_memory = 10 * 1024 * 1024;
+
+		assert!(!exceeds_memory_share(128, total_memory));
+		assert!(!exceeds_memory_share(8 * 1024, total_memory));
+		assert!(exceeds_memory_share(8 * 1024 + 1, total_memory));
+		assert!(exceeds_memory_share(16 * 1024, total_memory));
+		assert!(!exceeds_memory_share(16 * 1024, 0));
+	}
+}
@@ -21,11 +21,15 @@ use crate::arg_enums::{
 	DEFAULT_EXECUTION_BLOCK_CONSTRUCTION, DEFAULT_EXECUTION_IMPORT_BLOCK,
 	DEFAULT_EXECUTION_OFFCHAIN_WORKER, DEFAULT_EXECUTION_OTHER, DEFAULT_EXECUTION_SYNCING,
 };
+use crate::error::Error;
 use crate::params::DatabaseParams;
 use crate::params::PruningParams;
 use sc_client_api::execution_extensions::ExecutionStrategies;
 use structopt::StructOpt;
 
+/// The smallest state cache size accepted, apart from 0 which disables the cache.
+const MIN_STATE_CACHE_SIZE: usize = 1024 * 1024;
+
 /// Parameters for block import.
 #[derive(Debug, StructOpt, Clone)]
 pub struct ImportParams {
@@ -42,14 +46,19 @@ pub struct ImportParams {
 	/// When running as a validator it is highly recommended to disable state
 	/// pruning (i.e. 'archive') which is the default. The node will refuse to
 	/// start as a validator if pruning is enabled unless this option is set.
+	///
+	/// This also switches an existing database to the requested `--pruning` mode instead of
+	/// refusing to start with another mode than the one the database was created with.
 	#[structopt(long = "unsafe-pruning")]
 	pub unsafe_pruning: bool,
 
 	/// Method for executing Wasm runtime code.
+	///
+	/// `Compiled` is only available if the node was built with the `wasmtime` feature.
 	#[structopt(
 		long = "wasm-execution",
 		value_name = "METHOD",
-		possible_values = &WasmExecutionMethod::enabled_variants(),
+		possible_values = &WasmExecutionMethod::variants(),
 		case_insensitive = true,
 		default_value = "Interpreted"
 	)]
@@ -59,11 +68,28 @@ pub struct ImportParams {
 	#[structopt(flatten)]
 	pub execution_strategies: ExecutionStrategiesParams,
 
+	/// Enable the offchain indexing API, which lets the runtime write to the offchain database
+	/// during block import.
+	///
+	/// Off by default. The blocks imported while it is off are not indexed, turning it on for an
+	/// existing database only indexes the blocks imported from then on.
+	#[structopt(
+		long = "enable-offchain-indexing",
+		value_name = "ENABLE_OFFCHAIN_INDEXING",
+		default_value = "false",
+		parse(try_from_str)
+	)]
+	pub offchain_indexing: bool,
+
 	/// Specify the state cache size.
+	///
+	/// Accepts a number of bytes or a size with a `KiB`, `MiB` or `GiB` suffix, like `512MiB`.
+	/// It must be at least 1 MiB, or 0 to disable the cache.
 	#[structopt(
 		long = "state-cache-size",
-		value_name = "Bytes",
-		default_value = "67108864"
+		value_name = "SIZE",
+		default_value = "64MiB",
+		parse(try_from_str = parse_state_cache_size)
 	)]
 	pub state_cache_size: usize,
 
@@ -93,38 +119,54 @@ impl ImportParams {
 		self.tracing_targets.clone()
 	}
 
+	/// Whether the offchain indexing API is enabled.
+	pub fn offchain_indexing(&self) -> bool {
+		self.offchain_indexing
+	}
+
 	/// Specify the state cache size.
 	pub fn state_cache_size(&self) -> usize {
 		self.state_cache_size
 	}
 
 	/// Get the WASM execution method from the parameters
-	pub fn wasm_method(&self) -> sc_service::config::WasmExecutionMethod {
-		self.wasm_method.into()
+	///
+	/// Fails if the method isn't enabled in this build.
+	pub fn wasm_method(&self) -> Result<sc_service::config::WasmExecutionMethod, Error> {
+		if !WasmExecutionMethod::enabled_variants().contains(&self.wasm_method.to_string().as_str()) {
+			return Err(Error::Input(format!(
+				"The {} Wasm execution method requires a node built with the `wasmtime` feature",
+				self.wasm_method,
+			)));
+		}
+		Ok(self.wasm_method.into())
 	}
 
 	/// Get execution strategies for the parameters
+	///
+	/// The strategy of a context is the one given for it, or else the one of `--execution`, or else
+	/// its default, `Native` for all contexts on development chains.
 	pub fn execution_strategies(
 		&self,
 		is_dev: bool,
 	) -> ExecutionStrategies {
 		let exec = &self.execution_strategies;
-		let exec_all_or = |strat: ExecutionStrategy, default: ExecutionStrategy| {
-			exec.execution.unwrap_or(if strat == default && is_dev {
+		let exec_or = |strat: Option<ExecutionStrategy>, default: ExecutionStrategy| {
+			strat.or(exec.execution).unwrap_or(if is_dev {
 				ExecutionStrategy::Native
 			} else {
-				strat
+				default
 			}).into()
 		};
 
 		ExecutionStrategies {
-			syncing: exec_all_or(exec.execution_syncing, DEFAULT_EXECUTION_SYNCING),
-			importing: exec_all_or(exec.execution_import_block, DEFAULT_EXECUTION_IMPORT_BLOCK),
+			syncing: exec_or(exec.execution_syncing, DEFAULT_EXECUTION_SYNCING),
+			importing: exec_or(exec.execution_import_block, DEFAULT_EXECUTION_IMPORT_BLOCK),
 			block_construction:
-				exec_all_or(exec.execution_block_construction, DEFAULT_EXECUTION_BLOCK_CONSTRUCTION),
+				exec_or(exec.execution_block_construction, DEFAULT_EXECUTION_BLOCK_CONSTRUCTION),
 			offchain_worker:
-				exec_all_or(exec.execution_offchain_worker, DEFAULT_EXECUTION_OFFCHAIN_WORKER),
-			other: exec_all_or(exec.execution_other, DEFAULT_EXECUTION_OTHER),
+				exec_or(exec.execution_offchain_worker, DEFAULT_EXECUTION_OFFCHAIN_WORKER),
+			other: exec_or(exec.execution_other, DEFAULT_EXECUTION_OTHER),
 		}
 	}
 }
@@ -133,68 +175,177 @@ impl ImportParams {
 #[derive(Debug, StructOpt, Clone)]
 pub struct ExecutionStrategiesParams {
 	/// The means of execution used when calling into the runtime while syncing blocks.
+	///
+	/// Defaults to `NativeElseWasm`, or to `--execution` if given.
 	#[structopt(
 		long = "execution-syncing",
 		value_name = "STRATEGY",
 		possible_values = &ExecutionStrategy::variants(),
 		case_insensitive = true,
-		default_value = DEFAULT_EXECUTION_SYNCING.as_str(),
 	)]
-	pub execution_syncing: ExecutionStrategy,
+	pub execution_syncing: Option<ExecutionStrategy>,
 
 	/// The means of execution used when calling into the runtime while importing blocks.
+	///
+	/// Defaults to `NativeElseWasm`, or to `--execution` if given.
 	#[structopt(
 		long = "execution-import-block",
 		value_name = "STRATEGY",
 		possible_values = &ExecutionStrategy::variants(),
 		case_insensitive = true,
-		default_value = DEFAULT_EXECUTION_IMPORT_BLOCK.as_str(),
 	)]
-	pub execution_import_block: ExecutionStrategy,
+	pub execution_import_block: Option<ExecutionStrategy>,
 
 	/// The means of execution used when calling into the runtime while constructing blocks.
+	///
+	/// Defaults to `Wasm`, or to `--execution` if given.
 	#[structopt(
 		long = "execution-block-construction",
 		value_name = "STRATEGY",
 		possible_values = &ExecutionStrategy::variants(),
 		case_insensitive = true,
-		default_value = DEFAULT_EXECUTION_BLOCK_CONSTRUCTION.as_str(),
 	)]
-	pub execution_block_construction: ExecutionStrategy,
+	pub execution_block_construction: Option<ExecutionStrategy>,
 
 	/// The means of execution used when calling into the runtime while using an off-chain worker.
+	///
+	/// Defaults to `Native`, or to `--execution` if given.
 	#[structopt(
 		long = "execution-offchain-worker",
 		value_name = "STRATEGY",
 		possible_values = &ExecutionStrategy::variants(),
 		case_insensitive = true,
-		default_value = DEFAULT_EXECUTION_OFFCHAIN_WORKER.as_str(),
 	)]
-	pub execution_offchain_worker: ExecutionStrategy,
+	pub execution_offchain_worker: Option<ExecutionStrategy>,
 
 	/// The means of execution used when calling into the runtime while not syncing, importing or constructing blocks.
+	///
+	/// Defaults to `Native`, or to `--execution` if given.
 	#[structopt(
 		long = "execution-other",
 		value_name = "STRATEGY",
 		possible_values = &ExecutionStrategy::variants(),
 		case_insensitive = true,
-		default_value = DEFAULT_EXECUTION_OTHER.as_str(),
 	)]
-	pub execution_other: ExecutionStrategy,
+	pub execution_other: Option<ExecutionStrategy>,
 
 	/// The execution strategy that should be used by all execution contexts.
+	///
+	/// The strategies given for specific contexts take precedence over this one.
 	#[structopt(
 		long = "execution",
 		value_name = "STRATEGY",
 		possible_values = &ExecutionStrategy::variants(),
 		case_insensitive = true,
-		conflicts_with_all = &[
-			"execution-other",
-			"execution-offchain-worker",
-			"execution-block-construction",
-			"execution-import-block",
-			"execution-syncing",
-		]
 	)]
 	pub execution: Option<ExecutionStrategy>,
 }
+
+/// Parse a number of bytes, optionally with a `KiB`, `MiB` or `GiB` suffix.
+fn parse_size(s: &str) -> Result<usize, String> {
+	let s = s.trim();
+	let digits = s.find(|c: char| !c.is_ascii_digit()).unwrap_or_else(|| s.len());
+	let (number, suffix) = s.split_at(digits);
+
+	let multiplier: usize = match suffix.trim().to_ascii_lowercase().as_str() {
+		"" | "b" => 1,
+		"kib" => 1024,
+		"mib" => 1024 * 1024,
+		"gib" => 1024 * 1024 * 1024,
+		_ => return Err(format!("Invalid size `{}`, expected bytes or a KiB, MiB or GiB suffix", s)),
+	};
+	let number: usize = number.parse()
+		.map_err(|_| format!("Invalid size `{}`, expected a non-negative number", s))?;
+
+	number.checked_mul(multiplier).ok_or_else(|| format!("Size `{}` is too large", s))
+}
+
+fn parse_state_cache_size(s: &str) -> Result<usize, String> {
+	let size = parse_size(s)?;
+	if size != 0 && size < MIN_STATE_CACHE_SIZE {
+		return Err(format!(
+			"State cache size of {} bytes is too small, it must be at least 1MiB or 0 to disable the cache",
+			size,
+		));
+	}
+	Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse_size_works() {
+		assert_eq!(parse_size("67108864"), Ok(67108864));
+		assert_eq!(parse_size("4KiB"), Ok(4 * 1024));
+		assert_eq!(parse_size("512MiB"), Ok(512 * 1024 * 1024));
+		assert_eq!(parse_size("2 gib"), Ok(2 * 1024 * 1024 * 1024));
+		assert_eq!(parse_size("100B"), Ok(100));
+		assert!(parse_size("-1").is_err());
+		assert!(parse_size("1.5GiB").is_err());
+		assert!(parse_size("12MB").is_err());
+		assert!(parse_size("MiB").is_err());
+	}
+
+	#[test]
+	fn state_cache_size_rejects_small_values() {
+		assert_eq!(parse_state_cache_size("0"), Ok(0));
+		assert_eq!(parse_state_cache_size("1MiB"), Ok(MIN_STATE_CACHE_SIZE));
+		assert!(parse_state_cache_size("1000").is_err());
+		assert_eq!(
+			ImportParams::from_iter(&["import"]).state_cache_size(),
+			67108864,
+		);
+	}
+
+	#[test]
+	fn wasm_method_requires_an_enabled_variant() {
+		let params = ImportParams::from_iter(&["import", "--wasm-execution", "compiled"]);
+
+		assert_eq!(params.wasm_method.to_string(), "Compiled");
+		assert_eq!(params.wasm_method().is_ok(), cfg!(feature = "wasmtime"));
+		assert!(ImportParams::from_iter(&["import"]).wasm_method().is_ok());
+	}
+
+	fn execution_strategies(args: &[&str], is_dev: bool) -> ExecutionStrategies {
+		ImportParams::from_iter(std::iter::once("import").chain(args.iter().cloned()))
+			.execution_strategies(is_dev)
+	}
+
+	#[test]
+	fn execution_strategies_defaults() {
+		let strategies = execution_strategies(&[], false);
+		assert_eq!(strategies.syncing, DEFAULT_EXECUTION_SYNCING.into());
+		assert_eq!(strategies.importing, DEFAULT_EXECUTION_IMPORT_BLOCK.into());
+		assert_eq!(strategies.block_construction, DEFAULT_EXECUTION_BLOCK_CONSTRUCTION.into());
+		assert_eq!(strategies.offchain_worker, DEFAULT_EXECUTION_OFFCHAIN_WORKER.into());
+		assert_eq!(strategies.other, DEFAULT_EXECUTION_OTHER.into());
+
+		let strategies = execution_strategies(&["--execution-syncing", "wasm"], true);
+		assert_eq!(strategies.syncing, ExecutionStrategy::Wasm.into());
+		assert_eq!(strategies.block_construction, ExecutionStrategy::Native.into());
+	}
+
+	#[test]
+	fn specific_execution_strategies_override_the_global_one() {
+		for &is_dev in &[false, true] {
+			let strategies = execution_strategies(&["--execution", "both"], is_dev);
+			assert_eq!(strategies.syncing, ExecutionStrategy::Both.into());
+			assert_eq!(strategies.importing, ExecutionStrategy::Both.into());
+			assert_eq!(strategies.block_construction, ExecutionStrategy::Both.into());
+			assert_eq!(strategies.offchain_worker, ExecutionStrategy::Both.into());
+			assert_eq!(strategies.other, ExecutionStrategy::Both.into());
+
+			let strategies = execution_strategies(
+				&["--execution", "both", "--execution-import-block", "native", "--execution-offchain-worker", "wasm"],
+				is_dev,
+			);
+			assert_eq!(strategies.syncing, ExecutionStrategy::Both.into());
+			assert_eq!(strategies.importing, ExecutionStrategy::Native.into());
+			assert_eq!(strategies.block_construction, ExecutionStrategy::Both.into());
+			assert_eq!(strategies.offchain_worker, ExecutionStrategy::Wasm.into());
+			assert_eq!(strategies.other, ExecutionStrategy::Both.into());
+		}
+	}
+}
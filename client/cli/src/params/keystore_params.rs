@@ -16,7 +16,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::error::Result;
+use crate::error::{Error, Result};
 use sc_service::config::KeystoreConfig;
 use std::fs;
 use std::path::PathBuf;
@@ -29,9 +29,23 @@ const DEFAULT_KEYSTORE_CONFIG_PATH: &'static str = "keystore";
 #[derive(Debug, StructOpt, Clone)]
 pub struct KeystoreParams {
 	/// Specify custom keystore path.
+	///
+	/// Defaults to the `keystore` directory of the chain in the base path. When given, it takes
+	/// precedence over `--base-path` for the keystore only, the database stays in the base path.
+	/// The directory is created on first use, only accessible by its owner on Unix.
 	#[structopt(long = "keystore-path", value_name = "PATH", parse(from_os_str))]
 	pub keystore_path: Option<PathBuf>,
 
+	/// Keep the keys in memory only, never writing them to disk.
+	///
+	/// The keys are lost when the node stops, including the development keys of `--dev` and of
+	/// the shortcuts like `--alice`.
+	#[structopt(
+		long = "keystore-in-memory",
+		conflicts_with_all = &[ "keystore-path", "password-interactive", "password", "password-filename" ]
+	)]
+	pub keystore_in_memory: bool,
+
 	/// Use interactive shell for entering the password used by the keystore.
 	#[structopt(
 		long = "password-interactive",
@@ -57,8 +71,21 @@ pub struct KeystoreParams {
 }
 
 impl KeystoreParams {
+	/// Whether any of the keystore parameters was given.
+	pub fn is_given(&self) -> bool {
+		self.keystore_path.is_some()
+			|| self.keystore_in_memory
+			|| self.password_interactive
+			|| self.password.is_some()
+			|| self.password_filename.is_some()
+	}
+
 	/// Get the keystore configuration for the parameters
 	pub fn keystore_config(&self, base_path: &PathBuf) -> Result<KeystoreConfig> {
+		if self.keystore_in_memory {
+			return Ok(KeystoreConfig::InMemory);
+		}
+
 		let password = if self.password_interactive {
 			#[cfg(not(target_os = "unknown"))]
 			{
@@ -78,10 +105,14 @@ impl KeystoreParams {
 			None
 		};
 
-		let path = self
-			.keystore_path
-			.clone()
-			.unwrap_or(base_path.join(DEFAULT_KEYSTORE_CONFIG_PATH));
+		let path = match &self.keystore_path {
+			Some(path) if path.exists() && !path.is_dir() => return Err(Error::Input(format!(
+				"The keystore path {} is not a directory",
+				path.display(),
+			))),
+			Some(path) => path.clone(),
+			None => base_path.join(DEFAULT_KEYSTORE_CONFIG_PATH),
+		};
 
 		Ok(KeystoreConfig::Path { path, password })
 	}
@@ -92,3 +123,38 @@ fn input_keystore_password() -> Result<String> {
 	rpassword::read_password_from_tty(Some("Keystore password: "))
 		.map_err(|e| format!("{:?}", e).into())
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn keystore_params(args: &[&str]) -> KeystoreParams {
+		KeystoreParams::from_iter(std::iter::once("substrate").chain(args.iter().cloned()))
+	}
+
+	#[test]
+	fn keystore_path_overrides_the_base_path() {
+		let dir = tempfile::tempdir().unwrap();
+		let keystore = dir.path().join("keystore-volume");
+		let base_path = dir.path().join("chains").join("dev");
+
+		let config = keystore_params(&["--keystore-path", keystore.to_str().unwrap()])
+			.keystore_config(&base_path)
+			.unwrap();
+		assert_eq!(config.path(), Some(keystore.as_path()));
+
+		let config = keystore_params(&[]).keystore_config(&base_path).unwrap();
+		assert_eq!(config.path(), Some(base_path.join("keystore").as_path()));
+	}
+
+	#[test]
+	fn keystore_path_must_be_a_directory() {
+		let dir = tempfile::tempdir().unwrap();
+		let file = dir.path().join("file");
+		fs::write(&file, b"").unwrap();
+
+		assert!(keystore_params(&["--keystore-path", file.to_str().unwrap()])
+			.keystore_config(&dir.path().to_path_buf())
+			.is_err());
+	}
+}
@@ -38,6 +38,8 @@ pub use crate::params::pruning_params::*;
 pub use crate::params::shared_params::*;
 pub use crate::params::transaction_pool_params::*;
 
+pub(crate) use crate::params::database_params::{check_database_cache_size, database_dir, resolve_database};
+
 /// Wrapper type of `String` that holds an unsigned integer of arbitrary size, formatted as a decimal.
 #[derive(Debug, Clone)]
 pub struct BlockNumber(String);
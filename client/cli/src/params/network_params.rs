@@ -19,7 +19,7 @@
 use crate::params::node_key_params::NodeKeyParams;
 use sc_network::{
 	config::{NetworkConfiguration, NodeKeyConfig, NonReservedPeerMode, TransportConfig},
-	multiaddr::Protocol,
+	multiaddr::{self, Protocol},
 };
 use sc_service::{ChainSpec, config::{Multiaddr, MultiaddrWithPeerId}};
 use std::path::PathBuf;
@@ -29,13 +29,25 @@ use structopt::StructOpt;
 #[derive(Debug, StructOpt, Clone)]
 pub struct NetworkParams {
 	/// Specify a list of bootnodes.
-	#[structopt(long = "bootnodes", value_name = "ADDR")]
+	///
+	/// For instance `/ip4/198.51.100.19/tcp/30333/p2p/QmSk5...`, `/ip6/2001:db8::1/tcp/30333/p2p/QmSk5...`
+	/// or `/dns4/example.com/tcp/30333/p2p/QmSk5...`. The `/p2p/<peer id>` is required to dial
+	/// the bootnode authenticated.
+	#[structopt(long = "bootnodes", value_name = "ADDR", parse(try_from_str = parse_bootnode))]
 	pub bootnodes: Vec<MultiaddrWithPeerId>,
 
 	/// Specify a list of reserved node addresses.
 	#[structopt(long = "reserved-nodes", value_name = "ADDR")]
 	pub reserved_nodes: Vec<MultiaddrWithPeerId>,
 
+	/// Read more reserved node addresses from a file.
+	///
+	/// The file has one address per line, like `/ip4/198.51.100.19/tcp/30333/p2p/QmSk5...`.
+	/// Blank lines and lines starting with `#` are ignored. The addresses are added to the ones
+	/// of `--reserved-nodes`.
+	#[structopt(long = "reserved-nodes-file", value_name = "PATH", parse(from_os_str))]
+	pub reserved_nodes_file: Option<PathBuf>,
+
 	/// Whether to only allow connections to/from reserved nodes.
 	///
 	/// If you are a validator your node might still connect to other validator
@@ -49,7 +61,9 @@ pub struct NetworkParams {
 	pub public_addr: Vec<Multiaddr>,
 
 	/// Listen on this multiaddress.
-	#[structopt(long = "listen-addr", value_name = "LISTEN_ADDR")]
+	///
+	/// For instance `/ip4/0.0.0.0/tcp/30333`, or `/ip6/::/tcp/30333/ws` for WebSocket connections.
+	#[structopt(long = "listen-addr", value_name = "LISTEN_ADDR", parse(try_from_str = parse_listen_addr))]
 	pub listen_addr: Vec<Multiaddr>,
 
 	/// Specify p2p protocol TCP port.
@@ -63,12 +77,16 @@ pub struct NetworkParams {
 	pub no_private_ipv4: bool,
 
 	/// Specify the number of outgoing connections we're trying to maintain.
-	#[structopt(long = "out-peers", value_name = "COUNT", default_value = "25")]
-	pub out_peers: u32,
+	///
+	/// Defaults to 25, or 8 for light clients.
+	#[structopt(long = "out-peers", value_name = "COUNT")]
+	pub out_peers: Option<u32>,
 
 	/// Specify the maximum number of incoming connections we're accepting.
-	#[structopt(long = "in-peers", value_name = "COUNT", default_value = "25")]
-	pub in_peers: u32,
+	///
+	/// Defaults to 25, or 0 for light clients.
+	#[structopt(long = "in-peers", value_name = "COUNT")]
+	pub in_peers: Option<u32>,
 
 	/// Disable mDNS discovery.
 	///
@@ -119,7 +137,7 @@ impl NetworkParams {
 		client_id: &str,
 		node_name: &str,
 		node_key: NodeKeyConfig,
-	) -> NetworkConfiguration {
+	) -> crate::Result<NetworkConfiguration> {
 		let port = self.port.unwrap_or(30333);
 
 		let listen_addresses = if self.listen_addr.is_empty() {
@@ -140,10 +158,10 @@ impl NetworkParams {
 		let mut boot_nodes = chain_spec.boot_nodes().to_vec();
 		boot_nodes.extend(self.bootnodes.clone());
 
-		NetworkConfiguration {
+		Ok(NetworkConfiguration {
 			boot_nodes,
 			net_config_path,
-			reserved_nodes: self.reserved_nodes.clone(),
+			reserved_nodes: self.reserved_nodes()?,
 			non_reserved_mode: if self.reserved_only {
 				NonReservedPeerMode::Deny
 			} else {
@@ -155,8 +173,8 @@ impl NetworkParams {
 			node_key,
 			node_name: node_name.to_string(),
 			client_version: client_id.to_string(),
-			in_peers: self.in_peers,
-			out_peers: self.out_peers,
+			in_peers: self.in_peers.unwrap_or(25),
+			out_peers: self.out_peers.unwrap_or(25),
 			transport: TransportConfig::Normal {
 				enable_mdns: !is_dev && !self.no_mdns,
 				allow_private_ipv4: !self.no_private_ipv4,
@@ -166,6 +184,201 @@ impl NetworkParams {
 			max_parallel_downloads: self.max_parallel_downloads,
 			allow_non_globals_in_dht: self.discover_local || is_dev,
 			use_new_block_requests_protocol: !self.legacy_network_protocol,
+		})
+	}
+
+	/// The reserved nodes of `--reserved-nodes` followed by the new ones of `--reserved-nodes-file`.
+	pub fn reserved_nodes(&self) -> crate::Result<Vec<MultiaddrWithPeerId>> {
+		let mut reserved_nodes = self.reserved_nodes.clone();
+		if let Some(path) = &self.reserved_nodes_file {
+			let content = std::fs::read_to_string(path).map_err(|e| crate::Error::Input(format!(
+				"Cannot read the reserved nodes file {}: {}",
+				path.display(),
+				e,
+			)))?;
+			let from_file = parse_reserved_nodes(&content).map_err(|e| crate::Error::Input(format!(
+				"Invalid reserved nodes file {}: {}",
+				path.display(),
+				e,
+			)))?;
+
+			for node in from_file {
+				if !reserved_nodes.iter().any(|reserved| reserved.to_string() == node.to_string()) {
+					reserved_nodes.push(node);
+				}
+			}
 		}
+
+		Ok(reserved_nodes)
+	}
+}
+
+/// Parse the reserved node addresses of a file, one per line, skipping blank and `#` lines.
+fn parse_reserved_nodes(content: &str) -> Result<Vec<MultiaddrWithPeerId>, String> {
+	content.lines()
+		.enumerate()
+		.map(|(index, line)| (index + 1, line.trim()))
+		.filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+		.map(|(number, line)| line.parse().map_err(|e| format!("line {}: `{}`: {}", number, line, e)))
+		.collect()
+}
+
+/// Parse a listen address, naming the malformed component of an invalid one.
+fn parse_listen_addr(addr: &str) -> Result<Multiaddr, String> {
+	let listen_addr = parse_multiaddr(addr)?;
+	if !listen_addr.iter().any(|protocol| matches!(protocol, Protocol::Tcp(_) | Protocol::Memory(_))) {
+		return Err(format!("`{}` has no TCP port, like `/ip4/0.0.0.0/tcp/30333`", addr));
+	}
+
+	Ok(listen_addr)
+}
+
+/// Parse a bootnode address, which starts with an IP address or a domain name the node resolves
+/// and ends with the peer id of the bootnode.
+fn parse_bootnode(addr: &str) -> Result<MultiaddrWithPeerId, String> {
+	let bootnode = parse_multiaddr(addr)?;
+	match bootnode.iter().next() {
+		Some(Protocol::Memory(_)) => return with_peer_id(addr),
+		Some(Protocol::Ip4(_)) | Some(Protocol::Ip6(_)) |
+		Some(Protocol::Dns(_)) | Some(Protocol::Dns4(_)) | Some(Protocol::Dns6(_)) => {},
+		_ => return Err(format!(
+			"`{}` must start with the `/ip4`, `/ip6`, `/dns`, `/dns4` or `/dns6` address of the bootnode",
+			addr,
+		)),
+	}
+
+	if !bootnode.iter().any(|protocol| matches!(protocol, Protocol::Tcp(_))) {
+		return Err(format!("`{}` has no TCP port, like `/dns4/example.com/tcp/30333/p2p/QmSk5...`", addr));
+	}
+
+	with_peer_id(addr)
+}
+
+/// Parse a valid multiaddress ending with a peer id.
+fn with_peer_id(addr: &str) -> Result<MultiaddrWithPeerId, String> {
+	addr.parse().map_err(|e| format!(
+		"`{}` must end with the `/p2p/<peer id>` of the node to dial it authenticated: {}",
+		addr,
+		e,
+	))
+}
+
+/// Parse a multiaddress, naming the malformed component of an invalid one.
+fn parse_multiaddr(addr: &str) -> Result<Multiaddr, String> {
+	let mut parts = addr.split('/').peekable();
+	if parts.next() != Some("") {
+		return Err(format!("`{}` must start with `/`, like `/ip4/0.0.0.0/tcp/30333`", addr));
+	}
+
+	let mut multiaddr = Multiaddr::empty();
+	while let Some(&name) = parts.peek() {
+		let value = parts.clone().nth(1).unwrap_or_default();
+		let protocol = Protocol::from_str_parts(&mut parts).map_err(|e| match e {
+			_ if name.is_empty() => format!("`{}` has an empty component", addr),
+			multiaddr::Error::UnknownProtocolString(_) => format!("Unknown protocol `{}` in `{}`", name, addr),
+			multiaddr::Error::InvalidProtocolString => format!("Missing the value of `{}` in `{}`", name, addr),
+			_ if name == "tcp" || name == "udp" => format!("Invalid {} port `{}` in `{}`", name, value, addr),
+			e => format!("Invalid {} value `{}` in `{}`: {}", name, value, addr, e),
+		})?;
+		multiaddr.push(protocol);
+	}
+
+	Ok(multiaddr)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn listen_addr_is_validated() {
+		assert!(parse_listen_addr("/ip4/0.0.0.0/tcp/30333").is_ok());
+		assert!(parse_listen_addr("/ip6/::/tcp/30333/ws").is_ok());
+		assert!(parse_listen_addr("/dns4/example.com/tcp/30333").is_ok());
+
+		assert_eq!(
+			parse_listen_addr("/ip4/0.0.0.0/tcp/99999").unwrap_err(),
+			"Invalid tcp port `99999` in `/ip4/0.0.0.0/tcp/99999`",
+		);
+		assert_eq!(
+			parse_listen_addr("/ip4/0.0.0.0/tpc/30333").unwrap_err(),
+			"Unknown protocol `tpc` in `/ip4/0.0.0.0/tpc/30333`",
+		);
+		assert_eq!(
+			parse_listen_addr("/ip4/0.0.0.0/tcp").unwrap_err(),
+			"Missing the value of `tcp` in `/ip4/0.0.0.0/tcp`",
+		);
+		assert!(parse_listen_addr("/ip4/256.0.0.1/tcp/30333").unwrap_err().contains("ip4 value `256.0.0.1`"));
+		assert!(parse_listen_addr("ip4/0.0.0.0/tcp/30333").is_err());
+		assert!(parse_listen_addr("/ip4/0.0.0.0/tcp/30333/").is_err());
+		assert!(parse_listen_addr("/ip4/0.0.0.0").is_err());
+	}
+
+	#[test]
+	fn bootnodes_are_validated() {
+		let peer_id = "QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV";
+		for addr in &[
+			"/ip4/198.51.100.19/tcp/30333",
+			"/ip6/2001:db8::1/tcp/30333",
+			"/dns/example.com/tcp/30333",
+			"/dns4/example.com/tcp/30333/ws",
+			"/dns6/example.com/tcp/443/wss",
+		] {
+			assert!(parse_bootnode(&format!("{}/p2p/{}", addr, peer_id)).is_ok(), "{}", addr);
+			assert!(parse_bootnode(addr).unwrap_err().contains("/p2p/<peer id>"), "{}", addr);
+		}
+
+		assert!(parse_bootnode("/dnsaddr/example.com/p2p/QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV")
+			.unwrap_err().contains("must start with"));
+		assert!(parse_bootnode("/tcp/30333").unwrap_err().contains("must start with"));
+		assert!(parse_bootnode("/dns4/example.com").unwrap_err().contains("no TCP port"));
+		assert!(parse_bootnode("/ip6/2001:db8::1/tcp/303330").unwrap_err().contains("Invalid tcp port"));
+		assert!(parse_bootnode("dns4/example.com/tcp/30333").is_err());
+	}
+
+	#[test]
+	fn bootnodes_without_a_peer_id_are_rejected() {
+		let with_peer_id = "/dns4/example.com/tcp/30333/p2p/QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV";
+		let params = NetworkParams::from_iter(&["substrate", "--bootnodes", with_peer_id]);
+		assert_eq!(
+			params.bootnodes.iter().map(ToString::to_string).collect::<Vec<_>>(),
+			vec![with_peer_id.to_string()],
+		);
+
+		assert!(NetworkParams::from_iter_safe(&[
+			"substrate",
+			"--bootnodes", with_peer_id,
+			"--bootnodes", "/ip6/2001:db8::1/tcp/30333",
+		]).is_err());
+	}
+
+	#[test]
+	fn reserved_nodes_file_is_merged_with_the_inline_ones() {
+		let inline = "/ip4/198.51.100.19/tcp/30333/p2p/QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV";
+		let other = "/ip4/198.51.100.20/tcp/30333/p2p/QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV";
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("reserved");
+		std::fs::write(&path, format!("# Validators\n\n{}\n  {}  \n", inline, other)).unwrap();
+
+		let params = NetworkParams::from_iter(&[
+			"substrate",
+			"--reserved-nodes", inline,
+			"--reserved-nodes-file", path.to_str().unwrap(),
+		]);
+		assert_eq!(
+			params.reserved_nodes().unwrap().iter().map(ToString::to_string).collect::<Vec<_>>(),
+			vec![inline.to_string(), other.to_string()],
+		);
+
+		std::fs::write(&path, format!("{}\n# Broken\n/ip4/198.51.100.20/tcp/30333\n", inline)).unwrap();
+		let error = parse_reserved_nodes(&std::fs::read_to_string(&path).unwrap()).unwrap_err();
+		assert!(error.starts_with("line 3: `/ip4/198.51.100.20/tcp/30333`"), "{}", error);
+		assert!(params.reserved_nodes().is_err());
+
+		let missing = NetworkParams::from_iter(&[
+			"substrate",
+			"--reserved-nodes-file", dir.path().join("missing").to_str().unwrap(),
+		]);
+		assert!(missing.reserved_nodes().is_err());
 	}
 }
@@ -45,20 +45,12 @@ pub struct OffchainWorkerParams {
 		default_value = "WhenValidating"
 	)]
 	pub enabled: OffchainWorkerEnabled,
-
-	/// Enable Offchain Indexing API, which allows block import to write to Offchain DB.
-	///
-	/// Enables a runtime to write directly to a offchain workers
-	/// DB during block import.
-	#[structopt(
-		long = "enable-offchain-indexing",
-		value_name = "ENABLE_OFFCHAIN_INDEXING"
-	)]
-	pub indexing_enabled: bool,
 }
 
 impl OffchainWorkerParams {
 	/// Load spec to `Configuration` from `OffchainWorkerParams` and spec factory.
+	///
+	/// Offchain indexing is left disabled, it is enabled by the import parameters.
 	pub fn offchain_worker(
 		&self,
 		role: &Role,
@@ -71,8 +63,6 @@ impl OffchainWorkerParams {
 			(OffchainWorkerEnabled::WhenValidating, _) => false,
 		};
 
-		let indexing_enabled = enabled && self.indexing_enabled;
-
-		Ok(OffchainWorkerConfig { enabled, indexing_enabled })
+		Ok(OffchainWorkerConfig { enabled, indexing_enabled: false })
 	}
 }
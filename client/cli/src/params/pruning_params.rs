@@ -17,30 +17,51 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::error;
-use sc_service::{PruningMode, Role};
+use sc_service::{KeepBlocks, PruningMode, Role};
 use structopt::StructOpt;
 
+/// The fewest blocks whose body may be kept without `--keep-blocks-force`.
+///
+/// Fewer blocks leave too little history for peers syncing from the node and for the RPC.
+pub const MIN_PRUNING_BLOCKS: u32 = 32;
+
 /// Parameters to define the pruning mode
 #[derive(Debug, StructOpt, Clone)]
 pub struct PruningParams {
-	/// Specify the state pruning mode, a number of blocks to keep or 'archive'.
+	/// Specify the state pruning mode, a number of blocks to keep, 'archive' or
+	/// 'archive-canonical'.
+	///
+	/// 'archive' keeps the state of all blocks, 'archive-canonical' only the state of the
+	/// finalized blocks.
 	///
 	/// Default is to keep all block states if the node is running as a
 	/// validator (i.e. 'archive'), otherwise state is only kept for the last
 	/// 256 blocks.
 	#[structopt(long = "pruning", value_name = "PRUNING_MODE")]
 	pub pruning: Option<String>,
+
+	/// Specify the number of finalized blocks whose body is kept, at least 32.
+	///
+	/// The headers and justifications of all blocks are always kept, but the blocks whose body
+	/// was pruned can't be exported anymore. Default is to keep the bodies of all blocks.
+	#[structopt(long = "keep-blocks", value_name = "COUNT")]
+	pub keep_blocks: Option<u32>,
+
+	/// Allow `--keep-blocks` to keep the bodies of fewer than 32 blocks.
+	#[structopt(long = "keep-blocks-force", requires = "keep-blocks")]
+	pub keep_blocks_force: bool,
 }
 
 impl PruningParams {
-	/// Get the pruning value from the parameters
-	pub fn pruning(&self, unsafe_pruning: bool, role: &Role) -> error::Result<PruningMode> {
+	/// Get the state pruning value from the parameters
+	pub fn state_pruning(&self, unsafe_pruning: bool, role: &Role) -> error::Result<PruningMode> {
 		// by default we disable pruning if the node is an authority (i.e.
 		// `ArchiveAll`), otherwise we keep state for the last 256 blocks. if the
 		// node is an authority and pruning is enabled explicitly, then we error
 		// unless `unsafe_pruning` is set.
 		Ok(match &self.pruning {
 			Some(ref s) if s == "archive" => PruningMode::ArchiveAll,
+			Some(ref s) if s == "archive-canonical" => PruningMode::ArchiveCanonical,
 			None if role.is_network_authority() => PruningMode::ArchiveAll,
 			None => PruningMode::default(),
 			Some(s) => {
@@ -52,10 +73,95 @@ impl PruningParams {
 					));
 				}
 
-				PruningMode::keep_blocks(s.parse().map_err(|_| {
+				let blocks = s.parse().map_err(|_| {
 					error::Error::Input("Invalid pruning mode specified".to_string())
-				})?)
+				})?;
+				PruningMode::keep_blocks(blocks)
 			}
 		})
 	}
+
+	/// Get the block body pruning value from the parameters
+	pub fn keep_blocks(&self) -> error::Result<KeepBlocks> {
+		Ok(match self.keep_blocks {
+			Some(0) => return Err(error::Error::Input("`--keep-blocks` must keep at least 1 block".into())),
+			Some(blocks) if self.keep_blocks_force && blocks < MIN_PRUNING_BLOCKS => {
+				log::warn!(
+					"Only keeping the bodies of the last {} finalized blocks, fewer than the {} \
+					recommended (`--keep-blocks-force`).",
+					blocks,
+					MIN_PRUNING_BLOCKS,
+				);
+				KeepBlocks::Some(blocks)
+			},
+			Some(blocks) if blocks < MIN_PRUNING_BLOCKS => return Err(error::Error::Input(format!(
+				"`--keep-blocks` must keep at least {} blocks, got {}. \
+				You can keep fewer with `--keep-blocks-force`.",
+				MIN_PRUNING_BLOCKS,
+				blocks,
+			))),
+			Some(blocks) => KeepBlocks::Some(blocks),
+			None => KeepBlocks::All,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn params(args: &[&str]) -> PruningParams {
+		PruningParams::from_iter(std::iter::once("pruning").chain(args.iter().cloned()))
+	}
+
+	#[test]
+	fn state_pruning_accepts_every_spelling() {
+		let state_pruning = |args: &[&str]| params(args).state_pruning(false, &Role::Full);
+
+		assert_eq!(state_pruning(&[]).unwrap(), PruningMode::default());
+		assert_eq!(state_pruning(&["--pruning", "archive"]).unwrap(), PruningMode::ArchiveAll);
+		assert_eq!(
+			state_pruning(&["--pruning", "archive-canonical"]).unwrap(),
+			PruningMode::ArchiveCanonical,
+		);
+		assert_eq!(state_pruning(&["--pruning", "1000"]).unwrap(), PruningMode::keep_blocks(1000));
+		assert_eq!(state_pruning(&["--pruning", "8"]).unwrap(), PruningMode::keep_blocks(8));
+		assert!(state_pruning(&["--pruning", "everything"]).is_err());
+	}
+
+	#[test]
+	fn state_pruning_of_authorities_is_archive() {
+		assert_eq!(
+			params(&[]).state_pruning(false, &Role::Authority { sentry_nodes: Vec::new() }).unwrap(),
+			PruningMode::ArchiveAll,
+		);
+		assert!(params(&["--pruning", "1000"])
+			.state_pruning(false, &Role::Authority { sentry_nodes: Vec::new() })
+			.is_err());
+		assert!(params(&["--pruning", "1000"])
+			.state_pruning(true, &Role::Authority { sentry_nodes: Vec::new() })
+			.is_ok());
+	}
+
+	#[test]
+	fn keep_blocks_works() {
+		assert_eq!(params(&[]).keep_blocks().unwrap(), KeepBlocks::All);
+		assert_eq!(params(&["--keep-blocks", "512"]).keep_blocks().unwrap(), KeepBlocks::Some(512));
+		assert!(params(&["--keep-blocks", "0"]).keep_blocks().is_err());
+	}
+
+	#[test]
+	fn keep_blocks_below_the_minimum_requires_force() {
+		assert!(params(&["--keep-blocks", "31"]).keep_blocks().is_err());
+		assert_eq!(
+			params(&["--keep-blocks", "8", "--keep-blocks-force"]).keep_blocks().unwrap(),
+			KeepBlocks::Some(8),
+		);
+		assert!(params(&["--keep-blocks", "0", "--keep-blocks-force"]).keep_blocks().is_err());
+		assert!(PruningParams::from_iter_safe(&["pruning", "--keep-blocks-force"]).is_err());
+		// The flag only affects `--keep-blocks`, it isn't a generic `--force`.
+		assert!(
+			PruningParams::from_iter_safe(&["pruning", "--keep-blocks", "8", "--force"]).is_err()
+		);
+	}
 }
@@ -16,6 +16,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::ChainSpecDownload;
 use std::path::PathBuf;
 use structopt::StructOpt;
 
@@ -23,9 +24,19 @@ use structopt::StructOpt;
 #[derive(Debug, StructOpt, Clone)]
 pub struct SharedParams {
 	/// Specify the chain specification (one of dev, local, or staging).
+	///
+	/// An `http://` or `https://` URL is downloaded once and cached in the base path.
 	#[structopt(long, value_name = "CHAIN_SPEC")]
 	pub chain: Option<String>,
 
+	/// Download the chain specification given as URL with `--chain` again, even if it is cached.
+	#[structopt(long)]
+	pub refresh_chain_spec: bool,
+
+	/// Expected SHA-256 hash of the chain specification given as URL with `--chain`, hex-encoded.
+	#[structopt(long, value_name = "HEX")]
+	pub chain_spec_sha256: Option<String>,
+
 	/// Specify the development chain.
 	#[structopt(long, conflicts_with_all = &["chain"])]
 	pub dev: bool,
@@ -45,6 +56,25 @@ pub struct SharedParams {
 	/// By default, all targets log `info`. The global log level can be set with -l<level>.
 	#[structopt(short = "l", long, value_name = "LOG_PATTERN")]
 	pub log: Vec<String>,
+
+	/// Load default values of the shared and keystore parameters from a TOML file.
+	///
+	/// The keys of the file are the long names of the flags, e.g. `base-path = "/data"`.
+	/// Flags given on the command line take precedence over the values of the file.
+	#[structopt(long, value_name = "FILE", parse(from_os_str))]
+	pub config: Option<PathBuf>,
+
+	/// Print the resolved configuration and exit without executing the command.
+	#[structopt(long)]
+	pub dry_run: bool,
+
+	/// Reject chain specification files with fields unknown to this node, which are most likely
+	/// typos.
+	///
+	/// The unknown fields are reported with their JSON pointer. This builds the genesis of the
+	/// chain specification, so it isn't done by default.
+	#[structopt(long)]
+	pub check_chain_spec: bool,
 }
 
 impl SharedParams {
@@ -72,6 +102,24 @@ impl SharedParams {
 		}
 	}
 
+	/// Options of the download of a chain specification given as URL.
+	pub fn chain_spec_download(&self) -> ChainSpecDownload {
+		ChainSpecDownload {
+			refresh: self.refresh_chain_spec,
+			sha256: self.chain_spec_sha256.clone(),
+		}
+	}
+
+	/// Only print the resolved configuration instead of executing the command.
+	pub fn is_dry_run(&self) -> bool {
+		self.dry_run
+	}
+
+	/// Whether chain specification files are checked for fields unknown to the node.
+	pub fn check_chain_spec(&self) -> bool {
+		self.check_chain_spec
+	}
+
 	/// Get the filters for the logging
 	pub fn log_filters(&self) -> &[String] {
 		&self.log
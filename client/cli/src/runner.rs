@@ -24,8 +24,8 @@ use chrono::prelude::*;
 use futures::pin_mut;
 use futures::select;
 use futures::{future, future::FutureExt, Future};
-use log::info;
-use sc_service::{AbstractService, Configuration, Role, ServiceBuilderCommand, TaskType};
+use log::{info, warn};
+use sc_service::{AbstractService, BasePath, Configuration, Role, ServiceBuilderCommand, TaskType};
 use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
 use sp_utils::metrics::{TOKIO_THREADS_ALIVE, TOKIO_THREADS_TOTAL};
 use std::{str::FromStr, fmt::Debug, marker::PhantomData, sync::Arc};
@@ -108,6 +108,7 @@ where
 /// A Substrate CLI runtime that can be used to run a node or a command
 pub struct Runner<C: SubstrateCli> {
 	config: Configuration,
+	dry_run: bool,
 	tokio_runtime: tokio::runtime::Runtime,
 	phantom: PhantomData<C>,
 }
@@ -135,6 +136,7 @@ impl<C: SubstrateCli> Runner<C> {
 
 		Ok(Runner {
 			config: command.create_configuration(cli, task_executor)?,
+			dry_run: command.shared_params().is_dry_run(),
 			tokio_runtime,
 			phantom: PhantomData,
 		})
@@ -164,12 +166,39 @@ impl<C: SubstrateCli> Runner<C> {
 		info!("📋 Chain specification: {}", self.config.chain_spec.name());
 		info!("🏷  Node name: {}", self.config.network.node_name);
 		info!("👤 Role: {}", self.config.display_role());
+		if let Some(base_path) = &self.config.base_path {
+			info!(
+				"📂 Base path: {}{}",
+				base_path.path().display(),
+				if let BasePath::Temporary(_) = base_path { " (temporary)" } else { "" },
+			);
+		}
+		if self.config.force_authoring {
+			warn!("⚠️  Authoring is forced: blocks are produced even without peers or while syncing. \
+				This is only meant for development chains.");
+		}
 		info!("💾 Database: {} at {}",
 			self.config.database,
 			self.config.database.path().map_or_else(|| "<unknown>".to_owned(), |p| p.display().to_string())
 		);
+		info!("🗃  State cache size: {} bytes", self.config.state_cache_size);
+		info!(
+			"⚙️  Wasm execution: {:?}, execution strategies: syncing {:?}, importing {:?}, \
+			block construction {:?}, offchain worker {:?}, other {:?}",
+			self.config.wasm_method,
+			self.config.execution_strategies.syncing,
+			self.config.execution_strategies.importing,
+			self.config.execution_strategies.block_construction,
+			self.config.execution_strategies.offchain_worker,
+			self.config.execution_strategies.other,
+		);
 		info!("⛓  Native runtime: {}", runtime_version);
 
+		if self.dry_run {
+			self.print_configuration();
+			return Ok(());
+		}
+
 		match self.config.role {
 			Role::Light => self.run_service_until_exit(new_light),
 			_ => self.run_service_until_exit(new_full),
@@ -187,6 +216,23 @@ impl<C: SubstrateCli> Runner<C> {
 		<BB as BlockT>::Hash: FromStr,
 		<<BB as BlockT>::Hash as FromStr>::Err: Debug,
 	{
+		if self.dry_run {
+			// A dry run of `build-spec` editing the genesis prints the edited genesis instead.
+			if let Subcommand::BuildSpec(cmd) = subcommand {
+				if cmd.edits_genesis() {
+					return cmd.run(self.config);
+				}
+			}
+
+			// A dry run of `prune` estimates the size of the state it would remove.
+			if let Subcommand::Prune(cmd) = subcommand {
+				return cmd.run::<BB>(self.config);
+			}
+
+			self.print_configuration();
+			return Ok(());
+		}
+
 		match subcommand {
 			Subcommand::BuildSpec(cmd) => cmd.run(self.config),
 			Subcommand::ExportBlocks(cmd) => {
@@ -201,6 +247,11 @@ impl<C: SubstrateCli> Runner<C> {
 			Subcommand::Revert(cmd) => cmd.run(self.config, builder),
 			Subcommand::PurgeChain(cmd) => cmd.run(self.config),
 			Subcommand::ExportState(cmd) => cmd.run(self.config, builder),
+			Subcommand::ImportState(cmd) => cmd.run(self.config, builder),
+			Subcommand::CompactDb(cmd) => cmd.run(self.config),
+			Subcommand::Backup(cmd) => cmd.run::<BB>(self.config),
+			Subcommand::InspectBlock(cmd) => cmd.run::<BB>(self.config),
+			Subcommand::Prune(cmd) => cmd.run::<BB>(self.config),
 		}
 	}
 
@@ -209,6 +260,9 @@ impl<C: SubstrateCli> Runner<C> {
 		F: FnOnce(Configuration) -> std::result::Result<T, sc_service::error::Error>,
 		T: AbstractService + Unpin,
 	{
+		// The service drops the configuration early, while a temporary base path must only be
+		// removed once the node stopped.
+		let base_path = self.config.base_path.clone();
 		let service = service_builder(self.config)?;
 
 		let informant_future = sc_informant::build(&service, sc_informant::OutputFormat::Coloured);
@@ -230,12 +284,18 @@ impl<C: SubstrateCli> Runner<C> {
 		// to all the tasks. Dropping `tokio_runtime` will block the thread until all tasks have
 		// shut down.
 		drop(self.tokio_runtime);
+		drop(base_path);
 
 		Ok(())
 	}
 
 	/// A helper function that runs a command with the configuration of this node
 	pub fn sync_run(self, runner: impl FnOnce(Configuration) -> Result<()>) -> Result<()> {
+		if self.dry_run {
+			self.print_configuration();
+			return Ok(());
+		}
+
 		runner(self.config)
 	}
 
@@ -245,9 +305,51 @@ impl<C: SubstrateCli> Runner<C> {
 	where
 		FUT: Future<Output = Result<()>>,
 	{
+		if self.dry_run {
+			self.print_configuration();
+			return Ok(());
+		}
+
 		run_until_exit(self.tokio_runtime, runner(self.config))
 	}
 
+	/// Print the resolved configuration, used instead of running the command with `--dry-run`.
+	fn print_configuration(&self) {
+		let config = &self.config;
+		let join = |items: Vec<String>| if items.is_empty() { "<none>".into() } else { items.join(", ") };
+
+		println!("Chain specification: {} ({})", config.chain_spec.name(), config.chain_spec.id());
+		println!("Base path: {}", config.base_path.as_ref()
+			.map_or_else(|| "<none>".to_owned(), |p| p.path().display().to_string()),
+		);
+		println!("Chain data directory: {}", config.network.net_config_path
+			.as_ref()
+			.and_then(|p| p.parent())
+			.map_or_else(|| "<unknown>".to_owned(), |p| p.display().to_string()),
+		);
+		println!("Database: {} at {}",
+			config.database,
+			config.database.path().map_or_else(|| "<unknown>".to_owned(), |p| p.display().to_string())
+		);
+		println!("Role: {}", config.display_role());
+		println!("Force authoring: {}", config.force_authoring);
+		println!("Node name: {}", config.network.node_name);
+		println!("Network config path: {}", config.network.net_config_path.as_ref().map_or_else(
+			|| "<none>".to_owned(),
+			|p| p.display().to_string(),
+		));
+		println!("Listen addresses: {}", join(
+			config.network.listen_addresses.iter().map(ToString::to_string).collect(),
+		));
+		println!("Boot nodes: {}", join(
+			config.network.boot_nodes.iter().map(ToString::to_string).collect(),
+		));
+		println!("Reserved nodes: {}", join(
+			config.network.reserved_nodes.iter().map(ToString::to_string).collect(),
+		));
+		println!("Peers: {} in, {} out", config.network.in_peers, config.network.out_peers);
+	}
+
 	/// Get an immutable reference to the node Configuration
 	pub fn config(&self) -> &Configuration {
 		&self.config
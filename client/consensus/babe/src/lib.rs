@@ -976,6 +976,9 @@ impl<Block, Client, Inner> BlockImport<Block> for BabeBlockImport<Block, Client,
 		// early exit if block already in chain, otherwise the check for
 		// epoch changes will error when trying to re-import an epoch change
 		match self.client.status(BlockId::Hash(hash)) {
+			// blocks being checked are only executed again, the epoch changes are left untouched
+			Ok(sp_blockchain::BlockStatus::InChain) if block.check_only =>
+				return self.inner.import_block(block, new_cache).map_err(Into::into),
 			Ok(sp_blockchain::BlockStatus::InChain) => return Ok(ImportResult::AlreadyInChain),
 			Ok(sp_blockchain::BlockStatus::Unknown) => {},
 			Err(e) => return Err(ConsensusError::ClientImport(e.to_string())),
@@ -38,6 +38,7 @@ mod children;
 mod cache;
 mod changes_tries_storage;
 mod storage_cache;
+mod state_import;
 #[cfg(any(feature = "kvdb-rocksdb", test))]
 mod upgrade;
 mod utils;
@@ -90,6 +91,9 @@ use log::{trace, debug, warn};
 // Re-export the Database trait so that one can pass an implementation of it.
 pub use sp_database::Database;
 pub use sc_state_db::PruningMode;
+pub use utils::{NUM_COLUMNS, meta_keys, column_family_name, check_pruning_mode};
+pub use sc_state_db::meta_keys as state_meta_keys;
+pub use state_import::StateImport;
 
 #[cfg(any(feature = "kvdb-rocksdb", test))]
 pub use bench::BenchmarkingState;
@@ -99,6 +103,10 @@ const MIN_BLOCKS_TO_KEEP_CHANGES_TRIES_FOR: u32 = 32768;
 /// Default value for storage cache child ratio.
 const DEFAULT_CHILD_RATIO: (usize, usize) = (1, 10);
 
+/// Maximum number of bodies older than the kept blocks removed per finalized block, when body
+/// pruning was just enabled or lowered on an existing database.
+const MAX_PRUNED_OLD_BODIES: u32 = 1024;
+
 /// DB-backed patricia trie state, transaction type is an overlay of changes to commit.
 pub type DbState<B> = sp_state_machine::TrieBackend<
 	Arc<dyn sp_state_machine::Storage<HashFor<B>>>, HashFor<B>
@@ -266,10 +274,21 @@ pub struct DatabaseSettings {
 	pub state_cache_child_ratio: Option<(usize, usize)>,
 	/// Pruning mode.
 	pub pruning: PruningMode,
+	/// Block body pruning mode.
+	pub keep_blocks: KeepBlocks,
 	/// Where to find the database.
 	pub source: DatabaseSettingsSrc,
 }
 
+/// Which block bodies to keep in the database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepBlocks {
+	/// Keep the bodies of all blocks.
+	All,
+	/// Keep the bodies of the given number of last finalized blocks.
+	Some(u32),
+}
+
 /// Where to find the database..
 #[derive(Clone)]
 pub enum DatabaseSettingsSrc {
@@ -314,6 +333,15 @@ impl DatabaseSettingsSrc {
 			_ => false,
 		}
 	}
+	/// Check if the backend of the database was compiled in.
+	pub fn is_enabled(&self) -> bool {
+		match self {
+			DatabaseSettingsSrc::RocksDb { .. } => cfg!(any(feature = "kvdb-rocksdb", test)),
+			DatabaseSettingsSrc::ParityDb { .. } => cfg!(feature = "parity-db"),
+			DatabaseSettingsSrc::SubDb { .. } => cfg!(feature = "subdb"),
+			DatabaseSettingsSrc::Custom(_) => true,
+		}
+	}
 }
 
 impl std::fmt::Display for DatabaseSettingsSrc {
@@ -328,19 +356,29 @@ impl std::fmt::Display for DatabaseSettingsSrc {
 	}
 }
 
-pub(crate) mod columns {
+/// Columns of the full client database.
+pub mod columns {
+	/// Database metadata, see `meta_keys`.
 	pub const META: u32 = crate::utils::COLUMN_META;
+	/// Trie nodes of the state.
 	pub const STATE: u32 = 1;
+	/// Metadata of the state database.
 	pub const STATE_META: u32 = 2;
 	/// maps hashes to lookup keys and numbers to canon hashes.
 	pub const KEY_LOOKUP: u32 = 3;
+	/// Block headers, by lookup key.
 	pub const HEADER: u32 = 4;
+	/// Block bodies, by lookup key.
 	pub const BODY: u32 = 5;
+	/// Block justifications, by lookup key.
 	pub const JUSTIFICATION: u32 = 6;
+	/// Changes tries nodes.
 	pub const CHANGES_TRIE: u32 = 7;
+	/// Auxiliary data.
 	pub const AUX: u32 = 8;
 	/// Offchain workers local storage
 	pub const OFFCHAIN: u32 = 9;
+	/// Caches of the client.
 	pub const CACHE: u32 = 10;
 }
 
@@ -777,6 +815,7 @@ pub struct Backend<Block: BlockT> {
 	shared_cache: SharedCache<Block>,
 	import_lock: Arc<RwLock<()>>,
 	is_archive: bool,
+	keep_blocks: KeepBlocks,
 	io_stats: FrozenForDuration<(kvdb::IoStats, StateUsageInfo)>,
 	state_usage: Arc<StateUsageStats>,
 }
@@ -799,6 +838,7 @@ impl<Block: BlockT> Backend<Block> {
 			state_cache_size: 16777216,
 			state_cache_child_ratio: Some((50, 100)),
 			pruning: PruningMode::keep_blocks(keep_blocks),
+			keep_blocks: KeepBlocks::All,
 			source: DatabaseSettingsSrc::Custom(db),
 		};
 
@@ -855,6 +895,7 @@ impl<Block: BlockT> Backend<Block> {
 			),
 			import_lock: Default::default(),
 			is_archive: is_archive_pruning,
+			keep_blocks: config.keep_blocks,
 			io_stats: FrozenForDuration::new(std::time::Duration::from_secs(1)),
 			state_usage: Arc::new(StateUsageStats::new()),
 		})
@@ -1312,6 +1353,7 @@ impl<Block: BlockT> Backend<Block> {
 		}
 
 		let new_displaced = self.blockchain.leaves.write().finalize_height(f_num);
+		self.prune_block_bodies(transaction, f_num, &new_displaced)?;
 		match displaced {
 			x @ &mut None => *x = Some(new_displaced),
 			&mut Some(ref mut displaced) => displaced.merge(new_displaced),
@@ -1319,6 +1361,77 @@ impl<Block: BlockT> Backend<Block> {
 
 		Ok(())
 	}
+
+	/// Remove the bodies of the finalized blocks falling out of the kept blocks when `f_num` is
+	/// finalized, and of the forks its finalization displaced.
+	fn prune_block_bodies(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		f_num: NumberFor<Block>,
+		displaced: &FinalizationDisplaced<Block::Hash, NumberFor<Block>>,
+	) -> ClientResult<()> {
+		let keep_blocks = match self.keep_blocks {
+			KeepBlocks::All => return Ok(()),
+			KeepBlocks::Some(keep_blocks) => NumberFor::<Block>::from(keep_blocks),
+		};
+
+		// The displaced forks can't be finalized anymore, follow them back to the canonical chain.
+		for leaf in displaced.leaves() {
+			let mut hash = *leaf;
+			while let Some(header) = self.blockchain.header(BlockId::Hash(hash))? {
+				let number = *header.number();
+				if self.blockchain.hash(number)? == Some(hash) {
+					break;
+				}
+				let lookup_key = utils::number_and_hash_to_lookup_key(number, hash)?;
+				transaction.remove(columns::BODY, &lookup_key);
+				hash = *header.parent_hash();
+			}
+		}
+
+		if f_num < keep_blocks {
+			return Ok(());
+		}
+		let number = f_num - keep_blocks;
+
+		// The canonical bodies are removed from the oldest one up, so the kept ones are always
+		// above the removed ones. Older bodies are only left when body pruning was enabled or
+		// lowered on an existing database: remove them a bounded number at a time.
+		let mut first = number;
+		if !number.is_zero() && self.canonical_body_key(number - One::one())?.is_some() {
+			let (mut low, mut high) = (Zero::zero(), number - One::one());
+			while low < high {
+				let middle = low + (high - low) / 2u32.into();
+				match self.canonical_body_key(middle)? {
+					Some(_) => high = middle,
+					None => low = middle + One::one(),
+				}
+			}
+			first = low;
+		}
+		let last = std::cmp::min(number, first + (MAX_PRUNED_OLD_BODIES - 1).into());
+
+		let mut current = first;
+		while current <= last {
+			if let Some(key) = self.canonical_body_key(current)? {
+				transaction.remove(columns::BODY, &key);
+			}
+			current = current + One::one();
+		}
+
+		Ok(())
+	}
+
+	/// The lookup key of the canonical block at `number`, if its body is stored.
+	fn canonical_body_key(&self, number: NumberFor<Block>) -> ClientResult<Option<Vec<u8>>> {
+		let hash = match self.blockchain.hash(number)? {
+			Some(hash) => hash,
+			None => return Ok(None),
+		};
+		let key = utils::number_and_hash_to_lookup_key(number, hash)?;
+
+		Ok(self.storage.db.get(columns::BODY, &key).map(|_| key))
+	}
 }
 
 fn apply_state_commit(transaction: &mut Transaction<DbHash>, commit: sc_state_db::CommitSet<Vec<u8>>) {
@@ -1777,6 +1890,7 @@ pub(crate) mod tests {
 			state_cache_size: 16777216,
 			state_cache_child_ratio: Some((50, 100)),
 			pruning: PruningMode::keep_blocks(1),
+			keep_blocks: KeepBlocks::All,
 			source: DatabaseSettingsSrc::Custom(backing),
 		}, 0).unwrap();
 		assert_eq!(backend.blockchain().info().best_number, 9);
@@ -2297,4 +2411,105 @@ pub(crate) mod tests {
 			backend.commit_operation(op).unwrap_err();
 		}
 	}
+
+	#[test]
+	fn finalization_prunes_block_bodies() {
+		let backend = Backend::<Block>::new(DatabaseSettings {
+			state_cache_size: 16777216,
+			state_cache_child_ratio: Some((50, 100)),
+			pruning: PruningMode::keep_blocks(10),
+			keep_blocks: KeepBlocks::Some(2),
+			source: DatabaseSettingsSrc::Custom(sp_database::as_database(
+				kvdb_memorydb::create(crate::utils::NUM_COLUMNS),
+			)),
+		}, 0).unwrap();
+
+		let mut parent = Default::default();
+		for number in 0..5 {
+			parent = insert_header(&backend, number, parent, None, Default::default());
+		}
+		for number in 1..5 {
+			backend.finalize_block(BlockId::Number(number), None).unwrap();
+		}
+
+		let body = |number| backend.blockchain().body(BlockId::Number(number)).unwrap();
+		assert_eq!(body(0), None);
+		assert_eq!(body(2), None);
+		assert_eq!(body(3), Some(Vec::new()));
+		assert_eq!(body(4), Some(Vec::new()));
+		// Headers are kept.
+		assert!(backend.blockchain().header(BlockId::Number(0)).unwrap().is_some());
+	}
+
+	#[test]
+	fn finalization_prunes_bodies_of_displaced_forks() {
+		let backend = Backend::<Block>::new(DatabaseSettings {
+			state_cache_size: 16777216,
+			state_cache_child_ratio: Some((50, 100)),
+			pruning: PruningMode::keep_blocks(10),
+			keep_blocks: KeepBlocks::Some(10),
+			source: DatabaseSettingsSrc::Custom(sp_database::as_database(
+				kvdb_memorydb::create(crate::utils::NUM_COLUMNS),
+			)),
+		}, 0).unwrap();
+
+		let mut parent = Default::default();
+		let mut canonical = Vec::new();
+		let mut forks = Vec::new();
+		for number in 0..5 {
+			if number == 2 {
+				// A fork of two blocks, the canonical blocks are then imported as best.
+				let fork1 = insert_header(&backend, 2, parent, None, H256::from([1; 32]));
+				let fork2 = insert_header(&backend, 3, fork1, None, Default::default());
+				forks.extend(vec![fork1, fork2]);
+			}
+			parent = insert_header(&backend, number, parent, None, Default::default());
+			canonical.push(parent);
+		}
+		for number in 1..5 {
+			backend.finalize_block(BlockId::Number(number), None).unwrap();
+		}
+
+		let body = |hash| backend.blockchain().body(BlockId::Hash(hash)).unwrap();
+		for hash in forks {
+			assert_eq!(body(hash), None);
+		}
+		for hash in canonical {
+			assert_eq!(body(hash), Some(Vec::new()));
+		}
+	}
+
+	#[test]
+	fn enabling_body_pruning_prunes_older_bodies() {
+		let db = sp_database::as_database(kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
+		let settings = |keep_blocks| DatabaseSettings {
+			state_cache_size: 16777216,
+			state_cache_child_ratio: Some((50, 100)),
+			pruning: PruningMode::keep_blocks(10),
+			keep_blocks,
+			source: DatabaseSettingsSrc::Custom(db.clone()),
+		};
+
+		let mut parent = Default::default();
+		{
+			let backend = Backend::<Block>::new(settings(KeepBlocks::All), 0).unwrap();
+			for number in 0..10 {
+				parent = insert_header(&backend, number, parent, None, Default::default());
+			}
+			for number in 1..10 {
+				backend.finalize_block(BlockId::Number(number), None).unwrap();
+			}
+		}
+
+		let backend = Backend::<Block>::new(settings(KeepBlocks::Some(2)), 0).unwrap();
+		insert_header(&backend, 10, parent, None, Default::default());
+		backend.finalize_block(BlockId::Number(10), None).unwrap();
+
+		let body = |number| backend.blockchain().body(BlockId::Number(number)).unwrap();
+		for number in 0..9 {
+			assert_eq!(body(number), None);
+		}
+		assert_eq!(body(9), Some(Vec::new()));
+		assert_eq!(body(10), Some(Vec::new()));
+	}
 }
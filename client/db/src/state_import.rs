@@ -0,0 +1,315 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Import of a whole state into the state column of a new database.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use codec::Encode;
+use hash_db::{AsHashDB, HashDB, Hasher, Prefix};
+use sp_core::storage::{ChildInfo, well_known_keys};
+use sp_database::Transaction;
+use sp_runtime::traits::{Block as BlockT, Hash as HashT, HashFor};
+use sp_state_machine::{TrieDBMut, TrieMut};
+use sp_trie::{DBValue, KeySpacedDBMut, PrefixedMemoryDB, prefixed_key};
+use crate::{columns, Database, DbHash, DatabaseSettings};
+use crate::utils::{DatabaseType, meta_keys, open_database};
+
+/// Number of key/value pairs inserted in the tries per database write.
+const BATCH_SIZE: usize = 10_000;
+
+/// Writes the tries of a state straight into the state column of a new database.
+///
+/// The pairs are inserted in bounded batches, and only the pairs of a batch and the trie nodes
+/// they touch are held in memory, so the state doesn't need to fit in memory. The nodes are not
+/// part of any block: the state must then be committed as the state of the genesis block, with
+/// the root returned by `finish`.
+pub struct StateImport<Block: BlockT> {
+	db: Arc<dyn Database<DbHash>>,
+	/// Root of the top trie, `None` while it is empty.
+	root: Option<Block::Hash>,
+	/// Roots of the default child tries by storage key.
+	child_roots: BTreeMap<Vec<u8>, Block::Hash>,
+	/// The changes trie configuration found in the top trie, if any.
+	changes_trie_config: Option<Vec<u8>>,
+	/// Pairs not inserted yet, with the storage key of their child trie.
+	pending: Vec<(Option<Vec<u8>>, Vec<u8>, Vec<u8>)>,
+}
+
+impl<Block: BlockT> StateImport<Block> {
+	/// Open the database of `settings`, which must not have a genesis block yet.
+	pub fn new(settings: &DatabaseSettings) -> sp_blockchain::Result<Self> {
+		let db = open_database::<Block>(settings, DatabaseType::Full)?;
+		if db.get(columns::META, meta_keys::GENESIS_HASH).is_some() {
+			return Err(sp_blockchain::Error::Backend(
+				"The state can only be imported in a database without a genesis block".into(),
+			));
+		}
+
+		Ok(StateImport {
+			db,
+			root: None,
+			child_roots: BTreeMap::new(),
+			changes_trie_config: None,
+			pending: Vec::with_capacity(BATCH_SIZE),
+		})
+	}
+
+	/// Insert a key/value pair of the top trie.
+	pub fn insert(&mut self, key: Vec<u8>, value: Vec<u8>) -> sp_blockchain::Result<()> {
+		if well_known_keys::is_child_storage_key(&key) {
+			return Err(sp_blockchain::Error::GenesisInvalid);
+		}
+		if key == well_known_keys::CHANGES_TRIE_CONFIG {
+			self.changes_trie_config = Some(value.clone());
+		}
+
+		self.push(None, key, value)
+	}
+
+	/// Insert a key/value pair of the default child trie with the given storage key.
+	pub fn insert_child(
+		&mut self,
+		storage_key: Vec<u8>,
+		key: Vec<u8>,
+		value: Vec<u8>,
+	) -> sp_blockchain::Result<()> {
+		self.push(Some(storage_key), key, value)
+	}
+
+	/// Write the pending pairs and the roots of the child tries to the database.
+	///
+	/// Returns the state root and the encoded changes trie configuration of the state, if any.
+	pub fn finish(mut self) -> sp_blockchain::Result<(Block::Hash, Option<Vec<u8>>)> {
+		self.flush()?;
+
+		// The roots are only final once all the pairs of the child tries are inserted.
+		let child_roots = std::mem::take(&mut self.child_roots);
+		for (storage_key, root) in child_roots {
+			let prefixed_storage_key = ChildInfo::new_default_from_vec(storage_key)
+				.into_prefixed_storage_key()
+				.into_inner();
+			self.pending.push((None, prefixed_storage_key, root.encode()));
+		}
+		self.flush()?;
+
+		let root = self.root
+			.unwrap_or_else(|| <HashFor<Block> as HashT>::trie_root(Vec::new()));
+		Ok((root, self.changes_trie_config))
+	}
+
+	fn push(
+		&mut self,
+		storage_key: Option<Vec<u8>>,
+		key: Vec<u8>,
+		value: Vec<u8>,
+	) -> sp_blockchain::Result<()> {
+		self.pending.push((storage_key, key, value));
+		if self.pending.len() >= BATCH_SIZE {
+			self.flush()?;
+		}
+
+		Ok(())
+	}
+
+	/// Insert the pending pairs in their tries and write the nodes they changed.
+	fn flush(&mut self) -> sp_blockchain::Result<()> {
+		let mut pending = std::mem::take(&mut self.pending);
+		// Group the pairs by trie. The sort is stable, so a key set twice keeps its last value.
+		pending.sort_by(|a, b| a.0.cmp(&b.0));
+
+		let mut overlay = Overlay::<HashFor<Block>>::new(&*self.db);
+		let mut pairs = pending.drain(..).peekable();
+		while let Some((storage_key, key, value)) = pairs.next() {
+			let child_info = storage_key.map(ChildInfo::new_default_from_vec);
+			let root = match &child_info {
+				Some(child_info) => self.child_roots.get(child_info.storage_key()).cloned(),
+				None => self.root,
+			};
+
+			let new_root = {
+				let mut keyspaced;
+				let db: &mut dyn HashDB<HashFor<Block>, DBValue> = match &child_info {
+					Some(child_info) => {
+						keyspaced = KeySpacedDBMut::new(&mut overlay, child_info.keyspace());
+						&mut keyspaced
+					},
+					None => &mut overlay,
+				};
+
+				let mut new_root = root.unwrap_or_default();
+				let mut trie = match root {
+					Some(_) => TrieDBMut::<HashFor<Block>>::from_existing(db, &mut new_root)
+						.map_err(trie_error)?,
+					None => TrieDBMut::<HashFor<Block>>::new(db, &mut new_root),
+				};
+				trie.insert(&key, &value).map_err(trie_error)?;
+				while pairs.peek().map_or(false, |(next, _, _)| {
+					next.as_deref() == child_info.as_ref().map(|info| info.storage_key())
+				}) {
+					let (_, key, value) = pairs.next().expect("peeked above; qed");
+					trie.insert(&key, &value).map_err(trie_error)?;
+				}
+				drop(trie);
+				new_root
+			};
+
+			match child_info {
+				Some(child_info) => {
+					self.child_roots.insert(child_info.storage_key().to_vec(), new_root);
+				},
+				None => self.root = Some(new_root),
+			}
+		}
+		drop(pairs);
+		overlay.commit();
+
+		// Reuse the allocation of the batch.
+		self.pending = pending;
+		Ok(())
+	}
+}
+
+fn trie_error<E: std::fmt::Debug>(err: E) -> sp_blockchain::Error {
+	sp_blockchain::Error::Backend(format!("Failed to import the state: {:?}", err))
+}
+
+/// The trie nodes changed by a batch, over the ones already written to the state column.
+struct Overlay<'a, H: Hasher> {
+	db: &'a dyn Database<DbHash>,
+	changes: PrefixedMemoryDB<H>,
+}
+
+impl<'a, H: Hasher> Overlay<'a, H> {
+	fn new(db: &'a dyn Database<DbHash>) -> Self {
+		Overlay { db, changes: Default::default() }
+	}
+
+	/// Write the inserted nodes and delete the removed ones.
+	fn commit(mut self) {
+		let mut transaction = Transaction::new();
+		for (key, (value, rc)) in self.changes.drain() {
+			if rc > 0 {
+				transaction.set_from_vec(columns::STATE, &key, value);
+			} else if rc < 0 {
+				transaction.remove(columns::STATE, &key);
+			}
+		}
+		self.db.commit(transaction);
+	}
+}
+
+impl<'a, H: Hasher> HashDB<H, DBValue> for Overlay<'a, H> {
+	fn get(&self, key: &H::Out, prefix: Prefix) -> Option<DBValue> {
+		HashDB::get(&self.changes, key, prefix)
+			.or_else(|| self.db.get(columns::STATE, &prefixed_key::<H>(key, prefix)))
+	}
+
+	fn contains(&self, key: &H::Out, prefix: Prefix) -> bool {
+		HashDB::get(self, key, prefix).is_some()
+	}
+
+	fn insert(&mut self, prefix: Prefix, value: &[u8]) -> H::Out {
+		HashDB::insert(&mut self.changes, prefix, value)
+	}
+
+	fn emplace(&mut self, key: H::Out, prefix: Prefix, value: DBValue) {
+		HashDB::emplace(&mut self.changes, key, prefix, value)
+	}
+
+	fn remove(&mut self, key: &H::Out, prefix: Prefix) {
+		HashDB::remove(&mut self.changes, key, prefix)
+	}
+}
+
+impl<'a, H: Hasher> AsHashDB<H, DBValue> for Overlay<'a, H> {
+	fn as_hash_db(&self) -> &dyn HashDB<H, DBValue> {
+		self
+	}
+
+	fn as_hash_db_mut<'b>(&'b mut self) -> &'b mut (dyn HashDB<H, DBValue> + 'b) {
+		self
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_runtime::traits::BlakeTwo256;
+	use sp_trie::{Trie, trie_types::TrieDB};
+	use crate::DatabaseSettingsSrc;
+	use crate::tests::Block;
+
+	fn settings() -> DatabaseSettings {
+		DatabaseSettings {
+			state_cache_size: 0,
+			state_cache_child_ratio: None,
+			pruning: crate::PruningMode::ArchiveAll,
+			keep_blocks: crate::KeepBlocks::All,
+			source: DatabaseSettingsSrc::Custom(
+				sp_database::as_database(kvdb_memorydb::create(crate::NUM_COLUMNS)),
+			),
+		}
+	}
+
+	#[test]
+	fn imported_state_has_the_root_of_the_state() {
+		let settings = settings();
+		let mut import = StateImport::<Block>::new(&settings).unwrap();
+		let mut top = BTreeMap::new();
+		// Several batches of top pairs, with a key set twice.
+		import.insert(0u32.encode(), b"overwritten".to_vec()).unwrap();
+		for i in 0..BATCH_SIZE as u32 * 2 + 7 {
+			let (key, value) = (i.encode(), vec![1; (i % 50) as usize + 1]);
+			import.insert(key.clone(), value.clone()).unwrap();
+			top.insert(key, value);
+		}
+		import.insert_child(b"child".to_vec(), b"key".to_vec(), b"value".to_vec()).unwrap();
+		let (root, changes_trie_config) = import.finish().unwrap();
+
+		let child_root = BlakeTwo256::trie_root(vec![(b"key".to_vec(), b"value".to_vec())]);
+		let child_key = ChildInfo::new_default(b"child").prefixed_storage_key().into_inner();
+		top.insert(child_key, child_root.encode());
+		assert_eq!(root, BlakeTwo256::trie_root(top.into_iter().collect()));
+		assert_eq!(changes_trie_config, None);
+
+		let db = match &settings.source {
+			DatabaseSettingsSrc::Custom(db) => db.clone(),
+			_ => unreachable!(),
+		};
+		let overlay = Overlay::<BlakeTwo256>::new(&*db);
+		let state: &dyn HashDB<BlakeTwo256, DBValue> = &overlay;
+		let trie = TrieDB::<BlakeTwo256>::new(&state, &root).unwrap();
+		assert_eq!(trie.get(&0u32.encode()).unwrap(), Some(vec![1]));
+	}
+
+	#[test]
+	fn empty_state_has_the_empty_root() {
+		let import = StateImport::<Block>::new(&settings()).unwrap();
+
+		assert_eq!(import.finish().unwrap().0, BlakeTwo256::trie_root(Vec::new()));
+	}
+
+	#[test]
+	fn child_storage_keys_are_rejected_in_the_top_trie() {
+		let mut import = StateImport::<Block>::new(&settings()).unwrap();
+		let key = ChildInfo::new_default(b"child").prefixed_storage_key().into_inner();
+
+		assert!(import.insert(key, Vec::new()).is_err());
+	}
+}
@@ -32,11 +32,11 @@ use sp_runtime::traits::{
 	Block as BlockT, Header as HeaderT, Zero,
 	UniqueSaturatedFrom, UniqueSaturatedInto,
 };
+use sc_state_db::{PruningMode, meta_keys as state_meta_keys};
 use crate::{DatabaseSettings, DatabaseSettingsSrc, Database, DbHash};
 
 /// Number of columns in the db. Must be the same for both full && light dbs.
 /// Otherwise RocksDb will fail to open database && check its type.
-#[cfg(any(feature = "kvdb-rocksdb", feature = "test-helpers", test))]
 pub const NUM_COLUMNS: u32 = 11;
 /// Meta column. The set of keys in the column is shared by full && light storages.
 pub const COLUMN_META: u32 = 0;
@@ -61,6 +61,11 @@ pub mod meta_keys {
 	pub const CHILDREN_PREFIX: &[u8; 8] = b"children";
 }
 
+/// Name of the RocksDB column family that stores the given column.
+pub fn column_family_name(column: u32) -> String {
+	format!("col{}", column)
+}
+
 /// Database metadata.
 #[derive(Debug)]
 pub struct Meta<N, H> {
@@ -304,6 +309,82 @@ pub fn check_database_type(db: &dyn Database<DbHash>, db_type: DatabaseType) ->
 	Ok(())
 }
 
+/// Check that `pruning` is the state pruning mode the database at `source` was created with.
+///
+/// The state database doesn't open with another mode than the one it recorded on creation. With
+/// `unsafe_pruning`, the database is switched to `pruning` instead, keeping the state it has. A
+/// pruned database is never switched to an archive mode since the state it pruned is gone.
+pub fn check_pruning_mode(
+	source: &DatabaseSettingsSrc,
+	pruning: &PruningMode,
+	unsafe_pruning: bool,
+) -> sp_blockchain::Result<()> {
+	match source {
+		// Only RocksDB databases that already exist have a mode to compare.
+		#[cfg(any(feature = "kvdb-rocksdb", test))]
+		DatabaseSettingsSrc::RocksDb { path, .. } if path.join("CURRENT").exists() => {
+			let path = path.to_str()
+				.ok_or_else(|| sp_blockchain::Error::Backend("Invalid database path".into()))?;
+			let db_config = kvdb_rocksdb::DatabaseConfig::with_columns(NUM_COLUMNS);
+			let db = kvdb_rocksdb::Database::open(&db_config, &path)
+				.map_err(|err| sp_blockchain::Error::Backend(format!("{}", err)))?;
+			check_stored_pruning_mode(&*sp_database::as_database(db), pruning, unsafe_pruning)
+		},
+		DatabaseSettingsSrc::Custom(db) => check_stored_pruning_mode(&**db, pruning, unsafe_pruning),
+		_ => Ok(()),
+	}
+}
+
+fn check_stored_pruning_mode(
+	db: &dyn Database<DbHash>,
+	pruning: &PruningMode,
+	unsafe_pruning: bool,
+) -> sp_blockchain::Result<()> {
+	let created_with = match db.get(crate::columns::STATE_META, state_meta_keys::PRUNING_MODE) {
+		Some(mode) if mode != pruning.id() => mode,
+		_ => return Ok(()),
+	};
+
+	let pruned = created_with == PruningMode::keep_blocks(0).id();
+	if pruned && pruning.is_archive() {
+		return Err(sp_blockchain::Error::Backend(format!(
+			"The database was created with {} and no longer has the state {} needs. Resync from \
+			scratch after `purge-chain` to run an archive node.",
+			describe_pruning_mode(&created_with),
+			describe_pruning_mode(pruning.id()),
+		)));
+	}
+	if !unsafe_pruning {
+		return Err(sp_blockchain::Error::Backend(format!(
+			"The database was created with {} but {} is requested. Restart with the `--pruning` \
+			value the database was created with, resync from scratch after `purge-chain`, or pass \
+			`--unsafe-pruning` to switch the database to the new mode.",
+			describe_pruning_mode(&created_with),
+			describe_pruning_mode(pruning.id()),
+		)));
+	}
+
+	log::warn!(
+		"Switching the database from {} to {}, the state it has is kept as it is.",
+		describe_pruning_mode(&created_with),
+		describe_pruning_mode(pruning.id()),
+	);
+	let mut transaction = Transaction::new();
+	transaction.set(crate::columns::STATE_META, state_meta_keys::PRUNING_MODE, pruning.id());
+	db.commit(transaction);
+	Ok(())
+}
+
+/// The `--pruning` value of a pruning mode recorded in the state metadata.
+fn describe_pruning_mode(id: &[u8]) -> String {
+	match id {
+		b"archive" => "`--pruning archive`".into(),
+		b"archive_canonical" => "`--pruning archive-canonical`".into(),
+		b"constrained" => "`--pruning <number of blocks>`".into(),
+		other => format!("the unknown pruning mode `{}`", String::from_utf8_lossy(other)),
+	}
+}
+
 /// Read database column entry for the given block.
 pub fn read_db<Block>(
 	db: &dyn Database<DbHash>,
@@ -439,4 +520,48 @@ mod tests {
 		assert_eq!(DatabaseType::Full.as_str(), "full");
 		assert_eq!(DatabaseType::Light.as_str(), "light");
 	}
+
+	fn database_with_mode(mode: &[u8]) -> DatabaseSettingsSrc {
+		let db = sp_database::as_database(kvdb_memorydb::create(NUM_COLUMNS));
+		let mut transaction = Transaction::new();
+		transaction.set(crate::columns::STATE_META, state_meta_keys::PRUNING_MODE, mode);
+		db.commit(transaction);
+		DatabaseSettingsSrc::Custom(db)
+	}
+
+	#[test]
+	fn check_pruning_mode_detects_a_change() {
+		let source = database_with_mode(b"archive");
+
+		assert!(check_pruning_mode(&source, &PruningMode::ArchiveAll, false).is_ok());
+		assert!(check_pruning_mode(&source, &PruningMode::keep_blocks(256), false).is_err());
+		assert!(check_pruning_mode(&source, &PruningMode::ArchiveCanonical, false).is_err());
+	}
+
+	#[test]
+	fn check_pruning_mode_switches_with_unsafe_pruning() {
+		let source = database_with_mode(b"archive");
+
+		assert!(check_pruning_mode(&source, &PruningMode::keep_blocks(256), true).is_ok());
+		assert!(check_pruning_mode(&source, &PruningMode::keep_blocks(256), false).is_ok());
+		assert!(check_pruning_mode(&source, &PruningMode::ArchiveAll, false).is_err());
+	}
+
+	#[test]
+	fn check_pruning_mode_never_switches_a_pruned_database_to_archive() {
+		let source = database_with_mode(b"constrained");
+
+		assert!(check_pruning_mode(&source, &PruningMode::ArchiveAll, true).is_err());
+		assert!(check_pruning_mode(&source, &PruningMode::ArchiveCanonical, true).is_err());
+		assert!(check_pruning_mode(&source, &PruningMode::keep_blocks(256), false).is_ok());
+	}
+
+	#[test]
+	fn check_pruning_mode_ignores_new_databases() {
+		let dir = tempfile::tempdir().unwrap();
+		let source = DatabaseSettingsSrc::RocksDb { path: dir.path().join("db"), cache_size: 128 };
+
+		assert!(check_pruning_mode(&source, &PruningMode::ArchiveAll, false).is_ok());
+		assert!(!dir.path().join("db").exists());
+	}
 }
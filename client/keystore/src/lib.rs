@@ -17,7 +17,7 @@
 //! Keystore (and session key management) for ed25519 based chains like Polkadot.
 
 #![warn(missing_docs)]
-use std::{collections::{HashMap, HashSet}, path::PathBuf, fs::{self, File}, io::{self, Write}, sync::Arc};
+use std::{collections::{HashMap, HashSet}, path::{Path, PathBuf}, fs::{self, File}, io::{self, Write}, sync::Arc};
 use sp_core::{
 	crypto::{IsWrappedBy, CryptoTypePublicPair, KeyTypeId, Pair as PairT, Protected, Public},
 	traits::{BareCryptoStore, BareCryptoStoreError as TraitError},
@@ -102,7 +102,7 @@ impl Store {
 	/// Optionally takes a password that will be used to encrypt/decrypt the keys.
 	pub fn open<T: Into<PathBuf>>(path: T, password: Option<Protected<String>>) -> Result<KeyStorePtr> {
 		let path = path.into();
-		fs::create_dir_all(&path)?;
+		create_keystore_dir(&path)?;
 
 		let instance = Self { path: Some(path), additional: HashMap::new(), password };
 		Ok(Arc::new(RwLock::new(instance)))
@@ -258,7 +258,9 @@ impl Store {
 	}
 
 	/// Returns the file path for the given public key and key type.
-	fn key_file_path(&self, public: &[u8], key_type: KeyTypeId) -> Option<PathBuf> {
+	///
+	/// `None` for an in-memory store.
+	pub fn key_file_path(&self, public: &[u8], key_type: KeyTypeId) -> Option<PathBuf> {
 		let mut buf = self.path.as_ref()?.clone();
 		let key_type = hex::encode(key_type.0);
 		let key = hex::encode(public);
@@ -428,6 +430,12 @@ impl BareCryptoStore for Store {
 	fn insert_unknown(&mut self, key_type: KeyTypeId, suri: &str, public: &[u8])
 		-> std::result::Result<(), ()>
 	{
+		// An in-memory store has no file to write to, it keeps the key with the ephemeral ones.
+		if self.path.is_none() {
+			self.additional.insert((key_type, public.to_vec()), suri.into());
+			return Ok(());
+		}
+
 		Store::insert_unknown(self, key_type, suri, public).map_err(|_| ())
 	}
 
@@ -440,6 +448,33 @@ impl BareCryptoStore for Store {
 	}
 }
 
+/// Create the directory of a keystore, only accessible by its owner, or warn if an existing one is
+/// accessible by other users.
+#[cfg(unix)]
+fn create_keystore_dir(path: &Path) -> io::Result<()> {
+	use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+	if !path.exists() {
+		return fs::DirBuilder::new().recursive(true).mode(0o700).create(path);
+	}
+
+	let mode = fs::metadata(path)?.permissions().mode() & 0o777;
+	if mode & 0o077 != 0 {
+		log::warn!(
+			"The keystore at {} is accessible by other users (mode {:o}), restrict it with `chmod 700`.",
+			path.display(),
+			mode,
+		);
+	}
+
+	Ok(())
+}
+
+#[cfg(not(unix))]
+fn create_keystore_dir(path: &Path) -> io::Result<()> {
+	fs::create_dir_all(path)
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -549,6 +584,35 @@ mod tests {
 		assert_eq!(key_pair.public(), store_key_pair.public());
 	}
 
+	#[test]
+	fn in_memory_store_keeps_unknown_keys() {
+		let store = Store::new_in_memory();
+		let key_pair = sr25519::AppPair::from_string("//Alice", None).expect("Generates key pair");
+		let public = key_pair.public().as_ref().to_vec();
+
+		assert!(!BareCryptoStore::has_keys(&*store.read(), &[(public.clone(), SR25519)]));
+		BareCryptoStore::insert_unknown(&mut *store.write(), SR25519, "//Alice", &public)
+			.expect("Inserts unknown key");
+
+		assert!(BareCryptoStore::has_keys(&*store.read(), &[(public, SR25519)]));
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn store_creates_a_private_directory() {
+		use std::os::unix::fs::PermissionsExt;
+
+		let temp_dir = TempDir::new().unwrap();
+		let path = temp_dir.path().join("chains").join("keystore");
+		Store::open(path.clone(), None).unwrap();
+
+		assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o700);
+		// An existing directory is left as it is.
+		fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+		Store::open(path.clone(), None).unwrap();
+		assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o755);
+	}
+
 	#[test]
 	fn store_ignores_files_with_invalid_name() {
 		let temp_dir = TempDir::new().unwrap();
@@ -747,6 +747,7 @@ impl<B: BlockT> ChainSync<B> {
 										origin: block_data.origin,
 										allow_missing_state: true,
 										import_existing: false,
+										check_only: false,
 									}
 								}).collect()
 						}
@@ -766,6 +767,7 @@ impl<B: BlockT> ChainSync<B> {
 									origin: Some(who.clone()),
 									allow_missing_state: true,
 									import_existing: false,
+									check_only: false,
 								}
 							}).collect()
 						}
@@ -851,6 +853,7 @@ impl<B: BlockT> ChainSync<B> {
 							origin: Some(who.clone()),
 							allow_missing_state: true,
 							import_existing: false,
+							check_only: false,
 						}
 					}).collect()
 				}
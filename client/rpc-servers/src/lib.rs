@@ -25,8 +25,8 @@ use jsonrpc_core::IoHandlerExtension;
 use log::error;
 use pubsub::PubSubMetadata;
 
-/// Maximal payload accepted by RPC servers.
-const MAX_PAYLOAD: usize = 15 * 1024 * 1024;
+/// Default maximal payload accepted by RPC servers, in MiB.
+const MAX_PAYLOAD: usize = 15;
 
 /// Default maximum number of connections for WS RPC servers.
 const WS_MAX_CONNECTIONS: usize = 100;
@@ -58,6 +58,11 @@ pub fn rpc_handler<M: PubSubMetadata>(
 	io
 }
 
+/// The maximal payload of `max_payload` MiB, or of the default if `None`, in bytes.
+fn max_payload_bytes(max_payload: Option<usize>) -> usize {
+	max_payload.unwrap_or(MAX_PAYLOAD).saturating_mul(1024 * 1024)
+}
+
 #[cfg(not(target_os = "unknown"))]
 mod inner {
 	use super::*;
@@ -66,12 +71,15 @@ mod inner {
 	pub type HttpServer = http::Server;
 	/// Type alias for ws server
 	pub type WsServer = ws::Server;
+	/// Type alias for ipc server
+	pub type IpcServer = ipc::Server;
 
 	/// Start HTTP server listening on given address.
 	///
 	/// **Note**: Only available if `not(target_os = "unknown")`.
 	pub fn start_http<M: pubsub::PubSubMetadata + Default>(
 		addr: &std::net::SocketAddr,
+		max_payload: Option<usize>,
 		cors: Option<&Vec<String>>,
 		io: RpcHandler<M>,
 	) -> io::Result<http::Server> {
@@ -85,7 +93,7 @@ mod inner {
 				http::RestApi::Unsecure
 			})
 			.cors(map_cors::<http::AccessControlAllowOrigin>(cors))
-			.max_request_body_size(MAX_PAYLOAD)
+			.max_request_body_size(max_payload_bytes(max_payload))
 			.start_http(addr)
 	}
 
@@ -94,12 +102,13 @@ mod inner {
 	/// **Note**: Only available if `not(target_os = "unknown")`.
 	pub fn start_ws<M: pubsub::PubSubMetadata + From<jsonrpc_core::futures::sync::mpsc::Sender<String>>> (
 		addr: &std::net::SocketAddr,
+		max_payload: Option<usize>,
 		max_connections: Option<usize>,
 		cors: Option<&Vec<String>>,
 		io: RpcHandler<M>,
 	) -> io::Result<ws::Server> {
 		ws::ServerBuilder::with_meta_extractor(io, |context: &ws::RequestContext| context.sender().into())
-			.max_payload(MAX_PAYLOAD)
+			.max_payload(max_payload_bytes(max_payload))
 			.max_connections(max_connections.unwrap_or(WS_MAX_CONNECTIONS))
 			.allowed_origins(map_cors(cors))
 			.allowed_hosts(hosts_filtering(cors.is_some()))
@@ -114,6 +123,73 @@ mod inner {
 			})
 	}
 
+	/// Start IPC server listening on the Unix domain socket, or the named pipe on Windows, at `path`.
+	///
+	/// A socket left behind by a server that stopped is replaced. The socket is only accessible to
+	/// its owner.
+	///
+	/// **Note**: Only available if `not(target_os = "unknown")`.
+	pub fn start_ipc<M: pubsub::PubSubMetadata + From<jsonrpc_core::futures::sync::mpsc::Sender<String>>> (
+		path: &std::path::Path,
+		io: RpcHandler<M>,
+	) -> io::Result<ipc::Server> {
+		#[cfg(unix)]
+		remove_stale_socket(path)?;
+
+		let builder = ipc::ServerBuilder::with_meta_extractor(
+			io,
+			|context: &ipc::RequestContext| context.sender.clone().into(),
+		);
+
+		// Anyone able to connect to the socket can call the methods it serves, so it is created
+		// accessible to its owner only rather than restricted once it is listening.
+		owner_only(|| builder.start(&path.to_string_lossy()))
+	}
+
+	/// Run `f` with a file mode creation mask giving access to the owner only.
+	///
+	/// The mask applies to the whole process, so it is restored as soon as `f` returns.
+	#[cfg(unix)]
+	fn owner_only<T>(f: impl FnOnce() -> T) -> T {
+		use nix::sys::stat::{umask, Mode};
+
+		let previous = umask(Mode::from_bits_truncate(0o177));
+		let result = f();
+		umask(previous);
+		result
+	}
+
+	#[cfg(not(unix))]
+	fn owner_only<T>(f: impl FnOnce() -> T) -> T {
+		f()
+	}
+
+	/// Remove the socket at `path` if no server accepts connections on it anymore.
+	#[cfg(unix)]
+	fn remove_stale_socket(path: &std::path::Path) -> io::Result<()> {
+		use std::os::unix::{fs::FileTypeExt, net::UnixStream};
+
+		match std::fs::symlink_metadata(path) {
+			Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+			Err(e) => Err(e),
+			Ok(metadata) if !metadata.file_type().is_socket() => Err(io::Error::new(
+				io::ErrorKind::AlreadyExists,
+				format!("{} exists and is not a socket", path.display()),
+			)),
+			Ok(_) => match UnixStream::connect(path) {
+				Ok(_) => Err(io::Error::new(
+					io::ErrorKind::AddrInUse,
+					format!("Another process serves {}", path.display()),
+				)),
+				Err(ref e) if e.kind() == io::ErrorKind::ConnectionRefused => {
+					log::warn!("Replacing the stale IPC socket {}", path.display());
+					std::fs::remove_file(path)
+				},
+				Err(e) => Err(e),
+			},
+		}
+	}
+
 	fn map_cors<T: for<'a> From<&'a str>>(
 		cors: Option<&Vec<String>>
 	) -> http::DomainsValidation<T> {
@@ -130,6 +206,31 @@ mod inner {
 			http::DomainsValidation::Disabled
 		}
 	}
+
+	#[cfg(all(test, unix))]
+	mod tests {
+		use super::*;
+		use std::os::unix::net::UnixListener;
+
+		#[test]
+		fn only_stale_sockets_are_removed() {
+			let dir = tempfile::tempdir().unwrap();
+			let path = dir.path().join("node.ipc");
+			assert!(remove_stale_socket(&path).is_ok());
+
+			let listener = UnixListener::bind(&path).unwrap();
+			assert_eq!(remove_stale_socket(&path).unwrap_err().kind(), io::ErrorKind::AddrInUse);
+
+			drop(listener);
+			assert!(path.exists());
+			remove_stale_socket(&path).unwrap();
+			assert!(!path.exists());
+
+			std::fs::write(&path, b"not a socket").unwrap();
+			assert!(remove_stale_socket(&path).is_err());
+			assert!(path.exists());
+		}
+	}
 }
 
 #[cfg(target_os = "unknown")]
@@ -25,7 +25,7 @@ use sc_client_api::{
 	self, BlockchainEvents, backend::RemoteBackend, light::RemoteBlockchain, execution_extensions::ExtensionsFactory,
 	ExecutorProvider, CallExecutor, ForkBlocks, BadBlocks, CloneableSpawn, UsageProvider,
 };
-use crate::client::{Client, ClientConfig};
+use crate::client::{Client, ClientConfig, genesis::construct_genesis_block};
 use sp_utils::mpsc::{tracing_unbounded, TracingUnboundedSender};
 use sc_chain_spec::get_extension;
 use sp_consensus::{
@@ -43,25 +43,25 @@ use sc_network::{NetworkService, NetworkStateInfo};
 use parking_lot::{Mutex, RwLock};
 use sp_runtime::generic::BlockId;
 use sp_runtime::traits::{
-	Block as BlockT, NumberFor, SaturatedConversion, HashFor,
+	Block as BlockT, Header as HeaderT, NumberFor, SaturatedConversion, HashFor, Zero,
 };
 use sp_api::ProvideRuntimeApi;
 use sc_executor::{NativeExecutor, NativeExecutionDispatch, RuntimeInfo};
 use std::{
 	collections::HashMap,
-	io::{Read, Write, Seek},
-	marker::PhantomData, sync::Arc, pin::Pin
+	io::{BufRead, Read, Write},
+	marker::PhantomData, sync::Arc, pin::Pin, path::PathBuf,
 };
 use wasm_timer::SystemTime;
 use sc_telemetry::{telemetry, SUBSTRATE_INFO};
 use sp_transaction_pool::{MaintainedTransactionPool, ChainEvent};
 use sp_blockchain;
 use prometheus_endpoint::Registry;
-use sc_client_db::{Backend, DatabaseSettings};
+use sc_client_db::{Backend, DatabaseSettings, StateImport};
 use sp_core::traits::CodeExecutor;
 use sp_runtime::BuildStorage;
 use sc_client_api::execution_extensions::ExecutionExtensions;
-use sp_core::storage::Storage;
+use sp_core::storage::{Storage, well_known_keys};
 
 pub type BackgroundTask = Pin<Box<dyn Future<Output=()> + Send>>;
 
@@ -254,6 +254,7 @@ fn new_full_parts<TBl, TRtApi, TExecDisp>(
 			state_cache_child_ratio:
 			config.state_cache_child_ratio.map(|v| (v, 100)),
 			pruning: config.pruning.clone(),
+			keep_blocks: config.keep_blocks,
 			source: config.database.clone(),
 		};
 
@@ -274,6 +275,7 @@ fn new_full_parts<TBl, TRtApi, TExecDisp>(
 			ClientConfig {
 				offchain_worker_enabled : config.offchain_worker.enabled ,
 				offchain_indexing_api: config.offchain_worker.indexing_enabled,
+				wasm_runtime_overrides: config.wasm_runtime_overrides.clone(),
 			},
 		)?
 	};
@@ -281,6 +283,87 @@ fn new_full_parts<TBl, TRtApi, TExecDisp>(
 	Ok((client, backend, keystore, task_manager))
 }
 
+/// Auxiliary storage key recording whether the blocks are imported with offchain indexing.
+const OFFCHAIN_INDEXING_KEY: &[u8] = b"offchain_indexing";
+
+/// Record whether the blocks are imported with offchain indexing, warning when it gets enabled on a
+/// database with blocks imported without it.
+///
+/// Only called by the commands importing blocks. Returns whether the warning was logged.
+pub(crate) fn record_offchain_indexing<Block, B>(
+	backend: &B,
+	enabled: bool,
+) -> sp_blockchain::Result<bool> where
+	Block: BlockT,
+	B: sc_client_api::backend::Backend<Block>,
+{
+	use sp_blockchain::HeaderBackend;
+
+	let recorded = backend.get_aux(OFFCHAIN_INDEXING_KEY)?.map(|value| value == [1]);
+	let best_number = backend.blockchain().info().best_number;
+	let unindexed = enabled && recorded != Some(true) && !best_number.is_zero();
+	if unindexed {
+		warn!(
+			"Offchain indexing is enabled on a database with blocks imported without it. The blocks \
+			up to #{} are not indexed, only the blocks imported from now on are.",
+			best_number,
+		);
+	}
+
+	if recorded != Some(enabled) {
+		backend.insert_aux(&[(OFFCHAIN_INDEXING_KEY, &[enabled as u8][..])], &[])?;
+	}
+	Ok(unindexed)
+}
+
+/// Number of blocks the state of which is kept unfinalized by the db-backed client.
+const CANONICALIZATION_DELAY: u64 = 4096;
+
+/// Create the database of `config` with a genesis block whose state is the one inserted by
+/// `import_state`, instead of the genesis of the chain spec.
+///
+/// The state is written to the database in bounded batches while it is inserted, so it doesn't
+/// need to fit in memory. Returns the state root of the genesis block.
+pub fn new_database_with_genesis_state<Block: BlockT>(
+	config: &Configuration,
+	import_state: impl FnOnce(&mut StateImport<Block>) -> Result<(), Error>,
+) -> Result<Block::Hash, Error> {
+	use sc_client_api::backend::{Backend as _, BlockImportOperation, NewBlockState};
+
+	let settings = DatabaseSettings {
+		state_cache_size: config.state_cache_size,
+		state_cache_child_ratio: config.state_cache_child_ratio.map(|v| (v, 100)),
+		pruning: config.pruning.clone(),
+		keep_blocks: config.keep_blocks,
+		source: config.database.clone(),
+	};
+
+	let mut import = StateImport::<Block>::new(&settings)?;
+	import_state(&mut import)?;
+	let (state_root, changes_trie_config) = import.finish()?;
+
+	let backend = Backend::<Block>::new(settings, CANONICALIZATION_DELAY)?;
+	let mut op = backend.begin_operation()?;
+	backend.begin_state_operation(&mut op, BlockId::Hash(Default::default()))?;
+	// The state is already in the database, only the changes trie configuration is taken from
+	// this storage. Its trie nodes are written too, but are not referenced by any block.
+	let mut storage = Storage::default();
+	if let Some(config) = changes_trie_config {
+		storage.top.insert(well_known_keys::CHANGES_TRIE_CONFIG.to_vec(), config);
+	}
+	op.reset_storage(storage)?;
+
+	let genesis_block = construct_genesis_block::<Block>(state_root);
+	info!(
+		"🔨 Initializing Genesis block with the imported state (state: {}, header-hash: {})",
+		genesis_block.header().state_root(),
+		genesis_block.header().hash(),
+	);
+	op.set_block_data(genesis_block.deconstruct().0, Some(vec![]), None, NewBlockState::Final)?;
+	backend.commit_operation(op)?;
+
+	Ok(state_root)
+}
 
 /// Create an instance of db-backed client.
 pub fn new_client<E, Block, RA>(
@@ -308,10 +391,8 @@ pub fn new_client<E, Block, RA>(
 		Block: BlockT,
 		E: CodeExecutor + RuntimeInfo,
 {
-	const CANONICALIZATION_DELAY: u64 = 4096;
-
 	let backend = Arc::new(Backend::new(settings, CANONICALIZATION_DELAY)?);
-	let executor = crate::client::LocalCallExecutor::new(backend.clone(), executor, spawn_handle, config.clone());
+	let executor = crate::client::LocalCallExecutor::try_new(backend.clone(), executor, spawn_handle, config.clone())?;
 	Ok((
 		crate::client::Client::new(
 			backend.clone(),
@@ -408,6 +489,7 @@ impl ServiceBuilder<(), (), (), (), (), (), (), (), (), (), ()> {
 				state_cache_child_ratio:
 					config.state_cache_child_ratio.map(|v| (v, 100)),
 				pruning: config.pruning.clone(),
+				keep_blocks: config.keep_blocks,
 				source: config.database.clone(),
 			};
 			sc_client_db::light::LightStorage::new(db_settings)?
@@ -827,11 +909,17 @@ pub trait ServiceBuilderCommand {
 	/// Native execution dispatch required by some commands.
 	type NativeDispatch: NativeExecutionDispatch + 'static;
 	/// Starts the process of importing blocks.
+	///
+	/// The input is read as a stream, so it can be a pipe. With a `checkpoint` file, the last
+	/// persisted block is recorded periodically and the blocks up to it are skipped on restart.
+	/// `memory_budget` caps the estimated size in bytes of the blocks waiting in the import queue.
 	fn import_blocks(
 		self,
-		input: impl Read + Seek + Send + 'static,
+		input: impl Read + Send + 'static,
 		force: bool,
 		binary: bool,
+		checkpoint: Option<PathBuf>,
+		memory_budget: Option<u64>,
 	) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>>;
 
 	/// Performs the blocks export.
@@ -840,9 +928,17 @@ pub trait ServiceBuilderCommand {
 		output: impl Write + 'static,
 		from: NumberFor<Self::Block>,
 		to: Option<NumberFor<Self::Block>>,
-		binary: bool
+		format: crate::chain_ops::BlocksFormat,
 	) -> Pin<Box<dyn Future<Output = Result<(), Error>>>>;
 
+	/// Check that the JSON lines blocks of `exported` were exported from this chain and return the
+	/// number of the next block to export, or `None` if the export already reaches `to`.
+	fn next_block_to_export(
+		&self,
+		exported: impl BufRead,
+		to: Option<NumberFor<Self::Block>>,
+	) -> Result<Option<NumberFor<Self::Block>>, Error>;
+
 	/// Performs a revert of `blocks` blocks.
 	fn revert_chain(
 		&self,
@@ -855,12 +951,45 @@ pub trait ServiceBuilderCommand {
 		block: BlockId<Self::Block>
 	) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>>;
 
+	/// Re-validate the known blocks from `from` to `to` without importing them again.
+	///
+	/// The result of each block is returned in block order. By default checking a range of blocks
+	/// is not supported.
+	fn check_blocks(
+		self,
+		_from: NumberFor<Self::Block>,
+		_to: NumberFor<Self::Block>,
+	) -> Pin<Box<dyn Future<
+		Output = Result<Vec<(NumberFor<Self::Block>, Result<(), String>)>, Error>
+	> + Send>> where Self: Sized {
+		Box::pin(ready(Err(Error::Other("Checking a range of blocks is not supported".into()))))
+	}
+
 	/// Export the raw state at the given `block`. If `block` is `None`, the
 	/// best block will be used.
+	///
+	/// Returns the state with the header of the block it was exported at. Fails if the state of
+	/// the block was pruned.
 	fn export_raw_state(
 		&self,
 		block: Option<BlockId<Self::Block>>,
-	) -> Result<Storage, Error>;
+	) -> Result<(Storage, <Self::Block as BlockT>::Header), Error>;
+
+	/// Returns the state root of the genesis block, creating the database and the genesis block
+	/// from the chain spec if needed.
+	///
+	/// By default reading the genesis state root is not supported.
+	fn genesis_state_root(&self) -> Result<<Self::Block as BlockT>::Hash, Error> {
+		Err(Error::Other("Reading the genesis state root is not supported".into()))
+	}
+
+	/// Returns the hash of the genesis block, creating the database and the genesis block from
+	/// the chain spec if needed.
+	///
+	/// By default reading the genesis hash is not supported.
+	fn genesis_hash(&self) -> Result<<Self::Block as BlockT>::Hash, Error> {
+		Err(Error::Other("Reading the genesis hash is not supported".into()))
+	}
 }
 
 impl<TBl, TRtApi, TBackend, TExec, TSc, TImpQu, TExPool, TRpc>
@@ -935,6 +1064,10 @@ ServiceBuilder<
 			block_announce_validator_builder,
 		} = self;
 
+		if !matches!(config.role, Role::Light) {
+			record_offchain_indexing(&*backend, config.offchain_worker.indexing_enabled)?;
+		}
+
 		sp_session::generate_initial_session_keys(
 			client.clone(),
 			&BlockId::Hash(client.chain_info().best_hash),
@@ -1342,3 +1475,32 @@ ServiceBuilder<
 		})
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_block_builder::BlockBuilderProvider;
+	use sc_client_api::backend::AuxStore;
+	use sp_consensus::BlockOrigin;
+	use substrate_test_runtime_client::prelude::*;
+
+	#[test]
+	fn offchain_indexing_enabled_on_imported_blocks_warns_then_is_recorded() {
+		let builder = TestClientBuilder::new();
+		let backend = builder.backend();
+		let mut client = builder.build();
+		let recorded = || backend.get_aux(OFFCHAIN_INDEXING_KEY).unwrap();
+
+		// Nothing to warn about without blocks.
+		assert!(!record_offchain_indexing(&*backend, false).unwrap());
+		assert_eq!(recorded(), Some(vec![0]));
+
+		let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+		client.import(BlockOrigin::Own, block).unwrap();
+
+		assert!(record_offchain_indexing(&*backend, true).unwrap());
+		assert_eq!(recorded(), Some(vec![1]));
+		// Recorded, so it only warns once.
+		assert!(!record_offchain_indexing(&*backend, true).unwrap());
+	}
+}
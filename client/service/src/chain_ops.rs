@@ -19,7 +19,7 @@
 //! Chain utilities.
 
 use crate::error;
-use crate::builder::{ServiceBuilderCommand, ServiceBuilder};
+use crate::builder::{ServiceBuilderCommand, ServiceBuilder, record_offchain_indexing};
 use crate::error::Error;
 use sc_chain_spec::ChainSpec;
 use log::{warn, info};
@@ -35,13 +35,16 @@ use sp_consensus::{
 	import_queue::{IncomingBlock, Link, BlockImportError, BlockImportResult, ImportQueue},
 };
 use sc_executor::{NativeExecutor, NativeExecutionDispatch};
+use sp_core::Bytes;
 use sp_core::storage::{StorageKey, well_known_keys, ChildInfo, Storage, StorageChild, StorageMap};
-use sc_client_api::{StorageProvider, BlockBackend, UsageProvider};
+use sc_client_api::{StorageProvider, BlockBackend, UsageProvider, CallExecutor};
 
-use std::{io::{Read, Write, Seek}, pin::Pin, collections::HashMap};
+use std::{io::{BufRead, Read, Write}, pin::Pin, collections::{HashMap, VecDeque}, marker::PhantomData};
+use std::{fs, path::{Path, PathBuf}};
 use std::time::{Duration, Instant};
 use futures_timer::Delay;
 use std::task::Poll;
+use serde::{Deserialize, Serialize};
 use serde_json::{de::IoRead as JsonIoRead, Deserializer, StreamDeserializer};
 use std::convert::{TryFrom, TryInto};
 use sp_runtime::traits::{CheckedDiv, Saturating};
@@ -55,6 +58,77 @@ const DELAY_TIME: u64 = 2_000;
 /// Number of milliseconds that must have passed between two updates.
 const TIME_BETWEEN_UPDATES: u64 = 3_000;
 
+/// Number of updates the import rate used to estimate the remaining time is averaged over.
+const RATE_WINDOW: usize = 10;
+
+/// Number of blocks imported between two writes of the import checkpoint.
+const CHECKPOINT_INTERVAL: u32 = 1_000;
+
+/// Number of blocks written between two flushes of the output when exporting JSON lines.
+const JSON_LINES_FLUSH_INTERVAL: u32 = 1_000;
+
+/// Format of exported blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlocksFormat {
+	/// A stream of JSON encoded blocks.
+	Json,
+	/// One self-contained JSON object per line, holding the SCALE encoded block.
+	JsonLines,
+	/// The number of blocks followed by the SCALE encoded blocks.
+	Binary,
+}
+
+/// A block of the JSON lines format.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonLinesBlock<N, H> {
+	number: N,
+	hash: H,
+	parent_hash: H,
+	/// The SCALE encoded `SignedBlock`.
+	block: Bytes,
+}
+
+impl<N, H> JsonLinesBlock<N, H> {
+	/// Returns true if `value` looks like a block of the JSON lines format.
+	fn matches(value: &serde_json::Value) -> bool {
+		value.get("block").map_or(false, |block| block.is_string())
+			&& value.get("number").is_some()
+	}
+}
+
+/// The last block of an import known to be persisted, used to resume an interrupted import.
+#[derive(Serialize, Deserialize)]
+struct ImportCheckpoint<N, H> {
+	number: N,
+	hash: H,
+}
+
+/// Read the import checkpoint at `path`, if it exists.
+fn read_checkpoint<N, H>(path: &Path) -> Result<Option<ImportCheckpoint<N, H>>, Error> where
+	N: serde::de::DeserializeOwned,
+	H: serde::de::DeserializeOwned,
+{
+	if !path.exists() {
+		return Ok(None);
+	}
+
+	let data = fs::read(path)?;
+	serde_json::from_slice(&data)
+		.map(Some)
+		.map_err(|e| format!("Invalid import checkpoint {}: {}", path.display(), e).into())
+}
+
+/// Write the import checkpoint at `path`, replacing it atomically.
+fn write_checkpoint<N: Serialize, H: Serialize>(path: &Path, number: N, hash: H) -> Result<(), Error> {
+	let data = serde_json::to_vec(&ImportCheckpoint { number, hash })
+		.map_err(|e| format!("Failed to encode import checkpoint: {}", e))?;
+	let tmp_path = path.with_extension("tmp");
+	fs::write(&tmp_path, data)?;
+	fs::rename(&tmp_path, path)?;
+	Ok(())
+}
+
 /// Build a chain spec json
 pub fn build_spec(spec: &dyn ChainSpec, raw: bool) -> error::Result<String> {
 	spec.as_json(raw).map_err(Into::into)
@@ -64,7 +138,7 @@ pub fn build_spec(spec: &dyn ChainSpec, raw: bool) -> error::Result<String> {
 /// Helper enum that wraps either a binary decoder (from parity-scale-codec), or a JSON decoder (from serde_json).
 /// Implements the Iterator Trait, calling `next()` will decode the next SignedBlock and return it.
 enum BlockIter<R, B> where
-	R: std::io::Read + std::io::Seek,
+	R: std::io::Read,
 {
 	Binary {
 		// Total number of blocks we are expecting to decode.
@@ -77,13 +151,16 @@ enum BlockIter<R, B> where
 	Json {
 		// Nubmer of blocks we have decoded thus far.
 		read_block_count: u64,
+		// Whether the blocks are in the JSON lines format, detected from the first block.
+		json_lines: Option<bool>,
 		// Stream to the data, used for decoding new blocks.
-		reader: StreamDeserializer<'static, JsonIoRead<R>, SignedBlock<B>>,
+		reader: StreamDeserializer<'static, JsonIoRead<R>, serde_json::Value>,
+		_phantom: PhantomData<B>,
 	},
 }
 
 impl<R, B> BlockIter<R, B> where
-	R: Read + Seek + 'static,
+	R: Read + 'static,
 	B: BlockT + MaybeSerializeDeserialize,
 {
 	fn new(input: R, binary: bool) -> Result<Self, String> {
@@ -99,11 +176,15 @@ impl<R, B> BlockIter<R, B> where
 				reader,
 			})
 		} else {
+			// Both the JSON and the JSON lines formats are streams of JSON objects, which are
+			// told apart when reading the first block.
 			let stream_deser = Deserializer::from_reader(input)
-				.into_iter::<SignedBlock<B>>();
+				.into_iter::<serde_json::Value>();
 			Ok(BlockIter::Json {
 				reader: stream_deser,
 				read_block_count: 0,
+				json_lines: None,
+				_phantom: PhantomData,
 			})
 		}
 	}
@@ -127,7 +208,7 @@ impl<R, B> BlockIter<R, B> where
 }
 
 impl<R, B> Iterator for BlockIter<R, B> where
-	R: Read + Seek + 'static,
+	R: Read + 'static,
 	B: BlockT + MaybeSerializeDeserialize,
 {
 	type Item = Result<SignedBlock<B>, String>;
@@ -145,10 +226,22 @@ impl<R, B> Iterator for BlockIter<R, B> where
 					None
 				}
 			}
-			BlockIter::Json { reader, read_block_count } => {
-				let res = Some(reader.next()?.map_err(|e| e.to_string()));
+			BlockIter::Json { reader, read_block_count, json_lines, .. } => {
+				let res = reader.next()?.map_err(|e| e.to_string()).and_then(|value| {
+					let is_json_lines = *json_lines.get_or_insert_with(|| {
+						JsonLinesBlock::<(), ()>::matches(&value)
+					});
+
+					if is_json_lines {
+						let line: JsonLinesBlock<serde_json::Value, serde_json::Value> =
+							serde_json::from_value(value).map_err(|e| e.to_string())?;
+						SignedBlock::<B>::decode(&mut &line.block.0[..]).map_err(|e| e.to_string())
+					} else {
+						serde_json::from_value(value).map_err(|e| e.to_string())
+					}
+				});
 				*read_block_count += 1;
-				res
+				Some(res)
 			}
 		}
 	}
@@ -192,21 +285,77 @@ fn importing_is_done(
 	}
 }
 
+/// Limit of the number of blocks waiting in the import queue.
+struct PendingLimit {
+	/// Maximum size in bytes of the blocks waiting in the queue, if any.
+	memory_budget: Option<u64>,
+	/// Total encoded size of the blocks read thus far.
+	read_bytes: u64,
+	/// Number of blocks read thus far.
+	read_blocks: u64,
+}
+
+impl PendingLimit {
+	fn new(memory_budget: Option<u64>) -> Self {
+		Self { memory_budget, read_bytes: 0, read_blocks: 0 }
+	}
+
+	/// Records the size of a block read from the input.
+	fn record<B: BlockT>(&mut self, block: &SignedBlock<B>) {
+		self.read_bytes += block.encoded_size() as u64;
+		self.read_blocks += 1;
+	}
+
+	/// Returns the maximum number of blocks to add to the queue, estimated from the memory budget
+	/// and the average encoded size of the blocks read thus far.
+	fn max_pending_blocks(&self) -> u64 {
+		let budget = match self.memory_budget {
+			Some(budget) => budget,
+			None => return MAX_PENDING_BLOCKS,
+		};
+		let average_size = self.read_bytes.checked_div(self.read_blocks).unwrap_or(0).max(1);
+		(budget / average_size).max(1).min(MAX_PENDING_BLOCKS)
+	}
+}
+
 /// Structure used to log the block importing speed.
 struct Speedometer<B: BlockT> {
 	best_number: NumberFor<B>,
 	last_number: Option<NumberFor<B>>,
 	last_update: Instant,
+	/// Total number of blocks to import, if known.
+	total_blocks: Option<u64>,
+	/// Number of imported blocks at the last updates, used to compute the average import rate.
+	imported_samples: VecDeque<(Instant, u64)>,
 }
 
 impl<B: BlockT> Speedometer<B> {
 	/// Creates a fresh Speedometer.
-	fn new() -> Self {
+	fn new(total_blocks: Option<u64>) -> Self {
 		Self {
 			best_number: NumberFor::<B>::from(0),
 			last_number: None,
 			last_update: Instant::now(),
+			total_blocks,
+			imported_samples: VecDeque::with_capacity(RATE_WINDOW + 1),
+		}
+	}
+
+	/// Returns the number of remaining blocks and the estimated time to import them, based on the
+	/// average import rate of the last updates.
+	fn eta(&self) -> Option<(u64, Duration)> {
+		let total = self.total_blocks?;
+		let (first_time, first_imported) = self.imported_samples.front()?;
+		let (last_time, last_imported) = self.imported_samples.back()?;
+		let remaining = total.saturating_sub(*last_imported);
+
+		let elapsed = last_time.duration_since(*first_time).as_secs_f64();
+		let rate = last_imported.saturating_sub(*first_imported) as f64 / elapsed;
+		if !rate.is_finite() || rate <= 0.0 {
+			return None;
 		}
+
+		Some((remaining, Duration::from_secs_f64(remaining as f64 / rate)))
 	}
 
 	/// Calculates `(best_number - last_number) / (now - last_update)` and 
@@ -231,7 +380,16 @@ impl<B: BlockT> Speedometer<B> {
 			// do the math and turn it into a `f64`.
 			let speed = diff.saturating_mul(10_000).checked_div(u128::from(elapsed_ms))
 				.map_or(0.0, |s| s as f64) / 10.0;
-			info!("📦 Current best block: {} ({:4.1} bps)", self.best_number, speed);
+			match self.eta() {
+				Some((remaining, eta)) => info!(
+					"📦 Current best block: {} ({:4.1} bps), remaining: {} blocks, eta: {}",
+					self.best_number,
+					speed,
+					remaining,
+					format_eta(eta),
+				),
+				None => info!("📦 Current best block: {} ({:4.1} bps)", self.best_number, speed),
+			}
 		} else {
 			// If the number of blocks can't be converted to a regular integer, then we need a more
 			// algebraic approach and we stay within the realm of integers.
@@ -247,26 +405,139 @@ impl<B: BlockT> Speedometer<B> {
 	}
 
 	/// Updates the Speedometer.
-	fn update(&mut self, best_number: NumberFor<B>) {
+	fn update(&mut self, best_number: NumberFor<B>, imported_blocks: u64) {
 		self.last_number = Some(self.best_number);
 		self.best_number = best_number;
 		self.last_update = Instant::now();
+
+		self.imported_samples.push_back((self.last_update, imported_blocks));
+		if self.imported_samples.len() > RATE_WINDOW {
+			self.imported_samples.pop_front();
+		}
 	}
 
 	// If more than TIME_BETWEEN_UPDATES has elapsed since last update,
 	// then print and update the speedometer.
-	fn notify_user(&mut self, best_number: NumberFor<B>) {
+	fn notify_user(&mut self, best_number: NumberFor<B>, imported_blocks: u64) {
 		let delta = Duration::from_millis(TIME_BETWEEN_UPDATES);
 		if Instant::now().duration_since(self.last_update) >= delta {
 			self.display_speed();
-			self.update(best_number);
+			self.update(best_number, imported_blocks);
 		}
 	}
 }
 
+/// Format an estimated remaining time like `1h 02m 03s`.
+fn format_eta(eta: Duration) -> String {
+	let secs = eta.as_secs();
+	match (secs / 3600, secs / 60 % 60, secs % 60) {
+		(0, 0, s) => format!("{}s", s),
+		(0, m, s) => format!("{}m {:02}s", m, s),
+		(h, m, s) => format!("{}h {:02}m {:02}s", h, m, s),
+	}
+}
+
+/// Queues the known block `number` to be verified and executed again without importing it.
+///
+/// Returns the hash of the queued block.
+fn queue_known_block<TBackend, TExec, TBl, TRtApi, TImpQu>(
+	client: &Client<TBackend, TExec, TBl, TRtApi>,
+	queue: &mut TImpQu,
+	number: NumberFor<TBl>,
+) -> Result<TBl::Hash, String> where
+	TBl: BlockT,
+	TBackend: sc_client_api::backend::Backend<TBl>,
+	TExec: CallExecutor<TBl>,
+	TImpQu: ImportQueue<TBl>,
+{
+	let block = client.block(&BlockId::Number(number))
+		.map_err(|e| format!("Error reading block: {:?}", e))?
+		.ok_or_else(|| "Unknown block".to_string())?;
+
+	let (header, extrinsics) = block.block.deconstruct();
+	if client.state_at(&BlockId::Hash(*header.parent_hash())).is_err() {
+		return Err("The state of the parent block was pruned".into());
+	}
+
+	let hash = header.hash();
+	queue.import_blocks(BlockOrigin::File, vec![
+		IncomingBlock::<TBl> {
+			hash,
+			header: Some(header),
+			body: Some(extrinsics),
+			justification: block.justification,
+			origin: None,
+			allow_missing_state: false,
+			import_existing: true,
+			check_only: true,
+		}
+	]);
+	Ok(hash)
+}
+
+/// Describes why the import queue rejected a checked block.
+fn check_error(error: BlockImportError) -> String {
+	match error {
+		BlockImportError::VerificationFailed(_, e) => format!("Verification failed: {}", e),
+		BlockImportError::Other(e) => e.to_string(),
+		e => format!("{:?}", e),
+	}
+}
+
+/// Returns the number of the earliest canonical block above `pruned` whose state is available.
+///
+/// The states still available always form a contiguous range up to the best block.
+fn earliest_state<TBackend, TExec, TBl, TRtApi>(
+	client: &Client<TBackend, TExec, TBl, TRtApi>,
+	pruned: NumberFor<TBl>,
+) -> Option<NumberFor<TBl>> where
+	TBl: BlockT,
+	TBackend: sc_client_api::backend::Backend<TBl>,
+	TExec: CallExecutor<TBl>,
+{
+	let has_state = |number| client.state_at(&BlockId::Number(number)).is_ok();
+	let (mut low, mut high) = (pruned + One::one(), client.usage_info().chain.best_number);
+	if low > high || !has_state(high) {
+		return None;
+	}
+
+	while low < high {
+		let middle = low + (high - low) / 2u32.into();
+		if has_state(middle) {
+			high = middle;
+		} else {
+			low = middle + One::one();
+		}
+	}
+
+	Some(low)
+}
+
+/// Returns the block `number` to export, or `None` past the best block `best`.
+///
+/// Fails if the block exists but its body was pruned.
+fn block_to_export<TBl, C>(
+	client: &C,
+	number: NumberFor<TBl>,
+	best: NumberFor<TBl>,
+) -> Result<Option<SignedBlock<TBl>>, Error> where
+	TBl: BlockT,
+	C: BlockBackend<TBl>,
+{
+	match client.block(&BlockId::Number(number))? {
+		Some(block) => Ok(Some(block)),
+		None if number <= best => Err(Error::Other(format!(
+			"The body of block #{} was pruned, only the bodies of the blocks kept with \
+			`--keep-blocks` can be exported",
+			number,
+		))),
+		None => Ok(None),
+	}
+}
+
 /// Different State that the `import_blocks` future could be in.
 enum ImportState<R, B> where 
-	R: Read + Seek + 'static,
+	R: Read + 'static,
 	B: BlockT + MaybeSerializeDeserialize,
 {
 	/// We are reading from the BlockIter structure, adding those blocks to the queue if possible.
@@ -299,6 +570,7 @@ impl<
 	TExecDisp: 'static + NativeExecutionDispatch,
 	TImpQu: 'static + ImportQueue<TBl>,
 	TRtApi: 'static + Send + Sync,
+	Backend: sc_client_api::backend::Backend<TBl>,
 	Self: Send + 'static,
 {
 	type Block = TBl;
@@ -306,9 +578,11 @@ impl<
 
 	fn import_blocks(
 		mut self,
-		input: impl Read + Seek + Send + 'static,
+		input: impl Read + Send + 'static,
 		force: bool,
 		binary: bool,
+		checkpoint: Option<PathBuf>,
+		memory_budget: Option<u64>,
 	) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>> {
 		struct WaitLink {
 			imported_blocks: u64,
@@ -355,8 +629,40 @@ impl<
 			}
 		};
 
+		let indexing_enabled = self.config.offchain_worker.indexing_enabled;
+		if let Err(e) = record_offchain_indexing(&*self.backend, indexing_enabled) {
+			return future::ready(Err(e.into())).boxed();
+		}
+
+		// The blocks up to the checkpoint are known to be persisted by a previous import.
+		let resume_from = match checkpoint.as_ref().map(|path| read_checkpoint(path)).transpose() {
+			Ok(Some(Some(ImportCheckpoint { number, hash }))) => {
+				match self.client.block_hash(number) {
+					Ok(Some(known)) if known == hash => {
+						info!("Resuming import after block #{} ({})", number, hash);
+						Some((number, hash))
+					},
+					Ok(_) => return future::ready(Err(Error::Other(format!(
+						"The import checkpoint block #{} ({}) is not in the database",
+						number,
+						hash,
+					)))).boxed(),
+					Err(e) => return future::ready(Err(e.into())).boxed(),
+				}
+			},
+			Ok(_) => None,
+			Err(e) => return future::ready(Err(e)).boxed(),
+		};
+		let mut last_checkpoint = resume_from.map_or_else(Zero::zero, |(number, _)| number);
+
+		let num_expected_blocks = block_iter.num_expected_blocks();
+		if num_expected_blocks.is_none() {
+			info!("The number of blocks to import is unknown, the remaining time won't be estimated");
+		}
+
 		let mut state = Some(ImportState::Reading{block_iter});
-		let mut speedometer = Speedometer::<TBl>::new();
+		let mut speedometer = Speedometer::<TBl>::new(num_expected_blocks);
+		let mut pending_limit = PendingLimit::new(memory_budget);
 
 		// Importing blocks is implemented as a future, because we want the operation to be
 		// interruptible.
@@ -382,7 +688,30 @@ impl<
 							let read_block_count = block_iter.read_block_count();
 							match block_result {
 								Ok(block) => {
-									if read_block_count - link.imported_blocks >= MAX_PENDING_BLOCKS {
+									let header = block.block.header();
+									let skipped = match resume_from {
+										Some((number, _)) if *header.number() < number => true,
+										Some((number, hash)) if *header.number() == number => {
+											if header.hash() != hash {
+												return Poll::Ready(Err(Error::Other(format!(
+													"Block #{} of the input doesn't match the import checkpoint",
+													number,
+												))))
+											}
+											true
+										},
+										_ => false,
+									};
+
+									if !skipped {
+										pending_limit.record(&block);
+									}
+
+									if skipped {
+										// Already imported before the checkpoint was written.
+										link.imported_blocks += 1;
+										state = Some(ImportState::Reading{block_iter});
+									} else if read_block_count - link.imported_blocks >= pending_limit.max_pending_blocks() {
 										// The queue is full, so do not add this block and simply wait until
 										// the queue has made some progress.
 										let delay = Delay::new(Duration::from_millis(DELAY_TIME));
@@ -403,7 +732,7 @@ impl<
 				},
 				ImportState::WaitingForImportQueueToCatchUp{block_iter, mut delay, block} => {
 					let read_block_count = block_iter.read_block_count();
-					if read_block_count - link.imported_blocks >= MAX_PENDING_BLOCKS {
+					if read_block_count - link.imported_blocks >= pending_limit.max_pending_blocks() {
 						// Queue is still full, so wait until there is room to insert our block.
 						match Pin::new(&mut delay).poll(cx) {
 							Poll::Pending => {
@@ -426,6 +755,13 @@ impl<
 					// All the blocks have been added to the queue, which doesn't mean they 
 					// have all been properly imported.
 					if importing_is_done(num_expected_blocks, read_block_count, link.imported_blocks) {
+						if let Some(path) = &checkpoint {
+							let info = client.chain_info();
+							if let Err(e) = write_checkpoint(path, info.best_number, info.best_hash) {
+								return Poll::Ready(Err(e))
+							}
+						}
+
 						// Importing is done, we can log the result and return.
 						info!(
 							"🎉 Imported {} blocks. Best: #{}",
@@ -452,8 +788,19 @@ impl<
 
 			queue.poll_actions(cx, &mut link);
 
-			let best_number = client.chain_info().best_number;
-			speedometer.notify_user(best_number);
+			let info = client.chain_info();
+			speedometer.notify_user(info.best_number, link.imported_blocks);
+
+			// The best block is only updated once the block is committed to the database, so the
+			// checkpoint never gets ahead of the persisted blocks.
+			if let Some(path) = &checkpoint {
+				if info.best_number >= last_checkpoint + CHECKPOINT_INTERVAL.into() {
+					if let Err(e) = write_checkpoint(path, info.best_number, info.best_hash) {
+						return Poll::Ready(Err(e))
+					}
+					last_checkpoint = info.best_number;
+				}
+			}
 
 			if link.has_error {
 				return Poll::Ready(Err(
@@ -474,7 +821,7 @@ impl<
 		mut output: impl Write + 'static,
 		from: NumberFor<TBl>,
 		to: Option<NumberFor<TBl>>,
-		binary: bool
+		format: BlocksFormat,
 	) -> Pin<Box<dyn Future<Output = Result<(), Error>>>> {
 		let mut block = from;
 
@@ -483,6 +830,7 @@ impl<
 			Some(v) => v,
 			None => self.client.chain_info().best_number,
 		};
+		let best = self.client.chain_info().best_number;
 
 		let mut wrote_header = false;
 
@@ -502,7 +850,7 @@ impl<
 
 			if !wrote_header {
 				info!("Exporting blocks from #{} to #{}", block, last);
-				if binary {
+				if format == BlocksFormat::Binary {
 					let last_: u64 = last.saturated_into::<u64>();
 					let block_: u64 = block.saturated_into::<u64>();
 					let len: u64 = last_ - block_ + 1;
@@ -511,22 +859,47 @@ impl<
 				wrote_header = true;
 			}
 
-			match client.block(&BlockId::number(block))? {
-				Some(block) => {
-					if binary {
-						output.write_all(&block.encode())?;
-					} else {
-						serde_json::to_writer(&mut output, &block)
+			let signed_block = match block_to_export::<TBl, _>(&**client, block, best) {
+				Ok(signed_block) => signed_block,
+				Err(e) => {
+					output.flush()?;
+					return Poll::Ready(Err(e));
+				},
+			};
+			match signed_block {
+				Some(signed_block) => match format {
+					BlocksFormat::Binary => output.write_all(&signed_block.encode())?,
+					BlocksFormat::Json => serde_json::to_writer(&mut output, &signed_block)
+						.map_err(|e| format!("Error writing JSON: {}", e))?,
+					BlocksFormat::JsonLines => {
+						let header = signed_block.block.header();
+						let line = JsonLinesBlock {
+							number: *header.number(),
+							hash: header.hash(),
+							parent_hash: *header.parent_hash(),
+							block: Bytes(signed_block.encode()),
+						};
+						serde_json::to_writer(&mut output, &line)
 							.map_err(|e| format!("Error writing JSON: {}", e))?;
-					}
-			},
+						output.write_all(b"\n")?;
+
+						// Flush periodically so that streaming consumers see the blocks.
+						if (block % JSON_LINES_FLUSH_INTERVAL.into()).is_zero() {
+							output.flush()?;
+						}
+					},
+				},
 				// Reached end of the chain.
-				None => return Poll::Ready(Ok(())),
+				None => {
+					output.flush()?;
+					return Poll::Ready(Ok(()));
+				},
 			}
 			if (block % 10000.into()).is_zero() {
 				info!("#{}", block);
 			}
 			if block == last {
+				output.flush()?;
 				return Poll::Ready(Ok(()));
 			}
 			block += One::one();
@@ -539,6 +912,53 @@ impl<
 		Box::pin(export)
 	}
 
+	fn next_block_to_export(
+		&self,
+		exported: impl BufRead,
+		to: Option<NumberFor<TBl>>,
+	) -> Result<Option<NumberFor<TBl>>, Error> {
+		let mut first = None;
+		let mut last = None;
+		for line in exported.lines() {
+			let line = line?;
+			if line.trim().is_empty() {
+				continue;
+			}
+			let block: JsonLinesBlock<NumberFor<TBl>, TBl::Hash> = serde_json::from_str(&line)
+				.map_err(|e| format!("Invalid JSON lines block: {}", e))?;
+			if first.is_none() {
+				first = Some((block.number, block.parent_hash));
+			}
+			last = Some((block.number, block.hash));
+		}
+
+		let (first, last) = match (first, last) {
+			(Some(first), Some(last)) => (first, last),
+			_ => return Ok(Some(One::one())),
+		};
+
+		// The parent of the first block is the genesis block for a complete export.
+		let (first_number, first_parent) = first;
+		if !first_number.is_zero()
+			&& self.client.block_hash(first_number - One::one())? != Some(first_parent)
+		{
+			return Err("The exported blocks are from a different chain".into());
+		}
+
+		let (last_number, last_hash) = last;
+		if self.client.block_hash(last_number)? != Some(last_hash) {
+			return Err(format!(
+				"The exported block #{} is not part of the chain, the export is from a different \
+				chain or a fork",
+				last_number,
+			).into());
+		}
+
+		let next = last_number + One::one();
+		let last = to.unwrap_or_else(|| self.client.chain_info().best_number);
+		Ok(if next > last { None } else { Some(next) })
+	}
+
 	fn revert_chain(
 		&self,
 		blocks: NumberFor<TBl>
@@ -564,20 +984,113 @@ impl<
 				1u64.encode_to(&mut buf);
 				block.encode_to(&mut buf);
 				let reader = std::io::Cursor::new(buf);
-				self.import_blocks(reader, true, true)
+				self.import_blocks(reader, true, true, None, None)
 			}
 			Ok(None) => Box::pin(future::err("Unknown block".into())),
 			Err(e) => Box::pin(future::err(format!("Error reading block: {:?}", e).into())),
 		}
 	}
 
+	fn check_blocks(
+		mut self,
+		from: NumberFor<TBl>,
+		to: NumberFor<TBl>,
+	) -> Pin<Box<dyn Future<
+		Output = Result<Vec<(NumberFor<TBl>, Result<(), String>)>, Error>
+	> + Send>> {
+		struct CheckLink<B: BlockT> {
+			/// The index in `results` of the blocks still in the queue.
+			pending: HashMap<B::Hash, usize>,
+			results: Vec<Option<Result<(), String>>>,
+		}
+
+		impl<B: BlockT> Link<B> for CheckLink<B> {
+			fn blocks_processed(
+				&mut self,
+				_imported: usize,
+				_num_expected_blocks: usize,
+				results: Vec<(Result<BlockImportResult<NumberFor<B>>, BlockImportError>, B::Hash)>
+			) {
+				for (result, hash) in results {
+					if let Some(index) = self.pending.remove(&hash) {
+						self.results[index] = Some(result.map(|_| ()).map_err(check_error));
+					}
+				}
+			}
+		}
+
+		if to < from {
+			return Box::pin(future::err(Error::Other(format!(
+				"The last block of the range #{} is before the first one #{}",
+				to,
+				from,
+			))))
+		}
+
+		let mut numbers = Vec::new();
+		let mut number = from;
+		while number <= to {
+			numbers.push(number);
+			number += One::one();
+		}
+
+		let mut link = CheckLink::<TBl> {
+			pending: HashMap::new(),
+			results: vec![None; numbers.len()],
+		};
+		let mut next = 0;
+
+		// Every block goes through the verifier of the import queue, so the seal and the
+		// consensus rules are checked before it is executed. Each block is queued on its own,
+		// so that a failure doesn't cancel the blocks queued with it.
+		let check = future::poll_fn(move |cx| {
+			while next < numbers.len() && (link.pending.len() as u64) < MAX_PENDING_BLOCKS {
+				match queue_known_block(&*self.client, &mut self.import_queue, numbers[next]) {
+					Ok(hash) => {
+						link.pending.insert(hash, next);
+					},
+					Err(e) => link.results[next] = Some(Err(e)),
+				}
+				next += 1;
+			}
+
+			self.import_queue.poll_actions(cx, &mut link);
+
+			if next < numbers.len() || !link.pending.is_empty() {
+				cx.waker().wake_by_ref();
+				return Poll::Pending
+			}
+
+			let results = std::mem::take(&mut link.results);
+			Poll::Ready(Ok(numbers.iter().cloned().zip(results.into_iter().map(|result| {
+				result.unwrap_or_else(|| Err("The block was not checked".into()))
+			})).collect()))
+		});
+		Box::pin(check)
+	}
+
 	fn export_raw_state(
 		&self,
 		block: Option<BlockId<Self::Block>>,
-	) -> Result<Storage, Error> {
+	) -> Result<(Storage, TBl::Header), Error> {
 		let block = block.unwrap_or_else(
 			|| BlockId::Hash(self.client.usage_info().chain.best_hash)
 		);
+		let header = self.client.header(&block)?
+			.ok_or_else(|| Error::from(format!("Unknown block {}", block)))?;
+		let (number, hash) = (*header.number(), header.hash());
+		let block = BlockId::Hash(hash);
+
+		if self.client.state_at(&block).is_err() {
+			return Err(match earliest_state(&*self.client, number) {
+				Some(earliest) => format!(
+					"State pruned at block #{}, earliest available is #{}",
+					number,
+					earliest,
+				),
+				None => format!("State pruned at block #{}", number),
+			}.into());
+		}
 
 		let empty_key = StorageKey(Vec::new());
 		let mut top_storage = self.client.storage_pairs(&block, &empty_key)?;
@@ -609,6 +1122,54 @@ impl<
 		}
 
 		let top = top_storage.into_iter().map(|(k, v)| (k.0, v.0)).collect();
-		Ok(Storage { top, children_default })
+		Ok((Storage { top, children_default }, header))
+	}
+
+	fn genesis_state_root(&self) -> Result<TBl::Hash, Error> {
+		let genesis = self.client.header(&BlockId::Number(Zero::zero()))?
+			.ok_or_else(|| Error::from("Missing genesis block"))?;
+		Ok(*genesis.state_root())
+	}
+
+	fn genesis_hash(&self) -> Result<TBl::Hash, Error> {
+		Ok(self.client.chain_info().genesis_hash)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::Arc;
+	use sc_block_builder::BlockBuilderProvider;
+	use sc_client_db::{Backend, DatabaseSettings, DatabaseSettingsSrc, KeepBlocks, PruningMode};
+	use substrate_test_runtime_client::{prelude::*, runtime::Block};
+
+	#[test]
+	fn exporting_a_pruned_body_fails() {
+		let tmp = tempfile::tempdir().unwrap();
+		let backend = Arc::new(Backend::new(
+			DatabaseSettings {
+				state_cache_size: 1 << 20,
+				state_cache_child_ratio: None,
+				pruning: PruningMode::ArchiveAll,
+				keep_blocks: KeepBlocks::Some(2),
+				source: DatabaseSettingsSrc::RocksDb {
+					path: tmp.path().into(),
+					cache_size: 1024,
+				},
+			},
+			u64::max_value(),
+		).unwrap());
+		let mut client = TestClientBuilder::with_backend(backend).build();
+		for _ in 0..4 {
+			let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+			client.import_as_final(BlockOrigin::Own, block).unwrap();
+		}
+		let best = client.chain_info().best_number;
+
+		assert!(block_to_export::<Block, _>(&client, 2, best).is_err());
+		assert!(block_to_export::<Block, _>(&client, 3, best).unwrap().is_some());
+		assert!(block_to_export::<Block, _>(&client, 4, best).unwrap().is_some());
+		assert!(block_to_export::<Block, _>(&client, 5, best).unwrap().is_none());
 	}
 }
@@ -18,6 +18,8 @@
 
 use std::{sync::Arc, panic::UnwindSafe, result, cell::RefCell};
 use codec::{Encode, Decode};
+use log::warn;
+use parking_lot::Mutex;
 use sp_runtime::{
 	generic::BlockId, traits::{Block as BlockT, HashFor, NumberFor},
 };
@@ -27,42 +29,119 @@ use sp_state_machine::{
 };
 use sc_executor::{RuntimeVersion, RuntimeInfo, NativeVersion};
 use sp_externalities::Extensions;
-use sp_core::{NativeOrEncoded, NeverNativeValue, traits::CodeExecutor, offchain::storage::OffchainOverlayedChanges};
+use sp_core::{
+	NativeOrEncoded, NeverNativeValue, traits::{CodeExecutor, RuntimeCode},
+	offchain::storage::OffchainOverlayedChanges,
+};
 use sp_api::{ProofRecorder, InitializeBlock, StorageTransactionCache};
 use sc_client_api::{backend, call_executor::CallExecutor, CloneableSpawn};
-use super::client::ClientConfig;
+use super::{client::ClientConfig, wasm_override::WasmOverride};
 
 /// Call executor that executes methods locally, querying all required
 /// data from local backend.
 pub struct LocalCallExecutor<B, E> {
 	backend: Arc<B>,
 	executor: E,
+	wasm_override: Option<WasmOverride>,
+	/// The version of the last on-chain runtime checked for an override, by code hash.
+	onchain_version: Arc<Mutex<Option<(Vec<u8>, RuntimeVersion)>>>,
 	spawn_handle: Box<dyn CloneableSpawn>,
 	client_config: ClientConfig,
 }
 
-impl<B, E> LocalCallExecutor<B, E> {
+impl<B, E> LocalCallExecutor<B, E> where E: RuntimeInfo {
 	/// Creates new instance of local call executor.
+	///
+	/// If the Wasm runtime overrides of the client configuration can't be read, the error is
+	/// logged and the on-chain runtimes are executed.
 	pub fn new(
 		backend: Arc<B>,
 		executor: E,
 		spawn_handle: Box<dyn CloneableSpawn>,
 		client_config: ClientConfig,
+	) -> Self {
+		let wasm_override = client_config.wasm_runtime_overrides.as_ref()
+			.and_then(|dir| WasmOverride::new(dir, &executor)
+				.map_err(|e| warn!("{}, executing the on-chain runtimes", e))
+				.ok()
+			);
+
+		Self::with_wasm_override(backend, executor, wasm_override, spawn_handle, client_config)
+	}
+
+	/// Creates new instance of local call executor.
+	///
+	/// Fails if the Wasm runtime overrides of the client configuration can't be read.
+	pub fn try_new(
+		backend: Arc<B>,
+		executor: E,
+		spawn_handle: Box<dyn CloneableSpawn>,
+		client_config: ClientConfig,
+	) -> sp_blockchain::Result<Self> {
+		let wasm_override = client_config.wasm_runtime_overrides.as_ref()
+			.map(|dir| WasmOverride::new(dir, &executor))
+			.transpose()?;
+
+		Ok(Self::with_wasm_override(backend, executor, wasm_override, spawn_handle, client_config))
+	}
+
+	fn with_wasm_override(
+		backend: Arc<B>,
+		executor: E,
+		wasm_override: Option<WasmOverride>,
+		spawn_handle: Box<dyn CloneableSpawn>,
+		client_config: ClientConfig,
 	) -> Self {
 		LocalCallExecutor {
 			backend,
 			executor,
+			wasm_override,
+			onchain_version: Default::default(),
 			spawn_handle,
 			client_config,
 		}
 	}
 }
 
+impl<B, E> LocalCallExecutor<B, E> where E: CodeExecutor + RuntimeInfo + Clone + 'static {
+	/// The code of the Wasm runtime override of the on-chain runtime at `id`, or `onchain_code`.
+	fn check_override<'a, Block>(
+		&'a self,
+		onchain_code: RuntimeCode<'a>,
+		id: &BlockId<Block>,
+	) -> sp_blockchain::Result<RuntimeCode<'a>>
+	where
+		B: backend::Backend<Block>,
+		Block: BlockT,
+	{
+		let wasm_override = match &self.wasm_override {
+			Some(wasm_override) if !wasm_override.is_empty() => wasm_override,
+			_ => return Ok(onchain_code),
+		};
+
+		// Reading the version executes the runtime, only do it when the on-chain code changes.
+		let mut onchain_version = self.onchain_version.lock();
+		let version = match &*onchain_version {
+			Some((hash, version)) if *hash == onchain_code.hash => version.clone(),
+			_ => {
+				let version = CallExecutor::runtime_version(self, id)?;
+				*onchain_version = Some((onchain_code.hash.clone(), version.clone()));
+				version
+			},
+		};
+		drop(onchain_version);
+
+		Ok(wasm_override.get(&version, onchain_code.heap_pages).unwrap_or(onchain_code))
+	}
+}
+
 impl<B, E> Clone for LocalCallExecutor<B, E> where E: Clone {
 	fn clone(&self) -> Self {
 		LocalCallExecutor {
 			backend: self.backend.clone(),
 			executor: self.executor.clone(),
+			wasm_override: self.wasm_override.clone(),
+			onchain_version: self.onchain_version.clone(),
 			spawn_handle: self.spawn_handle.clone(),
 			client_config: self.client_config.clone(),
 		}
@@ -98,6 +177,7 @@ where
 		)?;
 		let state = self.backend.state_at(*id)?;
 		let state_runtime_code = sp_state_machine::backend::BackendRuntimeCode::new(&state);
+		let runtime_code = self.check_override(state_runtime_code.runtime_code()?, id)?;
 		let return_data = StateMachine::new(
 			&state,
 			changes_trie,
@@ -107,7 +187,7 @@ where
 			method,
 			call_data,
 			extensions.unwrap_or_default(),
-			&state_runtime_code.runtime_code()?,
+			&runtime_code,
 			self.spawn_handle.clone(),
 		).execute_using_consensus_failure_handler::<_, NeverNativeValue, fn() -> _>(
 			strategy.get_manager(),
@@ -170,7 +250,7 @@ where
 				let state_runtime_code = sp_state_machine::backend::BackendRuntimeCode::new(&trie_state);
 				// It is important to extract the runtime code here before we create the proof
 				// recorder.
-				let runtime_code = state_runtime_code.runtime_code()?;
+				let runtime_code = self.check_override(state_runtime_code.runtime_code()?, at)?;
 
 				let backend = sp_state_machine::ProvingBackend::new_with_recorder(
 					trie_state,
@@ -195,7 +275,7 @@ where
 			},
 			None => {
 				let state_runtime_code = sp_state_machine::backend::BackendRuntimeCode::new(&state);
-				let runtime_code = state_runtime_code.runtime_code()?;
+				let runtime_code = self.check_override(state_runtime_code.runtime_code()?, at)?;
 				let mut state_machine = StateMachine::new(
 					&state,
 					changes_trie_state,
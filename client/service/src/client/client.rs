@@ -21,6 +21,7 @@
 use std::{
 	marker::PhantomData,
 	collections::{HashSet, BTreeMap, HashMap},
+	path::PathBuf,
 	sync::Arc, panic::UnwindSafe, result,
 };
 use log::{info, trace, warn};
@@ -180,6 +181,8 @@ pub struct ClientConfig {
 	pub offchain_worker_enabled: bool,
 	/// If true, allows access from the runtime to write into offchain worker db.
 	pub offchain_indexing_api: bool,
+	/// Directory of the Wasm runtimes executed instead of the on-chain runtimes of the same version.
+	pub wasm_runtime_overrides: Option<PathBuf>,
 }
 
 /// Create a client with the explicitly provided backend.
@@ -200,7 +203,7 @@ pub fn new_with_backend<B, E, Block, S, RA>(
 		Block: BlockT,
 		B: backend::LocalBackend<Block> + 'static,
 {
-	let call_executor = LocalCallExecutor::new(backend.clone(), executor, spawn_handle, config.clone());
+	let call_executor = LocalCallExecutor::try_new(backend.clone(), executor, spawn_handle, config.clone())?;
 	let extensions = ExecutionExtensions::new(Default::default(), keystore);
 	Client::new(
 		backend,
@@ -1696,6 +1699,11 @@ impl<B, E, Block, RA> sp_consensus::BlockImport<Block> for &Client<B, E, Block,
 			return Ok(res)
 		}
 
+		// The block was verified and executed again, nothing is written to the database.
+		if import_block.check_only {
+			return Ok(ImportResult::AlreadyInChain)
+		}
+
 		self.lock_import_and_run(|operation| {
 			self.apply_block(operation, import_block, new_cache)
 		}).map_err(|e| {
@@ -0,0 +1,209 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Local Wasm runtimes executed instead of the on-chain runtime of the same version.
+//!
+//! The overrides are the `.wasm` files of a directory. Each is keyed by the spec version it
+//! reports, and only replaces an on-chain runtime with the same spec name and spec version.
+
+use std::{borrow::Cow, collections::HashMap, fs, path::{Path, PathBuf}};
+use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+use log::{debug, info, warn};
+use sc_executor::RuntimeInfo;
+use sp_core::traits::{FetchRuntimeCode, RuntimeCode};
+use sp_state_machine::BasicExternalities;
+use sp_version::RuntimeVersion;
+
+/// Heap pages used to read the version of an override.
+const VERSION_HEAP_PAGES: u64 = 128;
+
+/// The code of a local Wasm runtime.
+#[derive(Clone, Debug)]
+struct WasmBlob {
+	code: Vec<u8>,
+	hash: Vec<u8>,
+	path: PathBuf,
+	version: RuntimeVersion,
+	/// Whether the override was executed already, shared by the clones.
+	used: Arc<AtomicBool>,
+}
+
+impl WasmBlob {
+	fn runtime_code(&self, heap_pages: Option<u64>) -> RuntimeCode {
+		RuntimeCode { code_fetcher: self, hash: self.hash.clone(), heap_pages }
+	}
+}
+
+impl FetchRuntimeCode for WasmBlob {
+	fn fetch_runtime_code<'a>(&'a self) -> Option<Cow<'a, [u8]>> {
+		Some(self.code.as_slice().into())
+	}
+}
+
+/// The Wasm runtime overrides of a directory, by spec version.
+#[derive(Clone, Debug, Default)]
+pub struct WasmOverride {
+	overrides: HashMap<u32, WasmBlob>,
+}
+
+impl WasmOverride {
+	/// Load the `.wasm` files of `dir`.
+	///
+	/// The files whose runtime version can't be read are reported and skipped, as well as the files
+	/// with the same spec version as a file already loaded. Fails if `dir` can't be read.
+	pub fn new<E: RuntimeInfo>(dir: &Path, executor: &E) -> sp_blockchain::Result<Self> {
+		let read_error = |e: std::io::Error| sp_blockchain::Error::Msg(format!(
+			"Cannot read the Wasm runtime overrides in {}: {}",
+			dir.display(),
+			e,
+		));
+
+		let mut overrides = HashMap::new();
+		for entry in fs::read_dir(dir).map_err(read_error)? {
+			let path = entry.map_err(read_error)?.path();
+			if path.extension().map_or(true, |extension| extension != "wasm") {
+				continue;
+			}
+
+			let blob = match load_blob(&path, executor) {
+				Ok(blob) => blob,
+				Err(e) => {
+					warn!("Ignoring the Wasm runtime override {}: {}", path.display(), e);
+					continue;
+				},
+			};
+
+			match overrides.get(&blob.version.spec_version) {
+				Some(WasmBlob { path: loaded, .. }) => warn!(
+					"Ignoring the Wasm runtime override {}: {} has the same spec version {}",
+					path.display(),
+					loaded.display(),
+					blob.version.spec_version,
+				),
+				None => {
+					info!(
+						"Loaded the Wasm runtime override {} of {} version {}",
+						path.display(),
+						blob.version.spec_name,
+						blob.version.spec_version,
+					);
+					overrides.insert(blob.version.spec_version, blob);
+				},
+			}
+		}
+
+		Ok(WasmOverride { overrides })
+	}
+
+	/// Whether no override was loaded.
+	pub fn is_empty(&self) -> bool {
+		self.overrides.is_empty()
+	}
+
+	/// The override of the on-chain runtime of `version`, if any.
+	///
+	/// An override is only returned if it has the same spec name as the on-chain runtime.
+	pub fn get(&self, version: &RuntimeVersion, heap_pages: Option<u64>) -> Option<RuntimeCode> {
+		let blob = self.overrides.get(&version.spec_version)?;
+		if blob.version.spec_name != version.spec_name {
+			return None;
+		}
+
+		// The override is executed for every call, only the first one is logged at `info`.
+		if blob.used.swap(true, Ordering::Relaxed) {
+			debug!(
+				target: "wasm_overrides",
+				"Executing the Wasm runtime override {}",
+				blob.path.display(),
+			);
+		} else {
+			info!(
+				target: "wasm_overrides",
+				"Executing the Wasm runtime override {} instead of the on-chain runtime of {} \
+				version {}",
+				blob.path.display(),
+				version.spec_name,
+				version.spec_version,
+			);
+		}
+		Some(blob.runtime_code(heap_pages))
+	}
+}
+
+/// Read the Wasm runtime at `path` and its version.
+fn load_blob<E: RuntimeInfo>(path: &Path, executor: &E) -> Result<WasmBlob, String> {
+	let code = fs::read(path).map_err(|e| e.to_string())?;
+	let mut blob = WasmBlob {
+		hash: sp_core::blake2_256(&code).to_vec(),
+		code,
+		path: path.to_path_buf(),
+		version: Default::default(),
+		used: Default::default(),
+	};
+
+	let mut ext = BasicExternalities::default();
+	blob.version = executor.runtime_version(&mut ext, &blob.runtime_code(Some(VERSION_HEAP_PAGES)))
+		.map_err(|e| format!("cannot read its runtime version: {}", e))?;
+
+	Ok(blob)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use substrate_test_runtime_client::{new_native_executor, runtime::WASM_BINARY};
+
+	#[test]
+	fn overrides_are_loaded_by_spec_version() {
+		let dir = tempfile::tempdir().unwrap();
+		fs::write(dir.path().join("runtime.wasm"), WASM_BINARY).unwrap();
+		fs::write(dir.path().join("broken.wasm"), b"not wasm").unwrap();
+		fs::write(dir.path().join("notes.txt"), b"ignored").unwrap();
+
+		let executor = new_native_executor();
+		let overrides = WasmOverride::new(dir.path(), &executor).unwrap();
+		let onchain = executor.native_version().runtime_version.clone();
+
+		assert_eq!(overrides.overrides.len(), 1);
+		assert!(overrides.get(&onchain, None).is_some());
+		// Still returned once it was used.
+		assert!(overrides.get(&onchain, None).is_some());
+		let upgraded = RuntimeVersion { spec_version: onchain.spec_version + 1, ..onchain.clone() };
+		assert!(overrides.get(&upgraded, None).is_none());
+	}
+
+	#[test]
+	fn overrides_of_other_spec_names_are_not_used() {
+		let dir = tempfile::tempdir().unwrap();
+		fs::write(dir.path().join("runtime.wasm"), WASM_BINARY).unwrap();
+
+		let executor = new_native_executor();
+		let overrides = WasmOverride::new(dir.path(), &executor).unwrap();
+		let onchain = executor.native_version().runtime_version.clone();
+
+		let other = RuntimeVersion { spec_name: "other".into(), ..onchain };
+		assert!(overrides.get(&other, None).is_none());
+	}
+
+	#[test]
+	fn missing_directory_fails() {
+		let dir = tempfile::tempdir().unwrap();
+
+		assert!(WasmOverride::new(&dir.path().join("missing"), &new_native_executor()).is_err());
+	}
+}
@@ -18,7 +18,7 @@
 
 //! Service configuration.
 
-pub use sc_client_db::{Database, PruningMode, DatabaseSettingsSrc as DatabaseConfig};
+pub use sc_client_db::{Database, KeepBlocks, PruningMode, DatabaseSettingsSrc as DatabaseConfig};
 pub use sc_network::Multiaddr;
 pub use sc_network::config::{ExtTransport, MultiaddrWithPeerId, NetworkConfiguration, Role, NodeKeyConfig};
 pub use sc_executor::WasmExecutionMethod;
@@ -55,18 +55,26 @@ pub struct Configuration {
 	pub state_cache_child_ratio: Option<usize>,
 	/// Pruning settings.
 	pub pruning: PruningMode,
+	/// Block body pruning settings.
+	pub keep_blocks: KeepBlocks,
 	/// Chain configuration.
 	pub chain_spec: Box<dyn ChainSpec>,
 	/// Wasm execution method.
 	pub wasm_method: WasmExecutionMethod,
 	/// Execution strategies.
 	pub execution_strategies: ExecutionStrategies,
+	/// Directory of the Wasm runtimes executed instead of the on-chain runtimes of the same version.
+	pub wasm_runtime_overrides: Option<PathBuf>,
 	/// RPC over HTTP binding address. `None` if disabled.
 	pub rpc_http: Option<SocketAddr>,
 	/// RPC over Websockets binding address. `None` if disabled.
 	pub rpc_ws: Option<SocketAddr>,
+	/// Path of the IPC RPC endpoint, a Unix domain socket or a named pipe. `None` if disabled.
+	pub rpc_ipc: Option<PathBuf>,
 	/// Maximum number of connections for WebSockets RPC server. `None` if default.
 	pub rpc_ws_max_connections: Option<usize>,
+	/// Maximum size of the requests and responses of the RPC servers, in MiB. `None` if default.
+	pub rpc_max_payload: Option<usize>,
 	/// CORS settings for HTTP & WS servers. `None` if all origins are allowed.
 	pub rpc_cors: Option<Vec<String>>,
 	/// RPC methods to expose (by default only a safe subset or all of them).
@@ -102,6 +110,8 @@ pub struct Configuration {
 	pub max_runtime_instances: usize,
 	/// Announce block automatically after they have been imported
 	pub announce_block: bool,
+	/// Base path of the data of the node, if it has one on disk.
+	pub base_path: Option<BasePath>,
 }
 
 /// Type for tasks spawned by the executor.
@@ -174,6 +184,36 @@ impl Configuration {
 	}
 }
 
+/// The directory the data of a node is stored in.
+#[derive(Debug, Clone)]
+pub enum BasePath {
+	/// A temporary directory, removed once the last clone of it is dropped.
+	Temporary(Arc<tempfile::TempDir>),
+	/// A directory kept after the node stops.
+	Permanent(PathBuf),
+}
+
+impl BasePath {
+	/// Create a new unique temporary directory.
+	pub fn new_temp_dir() -> std::io::Result<BasePath> {
+		Ok(BasePath::Temporary(Arc::new(tempfile::Builder::new().prefix("substrate").tempdir()?)))
+	}
+
+	/// The path of the directory.
+	pub fn path(&self) -> &Path {
+		match self {
+			BasePath::Temporary(dir) => dir.path(),
+			BasePath::Permanent(path) => path,
+		}
+	}
+}
+
+impl From<PathBuf> for BasePath {
+	fn from(path: PathBuf) -> Self {
+		BasePath::Permanent(path)
+	}
+}
+
 /// Available RPC methods.
 #[derive(Debug, Copy, Clone)]
 pub enum RpcMethods {
@@ -62,14 +62,16 @@ use sp_utils::mpsc::{tracing_unbounded, TracingUnboundedReceiver,  TracingUnboun
 
 pub use self::error::Error;
 pub use self::builder::{
-	new_full_client, new_client,
+	new_full_client, new_client, new_database_with_genesis_state,
 	ServiceBuilder, ServiceBuilderCommand, TFullClient, TLightClient, TFullBackend, TLightBackend,
 	TFullCallExecutor, TLightCallExecutor, RpcExtensionBuilder,
 };
-pub use config::{Configuration, DatabaseConfig, PruningMode, Role, RpcMethods, TaskType};
+pub use config::{
+	BasePath, Configuration, DatabaseConfig, KeepBlocks, PruningMode, Role, RpcMethods, TaskType,
+};
 pub use sc_chain_spec::{
 	ChainSpec, GenericChainSpec, Properties, RuntimeGenesis, Extension as ChainSpecExtension,
-	NoExtension, ChainType,
+	NoExtension, ChainType, RawGenesisEntry, read_raw_genesis,
 };
 pub use sp_transaction_pool::{TransactionPool, InPoolTransaction, error::IntoPoolError};
 pub use sc_transaction_pool::txpool::Options as TransactionPoolOptions;
@@ -528,6 +530,42 @@ mod waiting {
 			}
 		}
 	}
+
+	pub struct IpcServer(pub Option<sc_rpc_server::IpcServer>, pub std::path::PathBuf);
+	impl Drop for IpcServer {
+		fn drop(&mut self) {
+			if let Some(server) = self.0.take() {
+				server.close_handle().close();
+				server.wait();
+				#[cfg(unix)]
+				let _ = std::fs::remove_file(&self.1);
+			}
+		}
+	}
+}
+
+/// Whether the unsafe RPC methods are denied on a server listening on `addr`.
+///
+/// `RpcMethods::Auto` only allows them on a loopback address.
+#[cfg(not(target_os = "unknown"))]
+fn deny_unsafe(addr: &SocketAddr, methods: &RpcMethods) -> sc_rpc::DenyUnsafe {
+	let is_exposed_addr = !addr.ip().is_loopback();
+	match (is_exposed_addr, methods) {
+		| (_, RpcMethods::Unsafe)
+		| (false, RpcMethods::Auto) => sc_rpc::DenyUnsafe::No,
+		_ => sc_rpc::DenyUnsafe::Yes
+	}
+}
+
+/// Whether the unsafe RPC methods are denied on the IPC server.
+///
+/// The IPC server is local, so `RpcMethods::Auto` allows them.
+#[cfg(not(target_os = "unknown"))]
+fn deny_unsafe_ipc(methods: &RpcMethods) -> sc_rpc::DenyUnsafe {
+	match methods {
+		RpcMethods::Safe => sc_rpc::DenyUnsafe::Yes,
+		RpcMethods::Auto | RpcMethods::Unsafe => sc_rpc::DenyUnsafe::No,
+	}
 }
 
 /// Starts RPC servers that run in their own thread, and returns an opaque object that keeps them alive.
@@ -554,20 +592,12 @@ fn start_rpc_servers<H: FnMut(sc_rpc::DenyUnsafe) -> sc_rpc_server::RpcHandler<s
 		})
 	}
 
-	fn deny_unsafe(addr: &SocketAddr, methods: &RpcMethods) -> sc_rpc::DenyUnsafe {
-		let is_exposed_addr = !addr.ip().is_loopback();
-		match (is_exposed_addr, methods) {
-			| (_, RpcMethods::Unsafe)
-			| (false, RpcMethods::Auto) => sc_rpc::DenyUnsafe::No,
-			_ => sc_rpc::DenyUnsafe::Yes
-		}
-	}
-
 	Ok(Box::new((
 		maybe_start_server(
 			config.rpc_http,
 			|address| sc_rpc_server::start_http(
 				address,
+				config.rpc_max_payload,
 				config.rpc_cors.as_ref(),
 				gen_handler(deny_unsafe(&address, &config.rpc_methods)),
 			),
@@ -576,11 +606,19 @@ fn start_rpc_servers<H: FnMut(sc_rpc::DenyUnsafe) -> sc_rpc_server::RpcHandler<s
 			config.rpc_ws,
 			|address| sc_rpc_server::start_ws(
 				address,
+				config.rpc_max_payload,
 				config.rpc_ws_max_connections,
 				config.rpc_cors.as_ref(),
 				gen_handler(deny_unsafe(&address, &config.rpc_methods)),
 			),
 		)?.map(|s| waiting::WsServer(Some(s))),
+		match &config.rpc_ipc {
+			Some(path) => Some(waiting::IpcServer(
+				Some(sc_rpc_server::start_ipc(path, gen_handler(deny_unsafe_ipc(&config.rpc_methods)))?),
+				path.clone(),
+			)),
+			None => None,
+		},
 	)))
 }
 
@@ -756,4 +794,48 @@ mod tests {
 		// this should not panic
 		let _ = transactions[0].1.transfer();
 	}
+
+	#[test]
+	fn unsafe_rpc_methods_follow_the_rpc_methods_and_the_address() {
+		let denied = |addr: &str, methods| matches!(
+			deny_unsafe(&addr.parse().unwrap(), &methods),
+			sc_rpc::DenyUnsafe::Yes
+		);
+
+		for localhost in &["127.0.0.1:9933", "[::1]:9944"] {
+			assert!(!denied(localhost, RpcMethods::Auto));
+			assert!(denied(localhost, RpcMethods::Safe));
+			assert!(!denied(localhost, RpcMethods::Unsafe));
+		}
+		for exposed in &["0.0.0.0:9933", "[::]:9944", "192.0.2.1:9933"] {
+			assert!(denied(exposed, RpcMethods::Auto));
+			assert!(denied(exposed, RpcMethods::Safe));
+			assert!(!denied(exposed, RpcMethods::Unsafe));
+		}
+
+		let denied_ipc = |methods| matches!(deny_unsafe_ipc(&methods), sc_rpc::DenyUnsafe::Yes);
+		assert!(!denied_ipc(RpcMethods::Auto));
+		assert!(denied_ipc(RpcMethods::Safe));
+		assert!(!denied_ipc(RpcMethods::Unsafe));
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn ipc_socket_is_private_and_removed_on_drop() {
+		use std::os::unix::fs::PermissionsExt;
+
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("node.ipc");
+		let handler = sc_rpc_server::RpcHandler::<sc_rpc::Metadata>::default();
+		let server = waiting::IpcServer(
+			Some(sc_rpc_server::start_ipc(&path, handler).unwrap()),
+			path.clone(),
+		);
+
+		let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+		assert_eq!(mode & 0o777, 0o600);
+
+		drop(server);
+		assert!(!path.exists());
+	}
 }
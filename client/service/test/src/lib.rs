@@ -34,7 +34,7 @@ use sc_service::{
 	GenericChainSpec,
 	ChainSpecExtension,
 	Configuration,
-	config::{DatabaseConfig, KeystoreConfig},
+	config::{DatabaseConfig, KeepBlocks, KeystoreConfig},
 	RuntimeGenesis,
 	Role,
 	Error,
@@ -190,12 +190,16 @@ fn node_config<G: RuntimeGenesis + 'static, E: ChainSpecExtension + Clone + 'sta
 		state_cache_size: 16777216,
 		state_cache_child_ratio: None,
 		pruning: Default::default(),
+		keep_blocks: KeepBlocks::All,
 		chain_spec: Box::new((*spec).clone()),
 		wasm_method: sc_service::config::WasmExecutionMethod::Interpreted,
+		wasm_runtime_overrides: None,
 		execution_strategies: Default::default(),
 		rpc_http: None,
 		rpc_ws: None,
+		rpc_ipc: None,
 		rpc_ws_max_connections: None,
+		rpc_max_payload: None,
 		rpc_cors: None,
 		rpc_methods: Default::default(),
 		prometheus_config: None,
@@ -210,6 +214,7 @@ fn node_config<G: RuntimeGenesis + 'static, E: ChainSpecExtension + Clone + 'sta
 		tracing_receiver: Default::default(),
 		max_runtime_instances: 8,
 		announce_block: true,
+		base_path: Some(root.into()),
 	}
 }
 
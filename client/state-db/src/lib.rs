@@ -46,11 +46,26 @@ use log::trace;
 use parity_util_mem::{MallocSizeOf, malloc_size};
 use sc_client_api::{StateDbMemoryInfo, MemorySize};
 
-const PRUNING_MODE: &[u8] = b"mode";
+use meta_keys::PRUNING_MODE;
+
 const PRUNING_MODE_ARCHIVE: &[u8] = b"archive";
 const PRUNING_MODE_ARCHIVE_CANON: &[u8] = b"archive_canonical";
 const PRUNING_MODE_CONSTRAINED: &[u8] = b"constrained";
 
+/// Keys of the metadata stored by the state database, see `MetaDb`.
+pub mod meta_keys {
+	/// Pruning mode the database was created with, see `PruningMode::id`.
+	pub const PRUNING_MODE: &[u8] = b"mode";
+	/// Last canonicalized block, as `(hash, number)`.
+	pub const LAST_CANONICAL: &[u8] = b"last_canonical";
+	/// Suffix of the journal keys of the non-canonical blocks, after `(number, index)`.
+	pub const NON_CANONICAL_JOURNAL: &[u8] = b"noncanonical_journal";
+	/// Number of the last pruned block.
+	pub const LAST_PRUNED: &[u8] = b"last_pruned";
+	/// Suffix of the pruning journal keys, after the block number.
+	pub const PRUNING_JOURNAL: &[u8] = b"pruning_journal";
+}
+
 /// Database value type.
 pub type DBValue = Vec<u8>;
 
@@ -27,11 +27,9 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 use codec::{Encode, Decode};
 use crate::{CommitSet, Error, MetaDb, to_meta_key, Hash};
+use crate::meta_keys::{LAST_PRUNED, PRUNING_JOURNAL};
 use log::{trace, warn};
 
-const LAST_PRUNED: &[u8] = b"last_pruned";
-const PRUNING_JOURNAL: &[u8] = b"pruning_journal";
-
 /// See module documentation.
 #[derive(parity_util_mem_derive::MallocSizeOf)]
 pub struct RefWindow<BlockHash: Hash, Key: Hash> {
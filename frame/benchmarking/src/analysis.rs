@@ -22,9 +22,12 @@ use linregress::{FormulaRegressionBuilder, RegressionDataBuilder, RegressionMode
 use crate::BenchmarkResults;
 
 pub struct Analysis {
-	base: u128,
-	slopes: Vec<u128>,
-	names: Vec<String>,
+	/// The time taken with all components at zero, in nanoseconds.
+	pub base: u128,
+	/// The time added by each unit of a component, in nanoseconds.
+	pub slopes: Vec<u128>,
+	/// The names of the components, in the order of `slopes`.
+	pub names: Vec<String>,
 	value_dists: Option<Vec<(Vec<u32>, u128, u128)>>,
 	model: Option<RegressionModel>,
 }
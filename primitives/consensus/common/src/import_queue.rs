@@ -72,6 +72,8 @@ pub struct IncomingBlock<B: BlockT> {
 	pub allow_missing_state: bool,
 	/// Re-validate existing block.
 	pub import_existing: bool,
+	/// Verify and execute a block already in the chain without importing it again.
+	pub check_only: bool,
 }
 
 /// Type of keys in the blockchain cache that consensus module could use for its needs.
@@ -258,6 +260,7 @@ pub fn import_single_block<B: BlockT, V: Verifier<B>, Transaction>(
 		cache.extend(keys.into_iter());
 	}
 	import_block.allow_missing_state = block.allow_missing_state;
+	import_block.check_only = block.check_only;
 
 	import_error(import_handle.import_block(import_block.convert_transaction(), cache))
 }
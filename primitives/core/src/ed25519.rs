@@ -565,6 +565,36 @@ impl CryptoType for Pair {
 	type Pair = Pair;
 }
 
+/// Batch verification.
+///
+/// `messages`, `signatures` and `pub_keys` should all have equal length.
+///
+/// Returns `true` if all signatures are correct, `false` otherwise.
+#[cfg(feature = "std")]
+pub fn verify_batch(
+	messages: Vec<&[u8]>,
+	signatures: Vec<&Signature>,
+	pub_keys: Vec<&Public>,
+) -> bool {
+	let mut ed_pub_keys = Vec::with_capacity(pub_keys.len());
+	for pub_key in pub_keys {
+		match ed25519_dalek::PublicKey::from_bytes(&pub_key.0[..]) {
+			Ok(pk) => ed_pub_keys.push(pk),
+			Err(_) => return false,
+		};
+	}
+
+	let mut ed_signatures = Vec::with_capacity(signatures.len());
+	for signature in signatures {
+		match ed25519_dalek::Signature::from_bytes(&signature.0[..]) {
+			Ok(s) => ed_signatures.push(s),
+			Err(_) => return false,
+		};
+	}
+
+	ed25519_dalek::verify_batch(&messages, &ed_signatures, &ed_pub_keys).is_ok()
+}
+
 #[cfg(test)]
 mod test {
 	use super::*;
@@ -696,6 +726,20 @@ mod test {
 		assert!(Pair::verify(&signature, &message[..], &pair.public()));
 	}
 
+	#[test]
+	fn verify_batch_works() {
+		let pairs = (0u8..4).map(|i| Pair::from_seed(&[i; 32])).collect::<Vec<_>>();
+		let messages = [&b"first"[..], b"second", b"third", b"fourth"];
+		let signatures = pairs.iter().zip(&messages).map(|(pair, message)| pair.sign(message)).collect::<Vec<_>>();
+		let publics = pairs.iter().map(|pair| pair.public()).collect::<Vec<_>>();
+
+		assert!(verify_batch(messages.to_vec(), signatures.iter().collect(), publics.iter().collect()));
+
+		let mut swapped = publics.clone();
+		swapped.swap(0, 1);
+		assert!(!verify_batch(messages.to_vec(), signatures.iter().collect(), swapped.iter().collect()));
+	}
+
 	#[test]
 	fn signature_serialization_doesnt_panic() {
 		fn deserialize_signature(text: &str) -> Result<Signature, serde_json::error::Error> {
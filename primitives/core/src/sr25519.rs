@@ -58,6 +58,12 @@ const SIGNING_CTX: &[u8] = b"substrate";
 /// An identifier used to match public keys against sr25519 keys
 pub const CRYPTO_ID: CryptoTypeId = CryptoTypeId(*b"sr25");
 
+/// The byte length of the output of a VRF.
+pub const VRF_OUTPUT_LENGTH: usize = 32;
+
+/// The byte length of the proof of a VRF output.
+pub const VRF_PROOF_LENGTH: usize = 64;
+
 /// An Schnorrkel/Ristretto x25519 ("sr25519") public key.
 #[cfg_attr(feature = "full_crypto", derive(Hash))]
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Encode, Decode, Default, PassByInner)]
@@ -599,6 +605,37 @@ impl Pair {
 			Err(_) => false,
 		}
 	}
+
+	/// Evaluate the VRF of this key on `message` in the signing context `context`.
+	///
+	/// Returns the output of the VRF and its proof, checked by `vrf_verify`.
+	pub fn vrf_sign(
+		&self,
+		context: &[u8],
+		message: &[u8],
+	) -> ([u8; VRF_OUTPUT_LENGTH], [u8; VRF_PROOF_LENGTH]) {
+		let (inout, proof, _) = self.0.vrf_sign(signing_context(context).bytes(message));
+		(inout.to_output().to_bytes(), proof.to_bytes())
+	}
+
+	/// Verify that `output` is the output of the VRF of `public` on `message` in the signing
+	/// context `context`, as proven by `proof`.
+	pub fn vrf_verify(
+		public: &Public,
+		context: &[u8],
+		message: &[u8],
+		output: &[u8; VRF_OUTPUT_LENGTH],
+		proof: &[u8; VRF_PROOF_LENGTH],
+	) -> bool {
+		let verify = || -> Result<_, schnorrkel::SignatureError> {
+			let public = PublicKey::from_bytes(public.as_ref())?;
+			let output = schnorrkel::vrf::VRFOutput::from_bytes(output)?;
+			let proof = schnorrkel::vrf::VRFProof::from_bytes(proof)?;
+			public.vrf_verify(signing_context(context).bytes(message), &output, &proof)
+		};
+
+		verify().is_ok()
+	}
 }
 
 impl CryptoType for Public {
@@ -848,6 +885,21 @@ mod test {
 		assert_eq!(cmp, public);
 	}
 
+	#[test]
+	fn vrf_sign_and_verify_work() {
+		let pair = Pair::from_seed(b"12345678901234567890123456789012");
+		let (output, proof) = pair.vrf_sign(b"context", b"message");
+
+		assert!(Pair::vrf_verify(&pair.public(), b"context", b"message", &output, &proof));
+		assert!(!Pair::vrf_verify(&pair.public(), b"context", b"other message", &output, &proof));
+		assert!(!Pair::vrf_verify(&pair.public(), b"other context", b"message", &output, &proof));
+
+		let other = Pair::from_seed(b"09876543210987654321098765432109");
+		assert!(!Pair::vrf_verify(&other.public(), b"context", b"message", &output, &proof));
+		// The output is deterministic, unlike the proof.
+		assert_eq!(pair.vrf_sign(b"context", b"message").0, output);
+	}
+
 	#[test]
 	fn verify_from_old_wasm_works() {
 		// The values in this test case are compared to the output of `node-test.js` in schnorrkel-js.
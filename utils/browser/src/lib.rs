@@ -21,7 +21,7 @@ use std::sync::Arc;
 use sc_network::config::TransportConfig;
 use sc_service::{
 	AbstractService, RpcSession, Role, Configuration,
-	config::{DatabaseConfig, KeystoreConfig, NetworkConfiguration},
+	config::{DatabaseConfig, KeepBlocks, KeystoreConfig, NetworkConfiguration},
 	GenericChainSpec, RuntimeGenesis
 };
 use wasm_bindgen::prelude::*;
@@ -84,10 +84,13 @@ where
 		offchain_worker: Default::default(),
 		prometheus_config: Default::default(),
 		pruning: Default::default(),
+		keep_blocks: KeepBlocks::All,
 		rpc_cors: Default::default(),
 		rpc_http: Default::default(),
 		rpc_ws: Default::default(),
+		rpc_ipc: Default::default(),
 		rpc_ws_max_connections: Default::default(),
+		rpc_max_payload: Default::default(),
 		rpc_methods: Default::default(),
 		state_cache_child_ratio: Default::default(),
 		state_cache_size: Default::default(),
@@ -95,8 +98,10 @@ where
 		tracing_targets: Default::default(),
 		transaction_pool: Default::default(),
 		wasm_method: Default::default(),
+		wasm_runtime_overrides: None,
 		max_runtime_instances: 8,
 		announce_block: true,
+		base_path: None,
 	};
 
 	Ok(config)
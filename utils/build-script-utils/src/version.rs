@@ -39,7 +39,11 @@ pub fn generate_cargo_keys() {
 		},
 	};
 
-	println!("cargo:rustc-env=SUBSTRATE_CLI_IMPL_VERSION={}", get_version(&commit))
+	println!("cargo:rustc-env=SUBSTRATE_CLI_IMPL_VERSION={}", get_version(&commit));
+	println!("cargo:rustc-env=SUBSTRATE_CLI_COMMIT={}", commit);
+	// Set by cargo for build scripts.
+	println!("cargo:rustc-env=SUBSTRATE_CLI_PROFILE={}", std::env::var("PROFILE").unwrap_or_default());
+	println!("cargo:rustc-env=SUBSTRATE_CLI_TARGET={}", std::env::var("TARGET").unwrap_or_default());
 }
 
 fn get_platform() -> String {
@@ -0,0 +1,53 @@
+//! Weights generated by the Substrate benchmark CLI.
+//!
+//! THIS FILE WAS AUTO-GENERATED, DO NOT EDIT.
+//!
+//! DATE: 2020-06-01
+//! COMMAND: `substrate benchmark --pallet * --extrinsic *`
+
+/// Weight functions of `pallet_balances`.
+pub mod pallet_balances {
+	use frame_support::weights::Weight;
+
+	/// The weight of each benchmarked extrinsic.
+	pub trait WeightInfo {
+		fn transfer(u: u32, e: u32) -> Weight;
+		fn set_balance() -> Weight;
+	}
+
+	/// Weights measured on the benchmarking machine.
+	pub struct SubstrateWeight;
+
+	impl WeightInfo for SubstrateWeight {
+		fn transfer(u: u32, e: u32) -> Weight {
+			let base: Weight = 1_002_000;
+			base
+				.saturating_add(Weight::from(u).saturating_mul(10_000))
+				.saturating_add(Weight::from(e).saturating_mul(100_000))
+		}
+		fn set_balance() -> Weight {
+			2_100_000
+		}
+	}
+}
+
+/// Weight functions of `pallet_identity`.
+pub mod pallet_identity {
+	use frame_support::weights::Weight;
+
+	/// The weight of each benchmarked extrinsic.
+	pub trait WeightInfo {
+		fn add_registrar(r: u32) -> Weight;
+	}
+
+	/// Weights measured on the benchmarking machine.
+	pub struct SubstrateWeight;
+
+	impl WeightInfo for SubstrateWeight {
+		fn add_registrar(r: u32) -> Weight {
+			let base: Weight = 2_981_000;
+			base
+				.saturating_add(Weight::from(r).saturating_mul(20_000))
+		}
+	}
+}
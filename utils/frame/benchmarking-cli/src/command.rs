@@ -15,10 +15,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::BenchmarkCmd;
+use crate::{stats, writer, BenchmarkCmd, BenchmarkOutput};
 use codec::{Decode, Encode};
 use frame_benchmarking::{Analysis, BenchmarkBatch};
-use sc_cli::{SharedParams, CliConfiguration, ExecutionStrategy, Result};
+use sc_cli::{SharedParams, CliConfiguration, Error, ExecutionStrategy, Result};
 use sc_client_db::BenchmarkingState;
 use sc_executor::NativeExecutor;
 use sp_state_machine::StateMachine;
@@ -34,6 +34,7 @@ use sp_core::{
 	offchain::{OffchainExt, testing::TestOffchainExt},
 };
 use std::fmt::Debug;
+use std::time::{Duration, Instant};
 
 impl BenchmarkCmd {
 	/// Runs the command and benchmarks the chain.
@@ -49,8 +50,6 @@ impl BenchmarkCmd {
 		let strategy = self.execution.unwrap_or(ExecutionStrategy::Native);
 
 		let genesis_storage = spec.build_storage()?;
-		let mut changes = Default::default();
-		let mut offchain_changes = Default::default();
 		let cache_size = Some(self.database_cache_size as usize);
 		let state = BenchmarkingState::<BB>::new(genesis_storage, cache_size)?;
 		let executor = NativeExecutor::<ExecDispatch>::new(
@@ -59,81 +58,196 @@ impl BenchmarkCmd {
 			2, // The runtime instances cache size.
 		);
 
-		let mut extensions = Extensions::default();
-		extensions.register(KeystoreExt(KeyStore::new()));
-		let (offchain, _) = TestOffchainExt::new();
-		extensions.register(OffchainExt::new(offchain));
-
-		let result = StateMachine::<_, _, NumberFor<BB>, _>::new(
-			&state,
-			None,
-			&mut changes,
-			&mut offchain_changes,
-			&executor,
-			"Benchmark_dispatch_benchmark",
-			&(
-				&self.pallet,
-				&self.extrinsic,
-				self.lowest_range_values.clone(),
-				self.highest_range_values.clone(),
-				self.steps.clone(),
-				self.repeat,
-			).encode(),
-			extensions,
-			&sp_state_machine::backend::BackendRuntimeCode::new(&state).runtime_code()?,
-			tasks::executor(),
-		)
-		.execute(strategy.into())
-		.map_err(|e| format!("Error executing runtime benchmark: {:?}", e))?;
-
-		let results = <std::result::Result<Vec<BenchmarkBatch>, String> as Decode>::decode(&mut &result[..])
-			.map_err(|e| format!("Failed to decode benchmark results: {:?}", e))?;
-
-		match results {
-			Ok(batches) => for batch in batches.into_iter() {
-				// Print benchmark metadata
-				println!(
-					"Pallet: {:?}, Extrinsic: {:?}, Lowest values: {:?}, Highest values: {:?}, Steps: {:?}, Repeat: {:?}",
-					String::from_utf8(batch.pallet).expect("Encoded from String; qed"),
-					String::from_utf8(batch.benchmark).expect("Encoded from String; qed"),
-					self.lowest_range_values,
-					self.highest_range_values,
-					self.steps,
-					self.repeat,
-				);
-
-				// Skip raw data + analysis if there are no results
-				if batch.results.len() == 0 { continue }
-
-				if self.raw_data {
-					// Print the table header
-					batch.results[0].0.iter().for_each(|param| print!("{:?},", param.0));
-
-					print!("extrinsic_time,storage_root_time\n");
-					// Print the values
-					batch.results.iter().for_each(|result| {
-						let parameters = &result.0;
-						parameters.iter().for_each(|param| print!("{:?},", param.1));
-						// Print extrinsic time and storage root time
-						print!("{:?},{:?}\n", result.1, result.2);
-					});
-
-					println!();
-				}
-
-				// Conduct analysis.
-				if !self.no_median_slopes {
-					if let Some(analysis) = Analysis::median_slopes(&batch.results) {
-						println!("Median Slopes Analysis\n========\n{}", analysis);
-					}
-				}
-				if !self.no_min_squares {
-					if let Some(analysis) = Analysis::min_squares_iqr(&batch.results) {
-						println!("Min Squares Analysis\n========\n{}", analysis);
-					}
-				}
+		// Run the benchmarks of the runtime matching `pallet` and `extrinsic`.
+		let dispatch = |pallet: &[u8], extrinsic: &[u8], repeat: u32| -> Result<Vec<BenchmarkBatch>> {
+			let mut changes = Default::default();
+			let mut offchain_changes = Default::default();
+
+			let mut extensions = Extensions::default();
+			extensions.register(KeystoreExt(KeyStore::new()));
+			let (offchain, _) = TestOffchainExt::new();
+			extensions.register(OffchainExt::new(offchain));
+
+			let result = StateMachine::<_, _, NumberFor<BB>, _>::new(
+				&state,
+				None,
+				&mut changes,
+				&mut offchain_changes,
+				&executor,
+				"Benchmark_dispatch_benchmark",
+				&(
+					pallet,
+					extrinsic,
+					self.lowest_range_values.clone(),
+					self.highest_range_values.clone(),
+					self.steps.clone(),
+					repeat,
+				).encode(),
+				extensions,
+				&sp_state_machine::backend::BackendRuntimeCode::new(&state).runtime_code()?,
+				tasks::executor(),
+			)
+			.execute(strategy.into())
+			.map_err(|e| format!("Error executing runtime benchmark: {:?}", e))?;
+
+			let results = <std::result::Result<Vec<BenchmarkBatch>, String> as Decode>::decode(&mut &result[..])
+				.map_err(|e| format!("Failed to decode benchmark results: {:?}", e))?;
+
+			results.map_err(Into::into)
+		};
+
+		let wildcard = self.pallet == "*" || self.extrinsic == "*";
+		let targets = if wildcard {
+			// Without repetitions the runtime only reports the benchmarks it knows about.
+			dispatch(b"*", b"*", 0)?
+				.into_iter()
+				.map(|batch| (batch.pallet, batch.benchmark))
+				.filter(|(pallet, extrinsic)| {
+					(self.pallet == "*" || pallet[..] == *self.pallet.as_bytes())
+						&& (self.extrinsic == "*" || extrinsic[..] == *self.extrinsic.as_bytes())
+						&& !self.exclude_pallet.iter().any(|excluded| pallet[..] == *excluded.as_bytes())
+				})
+				.collect::<Vec<_>>()
+		} else {
+			vec![(self.pallet.as_bytes().to_vec(), self.extrinsic.as_bytes().to_vec())]
+		};
+
+		if targets.is_empty() {
+			return Err(Error::Input(format!(
+				"No benchmark found for pallet {:?} and extrinsic {:?}",
+				self.pallet,
+				self.extrinsic,
+			)));
+		}
+
+		let mut batches = Vec::new();
+		let mut failures = Vec::new();
+		let mut pallet_times = Vec::<(String, Duration)>::new();
+
+		for (pallet, extrinsic) in targets {
+			let pallet_name = String::from_utf8_lossy(&pallet).into_owned();
+			let extrinsic_name = String::from_utf8_lossy(&extrinsic).into_owned();
+
+			let started = Instant::now();
+			let result = dispatch(&pallet, &extrinsic, self.repeat);
+			let elapsed = started.elapsed();
+
+			match pallet_times.iter_mut().find(|(name, _)| *name == pallet_name) {
+				Some((_, time)) => *time += elapsed,
+				None => pallet_times.push((pallet_name.clone(), elapsed)),
+			}
+
+			match result {
+				Ok(results) => for batch in results {
+					self.print_batch(&batch);
+					batches.push(batch);
+				},
+				Err(error) => {
+					eprintln!("Benchmark {}::{} failed: {}", pallet_name, extrinsic_name, error);
+					failures.push((pallet_name, extrinsic_name, error));
+				},
+			}
+		}
+
+		self.write_outputs(&batches)?;
+
+		if wildcard {
+			println!("\n{:<24} {:>8}", "Pallet", "Time");
+			for (pallet, time) in &pallet_times {
+				println!("{:<24} {:>7.1}s", pallet, time.as_secs_f64());
+			}
+		}
+
+		if failures.is_empty() {
+			Ok(())
+		} else {
+			eprintln!("\n{} benchmarks failed:", failures.len());
+			for (pallet, extrinsic, error) in &failures {
+				eprintln!("  {}::{}: {}", pallet, extrinsic, error);
+			}
+
+			Err(Error::Other(format!("{} benchmarks failed", failures.len())))
+		}
+	}
+
+	/// Print the results and analysis of a single benchmark.
+	fn print_batch(&self, batch: &BenchmarkBatch) {
+		// Print benchmark metadata
+		println!(
+			"Pallet: {:?}, Extrinsic: {:?}, Lowest values: {:?}, Highest values: {:?}, Steps: {:?}, Repeat: {:?}",
+			String::from_utf8(batch.pallet.clone()).expect("Encoded from String; qed"),
+			String::from_utf8(batch.benchmark.clone()).expect("Encoded from String; qed"),
+			self.lowest_range_values,
+			self.highest_range_values,
+			self.steps,
+			self.repeat,
+		);
+
+		// Skip raw data + analysis if there are no results
+		if batch.results.len() == 0 { return }
+
+		if self.raw_data {
+			// Print the table header
+			batch.results[0].0.iter().for_each(|param| print!("{:?},", param.0));
+
+			print!("extrinsic_time,storage_root_time\n");
+			// Print the values
+			batch.results.iter().for_each(|result| {
+				let parameters = &result.0;
+				parameters.iter().for_each(|param| print!("{:?},", param.1));
+				// Print extrinsic time and storage root time
+				print!("{:?},{:?}\n", result.1, result.2);
+			});
+
+			println!();
+		}
+
+		if self.repeat > 1 {
+			stats::print_point_stats(&batch.results, self.max_rel_stddev);
+		}
+
+		// Conduct analysis.
+		if !self.no_median_slopes {
+			if let Some(analysis) = Analysis::median_slopes(&batch.results) {
+				println!("Median Slopes Analysis\n========\n{}", analysis);
+			}
+		}
+		if !self.no_min_squares {
+			if let Some(analysis) = Analysis::min_squares_iqr(&batch.results) {
+				println!("Min Squares Analysis\n========\n{}", analysis);
+			}
+		}
+	}
+
+	/// Write the results to the files requested with `--output`, `--json` and `--csv`.
+	fn write_outputs(&self, batches: &[BenchmarkBatch]) -> Result<()> {
+		if let Some(path) = &self.output {
+			let args = std::env::args().collect::<Vec<_>>();
+			let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+			let template = match &self.template {
+				Some(template) => Some((template.display().to_string(), std::fs::read_to_string(template)?)),
+				None => None,
+			};
+			let template = template.as_ref().map(|(name, template)| (name.as_str(), template.as_str()));
+
+			std::fs::write(path, writer::render(batches, &args, &date, template)?)?;
+			println!("Weights written to {}", path.display());
+		}
+
+		let output = BenchmarkOutput::new(batches);
+		match &self.json {
+			Some(Some(path)) => {
+				let file = std::fs::File::create(path)?;
+				serde_json::to_writer_pretty(file, &output).map_err(|e| e.to_string())?;
 			},
-			Err(error) => eprintln!("Error: {:?}", error),
+			Some(None) => println!(
+				"{}",
+				serde_json::to_string_pretty(&output).map_err(|e| e.to_string())?,
+			),
+			None => {},
+		}
+		if let Some(path) = &self.csv {
+			std::fs::write(path, output.to_csv())?;
 		}
 
 		Ok(())
@@ -16,14 +16,27 @@
 // limitations under the License.
 
 mod command;
+mod machine;
+mod output;
+mod stats;
+mod storage;
+mod writer;
+
+pub use output::{BenchmarkOutput, BenchmarkOutputEntry, BenchmarkSample, OUTPUT_VERSION};
+pub use machine::{MachineCmd, Metric, Requirement, REFERENCE_HARDWARE};
+pub use storage::StorageCmd;
 
 use sc_cli::{ExecutionStrategy, WasmExecutionMethod};
 use std::fmt::Debug;
+use std::path::PathBuf;
 
 /// The `benchmark` command used to benchmark FRAME Pallets.
 #[derive(Debug, structopt::StructOpt, Clone)]
 pub struct BenchmarkCmd {
-	/// Select a FRAME Pallet to benchmark, or `*` for all (in which case `extrinsic` must be `*`).
+	/// Select a FRAME Pallet to benchmark, or `*` for all.
+	///
+	/// With `*`, a failing benchmark doesn't stop the others and a summary of the time spent on
+	/// each pallet is printed at the end.
 	#[structopt(short, long)]
 	pub pallet: String,
 
@@ -31,7 +44,14 @@ pub struct BenchmarkCmd {
 	#[structopt(short, long)]
 	pub extrinsic: String,
 
+	/// Pallets left out when benchmarking all pallets with `*`.
+	#[structopt(long, value_name = "PALLET", use_delimiter = true)]
+	pub exclude_pallet: Vec<String>,
+
 	/// Select how many samples we should take across the variable components.
+	///
+	/// Takes one value per component, components without a value use the value of the previous
+	/// one, and the first defaults to 10.
 	#[structopt(short, long, use_delimiter = true)]
 	pub steps: Vec<u32>,
 
@@ -44,9 +64,16 @@ pub struct BenchmarkCmd {
 	pub highest_range_values: Vec<u32>,
 
 	/// Select how many repetitions of this benchmark should run.
+	///
+	/// Every point is sampled this many times. With more than one repetition, statistics of
+	/// the samples are printed for each point.
 	#[structopt(short, long, default_value = "1")]
 	pub repeat: u32,
 
+	/// Flag the points whose standard deviation exceeds this percentage of their mean.
+	#[structopt(long, value_name = "PERCENT", default_value = "10")]
+	pub max_rel_stddev: f64,
+
 	/// Print the raw results.
 	#[structopt(long = "raw")]
 	pub raw_data: bool,
@@ -59,6 +86,23 @@ pub struct BenchmarkCmd {
 	#[structopt(long)]
 	pub no_min_squares: bool,
 
+	/// Write the weights of the benchmarked extrinsics to this file, as a Rust module.
+	#[structopt(long, parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	/// Render the weights written with `--output` through this Handlebars template instead of the
+	/// built-in one.
+	#[structopt(long, value_name = "PATH", parse(from_os_str), requires = "output")]
+	pub template: Option<PathBuf>,
+
+	/// Write every sample as JSON to this file, or to stdout after the summary if no file is given.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	pub json: Option<Option<PathBuf>>,
+
+	/// Write every sample as CSV to this file.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	pub csv: Option<PathBuf>,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub shared_params: sc_cli::SharedParams,
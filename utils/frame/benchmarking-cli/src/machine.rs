@@ -0,0 +1,395 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmark of the hardware a node runs on, compared against reference hardware.
+
+use rand::{Rng, RngCore};
+use sc_cli::{CliConfiguration, Error, Result, SharedParams};
+use sc_service::Configuration;
+use sp_core::{sr25519, Pair};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::Instant;
+use structopt::StructOpt;
+
+const MIB: usize = 1024 * 1024;
+/// Amount of data hashed, copied, written and read by the workloads.
+const DATA_SIZE: usize = 256 * MIB;
+/// Number of signatures verified.
+const SIGNATURES: usize = 2_000;
+/// Size of the blocks written and read at random offsets.
+const RANDOM_BLOCK_SIZE: usize = 4096;
+/// Name of the scratch file the disk workloads write to and read from.
+const SCRATCH_FILE: &str = "machine-benchmark.tmp";
+
+/// A measured property of the hardware.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+	/// Throughput of BLAKE2-256 hashing.
+	Blake2256,
+	/// Number of sr25519 signatures verified per second.
+	Sr25519Verify,
+	/// Throughput of copying memory.
+	MemCopy,
+	/// Throughput of sequential writes to disk.
+	DiskSeqWrite,
+	/// Throughput of sequential reads from disk, bypassing the OS cache.
+	DiskSeqRead,
+	/// Throughput of writes of 4 KiB blocks at random offsets on disk.
+	DiskRndWrite,
+	/// Throughput of reads of 4 KiB blocks at random offsets from disk, bypassing the OS cache.
+	DiskRndRead,
+}
+
+impl Metric {
+	/// The unit the metric is measured in.
+	pub fn unit(&self) -> &'static str {
+		match self {
+			Metric::Sr25519Verify => "verifications/s",
+			_ => "MiB/s",
+		}
+	}
+}
+
+impl fmt::Display for Metric {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(match self {
+			Metric::Blake2256 => "BLAKE2-256",
+			Metric::Sr25519Verify => "SR25519-Verify",
+			Metric::MemCopy => "Copy",
+			Metric::DiskSeqWrite => "Seq Write",
+			Metric::DiskSeqRead => "Seq Read",
+			Metric::DiskRndWrite => "Rnd Write",
+			Metric::DiskRndRead => "Rnd Read",
+		})
+	}
+}
+
+/// The value a metric is expected to reach.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Requirement {
+	/// The measured metric.
+	pub metric: Metric,
+	/// The expected value, in the unit of the metric.
+	pub expected: f64,
+}
+
+/// The values measured on the reference hardware.
+///
+/// Chains expecting different hardware of their validators pass their own table to
+/// [`MachineCmd::run_with`].
+pub const REFERENCE_HARDWARE: &[Requirement] = &[
+	Requirement { metric: Metric::Blake2256, expected: 1_000.0 },
+	Requirement { metric: Metric::Sr25519Verify, expected: 20_000.0 },
+	Requirement { metric: Metric::MemCopy, expected: 10_000.0 },
+	Requirement { metric: Metric::DiskSeqWrite, expected: 900.0 },
+	Requirement { metric: Metric::DiskSeqRead, expected: 1_500.0 },
+	Requirement { metric: Metric::DiskRndWrite, expected: 400.0 },
+	Requirement { metric: Metric::DiskRndRead, expected: 50.0 },
+];
+
+/// The `benchmark-machine` command used to compare the hardware against reference hardware.
+#[derive(Debug, StructOpt, Clone)]
+pub struct MachineCmd {
+	/// Stop at the first metric falling short of its expected value and exit with an error.
+	#[structopt(long)]
+	pub fail_fast: bool,
+
+	/// How far below its expected value a metric may fall, in percent.
+	#[structopt(long, value_name = "PERCENT", default_value = "10")]
+	pub tolerance: f64,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+/// A measured metric next to its requirement.
+#[derive(Debug)]
+struct Measurement {
+	requirement: Requirement,
+	measured: f64,
+}
+
+impl MachineCmd {
+	/// Run the benchmark against the reference hardware.
+	pub fn run(&self, config: Configuration) -> Result<()> {
+		self.run_with(config, REFERENCE_HARDWARE)
+	}
+
+	/// Run the benchmark against the given requirements.
+	///
+	/// The disk workloads write to and read from the filesystem of the database of `config`.
+	pub fn run_with(&self, config: Configuration, requirements: &[Requirement]) -> Result<()> {
+		if !(0.0..=100.0).contains(&self.tolerance) {
+			return Err(Error::Input("`--tolerance` must be between 0 and 100".into()));
+		}
+
+		let dir = config.database.path()
+			.ok_or_else(|| Error::Input("The machine benchmark requires a database on disk".into()))?
+			.to_path_buf();
+		fs::create_dir_all(&dir)?;
+
+		let mut measurements = Vec::with_capacity(requirements.len());
+		for requirement in requirements {
+			let measured = measure(requirement.metric, &dir)?;
+			let passed = passes(measured, requirement.expected, self.tolerance);
+			measurements.push(Measurement { requirement: *requirement, measured });
+
+			if self.fail_fast && !passed {
+				print_measurements(&measurements, self.tolerance);
+				return Err(Error::Other(format!(
+					"{} is {:.2} {}, below the expected {:.2} {}",
+					requirement.metric,
+					measured,
+					requirement.metric.unit(),
+					requirement.expected,
+					requirement.metric.unit(),
+				)));
+			}
+		}
+
+		print_measurements(&measurements, self.tolerance);
+
+		Ok(())
+	}
+}
+
+impl CliConfiguration for MachineCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
+
+/// Whether `measured` is within `tolerance` percent below `expected`, or above it.
+fn passes(measured: f64, expected: f64, tolerance: f64) -> bool {
+	measured >= expected * (1.0 - tolerance / 100.0)
+}
+
+/// Run the workload of `metric`, using a scratch file in `dir` for the disk workloads.
+fn measure(metric: Metric, dir: &Path) -> Result<f64> {
+	Ok(match metric {
+		Metric::Blake2256 => hash_throughput(DATA_SIZE),
+		Metric::Sr25519Verify => verify_throughput(SIGNATURES),
+		Metric::MemCopy => copy_throughput(DATA_SIZE),
+		Metric::DiskSeqWrite => with_scratch_file(dir, |file| seq_write_throughput(file, DATA_SIZE))?,
+		Metric::DiskSeqRead => with_scratch_file(dir, |file| seq_read_throughput(file, DATA_SIZE))?,
+		Metric::DiskRndWrite => with_scratch_file(dir, |file| rnd_write_throughput(file, DATA_SIZE))?,
+		Metric::DiskRndRead => with_scratch_file(dir, |file| rnd_read_throughput(file, DATA_SIZE))?,
+	})
+}
+
+/// MiB per second processed if `bytes` were processed in the time elapsed since `started`.
+fn mib_per_sec(bytes: usize, started: Instant) -> f64 {
+	bytes as f64 / MIB as f64 / started.elapsed().as_secs_f64()
+}
+
+fn random_bytes(size: usize) -> Vec<u8> {
+	let mut data = vec![0; size];
+	rand::thread_rng().fill_bytes(&mut data);
+	data
+}
+
+fn hash_throughput(size: usize) -> f64 {
+	let data = random_bytes(MIB);
+
+	let started = Instant::now();
+	for _ in 0..size / MIB {
+		sp_core::hashing::blake2_256(&data);
+	}
+	mib_per_sec(size / MIB * MIB, started)
+}
+
+fn verify_throughput(signatures: usize) -> f64 {
+	let signed = (0..signatures).map(|_| {
+		let pair = sr25519::Pair::from_seed(&rand::thread_rng().gen());
+		let message = random_bytes(32);
+		(pair.sign(&message), message, pair.public())
+	}).collect::<Vec<_>>();
+
+	let started = Instant::now();
+	for (signature, message, public) in &signed {
+		assert!(sr25519::Pair::verify(signature, message, public));
+	}
+	signatures as f64 / started.elapsed().as_secs_f64()
+}
+
+fn copy_throughput(size: usize) -> f64 {
+	let source = random_bytes(size);
+	let mut destination = vec![0; size];
+
+	let started = Instant::now();
+	destination.copy_from_slice(&source);
+	let throughput = mib_per_sec(size, started);
+
+	// Keep the copy from being optimized out.
+	assert_eq!(destination[size - 1], source[size - 1]);
+	throughput
+}
+
+/// Run `workload` on a new scratch file in `dir`, removing the file afterwards.
+fn with_scratch_file(dir: &Path, workload: impl FnOnce(&mut File) -> Result<f64>) -> Result<f64> {
+	let path = dir.join(SCRATCH_FILE);
+	let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path)?;
+	let result = workload(&mut file);
+	drop(file);
+	fs::remove_file(&path)?;
+	result
+}
+
+fn seq_write_throughput(file: &mut File, size: usize) -> Result<f64> {
+	let data = random_bytes(MIB);
+
+	let started = Instant::now();
+	for _ in 0..size / MIB {
+		file.write_all(&data)?;
+	}
+	file.sync_all()?;
+	Ok(mib_per_sec(size / MIB * MIB, started))
+}
+
+fn rnd_write_throughput(file: &mut File, size: usize) -> Result<f64> {
+	let blocks = size / RANDOM_BLOCK_SIZE;
+	let data = random_bytes(RANDOM_BLOCK_SIZE);
+	let mut rng = rand::thread_rng();
+
+	// Writes at random offsets must not grow the file.
+	file.set_len((blocks * RANDOM_BLOCK_SIZE) as u64)?;
+	file.sync_all()?;
+
+	let started = Instant::now();
+	for _ in 0..blocks {
+		let offset = rng.gen_range(0, blocks) * RANDOM_BLOCK_SIZE;
+		file.seek(SeekFrom::Start(offset as u64))?;
+		file.write_all(&data)?;
+	}
+	file.sync_all()?;
+	Ok(mib_per_sec(blocks * RANDOM_BLOCK_SIZE, started))
+}
+
+/// Write `size` bytes to `file` and drop them from the OS cache, so that the read workloads read
+/// them back from the disk.
+fn fill_uncached(file: &mut File, size: usize) -> Result<()> {
+	let data = random_bytes(MIB);
+	let mut remaining = size;
+	while remaining > 0 {
+		let len = remaining.min(MIB);
+		file.write_all(&data[..len])?;
+		remaining -= len;
+	}
+	file.sync_all()?;
+	drop_os_cache(file)
+}
+
+fn seq_read_throughput(file: &mut File, size: usize) -> Result<f64> {
+	fill_uncached(file, size)?;
+	file.seek(SeekFrom::Start(0))?;
+	let mut buffer = vec![0; MIB];
+
+	let started = Instant::now();
+	for _ in 0..size / MIB {
+		file.read_exact(&mut buffer)?;
+	}
+	Ok(mib_per_sec(size / MIB * MIB, started))
+}
+
+fn rnd_read_throughput(file: &mut File, size: usize) -> Result<f64> {
+	let blocks = size / RANDOM_BLOCK_SIZE;
+	fill_uncached(file, blocks * RANDOM_BLOCK_SIZE)?;
+	let mut buffer = vec![0; RANDOM_BLOCK_SIZE];
+	let mut rng = rand::thread_rng();
+
+	let started = Instant::now();
+	for _ in 0..blocks {
+		let offset = rng.gen_range(0, blocks) * RANDOM_BLOCK_SIZE;
+		file.seek(SeekFrom::Start(offset as u64))?;
+		file.read_exact(&mut buffer)?;
+	}
+	Ok(mib_per_sec(blocks * RANDOM_BLOCK_SIZE, started))
+}
+
+/// Ask the OS to drop the cached pages of `file`.
+#[cfg(target_os = "linux")]
+fn drop_os_cache(file: &File) -> Result<()> {
+	use nix::fcntl::{posix_fadvise, PosixFadviseAdvice};
+	use std::os::unix::io::AsRawFd;
+
+	posix_fadvise(file.as_raw_fd(), 0, 0, PosixFadviseAdvice::POSIX_FADV_DONTNEED)
+		.map_err(|err| Error::Input(format!("Failed to drop the OS cache: {}", err)))?;
+	Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn drop_os_cache(_file: &File) -> Result<()> {
+	eprintln!("The OS cache can only be dropped on Linux, the disk reads may not be cold.");
+	Ok(())
+}
+
+fn print_measurements(measurements: &[Measurement], tolerance: f64) {
+	println!(
+		"{:<16} {:<16} {:>12} {:>12} {:>8}",
+		"Metric", "Unit", "Measured", "Expected", "Result",
+	);
+	for Measurement { requirement, measured } in measurements {
+		println!(
+			"{:<16} {:<16} {:>12.2} {:>12.2} {:>8}",
+			requirement.metric.to_string(),
+			requirement.metric.unit(),
+			measured,
+			requirement.expected,
+			if passes(*measured, requirement.expected, tolerance) { "ok" } else { "FAIL" },
+		);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn passes_applies_the_tolerance() {
+		assert!(passes(100.0, 100.0, 0.0));
+		assert!(!passes(99.9, 100.0, 0.0));
+		assert!(passes(90.0, 100.0, 10.0));
+		assert!(!passes(89.9, 100.0, 10.0));
+		assert!(passes(0.0, 100.0, 100.0));
+	}
+
+	#[test]
+	fn workloads_work() {
+		assert!(hash_throughput(2 * MIB) > 0.0);
+		assert!(verify_throughput(10) > 0.0);
+		assert!(copy_throughput(MIB) > 0.0);
+	}
+
+	#[test]
+	fn disk_workloads_remove_the_scratch_file() {
+		let dir = tempfile::tempdir().unwrap();
+
+		let run = |workload: fn(&mut File, usize) -> Result<f64>, size| {
+			with_scratch_file(dir.path(), |file| workload(file, size)).unwrap()
+		};
+
+		assert!(run(seq_write_throughput, 2 * MIB) > 0.0);
+		assert!(run(seq_read_throughput, 2 * MIB) > 0.0);
+		assert!(run(rnd_write_throughput, MIB) > 0.0);
+		assert!(run(rnd_read_throughput, MIB) > 0.0);
+		assert!(!dir.path().join(SCRATCH_FILE).exists());
+	}
+}
@@ -0,0 +1,168 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Machine readable output of the benchmark results.
+
+use frame_benchmarking::BenchmarkBatch;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+/// Version of the JSON output, bumped on incompatible changes of its schema.
+pub const OUTPUT_VERSION: u32 = 1;
+
+/// The results of a `benchmark` run, as written by `--json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkOutput {
+	/// Version of the schema, see [`OUTPUT_VERSION`].
+	pub version: u32,
+	/// The results of each benchmarked extrinsic.
+	pub benchmarks: Vec<BenchmarkOutputEntry>,
+}
+
+/// The results of benchmarking a single extrinsic.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkOutputEntry {
+	/// The pallet containing the extrinsic.
+	pub pallet: String,
+	/// The name of the extrinsic (or benchmark).
+	pub extrinsic: String,
+	/// Every sample taken, in the order they were taken.
+	pub samples: Vec<BenchmarkSample>,
+}
+
+/// A single run of a benchmark.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkSample {
+	/// The values of the components, by component name.
+	pub components: Vec<(String, u32)>,
+	/// Time taken by the extrinsic, in nanoseconds.
+	pub extrinsic_time: u64,
+	/// Time taken to compute the storage root afterwards, in nanoseconds.
+	pub storage_root_time: u64,
+}
+
+impl BenchmarkOutput {
+	/// Build the output of the given benchmark batches.
+	pub fn new(batches: &[BenchmarkBatch]) -> Self {
+		let nanos = |time: u128| u64::try_from(time).unwrap_or(u64::max_value());
+
+		BenchmarkOutput {
+			version: OUTPUT_VERSION,
+			benchmarks: batches.iter().map(|batch| BenchmarkOutputEntry {
+				pallet: String::from_utf8_lossy(&batch.pallet).into_owned(),
+				extrinsic: String::from_utf8_lossy(&batch.benchmark).into_owned(),
+				samples: batch.results.iter().map(|(params, extrinsic_time, storage_root_time)| {
+					BenchmarkSample {
+						components: params.iter()
+							.map(|(param, value)| (format!("{:?}", param), *value))
+							.collect(),
+						extrinsic_time: nanos(*extrinsic_time),
+						storage_root_time: nanos(*storage_root_time),
+					}
+				}).collect(),
+			}).collect(),
+		}
+	}
+
+	/// Render the samples as CSV, one line per sample.
+	///
+	/// Components are written in a single column as space separated `name=value` pairs, as the
+	/// components differ between extrinsics.
+	pub fn to_csv(&self) -> String {
+		let mut csv = String::from("pallet,extrinsic,components,extrinsic_time_ns,storage_root_time_ns\n");
+		for entry in &self.benchmarks {
+			for sample in &entry.samples {
+				let components = sample.components.iter()
+					.map(|(name, value)| format!("{}={}", name, value))
+					.collect::<Vec<_>>();
+				csv.push_str(&format!(
+					"{},{},{},{},{}\n",
+					entry.pallet,
+					entry.extrinsic,
+					components.join(" "),
+					sample.extrinsic_time,
+					sample.storage_root_time,
+				));
+			}
+		}
+		csv
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_benchmarking::BenchmarkParameter;
+
+	fn batches() -> Vec<BenchmarkBatch> {
+		vec![
+			BenchmarkBatch {
+				pallet: b"pallet-balances".to_vec(),
+				benchmark: b"transfer".to_vec(),
+				results: vec![
+					(vec![(BenchmarkParameter::u, 1), (BenchmarkParameter::e, 0)], 1_000, 200),
+					(vec![(BenchmarkParameter::u, 1), (BenchmarkParameter::e, 0)], 1_100, 210),
+					(vec![(BenchmarkParameter::u, 2), (BenchmarkParameter::e, 1)], 1_500, 230),
+				],
+			},
+			BenchmarkBatch {
+				pallet: b"pallet-balances".to_vec(),
+				benchmark: b"set_balance".to_vec(),
+				results: vec![(vec![], 2_000, 300)],
+			},
+		]
+	}
+
+	#[test]
+	fn json_round_trips() {
+		let output = BenchmarkOutput::new(&batches());
+		let json = serde_json::to_string(&output).unwrap();
+		let decoded: BenchmarkOutput = serde_json::from_str(&json).unwrap();
+
+		assert_eq!(decoded, output);
+		assert_eq!(decoded.version, OUTPUT_VERSION);
+		assert_eq!(decoded.benchmarks[0].samples[2], BenchmarkSample {
+			components: vec![("u".into(), 2), ("e".into(), 1)],
+			extrinsic_time: 1_500,
+			storage_root_time: 230,
+		});
+	}
+
+	#[test]
+	fn json_uses_the_documented_field_names() {
+		let json = serde_json::to_value(BenchmarkOutput::new(&batches())).unwrap();
+
+		assert_eq!(json["version"], OUTPUT_VERSION);
+		assert_eq!(json["benchmarks"][1]["extrinsic"], "set_balance");
+		assert_eq!(json["benchmarks"][1]["samples"][0]["extrinsicTime"], 2_000);
+		assert_eq!(json["benchmarks"][1]["samples"][0]["storageRootTime"], 300);
+	}
+
+	#[test]
+	fn to_csv_works() {
+		assert_eq!(
+			BenchmarkOutput::new(&batches()).to_csv(),
+			"pallet,extrinsic,components,extrinsic_time_ns,storage_root_time_ns\n\
+			pallet-balances,transfer,u=1 e=0,1000,200\n\
+			pallet-balances,transfer,u=1 e=0,1100,210\n\
+			pallet-balances,transfer,u=2 e=1,1500,230\n\
+			pallet-balances,set_balance,,2000,300\n",
+		);
+	}
+}
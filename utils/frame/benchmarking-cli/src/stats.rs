@@ -0,0 +1,167 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Statistics over the repeated samples taken at each point of a benchmark.
+
+use frame_benchmarking::BenchmarkResults;
+use std::collections::BTreeMap;
+
+/// Statistics of the extrinsic times measured for one set of component values.
+#[derive(Debug, PartialEq)]
+pub(crate) struct PointStats {
+	/// The values of the components.
+	pub params: Vec<u32>,
+	/// The number of samples taken.
+	pub samples: usize,
+	// Times in nanoseconds.
+	pub mean: u128,
+	pub median: u128,
+	pub stddev: u128,
+	pub min: u128,
+	pub max: u128,
+}
+
+impl PointStats {
+	/// The standard deviation relative to the mean, in percent.
+	pub fn relative_stddev(&self) -> f64 {
+		if self.mean == 0 { return 0.0 }
+		self.stddev as f64 * 100.0 / self.mean as f64
+	}
+}
+
+/// Group `results` by component values and compute the statistics of each point.
+pub(crate) fn point_stats(results: &[BenchmarkResults]) -> Vec<PointStats> {
+	let mut points = BTreeMap::<Vec<u32>, Vec<u128>>::new();
+	for (params, time, _) in results {
+		points.entry(params.iter().map(|p| p.1).collect()).or_default().push(*time);
+	}
+
+	points.into_iter().map(|(params, mut times)| {
+		times.sort();
+		let samples = times.len();
+		let mean = times.iter().sum::<u128>() / samples as u128;
+		let median = if samples % 2 == 0 {
+			(times[samples / 2 - 1] + times[samples / 2]) / 2
+		} else {
+			times[samples / 2]
+		};
+		let variance = times.iter()
+			.map(|t| {
+				let d = mean.max(*t) - mean.min(*t);
+				d * d
+			})
+			.sum::<u128>() / samples as u128;
+
+		PointStats {
+			params,
+			samples,
+			mean,
+			median,
+			stddev: (variance as f64).sqrt() as u128,
+			min: times[0],
+			max: times[samples - 1],
+		}
+	}).collect()
+}
+
+/// Format nanoseconds as microseconds.
+fn micros(nanos: u128) -> String {
+	format!("{:.3}", nanos as f64 / 1_000f64)
+}
+
+/// Print the statistics of each point of a benchmark, flagging the points whose relative standard
+/// deviation exceeds `threshold` percent.
+pub(crate) fn print_point_stats(results: &[BenchmarkResults], threshold: f64) {
+	let names = match results.first() {
+		Some(result) => result.0.iter()
+			.map(|p| format!("{:>5}", format!("{:?}", p.0)))
+			.collect::<Vec<_>>(),
+		None => return,
+	};
+	let stats = point_stats(results);
+
+	println!("Per point statistics:");
+	println!(
+		"{}  samples      mean µs    median µs     sigma µs       min µs       max µs",
+		names.join(" "),
+	);
+
+	let mut noisy = 0;
+	for point in &stats {
+		let flag = if point.relative_stddev() > threshold {
+			noisy += 1;
+			format!("  <- sigma {:.1}%", point.relative_stddev())
+		} else {
+			String::new()
+		};
+		println!(
+			"{}  {:>7}  {:>11}  {:>11}  {:>11}  {:>11}  {:>11}{}",
+			point.params.iter().map(|v| format!("{:>5}", v)).collect::<Vec<_>>().join(" "),
+			point.samples,
+			micros(point.mean),
+			micros(point.median),
+			micros(point.stddev),
+			micros(point.min),
+			micros(point.max),
+			flag,
+		);
+	}
+
+	if noisy > 0 {
+		println!(
+			"{} of {} points have a standard deviation above {}% of their mean, \
+			consider increasing `--repeat`",
+			noisy,
+			stats.len(),
+			threshold,
+		);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_benchmarking::BenchmarkParameter;
+
+	#[test]
+	fn point_stats_works() {
+		let results = vec![
+			(vec![(BenchmarkParameter::n, 2)], 100, 0),
+			(vec![(BenchmarkParameter::n, 1)], 40, 0),
+			(vec![(BenchmarkParameter::n, 2)], 300, 0),
+			(vec![(BenchmarkParameter::n, 1)], 50, 0),
+			(vec![(BenchmarkParameter::n, 2)], 200, 0),
+			(vec![(BenchmarkParameter::n, 1)], 60, 0),
+			(vec![(BenchmarkParameter::n, 1)], 50, 0),
+		];
+
+		assert_eq!(point_stats(&results), vec![
+			PointStats { params: vec![1], samples: 4, mean: 50, median: 50, stddev: 7, min: 40, max: 60 },
+			PointStats { params: vec![2], samples: 3, mean: 200, median: 200, stddev: 81, min: 100, max: 300 },
+		]);
+	}
+
+	#[test]
+	fn single_samples_have_no_deviation() {
+		let results = vec![(vec![], 1_000, 0)];
+		let stats = point_stats(&results);
+
+		assert_eq!(stats[0].stddev, 0);
+		assert_eq!(stats[0].relative_stddev(), 0.0);
+	}
+}
@@ -0,0 +1,466 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmark of the raw read and write costs of the client database.
+
+use rand::{Rng, RngCore};
+use sc_cli::{CliConfiguration, DatabaseParams, Error, Result, SharedParams};
+use sc_client_db::columns;
+use sc_service::{config::DatabaseConfig, Configuration};
+use serde::Serialize;
+use std::convert::TryFrom;
+use std::path::Path;
+use std::time::Instant;
+use structopt::StructOpt;
+
+/// Weight of one nanosecond.
+const WEIGHT_PER_NANOS: u64 = 1_000;
+/// Number of random positions looked at per key to read before giving up on finding more keys.
+#[cfg(feature = "db")]
+const SAMPLE_ATTEMPTS: usize = 10;
+
+/// How the results were measured, printed with them.
+const METHODOLOGY: &str = "\
+The benchmark runs on a checkpoint of the database, made in a temporary directory next to it and \
+removed afterwards. The database itself is never written to.
+Reads: existing keys are sampled from the state column by looking up the keys following random \
+positions of the key space, without scanning the column. Each is then read once from a newly \
+opened database, timing every read. Unless `--cold` is given, all sampled keys are read once \
+beforehand to warm the database and OS caches. With `--cold`, the OS cache of the database files \
+is dropped after sampling (on Linux only).
+Writes: batches of values under random keys are written and committed to the state column, \
+timing every commit. The latency of a write is the commit time divided by the batch size. The \
+written keys are deleted again after each batch, untimed.
+The suggested weights are the 99th percentiles of the latencies.";
+
+/// The `benchmark-storage` command used to measure the read and write costs of the database.
+#[derive(Debug, StructOpt, Clone)]
+pub struct StorageCmd {
+	/// Number of existing keys to read.
+	#[structopt(long, default_value = "10000")]
+	pub reads: usize,
+
+	/// Sizes in bytes of the values to write, one run per size.
+	#[structopt(long, value_name = "BYTES", use_delimiter = true, default_value = "32,256,1024,4096,16384")]
+	pub value_sizes: Vec<usize>,
+
+	/// Number of values written per committed batch.
+	#[structopt(long, default_value = "100")]
+	pub batch_size: usize,
+
+	/// Number of batches committed per value size.
+	#[structopt(long, default_value = "20")]
+	pub batches: usize,
+
+	/// Measure the reads with empty database and OS caches instead of warm ones.
+	#[structopt(long)]
+	pub cold: bool,
+
+	/// Print the results as JSON.
+	#[structopt(long)]
+	pub json: bool,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+/// Latency percentiles of a set of samples, in nanoseconds.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Latencies {
+	/// Number of samples.
+	pub samples: usize,
+	pub mean: u64,
+	pub p50: u64,
+	pub p90: u64,
+	pub p99: u64,
+	pub max: u64,
+}
+
+impl Latencies {
+	/// Compute the percentiles of `samples`, which must not be empty.
+	fn new(mut samples: Vec<u64>) -> Self {
+		samples.sort();
+		let percentile = |p: usize| samples[(samples.len() - 1) * p / 100];
+
+		Latencies {
+			samples: samples.len(),
+			mean: samples.iter().sum::<u64>() / samples.len() as u64,
+			p50: percentile(50),
+			p90: percentile(90),
+			p99: percentile(99),
+			max: samples[samples.len() - 1],
+		}
+	}
+}
+
+/// The write latencies for one value size.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteLatencies {
+	/// Size of the written values in bytes.
+	pub value_size: usize,
+	/// Latencies of a single write.
+	pub latencies: Latencies,
+}
+
+/// The results of the storage benchmark.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageResults {
+	/// How the results were measured.
+	pub methodology: &'static str,
+	/// Whether the caches were warmed before reading.
+	pub warm: bool,
+	/// Latencies of a single read.
+	pub reads: Latencies,
+	pub writes: Vec<WriteLatencies>,
+	/// Suggested weight of a database read.
+	pub read_weight: u64,
+	/// Suggested weight of a database write.
+	pub write_weight: u64,
+}
+
+impl StorageCmd {
+	/// Run the benchmark against the database of `config`.
+	pub fn run(&self, config: Configuration) -> Result<()> {
+		if self.reads == 0 || self.batch_size == 0 || self.batches == 0 || self.value_sizes.is_empty() {
+			return Err(Error::Input(
+				"`--reads`, `--batch-size`, `--batches` and `--value-sizes` must not be zero or empty".into(),
+			));
+		}
+
+		let path = match &config.database {
+			DatabaseConfig::RocksDb { path, .. } => path.clone(),
+			other => return Err(Error::Input(format!(
+				"The storage benchmark is not supported for the {} backend",
+				other,
+			))),
+		};
+		if !path.exists() {
+			return Err(Error::Input(format!("{:?} does not exist", path)));
+		}
+
+		let checkpoint = checkpoint_database(&path)?;
+		let path = checkpoint.path().join("db");
+		let keys = sample_keys(&path, self.reads)?;
+		if self.cold {
+			drop_os_cache(&path)?;
+		}
+
+		// Reopened after sampling so the reads start with an empty database cache.
+		let db = open_database(&path)?;
+		let results = self.measure(&*db, keys)?;
+
+		if self.json {
+			println!("{}", serde_json::to_string_pretty(&results).map_err(|e| e.to_string())?);
+		} else {
+			print_results(&results);
+		}
+
+		Ok(())
+	}
+
+	/// Measure the reads of `keys` and the writes on the state column of `db`.
+	fn measure(&self, db: &dyn kvdb::KeyValueDB, keys: Vec<Vec<u8>>) -> Result<StorageResults> {
+		let mut rng = rand::thread_rng();
+
+		if keys.is_empty() {
+			return Err(Error::Input("The state column of the database is empty".into()));
+		}
+
+		if !self.cold {
+			for key in &keys {
+				db.get(columns::STATE, key)?;
+			}
+		}
+
+		let mut reads = Vec::with_capacity(keys.len());
+		for key in &keys {
+			let started = Instant::now();
+			db.get(columns::STATE, key)?;
+			reads.push(nanos(started));
+		}
+
+		let mut writes = Vec::new();
+		let mut all_writes = Vec::new();
+		for &value_size in &self.value_sizes {
+			let mut samples = Vec::with_capacity(self.batches);
+			for _ in 0..self.batches {
+				let mut transaction = db.transaction();
+				let mut written = Vec::with_capacity(self.batch_size);
+				for _ in 0..self.batch_size {
+					let key = rng.gen::<[u8; 32]>();
+					let mut value = vec![0; value_size];
+					rng.fill_bytes(&mut value);
+					transaction.put_vec(columns::STATE, &key, value);
+					written.push(key);
+				}
+
+				let started = Instant::now();
+				db.write(transaction)?;
+				samples.push(nanos(started) / self.batch_size as u64);
+
+				let mut transaction = db.transaction();
+				for key in &written {
+					transaction.delete(columns::STATE, key);
+				}
+				db.write(transaction)?;
+			}
+
+			all_writes.extend(samples.iter().cloned());
+			writes.push(WriteLatencies { value_size, latencies: Latencies::new(samples) });
+		}
+
+		let reads = Latencies::new(reads);
+		let all_writes = Latencies::new(all_writes);
+
+		Ok(StorageResults {
+			methodology: METHODOLOGY,
+			warm: !self.cold,
+			read_weight: reads.p99.saturating_mul(WEIGHT_PER_NANOS),
+			write_weight: all_writes.p99.saturating_mul(WEIGHT_PER_NANOS),
+			reads,
+			writes,
+		})
+	}
+}
+
+impl CliConfiguration for StorageCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// Nanoseconds elapsed since `started`.
+fn nanos(started: Instant) -> u64 {
+	u64::try_from(started.elapsed().as_nanos()).unwrap_or(u64::max_value())
+}
+
+#[cfg(feature = "db")]
+fn open_database(path: &Path) -> Result<Box<dyn kvdb::KeyValueDB>> {
+	let path = path.to_str()
+		.ok_or_else(|| Error::Input(format!("{:?} is not a valid UTF-8 path", path)))?;
+	let config = kvdb_rocksdb::DatabaseConfig::with_columns(sc_client_db::NUM_COLUMNS);
+
+	Ok(Box::new(kvdb_rocksdb::Database::open(&config, path)?))
+}
+
+#[cfg(not(feature = "db"))]
+fn open_database(_path: &Path) -> Result<Box<dyn kvdb::KeyValueDB>> {
+	Err(Error::Input("The storage benchmark requires the `db` feature".into()))
+}
+
+#[cfg(feature = "db")]
+fn rocksdb_error(err: rocksdb::Error) -> Error {
+	Error::Input(format!("Database error: {}", err))
+}
+
+/// Open the RocksDB database at `path` with all its column families.
+#[cfg(feature = "db")]
+fn open_rocksdb(path: &Path) -> Result<rocksdb::DB> {
+	let opts = rocksdb::Options::default();
+	let columns = rocksdb::DB::list_cf(&opts, path).map_err(rocksdb_error)?;
+	rocksdb::DB::open_cf(&opts, path, &columns).map_err(rocksdb_error)
+}
+
+/// Create a checkpoint of the database at `path` in the `db` directory of a temporary directory
+/// next to it.
+///
+/// The files of the checkpoint are hard links to those of the database where possible, so it is
+/// cheap to create and shares the disk and OS cache of the database.
+#[cfg(feature = "db")]
+fn checkpoint_database(path: &Path) -> Result<tempfile::TempDir> {
+	let parent = path.parent().unwrap_or(path);
+	let dir = tempfile::Builder::new().prefix("benchmark-storage").tempdir_in(parent)?;
+	let db = open_rocksdb(path)?;
+	rocksdb::checkpoint::Checkpoint::new(&db)
+		.and_then(|checkpoint| checkpoint.create_checkpoint(dir.path().join("db")))
+		.map_err(rocksdb_error)?;
+
+	Ok(dir)
+}
+
+#[cfg(not(feature = "db"))]
+fn checkpoint_database(_path: &Path) -> Result<tempfile::TempDir> {
+	Err(Error::Input("The storage benchmark requires the `db` feature".into()))
+}
+
+/// Sample up to `count` distinct keys of the state column of the database at `path`.
+///
+/// Each key is the first one following a random position of the key space, so only the sampled
+/// keys are read from the database.
+#[cfg(feature = "db")]
+fn sample_keys(path: &Path, count: usize) -> Result<Vec<Vec<u8>>> {
+	use rocksdb::{Direction, IteratorMode};
+
+	let db = open_rocksdb(path)?;
+	let name = sc_client_db::column_family_name(columns::STATE);
+	let column = db.cf_handle(&name)
+		.ok_or_else(|| Error::Input(format!("The database has no column {}", name)))?;
+
+	let mut rng = rand::thread_rng();
+	let mut keys = std::collections::HashSet::new();
+	for _ in 0..count.saturating_mul(SAMPLE_ATTEMPTS) {
+		if keys.len() == count {
+			break;
+		}
+
+		let position = rng.gen::<[u8; 32]>();
+		let key = db.iterator_cf(column, IteratorMode::From(&position, Direction::Forward))
+			.next()
+			.or_else(|| db.iterator_cf(column, IteratorMode::Start).next());
+		match key {
+			Some((key, _)) => keys.insert(key.into_vec()),
+			None => break,
+		};
+	}
+
+	Ok(keys.into_iter().collect())
+}
+
+#[cfg(not(feature = "db"))]
+fn sample_keys(_path: &Path, _count: usize) -> Result<Vec<Vec<u8>>> {
+	Err(Error::Input("The storage benchmark requires the `db` feature".into()))
+}
+
+/// Ask the OS to drop the cached pages of the files of the database at `path`.
+#[cfg(target_os = "linux")]
+fn drop_os_cache(path: &Path) -> Result<()> {
+	use nix::fcntl::{posix_fadvise, PosixFadviseAdvice};
+	use std::os::unix::io::AsRawFd;
+
+	for entry in std::fs::read_dir(path)? {
+		let file = std::fs::File::open(entry?.path())?;
+		posix_fadvise(file.as_raw_fd(), 0, 0, PosixFadviseAdvice::POSIX_FADV_DONTNEED)
+			.map_err(|err| Error::Input(format!("Failed to drop the OS cache: {}", err)))?;
+	}
+
+	Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn drop_os_cache(_path: &Path) -> Result<()> {
+	eprintln!("The OS cache can only be dropped on Linux, the reads may not be cold.");
+	Ok(())
+}
+
+fn print_latencies(latencies: &Latencies) {
+	println!(
+		"  mean {} ns, p50 {} ns, p90 {} ns, p99 {} ns, max {} ns ({} samples)",
+		latencies.mean,
+		latencies.p50,
+		latencies.p90,
+		latencies.p99,
+		latencies.max,
+		latencies.samples,
+	);
+}
+
+fn print_results(results: &StorageResults) {
+	println!("{}\n", results.methodology);
+
+	println!("Reads ({} caches):", if results.warm { "warm" } else { "cold" });
+	print_latencies(&results.reads);
+
+	for writes in &results.writes {
+		println!("Writes of {} bytes:", writes.value_size);
+		print_latencies(&writes.latencies);
+	}
+
+	println!("\nSuggested weights: read {}, write {}", results.read_weight, results.write_weight);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn cmd() -> StorageCmd {
+		StorageCmd::from_iter(&["benchmark-storage", "--reads", "50", "--batches", "3", "--batch-size", "10"])
+	}
+
+	#[test]
+	fn latencies_works() {
+		let latencies = Latencies::new((1..=100).rev().collect());
+
+		assert_eq!(latencies, Latencies { samples: 100, mean: 50, p50: 50, p90: 90, p99: 99, max: 100 });
+		assert_eq!(Latencies::new(vec![7]), Latencies { samples: 1, mean: 7, p50: 7, p90: 7, p99: 7, max: 7 });
+	}
+
+	#[test]
+	fn measure_works() {
+		let db = kvdb_memorydb::create(sc_client_db::NUM_COLUMNS);
+		let mut transaction = db.transaction();
+		let mut keys = Vec::new();
+		for i in 0u32..100 {
+			transaction.put(columns::STATE, &i.to_le_bytes(), b"value");
+			keys.push(i.to_le_bytes().to_vec());
+		}
+		db.write(transaction).unwrap();
+
+		let results = cmd().measure(&db, keys[..50].to_vec()).unwrap();
+
+		assert_eq!(results.reads.samples, 50);
+		assert_eq!(results.writes.len(), 5);
+		assert!(results.writes.iter().all(|writes| writes.latencies.samples == 3));
+		// The written values are removed again.
+		assert_eq!(db.iter(columns::STATE).count(), 100);
+	}
+
+	#[test]
+	fn measure_fails_on_empty_state() {
+		let db = kvdb_memorydb::create(sc_client_db::NUM_COLUMNS);
+		assert!(cmd().measure(&db, Vec::new()).is_err());
+	}
+
+	#[cfg(feature = "db")]
+	#[test]
+	fn checkpoint_is_sampled_and_written_instead_of_the_database() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("db");
+		{
+			let db = open_database(&path).unwrap();
+			let mut transaction = db.transaction();
+			// Keys spread over the key space, as the hashes of trie nodes are.
+			for i in 0u8..100 {
+				transaction.put(columns::STATE, &[i * 2; 32], b"value");
+			}
+			db.write(transaction).unwrap();
+		}
+
+		let checkpoint = checkpoint_database(&path).unwrap();
+		let checkpoint_path = checkpoint.path().join("db");
+		let keys = sample_keys(&checkpoint_path, 50).unwrap();
+		assert_eq!(keys.len(), 50);
+
+		let db = open_database(&checkpoint_path).unwrap();
+		cmd().measure(&*db, keys).unwrap();
+		drop(db);
+		drop(checkpoint);
+
+		assert_eq!(open_database(&path).unwrap().iter(columns::STATE).count(), 100);
+		assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+	}
+}
@@ -0,0 +1,286 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rendering of benchmark results as a Rust module of weight functions.
+//!
+//! The module is rendered through a Handlebars template, the built-in one or one given with
+//! `--template`. Templates are rendered with the following data:
+//!
+//! - `date`: the date of the run, as `YYYY-MM-DD`.
+//! - `args`: the arguments of the CLI invocation, and `command` the same joined by spaces.
+//! - `pallets`: the benchmarked pallets, each with:
+//!   - `name`: the pallet name made a valid module name, e.g. `pallet_balances`.
+//!   - `pallet`: the pallet name as given to the runtime, e.g. `pallet-balances`.
+//!   - `benchmarks`: the benchmarked extrinsics, each with its `name`, its `base_weight` and its
+//!     `components`, each with a `name` and the `slope` of the weight per unit of the component.
+//!
+//! The `underscore` helper formats a number with `_` separating groups of three digits.
+
+use frame_benchmarking::{Analysis, BenchmarkBatch};
+use handlebars::{handlebars_helper, no_escape, Handlebars};
+use serde::Serialize;
+use std::convert::TryFrom;
+
+/// Weight of one nanosecond of execution time.
+const WEIGHT_PER_NANOS: u128 = 1_000;
+
+/// The template used when none is given, rendering a module per pallet with a `WeightInfo` trait
+/// and its implementation for `SubstrateWeight`.
+const DEFAULT_TEMPLATE: &str = include_str!("template.hbs");
+
+#[derive(Serialize)]
+struct TemplateData {
+	date: String,
+	args: Vec<String>,
+	command: String,
+	pallets: Vec<PalletData>,
+}
+
+#[derive(Serialize)]
+struct PalletData {
+	name: String,
+	pallet: String,
+	benchmarks: Vec<BenchmarkData>,
+}
+
+#[derive(Serialize)]
+struct BenchmarkData {
+	name: String,
+	base_weight: u64,
+	components: Vec<ComponentData>,
+}
+
+#[derive(Serialize)]
+struct ComponentData {
+	name: String,
+	slope: u64,
+}
+
+impl BenchmarkData {
+	/// Build the weight function from the min-squares analysis of all the samples, which leaves
+	/// out the outer quartiles of the samples taken at each point.
+	///
+	/// Benchmarks without components, or whose results can't be fitted, only get a base weight:
+	/// the median of their extrinsic times.
+	fn new(batch: &BenchmarkBatch) -> Self {
+		let name = String::from_utf8_lossy(&batch.benchmark).into_owned();
+		let analysis = if batch.results[0].0.is_empty() {
+			None
+		} else {
+			Analysis::min_squares_iqr(&batch.results)
+		};
+
+		match analysis {
+			Some(analysis) => Self {
+				name,
+				base_weight: to_weight(analysis.base),
+				components: analysis.names.into_iter()
+					.zip(analysis.slopes.into_iter())
+					.map(|(name, slope)| ComponentData { name, slope: to_weight(slope) })
+					.collect(),
+			},
+			None => {
+				let mut times = batch.results.iter().map(|r| r.1).collect::<Vec<_>>();
+				times.sort();
+				Self { name, base_weight: to_weight(times[times.len() / 2]), components: Vec::new() }
+			},
+		}
+	}
+}
+
+/// Convert a time in nanoseconds to a weight.
+fn to_weight(nanos: u128) -> u64 {
+	u64::try_from(nanos.saturating_mul(WEIGHT_PER_NANOS)).unwrap_or(u64::max_value())
+}
+
+/// Format a number with `_` separating groups of three digits.
+fn format_number(number: u64) -> String {
+	let digits = number.to_string();
+	let mut formatted = String::with_capacity(digits.len() * 4 / 3);
+	for (i, digit) in digits.chars().enumerate() {
+		if i > 0 && (digits.len() - i) % 3 == 0 {
+			formatted.push('_');
+		}
+		formatted.push(digit);
+	}
+	formatted
+}
+
+handlebars_helper!(underscore: |number: u64| format_number(number));
+
+/// Turn a pallet name like `pallet-balances` into a module name.
+fn module_name(pallet: &[u8]) -> String {
+	String::from_utf8_lossy(pallet)
+		.chars()
+		.map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+		.collect()
+}
+
+/// Render the results of `batches` through `template`, given with its name, or through the
+/// built-in template if `None`.
+///
+/// `args` is the CLI invocation and `date` the date of the run. Errors in the template are
+/// reported with their line in the template.
+pub(crate) fn render(
+	batches: &[BenchmarkBatch],
+	args: &[String],
+	date: &str,
+	template: Option<(&str, &str)>,
+) -> Result<String, String> {
+	// Group the benchmarks per pallet, keeping the order they were run in.
+	let mut pallets = Vec::<PalletData>::new();
+	for batch in batches.iter().filter(|batch| !batch.results.is_empty()) {
+		let pallet = String::from_utf8_lossy(&batch.pallet).into_owned();
+		let benchmark = BenchmarkData::new(batch);
+		match pallets.iter_mut().find(|data| data.pallet == pallet) {
+			Some(data) => data.benchmarks.push(benchmark),
+			None => pallets.push(PalletData {
+				name: module_name(&batch.pallet),
+				pallet,
+				benchmarks: vec![benchmark],
+			}),
+		}
+	}
+
+	let data = TemplateData {
+		date: date.into(),
+		args: args.to_vec(),
+		command: args.join(" "),
+		pallets,
+	};
+
+	let (name, template) = template.unwrap_or(("built-in template", DEFAULT_TEMPLATE));
+	let mut handlebars = Handlebars::new();
+	handlebars.set_strict_mode(true);
+	// The output is Rust code, not HTML.
+	handlebars.register_escape_fn(no_escape);
+	handlebars.register_helper("underscore", Box::new(underscore));
+	handlebars.register_template_string(name, template).map_err(|e| e.to_string())?;
+
+	handlebars.render(name, &data).map_err(|e| e.to_string())
+}
+
+/// The output of `render` for the batches of the tests, checked to compile.
+#[cfg(test)]
+#[path = "../res/sample_weights.rs"]
+mod sample_weights;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_benchmarking::BenchmarkParameter;
+
+	fn batch(pallet: &str, benchmark: &str, results: Vec<(Vec<(BenchmarkParameter, u32)>, u128)>) -> BenchmarkBatch {
+		BenchmarkBatch {
+			pallet: pallet.as_bytes().to_vec(),
+			benchmark: benchmark.as_bytes().to_vec(),
+			results: results.into_iter().map(|(params, time)| (params, time, 0)).collect(),
+		}
+	}
+
+	fn sample_batches() -> Vec<BenchmarkBatch> {
+		use BenchmarkParameter::{e, u};
+
+		vec![
+			// Fits 1_001.6 + 9.98 * u + 99.8 * e nanoseconds.
+			batch("pallet-balances", "transfer", vec![
+				(vec![(u, 0), (e, 0)], 1_003),
+				(vec![(u, 10), (e, 0)], 1_098),
+				(vec![(u, 20), (e, 0)], 1_201),
+				(vec![(u, 0), (e, 1)], 1_102),
+				(vec![(u, 0), (e, 2)], 1_199),
+				(vec![(u, 10), (e, 1)], 1_205),
+			]),
+			batch("pallet-balances", "set_balance", vec![
+				(vec![], 2_000),
+				(vec![], 2_500),
+				(vec![], 2_100),
+			]),
+			// Fits 2_981 + 20 * r nanoseconds.
+			batch("pallet-identity", "add_registrar", vec![
+				(vec![(BenchmarkParameter::r, 1)], 3_001),
+				(vec![(BenchmarkParameter::r, 2)], 3_022),
+				(vec![(BenchmarkParameter::r, 3)], 3_039),
+				(vec![(BenchmarkParameter::r, 4)], 3_062),
+			]),
+			// Benchmarks without results are left out.
+			batch("pallet-identity", "set_identity", vec![]),
+		]
+	}
+
+	fn args() -> Vec<String> {
+		["substrate", "benchmark", "--pallet", "*", "--extrinsic", "*"].iter().map(|a| a.to_string()).collect()
+	}
+
+	#[test]
+	fn render_matches_sample() {
+		let rendered = render(&sample_batches(), &args(), "2020-06-01", None);
+		assert_eq!(rendered.unwrap(), include_str!("../res/sample_weights.rs"));
+	}
+
+	#[test]
+	fn render_is_deterministic() {
+		let batches = sample_batches();
+		assert_eq!(render(&batches, &args(), "date", None), render(&batches, &args(), "date", None));
+	}
+
+	#[test]
+	fn render_uses_the_given_template() {
+		let template = "{{#each pallets}}{{pallet}}:{{#each benchmarks}} {{name}}={{underscore base_weight}}\
+			{{#each components}}+{{underscore slope}}*{{name}}{{/each}}{{/each}};{{/each}} ({{args.[1]}})";
+
+		assert_eq!(
+			render(&sample_batches(), &args(), "date", Some(("test.hbs", template))).unwrap(),
+			"pallet-balances: transfer=1_002_000+10_000*u+100_000*e set_balance=2_100_000;\
+			pallet-identity: add_registrar=2_981_000+20_000*r; (benchmark)",
+		);
+	}
+
+	#[test]
+	fn template_errors_report_their_line() {
+		let unclosed = "first line\n{{#each pallets}}\n";
+		let error = render(&sample_batches(), &args(), "date", Some(("unclosed.hbs", unclosed))).unwrap_err();
+		assert!(error.contains("unclosed.hbs"), "{}", error);
+
+		let unknown = "first line\n{{unknown_field}}\n";
+		let error = render(&sample_batches(), &args(), "date", Some(("unknown.hbs", unknown))).unwrap_err();
+		assert!(error.contains("line 2"), "{}", error);
+	}
+
+	#[test]
+	fn sample_weights_are_usable() {
+		use sample_weights::pallet_balances::{SubstrateWeight, WeightInfo};
+
+		assert_eq!(SubstrateWeight::transfer(1, 1), 1_112_000);
+		assert_eq!(SubstrateWeight::set_balance(), 2_100_000);
+	}
+
+	#[test]
+	fn format_number_works() {
+		assert_eq!(format_number(0), "0");
+		assert_eq!(format_number(999), "999");
+		assert_eq!(format_number(1_000), "1_000");
+		assert_eq!(format_number(65_949_000), "65_949_000");
+		assert_eq!(format_number(u64::max_value()), "18_446_744_073_709_551_615");
+	}
+
+	#[test]
+	fn module_name_works() {
+		assert_eq!(module_name(b"pallet-balances"), "pallet_balances");
+		assert_eq!(module_name(b"Identity"), "identity");
+	}
+}